@@ -0,0 +1,93 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use bytes::{BufMut, BytesMut};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use postgis::ewkb::{
+    AsEwkbLineString, AsEwkbMultiPolygon, EwkbRead, EwkbWrite, LineString, LineStringT,
+    MultiPolygon, Point, PolygonT,
+};
+use postgres_types::{ToSql, Type};
+use std::io::Cursor;
+
+fn large_multi_polygon() -> MultiPolygon {
+    let ring = |dx: f64| LineStringT {
+        points: (0..1000)
+            .map(|i| {
+                let a = i as f64 / 1000.0 * std::f64::consts::PI * 2.0;
+                Point::new(dx + a.cos(), a.sin(), None)
+            })
+            .collect(),
+        srid: None,
+    };
+    MultiPolygon {
+        polygons: (0..100)
+            .map(|i| PolygonT {
+                rings: vec![ring(i as f64 * 3.0)],
+                srid: None,
+            })
+            .collect(),
+        srid: Some(4326),
+    }
+}
+
+fn bench_write(c: &mut Criterion) {
+    let multi = large_multi_polygon();
+    c.bench_function("write_ewkb growing", |b| {
+        b.iter(|| {
+            let mut out = BytesMut::new();
+            multi
+                .as_ewkb()
+                .write_ewkb(&mut (&mut out).writer())
+                .unwrap();
+            black_box(out)
+        })
+    });
+    c.bench_function("to_sql", |b| {
+        b.iter(|| {
+            let mut out = BytesMut::new();
+            multi.to_sql(&Type::BYTEA, &mut out).unwrap();
+            black_box(out)
+        })
+    });
+}
+
+fn bench_hex(c: &mut Criterion) {
+    let multi = large_multi_polygon();
+    c.bench_function("to_hex_ewkb", |b| {
+        b.iter(|| black_box(multi.as_ewkb().to_hex_ewkb()))
+    });
+}
+
+fn bench_read(c: &mut Criterion) {
+    let line = LineStringT {
+        points: (0..100_000)
+            .map(|i| Point::new(i as f64, i as f64 / 2.0, None))
+            .collect(),
+        srid: Some(4326),
+    };
+    let mut buf = Vec::new();
+    line.as_ewkb().write_ewkb(&mut buf).unwrap();
+    c.bench_function("read_ewkb LineString", |b| {
+        b.iter(|| LineString::read_ewkb(&mut buf.as_slice()).unwrap())
+    });
+    c.bench_function("read_ewkb LineString from Cursor", |b| {
+        b.iter(|| LineString::read_ewkb(&mut Cursor::new(&buf)).unwrap())
+    });
+    c.bench_function("read_ewkb_from_slice LineString", |b| {
+        b.iter(|| LineString::read_ewkb_from_slice(&buf).unwrap())
+    });
+    let multi = large_multi_polygon();
+    let mut buf = Vec::new();
+    multi.as_ewkb().write_ewkb(&mut buf).unwrap();
+    c.bench_function("read_ewkb MultiPolygon", |b| {
+        b.iter(|| MultiPolygon::read_ewkb(&mut buf.as_slice()).unwrap())
+    });
+    c.bench_function("read_ewkb_from_slice MultiPolygon", |b| {
+        b.iter(|| MultiPolygon::read_ewkb_from_slice(&buf).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_write, bench_hex, bench_read);
+criterion_main!(benches);
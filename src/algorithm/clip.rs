@@ -0,0 +1,442 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use super::linear_ref::lerp;
+use crate::{
+    coord::Coord,
+    envelope::Envelope,
+    ewkb::{self, EwkbRead, NewFromOptVals},
+    types as postgis,
+};
+
+/// Clip geometries to a bounding box, e.g. decoded features to tile bounds.
+///
+/// Lines are clipped with the Cohen-Sutherland algorithm and may be split
+/// into several parts. Polygon rings are clipped with the Sutherland-Hodgman
+/// algorithm, which keeps concave polygons in one piece at the cost of
+/// degenerate edges along the box boundary. Z and M values of new vertices
+/// are interpolated. Returns `None` if nothing is left inside the box.
+///
+/// ```rust
+/// use postgis::{algorithm::ClipToBbox, envelope::Envelope, ewkb};
+///
+/// let bbox = Envelope::new(0.0, 0.0, 10.0, 10.0);
+/// let line = ewkb::LineString::from(vec![(-5.0, 5.0), (5.0, 5.0), (5.0, 15.0)]);
+/// let clipped = line.clip_to_bbox(&bbox).unwrap();
+/// assert_eq!(clipped.lines[0], ewkb::LineString::from(vec![(0.0, 5.0), (5.0, 5.0), (5.0, 10.0)]));
+///
+/// let square = Envelope::new(5.0, 5.0, 15.0, 15.0).to_polygon(None);
+/// let clipped = square.clip_to_bbox(&bbox).unwrap();
+/// assert_eq!(clipped.rings[0].points.len(), 5);
+/// ```
+pub trait ClipToBbox {
+    /// Geometry type of the clipped geometry.
+    type Output;
+
+    fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self::Output>;
+}
+
+const INSIDE: u8 = 0;
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const BOTTOM: u8 = 4;
+const TOP: u8 = 8;
+
+fn outcode(c: &Coord, bbox: &Envelope) -> u8 {
+    let mut code = INSIDE;
+    if c.x < bbox.xmin {
+        code |= LEFT;
+    } else if c.x > bbox.xmax {
+        code |= RIGHT;
+    }
+    if c.y < bbox.ymin {
+        code |= BOTTOM;
+    } else if c.y > bbox.ymax {
+        code |= TOP;
+    }
+    code
+}
+
+// Intersection of the segment with a vertical or horizontal line
+fn at_x(a: &Coord, b: &Coord, x: f64) -> Coord {
+    Coord {
+        x,
+        ..lerp(a, b, (x - a.x) / (b.x - a.x))
+    }
+}
+
+fn at_y(a: &Coord, b: &Coord, y: f64) -> Coord {
+    Coord {
+        y,
+        ..lerp(a, b, (y - a.y) / (b.y - a.y))
+    }
+}
+
+/// Cohen-Sutherland clipping of a single segment.
+fn clip_segment(mut a: Coord, mut b: Coord, bbox: &Envelope) -> Option<(Coord, Coord)> {
+    let mut code_a = outcode(&a, bbox);
+    let mut code_b = outcode(&b, bbox);
+    loop {
+        if code_a | code_b == INSIDE {
+            return Some((a, b));
+        }
+        if code_a & code_b != INSIDE {
+            return None;
+        }
+        let code = if code_a != INSIDE { code_a } else { code_b };
+        let p = if code & BOTTOM != 0 {
+            at_y(&a, &b, bbox.ymin)
+        } else if code & TOP != 0 {
+            at_y(&a, &b, bbox.ymax)
+        } else if code & RIGHT != 0 {
+            at_x(&a, &b, bbox.xmax)
+        } else {
+            at_x(&a, &b, bbox.xmin)
+        };
+        if code == code_a {
+            a = p;
+            code_a = outcode(&a, bbox);
+        } else {
+            b = p;
+            code_b = outcode(&b, bbox);
+        }
+    }
+}
+
+/// Clip a polyline, returning the parts inside the box.
+pub(crate) fn clip_line(coords: &[Coord], bbox: &Envelope) -> Vec<Vec<Coord>> {
+    let mut parts = Vec::new();
+    let mut current: Vec<Coord> = Vec::new();
+    for w in coords.windows(2) {
+        match clip_segment(w[0], w[1], bbox) {
+            Some((a, b)) => {
+                if current.last() != Some(&a) {
+                    if current.len() > 1 {
+                        parts.push(current);
+                    }
+                    current = vec![a];
+                }
+                current.push(b);
+                if b != w[1] {
+                    parts.push(current);
+                    current = Vec::new();
+                }
+            }
+            None => {
+                if current.len() > 1 {
+                    parts.push(current);
+                }
+                current = Vec::new();
+            }
+        }
+    }
+    if current.len() > 1 {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Sutherland-Hodgman clipping of a closed ring. The result is not closed.
+pub(crate) fn clip_ring(coords: &[Coord], bbox: &Envelope) -> Vec<Coord> {
+    let mut ring: Vec<Coord> = coords.to_vec();
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    // (vertical edge, minimum edge, edge coordinate)
+    let edges = [
+        (true, true, bbox.xmin),
+        (true, false, bbox.xmax),
+        (false, true, bbox.ymin),
+        (false, false, bbox.ymax),
+    ];
+    for &(vertical, minimum, v) in edges.iter() {
+        if ring.is_empty() {
+            break;
+        }
+        let inside = |c: &Coord| {
+            let u = if vertical { c.x } else { c.y };
+            if minimum {
+                u >= v
+            } else {
+                u <= v
+            }
+        };
+        let intersect = |a: &Coord, b: &Coord| {
+            if vertical {
+                at_x(a, b, v)
+            } else {
+                at_y(a, b, v)
+            }
+        };
+        let input = std::mem::take(&mut ring);
+        let mut prev = &input[input.len() - 1];
+        for p in input.iter() {
+            if inside(p) {
+                if !inside(prev) {
+                    ring.push(intersect(prev, p));
+                }
+                ring.push(*p);
+            } else if inside(prev) {
+                ring.push(intersect(prev, p));
+            }
+            prev = p;
+        }
+    }
+    ring
+}
+
+fn coords<P: postgis::Point>(points: &[P]) -> Vec<Coord> {
+    points.iter().map(Coord::from_point).collect()
+}
+
+fn line_from_coords<P>(coords: &[Coord], srid: Option<i32>) -> ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    ewkb::LineStringT {
+        points: coords
+            .iter()
+            .map(|c| P::new_from_opt_vals(c.x, c.y, c.z, c.m, srid))
+            .collect(),
+        srid,
+    }
+}
+
+macro_rules! impl_clip_for_point {
+    ($ptype:ident) => {
+        impl ClipToBbox for ewkb::$ptype {
+            type Output = Self;
+
+            fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self> {
+                if bbox.contains_point(self) {
+                    Some(*self)
+                } else {
+                    None
+                }
+            }
+        }
+    };
+}
+
+impl_clip_for_point!(Point);
+impl_clip_for_point!(PointZ);
+impl_clip_for_point!(PointM);
+impl_clip_for_point!(PointZM);
+
+impl<P> ClipToBbox for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    type Output = ewkb::MultiLineStringT<P>;
+
+    fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self::Output> {
+        let mut multi = ewkb::MultiLineStringT::new_with_srid(self.srid);
+        for part in clip_line(&coords(&self.points), bbox) {
+            multi.lines.push(line_from_coords(&part, self.srid));
+        }
+        if multi.lines.is_empty() {
+            None
+        } else {
+            Some(multi)
+        }
+    }
+}
+
+impl<P> ClipToBbox for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    type Output = Self;
+
+    fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self> {
+        let points: Vec<P> = self
+            .points
+            .iter()
+            .filter(|p| bbox.contains_point(*p))
+            .cloned()
+            .collect();
+        if points.is_empty() {
+            None
+        } else {
+            Some(ewkb::MultiPointT {
+                points,
+                srid: self.srid,
+            })
+        }
+    }
+}
+
+impl<P> ClipToBbox for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    type Output = Self;
+
+    fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self> {
+        let mut polygon = ewkb::PolygonT::new_with_srid(self.srid);
+        for (i, ring) in self.rings.iter().enumerate() {
+            let mut clipped = clip_ring(&coords(&ring.points), bbox);
+            if clipped.len() < 3 {
+                if i == 0 {
+                    // exterior ring vanished
+                    return None;
+                }
+                continue;
+            }
+            clipped.push(clipped[0]);
+            polygon.rings.push(line_from_coords(&clipped, ring.srid));
+        }
+        if polygon.rings.is_empty() {
+            None
+        } else {
+            Some(polygon)
+        }
+    }
+}
+
+impl<P> ClipToBbox for ewkb::MultiLineStringT<P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    type Output = Self;
+
+    fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self> {
+        let mut multi = ewkb::MultiLineStringT::new_with_srid(self.srid);
+        for line in self.lines.iter() {
+            if let Some(clipped) = line.clip_to_bbox(bbox) {
+                multi.lines.extend(clipped.lines);
+            }
+        }
+        if multi.lines.is_empty() {
+            None
+        } else {
+            Some(multi)
+        }
+    }
+}
+
+macro_rules! impl_clip_for_multi {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> ClipToBbox for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + NewFromOptVals + ClipToBbox<Output = P> + Clone,
+        {
+            type Output = Self;
+
+            fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self> {
+                let $itemname: Vec<_> = self
+                    .$itemname
+                    .iter()
+                    .filter_map(|item| item.clip_to_bbox(bbox))
+                    .collect();
+                if $itemname.is_empty() {
+                    None
+                } else {
+                    Some(ewkb::$geotype {
+                        $itemname,
+                        srid: self.srid,
+                    })
+                }
+            }
+        }
+    };
+}
+
+impl_clip_for_multi!(MultiPolygonT named polygons);
+impl_clip_for_multi!(GeometryCollectionT named geometries);
+
+impl<P> ClipToBbox for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals + ClipToBbox<Output = P> + Clone,
+{
+    type Output = Self;
+
+    fn clip_to_bbox(&self, bbox: &Envelope) -> Option<Self> {
+        use crate::ewkb::GeometryT as G;
+        Some(match *self {
+            G::Point(ref geom) => G::Point(geom.clip_to_bbox(bbox)?),
+            G::LineString(ref geom) => {
+                let mut parts = geom.clip_to_bbox(bbox)?;
+                if parts.lines.len() == 1 {
+                    G::LineString(parts.lines.remove(0))
+                } else {
+                    G::MultiLineString(parts)
+                }
+            }
+            G::Polygon(ref geom) => G::Polygon(geom.clip_to_bbox(bbox)?),
+            G::MultiPoint(ref geom) => G::MultiPoint(geom.clip_to_bbox(bbox)?),
+            G::MultiLineString(ref geom) => G::MultiLineString(geom.clip_to_bbox(bbox)?),
+            G::MultiPolygon(ref geom) => G::MultiPolygon(geom.clip_to_bbox(bbox)?),
+            G::GeometryCollection(ref geom) => G::GeometryCollection(geom.clip_to_bbox(bbox)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_clip_line() {
+        let bbox = Envelope::new(0.0, 0.0, 10.0, 10.0);
+        // leaves and reenters the box, Z interpolated
+        let line = LineStringZ::from(vec![
+            (5.0, 5.0, 0.0),
+            (15.0, 5.0, 10.0),
+            (15.0, 8.0, 10.0),
+            (5.0, 8.0, 20.0),
+        ]);
+        let clipped = line.clip_to_bbox(&bbox).unwrap();
+        assert_eq!(
+            clipped.lines,
+            vec![
+                LineStringZ::from(vec![(5.0, 5.0, 0.0), (10.0, 5.0, 5.0)]),
+                LineStringZ::from(vec![(10.0, 8.0, 15.0), (5.0, 8.0, 20.0)]),
+            ]
+        );
+        let outside = LineString::from(vec![(20.0, 0.0), (20.0, 10.0)]);
+        assert_eq!(outside.clip_to_bbox(&bbox), None);
+
+        let geom = Geometry::LineString(LineString::from(vec![(-1.0, 1.0), (1.0, 1.0)]));
+        assert_eq!(
+            geom.clip_to_bbox(&bbox),
+            Some(Geometry::LineString(LineString::from(vec![
+                (0.0, 1.0),
+                (1.0, 1.0)
+            ])))
+        );
+    }
+
+    #[test]
+    fn test_clip_polygon() {
+        let bbox = Envelope::new(0.0, 0.0, 10.0, 10.0);
+        let polygon = Polygon::from(vec![
+            vec![
+                (-5.0, -5.0),
+                (5.0, -5.0),
+                (5.0, 5.0),
+                (-5.0, 5.0),
+                (-5.0, -5.0),
+            ],
+            // hole outside the box
+            vec![(-4.0, -4.0), (-3.0, -4.0), (-3.0, -3.0), (-4.0, -4.0)],
+        ]);
+        let multi = MultiPolygon::from(vec![
+            polygon,
+            Envelope::new(20.0, 20.0, 30.0, 30.0).to_polygon(None),
+        ]);
+        let clipped = multi.clip_to_bbox(&bbox).unwrap();
+        assert_eq!(clipped.polygons.len(), 1);
+        assert_eq!(clipped.polygons[0].rings.len(), 1);
+        let ring = &clipped.polygons[0].rings[0].points;
+        assert_eq!(ring.first(), ring.last());
+        let mut corners: Vec<_> = ring[1..].iter().map(|p| (p.x, p.y)).collect();
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            corners,
+            vec![(0.0, 0.0), (0.0, 5.0), (5.0, 0.0), (5.0, 5.0)]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    ewkb::{self, EwkbRead},
+    types as postgis,
+};
+
+/// Remove repeated vertices, like `ST_RemoveRepeatedPoints`.
+///
+/// A vertex closer than the tolerance in X/Y to the previous kept vertex is
+/// removed. First and last vertex are always kept, so rings stay closed.
+/// Lines and rings which would end up with less than 2 or 4 vertices are
+/// left unchanged. In MultiPoints, a point closer than the tolerance to any
+/// earlier kept point is removed, not only consecutive repeats.
+///
+/// ```rust
+/// use postgis::{algorithm::DedupPoints, ewkb};
+///
+/// // GPS fixes while standing still
+/// let track = ewkb::LineString::from(vec![(0.0, 0.0), (0.5, 0.0), (0.5, 0.00001), (0.50001, 0.0), (1.0, 0.0)]);
+/// let track = track.dedup_points(0.001);
+/// assert_eq!(track, ewkb::LineString::from(vec![(0.0, 0.0), (0.5, 0.0), (1.0, 0.0)]));
+/// ```
+pub trait DedupPoints {
+    fn dedup_points(&self, tolerance: f64) -> Self;
+}
+
+macro_rules! impl_dedup_for_point {
+    ($ptype:ident) => {
+        impl DedupPoints for ewkb::$ptype {
+            fn dedup_points(&self, _tolerance: f64) -> Self {
+                *self
+            }
+        }
+    };
+}
+
+impl_dedup_for_point!(Point);
+impl_dedup_for_point!(PointZ);
+impl_dedup_for_point!(PointM);
+impl_dedup_for_point!(PointZM);
+
+fn dedup<P>(points: &[P], tolerance: f64, min_points: usize) -> Vec<P>
+where
+    P: postgis::Point + Clone,
+{
+    let within = |a: &P, b: &P| (a.x() - b.x()).hypot(a.y() - b.y()) <= tolerance;
+    let mut kept: Vec<P> = Vec::with_capacity(points.len());
+    for (i, point) in points.iter().enumerate() {
+        match kept.last() {
+            Some(prev) if within(prev, point) => {
+                // keep the end point instead of its predecessor
+                if i + 1 == points.len() && kept.len() > 1 {
+                    *kept.last_mut().unwrap() = point.clone();
+                }
+            }
+            _ => kept.push(point.clone()),
+        }
+    }
+    if kept.len() < min_points.min(points.len()) {
+        Vec::from(points)
+    } else {
+        kept
+    }
+}
+
+impl<P> DedupPoints for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn dedup_points(&self, tolerance: f64) -> Self {
+        ewkb::LineStringT {
+            points: dedup(&self.points, tolerance, 2),
+            srid: self.srid,
+        }
+    }
+}
+
+impl<P> DedupPoints for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn dedup_points(&self, tolerance: f64) -> Self {
+        let within = |a: &P, b: &P| (a.x() - b.x()).hypot(a.y() - b.y()) <= tolerance;
+        let mut points: Vec<P> = Vec::with_capacity(self.points.len());
+        for point in &self.points {
+            if !points.iter().any(|kept| within(kept, point)) {
+                points.push(point.clone());
+            }
+        }
+        ewkb::MultiPointT {
+            points,
+            srid: self.srid,
+        }
+    }
+}
+
+impl<P> DedupPoints for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn dedup_points(&self, tolerance: f64) -> Self {
+        ewkb::PolygonT {
+            rings: self
+                .rings
+                .iter()
+                .map(|ring| ewkb::LineStringT {
+                    points: dedup(&ring.points, tolerance, 4),
+                    srid: ring.srid,
+                })
+                .collect(),
+            srid: self.srid,
+        }
+    }
+}
+
+macro_rules! impl_dedup_for_multi {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> DedupPoints for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + Clone + DedupPoints,
+        {
+            fn dedup_points(&self, tolerance: f64) -> Self {
+                ewkb::$geotype {
+                    $itemname: self
+                        .$itemname
+                        .iter()
+                        .map(|item| item.dedup_points(tolerance))
+                        .collect(),
+                    srid: self.srid,
+                }
+            }
+        }
+    };
+}
+
+impl_dedup_for_multi!(MultiLineStringT named lines);
+impl_dedup_for_multi!(MultiPolygonT named polygons);
+impl_dedup_for_multi!(GeometryCollectionT named geometries);
+
+impl<P> DedupPoints for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + Clone + DedupPoints,
+{
+    fn dedup_points(&self, tolerance: f64) -> Self {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => G::Point(geom.dedup_points(tolerance)),
+            G::LineString(ref geom) => G::LineString(geom.dedup_points(tolerance)),
+            G::Polygon(ref geom) => G::Polygon(geom.dedup_points(tolerance)),
+            G::MultiPoint(ref geom) => G::MultiPoint(geom.dedup_points(tolerance)),
+            G::MultiLineString(ref geom) => G::MultiLineString(geom.dedup_points(tolerance)),
+            G::MultiPolygon(ref geom) => G::MultiPolygon(geom.dedup_points(tolerance)),
+            G::GeometryCollection(ref geom) => G::GeometryCollection(geom.dedup_points(tolerance)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_dedup_polygon() {
+        let polygon = Polygon::from(vec![
+            vec![
+                (0.0, 0.0),
+                (4.0, 0.0),
+                (4.0, 0.1),
+                (4.0, 4.0),
+                (0.0, 4.0),
+                (0.05, 0.0),
+                (0.0, 0.0),
+            ],
+            vec![(1.0, 1.0), (1.1, 1.0), (1.1, 1.1), (1.0, 1.0)],
+        ]);
+        let multi = MultiPolygon::from(vec![polygon]);
+        assert_eq!(
+            multi.dedup_points(0.2),
+            MultiPolygon::from(vec![Polygon::from(vec![
+                vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)],
+                // would collapse
+                vec![(1.0, 1.0), (1.1, 1.0), (1.1, 1.1), (1.0, 1.0)],
+            ])])
+        );
+        let line = LineString::from(vec![(0.0, 0.0), (0.0, 0.0)]);
+        assert_eq!(line.dedup_points(0.0), line);
+        let line = LineString::from(vec![(0.0, 0.0), (0.0, 0.0), (1.0, 0.0), (1.0, 0.0)]);
+        assert_eq!(
+            line.dedup_points(0.0),
+            LineString::from(vec![(0.0, 0.0), (1.0, 0.0)])
+        );
+
+        // repeats anywhere in a MultiPoint are removed
+        let multi = MultiPoint::from(vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 0.0),
+            (1.05, 0.0),
+            (2.0, 0.0),
+            (0.1, 0.0),
+        ]);
+        assert_eq!(
+            multi.dedup_points(0.2),
+            MultiPoint::from(vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)])
+        );
+        assert_eq!(multi.dedup_points(0.0).points.len(), 5);
+    }
+}
@@ -0,0 +1,276 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    error::Error,
+    ewkb::{self, EwkbRead, NewFromOptVals, PointType},
+    types::Point,
+};
+use std::cell::Cell;
+
+/// Convert the point type of a geometry, e.g. `LineStringZ` to `LineString`.
+///
+/// The closure receives `(x, y, z, m, srid)` of every point.
+#[doc(hidden)]
+pub trait ConvertPoints<Q> {
+    type Output;
+
+    fn convert_points<F>(&self, f: &F) -> Self::Output
+    where
+        F: Fn(f64, f64, Option<f64>, Option<f64>, Option<i32>) -> Q;
+}
+
+/// Conversion between the Point, PointZ, PointM and PointZM geometry families,
+/// like PostGIS `ST_Force2D`, `ST_Force3D`, `ST_Force3DM` and `ST_Force4D`.
+///
+/// Existing Z and M values are kept, missing ones are set to the given default.
+///
+/// ```rust
+/// use postgis::{algorithm::ForceDimension, ewkb};
+///
+/// let line = ewkb::LineStringZ::from(vec![(0.0, 0.0, 10.0), (1.0, 1.0, 11.0)]);
+/// let flat: ewkb::LineString = line.force_2d();
+/// assert_eq!(flat.points[1], ewkb::Point::new(1.0, 1.0, None));
+/// let measured: ewkb::LineStringZM = flat.force_4d(0.0, 5.0);
+/// assert_eq!(measured.points[1], ewkb::PointZM::new(1.0, 1.0, 0.0, 5.0, None));
+/// ```
+pub trait ForceDimension:
+    ConvertPoints<ewkb::Point>
+    + ConvertPoints<ewkb::PointZ>
+    + ConvertPoints<ewkb::PointM>
+    + ConvertPoints<ewkb::PointZM>
+{
+    fn force_2d(&self) -> <Self as ConvertPoints<ewkb::Point>>::Output {
+        self.convert_points(&|x, y, _, _, srid| ewkb::Point::new(x, y, srid))
+    }
+
+    fn force_3d(&self, default_z: f64) -> <Self as ConvertPoints<ewkb::PointZ>>::Output {
+        self.convert_points(&|x, y, z: Option<f64>, _, srid| {
+            ewkb::PointZ::new(x, y, z.unwrap_or(default_z), srid)
+        })
+    }
+
+    fn force_measured(&self, default_m: f64) -> <Self as ConvertPoints<ewkb::PointM>>::Output {
+        self.convert_points(&|x, y, _, m: Option<f64>, srid| {
+            ewkb::PointM::new(x, y, m.unwrap_or(default_m), srid)
+        })
+    }
+
+    fn force_4d(
+        &self,
+        default_z: f64,
+        default_m: f64,
+    ) -> <Self as ConvertPoints<ewkb::PointZM>>::Output {
+        self.convert_points(&|x, y, z: Option<f64>, m: Option<f64>, srid| {
+            ewkb::PointZM::new(x, y, z.unwrap_or(default_z), m.unwrap_or(default_m), srid)
+        })
+    }
+}
+
+impl<T> ForceDimension for T where
+    T: ConvertPoints<ewkb::Point>
+        + ConvertPoints<ewkb::PointZ>
+        + ConvertPoints<ewkb::PointM>
+        + ConvertPoints<ewkb::PointZM>
+{
+}
+
+/// Policy for Z or M values required by the target point type but missing in the source.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Missing {
+    /// Fail with an error.
+    Error,
+    /// Use the given value.
+    Fill(f64),
+}
+
+/// Cast the points of a geometry to any of the four ewkb point types.
+///
+/// Dimensions the target type doesn't have are dropped.
+///
+/// ```rust
+/// use postgis::{algorithm::{CastPoints, Missing}, ewkb};
+///
+/// let line = ewkb::LineStringZ::from(vec![(0.0, 0.0, 10.0), (1.0, 1.0, 11.0)]);
+/// let zm = line.cast_points::<ewkb::PointZM>(Missing::Error, Missing::Fill(f64::NAN)).unwrap();
+/// assert_eq!(zm.points[1].z, 11.0);
+/// assert!(line.cast_points::<ewkb::PointM>(Missing::Error, Missing::Error).is_err());
+/// ```
+pub trait CastPoints {
+    fn cast_points<Q>(
+        &self,
+        missing_z: Missing,
+        missing_m: Missing,
+    ) -> Result<<Self as ConvertPoints<Q>>::Output, Error>
+    where
+        Self: ConvertPoints<Q>,
+        Q: NewFromOptVals + EwkbRead,
+    {
+        let point_type = Q::point_type();
+        let needs_z = point_type == PointType::PointZ || point_type == PointType::PointZM;
+        let needs_m = point_type == PointType::PointM || point_type == PointType::PointZM;
+        let failed = Cell::new(None);
+        let fill = |value: Option<f64>, needed: bool, missing: Missing, dim: &'static str| match (
+            value, missing,
+        ) {
+            (Some(v), _) => Some(v),
+            (None, Missing::Fill(v)) => Some(v),
+            (None, Missing::Error) => {
+                if needed {
+                    failed.set(Some(dim));
+                }
+                Some(0.0)
+            }
+        };
+        let output = self.convert_points(&|x, y, z, m, srid| {
+            let z = fill(z, needs_z, missing_z, "Z");
+            let m = fill(m, needs_m, missing_m, "M");
+            Q::new_from_opt_vals(x, y, z, m, srid)
+        });
+        match failed.get() {
+            Some(dim) => Err(Error::Other(format!("missing {} value", dim))),
+            None => Ok(output),
+        }
+    }
+}
+
+impl<T: ?Sized> CastPoints for T {}
+
+macro_rules! impl_convert_points_for_point {
+    ($ptype:ident) => {
+        impl<Q> ConvertPoints<Q> for ewkb::$ptype {
+            type Output = Q;
+
+            fn convert_points<F>(&self, f: &F) -> Q
+            where
+                F: Fn(f64, f64, Option<f64>, Option<f64>, Option<i32>) -> Q,
+            {
+                f(self.x, self.y, self.opt_z(), self.opt_m(), self.srid)
+            }
+        }
+    };
+}
+
+impl_convert_points_for_point!(Point);
+impl_convert_points_for_point!(PointZ);
+impl_convert_points_for_point!(PointM);
+impl_convert_points_for_point!(PointZM);
+
+macro_rules! impl_convert_points_for_container {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P, Q> ConvertPoints<Q> for ewkb::$geotype<P>
+        where
+            P: Point + EwkbRead + ConvertPoints<Q, Output = Q>,
+            Q: Point + EwkbRead,
+        {
+            type Output = ewkb::$geotype<Q>;
+
+            fn convert_points<F>(&self, f: &F) -> Self::Output
+            where
+                F: Fn(f64, f64, Option<f64>, Option<f64>, Option<i32>) -> Q,
+            {
+                ewkb::$geotype {
+                    $itemname: self
+                        .$itemname
+                        .iter()
+                        .map(|item| item.convert_points(f))
+                        .collect(),
+                    srid: self.srid,
+                }
+            }
+        }
+    };
+}
+
+impl_convert_points_for_container!(LineStringT named points);
+impl_convert_points_for_container!(PolygonT named rings);
+impl_convert_points_for_container!(MultiPointT named points);
+impl_convert_points_for_container!(MultiLineStringT named lines);
+impl_convert_points_for_container!(MultiPolygonT named polygons);
+impl_convert_points_for_container!(GeometryCollectionT named geometries);
+
+impl<P, Q> ConvertPoints<Q> for ewkb::GeometryT<P>
+where
+    P: Point + EwkbRead + ConvertPoints<Q, Output = Q>,
+    Q: Point + EwkbRead,
+{
+    type Output = ewkb::GeometryT<Q>;
+
+    fn convert_points<F>(&self, f: &F) -> Self::Output
+    where
+        F: Fn(f64, f64, Option<f64>, Option<f64>, Option<i32>) -> Q,
+    {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => G::Point(geom.convert_points(f)),
+            G::LineString(ref geom) => G::LineString(geom.convert_points(f)),
+            G::Polygon(ref geom) => G::Polygon(geom.convert_points(f)),
+            G::MultiPoint(ref geom) => G::MultiPoint(geom.convert_points(f)),
+            G::MultiLineString(ref geom) => G::MultiLineString(geom.convert_points(f)),
+            G::MultiPolygon(ref geom) => G::MultiPolygon(geom.convert_points(f)),
+            G::GeometryCollection(ref geom) => G::GeometryCollection(geom.convert_points(f)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CastPoints, ForceDimension, Missing};
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_force_points() {
+        let p = PointZM::new(1.0, 2.0, 3.0, 4.0, Some(4326));
+        assert_eq!(p.force_2d(), Point::new(1.0, 2.0, Some(4326)));
+        assert_eq!(p.force_3d(0.0), PointZ::new(1.0, 2.0, 3.0, Some(4326)));
+        assert_eq!(
+            p.force_measured(0.0),
+            PointM::new(1.0, 2.0, 4.0, Some(4326))
+        );
+        let p = PointM::new(1.0, 2.0, 4.0, None);
+        assert_eq!(p.force_3d(9.0), PointZ::new(1.0, 2.0, 9.0, None));
+        assert_eq!(p.force_4d(9.0, 0.0), PointZM::new(1.0, 2.0, 9.0, 4.0, None));
+    }
+
+    #[test]
+    fn test_cast_points() {
+        let multi = MultiPointM::from(vec![
+            PointM::new(1.0, 2.0, 3.0, None),
+            PointM::new(4.0, 5.0, 6.0, None),
+        ]);
+        let cast = multi
+            .cast_points::<PointZM>(Missing::Fill(0.0), Missing::Error)
+            .unwrap();
+        assert_eq!(cast.points[1], PointZM::new(4.0, 5.0, 0.0, 6.0, None));
+        let cast = multi
+            .cast_points::<Point>(Missing::Error, Missing::Error)
+            .unwrap();
+        assert_eq!(cast.points[0], Point::new(1.0, 2.0, None));
+        assert!(multi
+            .cast_points::<PointZ>(Missing::Error, Missing::Error)
+            .is_err());
+    }
+
+    #[test]
+    fn test_force_geometry() {
+        let poly = Polygon::from(vec![vec![(0., 0.), (1., 0.), (0., 1.), (0., 0.)]]);
+        let poly: PolygonM = poly.force_measured(1.0);
+        let geom = GeometryT::GeometryCollection(GeometryCollectionT {
+            geometries: vec![GeometryT::Polygon(poly)],
+            srid: Some(3857),
+        });
+        match geom.force_3d(5.0) {
+            GeometryT::GeometryCollection(coll) => {
+                assert_eq!(coll.srid, Some(3857));
+                match coll.geometries[0] {
+                    GeometryT::Polygon(ref poly) => {
+                        assert_eq!(poly.rings[0].points[2], PointZ::new(0., 1., 5.0, None))
+                    }
+                    _ => panic!("expected Polygon"),
+                }
+            }
+            _ => panic!("expected GeometryCollection"),
+        }
+    }
+}
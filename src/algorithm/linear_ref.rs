@@ -0,0 +1,378 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    coord::Coord,
+    error::Error,
+    ewkb::{self, EwkbRead, NewFromOptVals},
+    twkb, types as postgis,
+};
+
+/// Linear referencing along LineStrings, like `ST_LineInterpolatePoint`,
+/// `ST_LineSubstring`, `ST_LineLocatePoint` and `ST_LocateAlong`.
+///
+/// Fractions are relative to the 2D length of the line. Z and M values are
+/// interpolated linearly between vertices.
+///
+/// ```rust
+/// use postgis::{algorithm::LinearReferencing, ewkb};
+///
+/// let line: ewkb::LineStringM = vec![
+///     ewkb::PointM::new(0.0, 0.0, 0.0, None),
+///     ewkb::PointM::new(10.0, 0.0, 100.0, None),
+/// ].into_iter().collect();
+/// let p = line.line_interpolate_point(0.25).unwrap();
+/// assert_eq!((p.x, p.y, p.m), (2.5, 0.0, 25.0));
+///
+/// let position = ewkb::Point::new(6.0, 1.0, None);
+/// assert_eq!(line.line_locate_point(&position).unwrap(), 0.6);
+/// assert_eq!(line.line_locate_point_m(&position).unwrap(), 60.0);
+/// assert_eq!(line.interpolate_m(75.0).unwrap().x, 7.5);
+/// ```
+pub trait LinearReferencing: Sized {
+    type Point;
+
+    /// Point at `fraction` (0.0 to 1.0) of the line length.
+    fn line_interpolate_point(&self, fraction: f64) -> Result<Self::Point, Error> {
+        let c = interpolate_point(&self.line_coords(), fraction)?;
+        Ok(self.new_point(c))
+    }
+
+    /// Part of the line between the two fractions of its length.
+    fn line_substring(&self, start_fraction: f64, end_fraction: f64) -> Result<Self, Error> {
+        let coords = substring(&self.line_coords(), start_fraction, end_fraction)?;
+        Ok(self.new_line(coords))
+    }
+
+    /// Fraction of the line length at the point of the line closest to `point`.
+    fn line_locate_point<T: postgis::Point>(&self, point: &T) -> Result<f64, Error> {
+        Ok(locate_point(&self.line_coords(), point)?.0)
+    }
+
+    /// M value at the point of the line closest to `point`.
+    /// Fails for lines without M values.
+    fn line_locate_point_m<T: postgis::Point>(&self, point: &T) -> Result<f64, Error> {
+        locate_point(&self.line_coords(), point)?
+            .1
+            .m
+            .ok_or_else(|| Error::Other("LineString has no M values".into()))
+    }
+
+    /// First point of the line with M value `m`. Fails if the line has no M
+    /// values or `m` is not within their range.
+    fn interpolate_m(&self, m: f64) -> Result<Self::Point, Error> {
+        let c = interpolate_m(&self.line_coords(), m)?;
+        Ok(self.new_point(c))
+    }
+
+    #[doc(hidden)]
+    fn line_coords(&self) -> Vec<Coord>;
+
+    #[doc(hidden)]
+    fn new_point(&self, c: Coord) -> Self::Point;
+
+    #[doc(hidden)]
+    fn new_line(&self, coords: Vec<Coord>) -> Self;
+}
+
+impl<P> LinearReferencing for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    type Point = P;
+
+    fn line_coords(&self) -> Vec<Coord> {
+        self.points.iter().map(Coord::from_point).collect()
+    }
+
+    fn new_point(&self, c: Coord) -> P {
+        P::new_from_opt_vals(c.x, c.y, c.z, c.m, self.srid)
+    }
+
+    fn new_line(&self, coords: Vec<Coord>) -> Self {
+        ewkb::LineStringT {
+            points: coords.into_iter().map(|c| self.new_point(c)).collect(),
+            srid: self.srid,
+        }
+    }
+}
+
+impl LinearReferencing for twkb::LineString {
+    type Point = twkb::Point;
+
+    fn line_coords(&self) -> Vec<Coord> {
+        self.points.iter().map(Coord::from_point).collect()
+    }
+
+    fn new_point(&self, c: Coord) -> twkb::Point {
+        twkb_point(c)
+    }
+
+    fn new_line(&self, coords: Vec<Coord>) -> Self {
+        twkb::LineString {
+            points: coords.into_iter().map(twkb_point).collect(),
+        }
+    }
+}
+
+fn twkb_point(c: Coord) -> twkb::Point {
+    twkb::Point {
+        x: c.x,
+        y: c.y,
+        z: c.z,
+        m: c.m,
+    }
+}
+
+fn check_fraction(fraction: f64) -> Result<(), Error> {
+    if (0.0..=1.0).contains(&fraction) {
+        Ok(())
+    } else {
+        Err(Error::Other(format!(
+            "fraction {} is not between 0 and 1",
+            fraction
+        )))
+    }
+}
+
+fn lerp_opt(a: Option<f64>, b: Option<f64>, t: f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + (b - a) * t),
+        _ => None,
+    }
+}
+
+pub(super) fn lerp(a: &Coord, b: &Coord, t: f64) -> Coord {
+    Coord {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        z: lerp_opt(a.z, b.z, t),
+        m: lerp_opt(a.m, b.m, t),
+    }
+}
+
+pub(super) fn segment_length(a: &Coord, b: &Coord) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+// Cumulated 2D length at each vertex
+fn cumulated_lengths(coords: &[Coord]) -> Result<Vec<f64>, Error> {
+    if coords.is_empty() {
+        return Err(Error::Other("empty LineString".into()));
+    }
+    let mut lengths = Vec::with_capacity(coords.len());
+    lengths.push(0.0);
+    for w in coords.windows(2) {
+        let last = lengths[lengths.len() - 1];
+        lengths.push(last + segment_length(&w[0], &w[1]));
+    }
+    Ok(lengths)
+}
+
+// Point at `distance` along the line, with the index of the following vertex
+fn point_at(coords: &[Coord], lengths: &[f64], distance: f64) -> (Coord, usize) {
+    for i in 1..coords.len() {
+        if distance <= lengths[i] {
+            let len = lengths[i] - lengths[i - 1];
+            let t = if len > 0.0 {
+                (distance - lengths[i - 1]) / len
+            } else {
+                0.0
+            };
+            return (lerp(&coords[i - 1], &coords[i], t), i);
+        }
+    }
+    (coords[coords.len() - 1], coords.len())
+}
+
+fn interpolate_point(coords: &[Coord], fraction: f64) -> Result<Coord, Error> {
+    check_fraction(fraction)?;
+    let lengths = cumulated_lengths(coords)?;
+    let total = lengths[lengths.len() - 1];
+    Ok(point_at(coords, &lengths, fraction * total).0)
+}
+
+fn substring(
+    coords: &[Coord],
+    start_fraction: f64,
+    end_fraction: f64,
+) -> Result<Vec<Coord>, Error> {
+    check_fraction(start_fraction)?;
+    check_fraction(end_fraction)?;
+    if start_fraction > end_fraction {
+        return Err(Error::Other(
+            "start fraction must be smaller than end fraction".into(),
+        ));
+    }
+    let lengths = cumulated_lengths(coords)?;
+    let total = lengths[lengths.len() - 1];
+    let (start, first) = point_at(coords, &lengths, start_fraction * total);
+    let end_distance = end_fraction * total;
+    let (end, _) = point_at(coords, &lengths, end_distance);
+    let mut result = vec![start];
+    for i in first..coords.len() {
+        if lengths[i] >= end_distance {
+            break;
+        }
+        result.push(coords[i]);
+    }
+    result.push(end);
+    Ok(result)
+}
+
+// Fraction and closest point of the line
+fn locate_point<T: postgis::Point>(coords: &[Coord], point: &T) -> Result<(f64, Coord), Error> {
+    let lengths = cumulated_lengths(coords)?;
+    let total = lengths[lengths.len() - 1];
+    let (x, y) = (point.x(), point.y());
+    let mut best = (f64::INFINITY, 0.0, coords[0]);
+    for (i, w) in coords.windows(2).enumerate() {
+        let (dx, dy) = (w[1].x - w[0].x, w[1].y - w[0].y);
+        let len2 = dx * dx + dy * dy;
+        let t = if len2 > 0.0 {
+            (((x - w[0].x) * dx + (y - w[0].y) * dy) / len2).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let c = lerp(&w[0], &w[1], t);
+        let dist = (c.x - x).hypot(c.y - y);
+        if dist < best.0 {
+            best = (dist, lengths[i] + t * (lengths[i + 1] - lengths[i]), c);
+        }
+    }
+    let fraction = if total > 0.0 { best.1 / total } else { 0.0 };
+    Ok((fraction, best.2))
+}
+
+fn interpolate_m(coords: &[Coord], m: f64) -> Result<Coord, Error> {
+    if coords.iter().any(|c| c.m.is_none()) {
+        return Err(Error::Other("LineString has no M values".into()));
+    }
+    if let [c] = coords {
+        if c.m == Some(m) {
+            return Ok(*c);
+        }
+    }
+    for w in coords.windows(2) {
+        let (m0, m1) = (w[0].m.unwrap_or(f64::NAN), w[1].m.unwrap_or(f64::NAN));
+        if m >= m0.min(m1) && m <= m0.max(m1) {
+            let t = if m1 != m0 { (m - m0) / (m1 - m0) } else { 0.0 };
+            return Ok(lerp(&w[0], &w[1], t));
+        }
+    }
+    Err(Error::Other(format!(
+        "M value {} not found on LineString",
+        m
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_line_interpolate_point() {
+        let line = LineStringZ::from(vec![(0.0, 0.0, 0.0), (3.0, 4.0, 10.0), (3.0, 9.0, 20.0)]);
+        assert_eq!(
+            line.line_interpolate_point(0.0).unwrap(),
+            PointZ::new(0.0, 0.0, 0.0, None)
+        );
+        assert_eq!(
+            line.line_interpolate_point(0.75).unwrap(),
+            PointZ::new(3.0, 6.5, 15.0, None)
+        );
+        assert_eq!(
+            line.line_interpolate_point(1.0).unwrap(),
+            PointZ::new(3.0, 9.0, 20.0, None)
+        );
+        assert!(line.line_interpolate_point(1.5).is_err());
+        assert!(LineString::new().line_interpolate_point(0.5).is_err());
+
+        let line = twkb::LineString {
+            points: vec![
+                twkb::Point {
+                    x: 0.0,
+                    y: 0.0,
+                    z: None,
+                    m: Some(1.0),
+                },
+                twkb::Point {
+                    x: 0.0,
+                    y: 2.0,
+                    z: None,
+                    m: Some(3.0),
+                },
+            ],
+        };
+        let p = line.line_interpolate_point(0.5).unwrap();
+        assert_eq!((p.x, p.y, p.z, p.m), (0.0, 1.0, None, Some(2.0)));
+    }
+
+    #[test]
+    fn test_line_substring() {
+        let mut line: LineStringM = vec![
+            PointM::new(0.0, 0.0, 0.0, None),
+            PointM::new(4.0, 0.0, 4.0, None),
+            PointM::new(4.0, 4.0, 8.0, None),
+            PointM::new(0.0, 4.0, 12.0, None),
+        ]
+        .into_iter()
+        .collect();
+        line.srid = Some(4326);
+        let sub = line.line_substring(0.25, 0.5).unwrap();
+        assert_eq!(sub.srid, Some(4326));
+        let coords: Vec<_> = sub.points.iter().map(|p| (p.x, p.y, p.m)).collect();
+        assert_eq!(
+            coords,
+            vec![(3.0, 0.0, 3.0), (4.0, 0.0, 4.0), (4.0, 2.0, 6.0)]
+        );
+        assert_eq!(line.line_substring(0.0, 1.0).unwrap().points.len(), 4);
+        assert!(line.line_substring(0.6, 0.5).is_err());
+    }
+
+    #[test]
+    fn test_line_locate_point() {
+        let line: LineStringM = vec![
+            PointM::new(0.0, 0.0, 100.0, None),
+            PointM::new(4.0, 0.0, 104.0, None),
+            PointM::new(4.0, 4.0, 112.0, None),
+        ]
+        .into_iter()
+        .collect();
+        let fraction = |x, y| line.line_locate_point(&Point::new(x, y, None)).unwrap();
+        assert_eq!(fraction(2.0, -1.0), 0.25);
+        assert_eq!(fraction(5.0, 2.0), 0.75);
+        assert_eq!(fraction(-3.0, -3.0), 0.0);
+        assert_eq!(fraction(9.0, 9.0), 1.0);
+        let p = Point::new(5.0, 2.0, None);
+        assert_eq!(line.line_locate_point_m(&p).unwrap(), 108.0);
+        assert!(LineString::from(vec![(0.0, 0.0), (1.0, 0.0)])
+            .line_locate_point_m(&p)
+            .is_err());
+    }
+
+    #[test]
+    fn test_interpolate_m() {
+        let line: LineStringM = vec![
+            PointM::new(0.0, 0.0, 100.0, None),
+            PointM::new(4.0, 0.0, 104.0, None),
+            PointM::new(4.0, 4.0, 112.0, None),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            line.interpolate_m(108.0).unwrap(),
+            PointM::new(4.0, 2.0, 108.0, None)
+        );
+        assert_eq!(
+            line.interpolate_m(100.0).unwrap(),
+            PointM::new(0.0, 0.0, 100.0, None)
+        );
+        assert!(line.interpolate_m(99.0).is_err());
+        assert!(LineString::from(vec![(0.0, 0.0), (1.0, 0.0)])
+            .interpolate_m(0.5)
+            .is_err());
+    }
+}
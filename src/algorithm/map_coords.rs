@@ -0,0 +1,176 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    ewkb::{self, EwkbRead},
+    types::Point,
+};
+
+/// Apply a function to every vertex of a geometry, returning a new geometry.
+///
+/// The closure receives `(x, y, z, m)` and returns the transformed values.
+/// SRIDs are kept unchanged. Returned Z/M values are ignored for point types
+/// without that dimension, and `None` keeps the original value of a point type
+/// which has it.
+///
+/// ```rust
+/// use postgis::{algorithm::MapCoords, ewkb};
+///
+/// let p = ewkb::Point::new(10.0, 20.0, Some(4326));
+/// let shifted = p.map_coords(|x, y, z, m| (x + 1.0, y - 1.0, z, m));
+/// assert_eq!(shifted, ewkb::Point::new(11.0, 19.0, Some(4326)));
+/// ```
+pub trait MapCoords: Sized {
+    fn map_coords<F>(&self, f: F) -> Self
+    where
+        F: Fn(f64, f64, Option<f64>, Option<f64>) -> (f64, f64, Option<f64>, Option<f64>),
+    {
+        self.map_coords_with(&f)
+    }
+
+    #[doc(hidden)]
+    fn map_coords_with<F>(&self, f: &F) -> Self
+    where
+        F: Fn(f64, f64, Option<f64>, Option<f64>) -> (f64, f64, Option<f64>, Option<f64>);
+}
+
+macro_rules! impl_map_coords_for_point {
+    ($ptype:ident) => {
+        impl MapCoords for ewkb::$ptype {
+            fn map_coords_with<F>(&self, f: &F) -> Self
+            where
+                F: Fn(f64, f64, Option<f64>, Option<f64>) -> (f64, f64, Option<f64>, Option<f64>),
+            {
+                let (x, y, z, m) = f(self.x, self.y, self.opt_z(), self.opt_m());
+                ewkb::$ptype::new_from_opt_vals(
+                    x,
+                    y,
+                    z.or_else(|| self.opt_z()),
+                    m.or_else(|| self.opt_m()),
+                    self.srid,
+                )
+            }
+        }
+    };
+}
+
+impl_map_coords_for_point!(Point);
+impl_map_coords_for_point!(PointZ);
+impl_map_coords_for_point!(PointM);
+impl_map_coords_for_point!(PointZM);
+
+macro_rules! impl_map_coords_for_container {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> MapCoords for ewkb::$geotype<P>
+        where
+            P: Point + EwkbRead + MapCoords,
+        {
+            fn map_coords_with<F>(&self, f: &F) -> Self
+            where
+                F: Fn(f64, f64, Option<f64>, Option<f64>) -> (f64, f64, Option<f64>, Option<f64>),
+            {
+                ewkb::$geotype {
+                    $itemname: self
+                        .$itemname
+                        .iter()
+                        .map(|item| item.map_coords_with(f))
+                        .collect(),
+                    srid: self.srid,
+                }
+            }
+        }
+    };
+}
+
+impl_map_coords_for_container!(LineStringT named points);
+impl_map_coords_for_container!(PolygonT named rings);
+impl_map_coords_for_container!(MultiPointT named points);
+impl_map_coords_for_container!(MultiLineStringT named lines);
+impl_map_coords_for_container!(MultiPolygonT named polygons);
+impl_map_coords_for_container!(GeometryCollectionT named geometries);
+
+impl<P> MapCoords for ewkb::GeometryT<P>
+where
+    P: Point + EwkbRead + MapCoords,
+{
+    fn map_coords_with<F>(&self, f: &F) -> Self
+    where
+        F: Fn(f64, f64, Option<f64>, Option<f64>) -> (f64, f64, Option<f64>, Option<f64>),
+    {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => G::Point(geom.map_coords_with(f)),
+            G::LineString(ref geom) => G::LineString(geom.map_coords_with(f)),
+            G::Polygon(ref geom) => G::Polygon(geom.map_coords_with(f)),
+            G::MultiPoint(ref geom) => G::MultiPoint(geom.map_coords_with(f)),
+            G::MultiLineString(ref geom) => G::MultiLineString(geom.map_coords_with(f)),
+            G::MultiPolygon(ref geom) => G::MultiPolygon(geom.map_coords_with(f)),
+            G::GeometryCollection(ref geom) => G::GeometryCollection(geom.map_coords_with(f)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MapCoords;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_map_point_types() {
+        let p = PointZ::new(1.0, 2.0, 3.0, Some(4326));
+        let p2 = p.map_coords(|x, y, z, m| (x * 2.0, y * 2.0, z.map(|z| z + 1.0), m));
+        assert_eq!(p2, PointZ::new(2.0, 4.0, 4.0, Some(4326)));
+
+        // missing Z keeps the original value
+        let p3 = p.map_coords(|x, y, _, m| (x, y, None, m));
+        assert_eq!(p3, p);
+
+        let p = PointM::new(1.0, 2.0, 7.0, None);
+        let p2 = p.map_coords(|x, y, z, m| (y, x, z, m.map(|m| m * 10.0)));
+        assert_eq!(p2, PointM::new(2.0, 1.0, 70.0, None));
+    }
+
+    #[test]
+    fn test_map_containers() {
+        let p = |x, y| Point::new(x, y, None);
+        let line = LineStringT::<Point> {
+            srid: Some(4326),
+            points: vec![p(0., 0.), p(1., 1.)],
+        };
+        let poly = PolygonT::<Point> {
+            srid: Some(4326),
+            rings: vec![line.clone()],
+        };
+        let shifted = poly.map_coords(|x, y, z, m| (x + 10.0, y, z, m));
+        assert_eq!(shifted.srid, Some(4326));
+        assert_eq!(shifted.rings[0].points, vec![p(10., 0.), p(11., 1.)]);
+
+        let mut collection = GeometryCollectionT::<Point>::new();
+        collection.geometries.push(GeometryT::LineString(line));
+        collection
+            .geometries
+            .push(GeometryT::GeometryCollection(GeometryCollectionT {
+                geometries: vec![GeometryT::Point(p(5., 5.))],
+                srid: None,
+            }));
+        let geom =
+            GeometryT::GeometryCollection(collection).map_coords(|x, y, z, m| (-x, -y, z, m));
+        match geom {
+            GeometryT::GeometryCollection(gc) => {
+                match gc.geometries[0] {
+                    GeometryT::LineString(ref l) => assert_eq!(l.points[1], p(-1., -1.)),
+                    _ => panic!("expected LineString"),
+                }
+                match gc.geometries[1] {
+                    GeometryT::GeometryCollection(ref inner) => match inner.geometries[0] {
+                        GeometryT::Point(ref pt) => assert_eq!(*pt, p(-5., -5.)),
+                        _ => panic!("expected Point"),
+                    },
+                    _ => panic!("expected GeometryCollection"),
+                }
+            }
+            _ => panic!("expected GeometryCollection"),
+        }
+    }
+}
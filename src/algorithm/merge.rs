@@ -0,0 +1,201 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    ewkb::{self, EwkbRead, GeometryT, Srid},
+    types as postgis,
+};
+use std::collections::HashMap;
+
+/// Stitch lines whose end points touch, like `ST_LineMerge`.
+///
+/// Lines are only joined at nodes where exactly two line ends meet, lines
+/// are reversed where needed. End points are compared exactly in X/Y.
+///
+/// ```rust
+/// use postgis::{algorithm::MergeLines, ewkb};
+///
+/// let lines = ewkb::MultiLineString::from(vec![
+///     ewkb::LineString::from(vec![(1.0, 0.0), (2.0, 0.0)]),
+///     ewkb::LineString::from(vec![(1.0, 0.0), (0.0, 0.0)]),
+///     ewkb::LineString::from(vec![(5.0, 5.0), (6.0, 6.0)]),
+/// ]);
+/// let merged = lines.merge_contiguous();
+/// assert_eq!(merged.lines[0], ewkb::LineString::from(vec![(2.0, 0.0), (1.0, 0.0), (0.0, 0.0)]));
+/// assert_eq!(merged.lines.len(), 2);
+/// ```
+pub trait MergeLines {
+    fn merge_contiguous(&self) -> Self;
+}
+
+// Node of a line end point, with -0.0 normalized to 0.0
+fn node<P: postgis::Point>(p: &P) -> (u64, u64) {
+    ((p.x() + 0.0).to_bits(), (p.y() + 0.0).to_bits())
+}
+
+impl<P> MergeLines for ewkb::MultiLineStringT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn merge_contiguous(&self) -> Self {
+        // line ends at each node: (line index, is start point)
+        let mut nodes: HashMap<(u64, u64), Vec<(usize, bool)>> = HashMap::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if let (Some(first), Some(last)) = (line.points.first(), line.points.last()) {
+                nodes.entry(node(first)).or_default().push((i, true));
+                nodes.entry(node(last)).or_default().push((i, false));
+            }
+        }
+        let degree = |p: &P| nodes.get(&node(p)).map_or(0, Vec::len);
+
+        let mut used: Vec<bool> = self.lines.iter().map(|l| l.points.is_empty()).collect();
+        let mut merged = ewkb::MultiLineStringT::new_with_srid(self.srid);
+        // chains starting at a line end not joinable, then remaining cycles
+        for pass in 0..2 {
+            for (i, line) in self.lines.iter().enumerate() {
+                if used[i] {
+                    continue;
+                }
+                let (first, last) = (&line.points[0], &line.points[line.points.len() - 1]);
+                let mut chain = line.clone();
+                if degree(first) == 2 && degree(last) != 2 {
+                    chain.points.reverse();
+                } else if pass == 0 && degree(first) == 2 {
+                    continue;
+                }
+                used[i] = true;
+                while let Some(end) = chain.points.last() {
+                    let end = node(end);
+                    let ends = &nodes[&end];
+                    if ends.len() != 2 {
+                        break;
+                    }
+                    let next = match ends.iter().find(|&&(j, _)| !used[j]) {
+                        Some(&next) => next,
+                        None => break,
+                    };
+                    used[next.0] = true;
+                    let points = &self.lines[next.0].points;
+                    if next.1 {
+                        chain.points.extend(points.iter().skip(1).cloned());
+                    } else {
+                        chain.points.extend(points.iter().rev().skip(1).cloned());
+                    }
+                }
+                merged.lines.push(chain);
+            }
+        }
+        merged
+    }
+}
+
+/// Combine geometries into a multi geometry, like `ST_Collect`.
+///
+/// Points, LineStrings and Polygons are collected into a MultiPoint,
+/// MultiLineString or MultiPolygon, mixed types or multi geometries into a
+/// GeometryCollection. The result has the SRID of the first geometry.
+///
+/// ```rust
+/// use postgis::{algorithm::collect, ewkb};
+///
+/// let points = vec![
+///     ewkb::Geometry::Point(ewkb::Point::new(1.0, 2.0, Some(4326))),
+///     ewkb::Geometry::Point(ewkb::Point::new(3.0, 4.0, Some(4326))),
+/// ];
+/// match collect(points) {
+///     ewkb::Geometry::MultiPoint(multi) => assert_eq!(multi.srid, Some(4326)),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn collect<P>(geometries: Vec<GeometryT<P>>) -> GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    use crate::ewkb::GeometryT as G;
+    let srid = geometries.first().and_then(Srid::srid);
+    let kind = |g: &G<P>| std::mem::discriminant(g);
+    let same_kind = geometries.windows(2).all(|w| kind(&w[0]) == kind(&w[1]));
+    match geometries.first() {
+        Some(G::Point(_)) if same_kind => {
+            let mut multi = ewkb::MultiPointT::new_with_srid(srid);
+            multi.points = geometries
+                .into_iter()
+                .filter_map(|g| g.into_point().ok())
+                .collect();
+            G::MultiPoint(multi)
+        }
+        Some(G::LineString(_)) if same_kind => {
+            let mut multi = ewkb::MultiLineStringT::new_with_srid(srid);
+            multi.lines = geometries
+                .into_iter()
+                .filter_map(|g| g.into_line_string().ok())
+                .collect();
+            G::MultiLineString(multi)
+        }
+        Some(G::Polygon(_)) if same_kind => {
+            let mut multi = ewkb::MultiPolygonT::new_with_srid(srid);
+            multi.polygons = geometries
+                .into_iter()
+                .filter_map(|g| g.into_polygon().ok())
+                .collect();
+            G::MultiPolygon(multi)
+        }
+        _ => G::GeometryCollection(ewkb::GeometryCollectionT { geometries, srid }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_merge_contiguous() {
+        let lines = MultiLineString::from(vec![
+            // branch at (1 0): three line ends
+            LineString::from(vec![(0.0, 0.0), (1.0, 0.0)]),
+            LineString::from(vec![(1.0, 0.0), (2.0, 0.0)]),
+            LineString::from(vec![(1.0, 1.0), (1.0, 0.0)]),
+            // ring of two lines
+            LineString::from(vec![(5.0, 5.0), (6.0, 5.0), (6.0, 6.0)]),
+            LineString::from(vec![(5.0, 5.0), (6.0, 6.0)]),
+            // chain in reverse order
+            LineString::from(vec![(9.0, 0.0), (10.0, 0.0)]),
+            LineString::from(vec![(8.0, 0.0), (9.0, 0.0)]),
+        ]);
+        let merged = lines.merge_contiguous();
+        assert_eq!(
+            merged.lines,
+            vec![
+                LineString::from(vec![(0.0, 0.0), (1.0, 0.0)]),
+                LineString::from(vec![(1.0, 0.0), (2.0, 0.0)]),
+                LineString::from(vec![(1.0, 1.0), (1.0, 0.0)]),
+                LineString::from(vec![(10.0, 0.0), (9.0, 0.0), (8.0, 0.0)]),
+                LineString::from(vec![(5.0, 5.0), (6.0, 5.0), (6.0, 6.0), (5.0, 5.0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        let line = LineString::from(vec![(0.0, 0.0), (1.0, 0.0)]);
+        let lines = collect(vec![
+            Geometry::LineString(line.clone()),
+            Geometry::LineString(line.clone()),
+        ]);
+        assert_eq!(
+            lines,
+            Geometry::MultiLineString(MultiLineString::from(vec![line.clone(), line.clone()]))
+        );
+        let mixed = collect(vec![
+            Geometry::LineString(line.clone()),
+            Geometry::Point(Point::new(0.0, 0.0, None)),
+        ]);
+        assert!(matches!(mixed, Geometry::GeometryCollection(ref c) if c.geometries.len() == 2));
+        assert!(matches!(
+            collect::<Point>(vec![]),
+            Geometry::GeometryCollection(ref c) if c.geometries.is_empty()
+        ));
+    }
+}
@@ -0,0 +1,39 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Algorithms operating on the [`ewkb`](crate::ewkb) geometry types.
+
+mod clip;
+mod dedup;
+mod force;
+mod linear_ref;
+mod map_coords;
+mod merge;
+#[cfg(feature = "proj")]
+mod reproject;
+mod repair;
+mod reverse;
+mod simplify;
+mod snap;
+mod split;
+mod stats;
+mod transform;
+
+#[cfg(feature = "mvt")]
+pub(crate) use self::clip::{clip_line, clip_ring};
+pub use self::clip::ClipToBbox;
+pub use self::dedup::DedupPoints;
+pub use self::force::{CastPoints, ConvertPoints, ForceDimension, Missing};
+pub use self::linear_ref::LinearReferencing;
+pub use self::map_coords::MapCoords;
+pub use self::merge::{collect, MergeLines};
+#[cfg(feature = "proj")]
+pub use self::reproject::Reproject;
+pub use self::repair::{Repair, RepairReport};
+pub use self::reverse::{Reverse, SegmentIter, Segments};
+pub use self::simplify::SimplifyVw;
+pub use self::snap::SnapToGrid;
+pub use self::split::SplitLine;
+pub use self::stats::GeometryStats;
+pub use self::transform::{Chain, Transform, TransformCoords};
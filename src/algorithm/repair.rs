@@ -0,0 +1,254 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    ewkb::{self, EwkbRead},
+    types as postgis,
+};
+
+/// Fixes applied by [`Repair::repair`].
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct RepairReport {
+    /// Consecutive vertices removed because they repeated the previous vertex.
+    pub removed_vertices: usize,
+    /// Rings closed by appending their first vertex.
+    pub closed_rings: usize,
+    /// Rings with less than 4 vertices or zero area removed.
+    pub removed_rings: usize,
+    /// Empty members of multi geometries and collections removed.
+    pub removed_members: usize,
+}
+
+impl RepairReport {
+    /// No fixes were applied.
+    pub fn is_clean(&self) -> bool {
+        *self == RepairReport::default()
+    }
+}
+
+/// Fix common defects before sending geometries to `ST_MakeValid`.
+///
+/// Repeated consecutive vertices are dropped and unclosed polygon rings are
+/// closed. Collapsed rings (less than 4 vertices or zero area) are removed;
+/// a collapsed exterior ring removes the whole polygon. Empty members of
+/// multi geometries and collections are discarded.
+///
+/// ```rust
+/// use postgis::{algorithm::Repair, ewkb};
+///
+/// let mut polygon = ewkb::Polygon::from(vec![
+///     vec![(0.0, 0.0), (4.0, 0.0), (4.0, 0.0), (4.0, 4.0)],
+///     vec![(1.0, 1.0), (2.0, 2.0), (3.0, 3.0), (1.0, 1.0)],
+/// ]);
+/// let report = polygon.repair();
+/// assert_eq!((report.removed_vertices, report.closed_rings, report.removed_rings), (1, 1, 1));
+/// assert_eq!(polygon, ewkb::Polygon::from(vec![vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 0.0)]]));
+/// ```
+pub trait Repair {
+    /// Repair in place and return the applied fixes.
+    fn repair(&mut self) -> RepairReport {
+        let mut report = RepairReport::default();
+        self.repair_with(&mut report);
+        report
+    }
+
+    /// Repaired copy and the applied fixes.
+    fn repaired(&self) -> (Self, RepairReport)
+    where
+        Self: Clone,
+    {
+        let mut geom = self.clone();
+        let report = geom.repair();
+        (geom, report)
+    }
+
+    #[doc(hidden)]
+    fn repair_with(&mut self, report: &mut RepairReport);
+
+    #[doc(hidden)]
+    fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+macro_rules! impl_repair_for_point {
+    ($ptype:ident) => {
+        impl Repair for ewkb::$ptype {
+            fn repair_with(&mut self, _report: &mut RepairReport) {}
+        }
+    };
+}
+
+impl_repair_for_point!(Point);
+impl_repair_for_point!(PointZ);
+impl_repair_for_point!(PointM);
+impl_repair_for_point!(PointZM);
+
+fn dedup_vertices<P: PartialEq>(points: &mut Vec<P>, report: &mut RepairReport) {
+    let len = points.len();
+    points.dedup();
+    report.removed_vertices += len - points.len();
+}
+
+fn ring_area<P: postgis::Point>(points: &[P]) -> f64 {
+    points
+        .windows(2)
+        .map(|w| w[0].x() * w[1].y() - w[1].x() * w[0].y())
+        .sum::<f64>()
+        / 2.0
+}
+
+impl<P> Repair for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + PartialEq,
+{
+    fn repair_with(&mut self, report: &mut RepairReport) {
+        dedup_vertices(&mut self.points, report);
+    }
+    fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+impl<P> Repair for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    // repeated points of a MultiPoint are valid
+    fn repair_with(&mut self, _report: &mut RepairReport) {}
+    fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+impl<P> Repair for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead + PartialEq + Clone,
+{
+    fn repair_with(&mut self, report: &mut RepairReport) {
+        let old_rings = core::mem::take(&mut self.rings);
+        let count = old_rings.len();
+        let mut rings = Vec::with_capacity(count);
+        for (i, mut ring) in old_rings.into_iter().enumerate() {
+            dedup_vertices(&mut ring.points, report);
+            if let (Some(first), Some(last)) = (ring.points.first(), ring.points.last()) {
+                if first != last {
+                    let first = first.clone();
+                    ring.points.push(first);
+                    report.closed_rings += 1;
+                }
+            }
+            if ring.points.len() >= 4 && ring_area(&ring.points) != 0.0 {
+                rings.push(ring);
+            } else {
+                report.removed_rings += 1;
+                if i == 0 {
+                    // collapsed exterior ring: holes are meaningless
+                    report.removed_rings += count - 1;
+                    break;
+                }
+            }
+        }
+        self.rings = rings;
+    }
+    fn is_empty(&self) -> bool {
+        self.rings.is_empty()
+    }
+}
+
+macro_rules! impl_repair_for_multi {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> Repair for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + PartialEq + Clone,
+        {
+            fn repair_with(&mut self, report: &mut RepairReport) {
+                let len = self.$itemname.len();
+                self.$itemname.retain_mut(|item| {
+                    item.repair_with(report);
+                    !item.is_empty()
+                });
+                report.removed_members += len - self.$itemname.len();
+            }
+            fn is_empty(&self) -> bool {
+                self.$itemname.is_empty()
+            }
+        }
+    };
+}
+
+impl_repair_for_multi!(MultiLineStringT named lines);
+impl_repair_for_multi!(MultiPolygonT named polygons);
+impl_repair_for_multi!(GeometryCollectionT named geometries);
+
+impl<P> Repair for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + PartialEq + Clone,
+{
+    fn repair_with(&mut self, report: &mut RepairReport) {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(_) => {}
+            G::LineString(ref mut geom) => geom.repair_with(report),
+            G::Polygon(ref mut geom) => geom.repair_with(report),
+            G::MultiPoint(ref mut geom) => geom.repair_with(report),
+            G::MultiLineString(ref mut geom) => geom.repair_with(report),
+            G::MultiPolygon(ref mut geom) => geom.repair_with(report),
+            G::GeometryCollection(ref mut geom) => geom.repair_with(report),
+        }
+    }
+    fn is_empty(&self) -> bool {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(_) => false,
+            G::LineString(ref geom) => geom.is_empty(),
+            G::Polygon(ref geom) => geom.is_empty(),
+            G::MultiPoint(ref geom) => geom.is_empty(),
+            G::MultiLineString(ref geom) => geom.is_empty(),
+            G::MultiPolygon(ref geom) => geom.is_empty(),
+            G::GeometryCollection(ref geom) => geom.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_repair_multi() {
+        let square = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)];
+        let flat = vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (0.0, 0.0)];
+        let mut multi = MultiPolygon::from(vec![
+            Polygon::from(vec![flat.clone(), square.clone()]),
+            Polygon::from(vec![square.clone()]),
+            Polygon::new(),
+        ]);
+        let report = multi.repair();
+        assert_eq!(
+            report,
+            RepairReport {
+                removed_vertices: 0,
+                closed_rings: 0,
+                removed_rings: 2,
+                removed_members: 2,
+            }
+        );
+        assert_eq!(multi, MultiPolygon::from(vec![Polygon::from(vec![square])]));
+        assert!(multi.repair().is_clean());
+
+        let collection = GeometryCollection {
+            geometries: vec![
+                Geometry::LineString(LineString::from(vec![(0.0, 0.0), (0.0, 0.0), (1.0, 1.0)])),
+                Geometry::MultiLineString(MultiLineString::new()),
+                Geometry::Point(Point::new(1.0, 1.0, None)),
+            ],
+            srid: None,
+        };
+        let (repaired, report) = collection.repaired();
+        assert_eq!(repaired.geometries.len(), 2);
+        assert_eq!((report.removed_vertices, report.removed_members), (1, 1));
+    }
+}
@@ -0,0 +1,127 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{algorithm::MapCoords, error::Error, ewkb::Srid};
+use proj4rs::{adaptors::transform_vertex_2d, Proj};
+use std::cell::RefCell;
+
+/// Transform geometries between coordinate reference systems with
+/// [proj4rs](https://docs.rs/proj4rs), a Rust port of PROJ.4.
+///
+/// [`reproject`](Reproject::reproject) interprets SRIDs as EPSG codes and
+/// knows WGS 84 (4326), ETRS89 (4258), NAD83 (4269), Web Mercator (3857) and
+/// the WGS 84 UTM zones (32601-32660, 32701-32760). Other reference systems
+/// are given as PROJ.4 strings to [`reproject_proj4`](Reproject::reproject_proj4).
+///
+/// Geographic coordinates are in degrees. All coordinates of the result are
+/// tagged with the target SRID. Z and M values are kept unchanged.
+///
+/// ```rust
+/// use postgis::{algorithm::Reproject, ewkb};
+///
+/// let p = ewkb::Point::new_wgs84(8.5, 47.4);
+/// let p = p.reproject(4326, 3857).unwrap();
+/// assert_eq!(p.srid, Some(3857));
+/// assert_eq!((p.x.round(), p.y.round()), (946216.0, 6007610.0));
+/// ```
+pub trait Reproject: Sized {
+    fn reproject(&self, from_srid: i32, to_srid: i32) -> Result<Self, Error> {
+        self.reproject_proj4(&proj4_string(from_srid)?, &proj4_string(to_srid)?, to_srid)
+    }
+
+    /// Transform from and to reference systems given as PROJ.4 strings.
+    fn reproject_proj4(&self, from: &str, to: &str, to_srid: i32) -> Result<Self, Error>;
+}
+
+fn proj4_string(srid: i32) -> Result<String, Error> {
+    let def = match srid {
+        4326 => "+proj=longlat +datum=WGS84 +no_defs".to_string(),
+        4258 => "+proj=longlat +ellps=GRS80 +towgs84=0,0,0,0,0,0,0 +no_defs".to_string(),
+        4269 => "+proj=longlat +datum=NAD83 +no_defs".to_string(),
+        3857 => "+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +no_defs".to_string(),
+        32601..=32660 => format!("+proj=utm +zone={} +datum=WGS84 +units=m +no_defs", srid - 32600),
+        32701..=32760 => format!(
+            "+proj=utm +zone={} +south +datum=WGS84 +units=m +no_defs",
+            srid - 32700
+        ),
+        _ => return Err(Error::Other(format!("unknown SRID {}", srid))),
+    };
+    Ok(def)
+}
+
+fn proj(def: &str) -> Result<Proj, Error> {
+    Proj::from_proj_string(def)
+        .map_err(|e| Error::Other(format!("cannot create projection {:?}: {}", def, e)))
+}
+
+impl<T> Reproject for T
+where
+    T: MapCoords + Srid,
+{
+    fn reproject_proj4(&self, from: &str, to: &str, to_srid: i32) -> Result<Self, Error> {
+        let (src, dst) = (proj(from)?, proj(to)?);
+        let failure = RefCell::new(None);
+        let mut geom = self.map_coords(|x, y, z, m| {
+            let xy = if src.is_latlong() {
+                (x.to_radians(), y.to_radians())
+            } else {
+                (x, y)
+            };
+            match transform_vertex_2d(&src, &dst, xy) {
+                Ok((x2, y2)) if dst.is_latlong() => (x2.to_degrees(), y2.to_degrees(), z, m),
+                Ok((x2, y2)) => (x2, y2, z, m),
+                Err(e) => {
+                    failure.borrow_mut().get_or_insert(e);
+                    (x, y, z, m)
+                }
+            }
+        });
+        if let Some(e) = failure.into_inner() {
+            return Err(Error::Other(format!("reprojection failed: {}", e)));
+        }
+        geom.set_srid(Some(to_srid));
+        Ok(geom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{LineStringZ, Point, PointZ};
+
+    #[test]
+    fn test_reproject() {
+        let p = Point::new(8.5, 47.4, Some(4326));
+        let merc = p.reproject(4326, 3857).unwrap();
+        assert_eq!(merc.srid, Some(3857));
+        let back = merc.reproject(3857, 4326).unwrap();
+        assert!((back.x - 8.5).abs() < 1e-9 && (back.y - 47.4).abs() < 1e-9);
+        assert_eq!(back.srid, Some(4326));
+
+        // Zurich in UTM zone 32N
+        let utm = p.reproject(4326, 32632).unwrap();
+        assert_eq!((utm.x.round(), utm.y.round()), (462272.0, 5249737.0));
+
+        let line = LineStringZ {
+            points: vec![
+                PointZ::new(0.0, 0.0, 5.0, None),
+                PointZ::new(1.0, 1.0, 6.0, None),
+            ],
+            srid: None,
+        };
+        let merc = line.reproject(4326, 3857).unwrap();
+        assert_eq!(merc.srid, Some(3857));
+        assert_eq!(merc.points[0].srid, Some(3857));
+        assert_eq!(
+            (merc.points[0].x, merc.points[0].y, merc.points[0].z),
+            (0.0, 0.0, 5.0)
+        );
+        assert_eq!(merc.points[1].z, 6.0);
+
+        assert!(p.reproject(4326, 1).is_err());
+        assert!(p
+            .reproject_proj4("+proj=nonsense", "+proj=longlat", 4326)
+            .is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{ewkb, ewkb::EwkbRead, types as postgis};
+
+/// Reverse the vertex order of linear geometries.
+///
+/// Polygon rings and all members of multi geometries are reversed individually,
+/// so the order of members is kept.
+pub trait Reverse {
+    fn reverse(&self) -> Self;
+    fn reverse_in_place(&mut self);
+}
+
+impl<P> Reverse for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn reverse(&self) -> Self {
+        ewkb::LineStringT {
+            points: self.points.iter().rev().cloned().collect(),
+            srid: self.srid,
+        }
+    }
+    fn reverse_in_place(&mut self) {
+        self.points.reverse();
+    }
+}
+
+macro_rules! impl_reverse_for_container {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> Reverse for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + Clone,
+        {
+            fn reverse(&self) -> Self {
+                ewkb::$geotype {
+                    $itemname: self.$itemname.iter().map(Reverse::reverse).collect(),
+                    srid: self.srid,
+                }
+            }
+            fn reverse_in_place(&mut self) {
+                for item in self.$itemname.iter_mut() {
+                    item.reverse_in_place();
+                }
+            }
+        }
+    };
+}
+
+impl_reverse_for_container!(PolygonT named rings);
+impl_reverse_for_container!(MultiLineStringT named lines);
+impl_reverse_for_container!(MultiPolygonT named polygons);
+
+/// Iterate over the segments of a LineString as pairs of consecutive points.
+///
+/// ```rust
+/// use postgis::{algorithm::Segments, ewkb};
+///
+/// let line: ewkb::LineString = vec![
+///     ewkb::Point::new(0.0, 0.0, None),
+///     ewkb::Point::new(1.0, 0.0, None),
+///     ewkb::Point::new(1.0, 1.0, None),
+/// ].into_iter().collect();
+/// assert_eq!(line.segments().count(), 2);
+/// ```
+pub trait Segments<'a> {
+    type ItemType: 'a + postgis::Point;
+    type Iter: Iterator<Item = &'a Self::ItemType>;
+    fn segments(&'a self) -> SegmentIter<'a, Self::ItemType, Self::Iter>;
+}
+
+impl<'a, T> Segments<'a> for T
+where
+    T: postgis::LineString<'a>,
+{
+    type ItemType = T::ItemType;
+    type Iter = T::Iter;
+    fn segments(&'a self) -> SegmentIter<'a, Self::ItemType, Self::Iter> {
+        let mut points = self.points();
+        let prev = points.next();
+        SegmentIter { points, prev }
+    }
+}
+
+/// Iterator returned by [`Segments::segments`].
+pub struct SegmentIter<'a, P, I>
+where
+    P: 'a,
+    I: Iterator<Item = &'a P>,
+{
+    points: I,
+    prev: Option<&'a P>,
+}
+
+impl<'a, P, I> Iterator for SegmentIter<'a, P, I>
+where
+    P: 'a,
+    I: Iterator<Item = &'a P>,
+{
+    type Item = (&'a P, &'a P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.prev?;
+        let end = self.points.next()?;
+        self.prev = Some(end);
+        Some((start, end))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.prev.is_none() {
+            return (0, Some(0));
+        }
+        self.points.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reverse, Segments};
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_reverse() {
+        let p = |x, y| Point::new(x, y, None);
+        let line = LineStringT::<Point> {
+            srid: Some(4326),
+            points: vec![p(0., 0.), p(1., 0.), p(2., 1.)],
+        };
+        let rev = line.reverse();
+        assert_eq!(rev.points, vec![p(2., 1.), p(1., 0.), p(0., 0.)]);
+        assert_eq!(rev.srid, Some(4326));
+
+        let mut multi = MultiLineStringT::<Point> {
+            srid: None,
+            lines: vec![line.clone(), rev],
+        };
+        multi.reverse_in_place();
+        assert_eq!(multi.lines[0].points[0], p(2., 1.));
+        assert_eq!(multi.lines[1].points[0], p(0., 0.));
+    }
+
+    #[test]
+    fn test_segments() {
+        let p = |x, y| Point::new(x, y, None);
+        let line = LineStringT::<Point> {
+            srid: None,
+            points: vec![p(0., 0.), p(1., 0.), p(2., 1.)],
+        };
+        let segments: Vec<_> = line.segments().collect();
+        assert_eq!(
+            segments,
+            vec![(&p(0., 0.), &p(1., 0.)), (&p(1., 0.), &p(2., 1.))]
+        );
+
+        let single = LineStringT::<Point> {
+            srid: None,
+            points: vec![p(0., 0.)],
+        };
+        assert_eq!(single.segments().count(), 0);
+        let empty = LineStringT::<Point>::new();
+        assert_eq!(empty.segments().count(), 0);
+    }
+}
@@ -0,0 +1,398 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    ewkb::{self, EwkbRead},
+    types as postgis,
+};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Simplify linear geometries with the Visvalingam–Whyatt algorithm.
+///
+/// Vertices are removed in order of the area of the triangle they form with
+/// their neighbours, as long as it is below `area_threshold`. A vertex is kept
+/// if removing it would make the line or any ring of the polygon intersect
+/// itself or another ring, so valid polygons stay valid. Endpoints of lines and
+/// the start point of rings are never removed, lines keep at least 2 and rings
+/// at least 4 vertices. Z and M values and SRIDs are kept unchanged.
+///
+/// ```rust
+/// use postgis::{algorithm::SimplifyVw, ewkb};
+///
+/// let line = ewkb::LineString::from(vec![(0.0, 0.0), (1.0, 0.1), (2.0, 0.0), (3.0, 2.0)]);
+/// let simplified = line.simplify_vw(0.5);
+/// assert_eq!(simplified.points.len(), 3);
+/// ```
+pub trait SimplifyVw {
+    fn simplify_vw(&self, area_threshold: f64) -> Self;
+}
+
+impl<P> SimplifyVw for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn simplify_vw(&self, area_threshold: f64) -> Self {
+        let kept = simplify_rings(&[coords(&self.points)], 2, area_threshold);
+        ewkb::LineStringT {
+            points: kept[0].iter().map(|&i| self.points[i].clone()).collect(),
+            srid: self.srid,
+        }
+    }
+}
+
+impl<P> SimplifyVw for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn simplify_vw(&self, area_threshold: f64) -> Self {
+        let rings: Vec<_> = self.rings.iter().map(|ring| coords(&ring.points)).collect();
+        let kept = simplify_rings(&rings, 4, area_threshold);
+        ewkb::PolygonT {
+            rings: self
+                .rings
+                .iter()
+                .zip(kept)
+                .map(|(ring, kept)| ewkb::LineStringT {
+                    points: kept.iter().map(|&i| ring.points[i].clone()).collect(),
+                    srid: ring.srid,
+                })
+                .collect(),
+            srid: self.srid,
+        }
+    }
+}
+
+macro_rules! impl_simplify_for_multi {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> SimplifyVw for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + Clone,
+        {
+            fn simplify_vw(&self, area_threshold: f64) -> Self {
+                ewkb::$geotype {
+                    $itemname: self
+                        .$itemname
+                        .iter()
+                        .map(|item| item.simplify_vw(area_threshold))
+                        .collect(),
+                    srid: self.srid,
+                }
+            }
+        }
+    };
+}
+
+impl_simplify_for_multi!(MultiLineStringT named lines);
+impl_simplify_for_multi!(MultiPolygonT named polygons);
+impl_simplify_for_multi!(GeometryCollectionT named geometries);
+
+impl<P> SimplifyVw for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + Clone,
+{
+    fn simplify_vw(&self, area_threshold: f64) -> Self {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => G::Point(geom.clone()),
+            G::LineString(ref geom) => G::LineString(geom.simplify_vw(area_threshold)),
+            G::Polygon(ref geom) => G::Polygon(geom.simplify_vw(area_threshold)),
+            G::MultiPoint(ref geom) => G::MultiPoint(geom.clone()),
+            G::MultiLineString(ref geom) => G::MultiLineString(geom.simplify_vw(area_threshold)),
+            G::MultiPolygon(ref geom) => G::MultiPolygon(geom.simplify_vw(area_threshold)),
+            G::GeometryCollection(ref geom) => {
+                G::GeometryCollection(geom.simplify_vw(area_threshold))
+            }
+        }
+    }
+}
+
+fn coords<P: postgis::Point>(points: &[P]) -> Vec<(f64, f64)> {
+    points.iter().map(|p| (p.x(), p.y())).collect()
+}
+
+struct Candidate {
+    area: f64,
+    ring: usize,
+    index: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // Reversed for a min-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .area
+            .total_cmp(&self.area)
+            .then_with(|| other.index.cmp(&self.index))
+            .then_with(|| other.ring.cmp(&self.ring))
+    }
+}
+
+struct Ring<'a> {
+    coords: &'a [(f64, f64)],
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    removed: Vec<bool>,
+    len: usize,
+}
+
+impl<'a> Ring<'a> {
+    fn segments(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut i = if self.coords.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        std::iter::from_fn(move || {
+            let j = i?;
+            let k = self.next[j];
+            if k >= self.coords.len() {
+                return None;
+            }
+            i = Some(k);
+            Some((j, k))
+        })
+    }
+
+    fn area(&self, i: usize) -> f64 {
+        triangle_area(
+            self.coords[self.prev[i]],
+            self.coords[i],
+            self.coords[self.next[i]],
+        )
+    }
+}
+
+// Indices of the vertices kept for each ring
+fn simplify_rings(
+    rings: &[Vec<(f64, f64)>],
+    min_len: usize,
+    area_threshold: f64,
+) -> Vec<Vec<usize>> {
+    let mut state: Vec<Ring> = rings
+        .iter()
+        .map(|coords| Ring {
+            coords,
+            prev: (0..coords.len()).map(|i| i.wrapping_sub(1)).collect(),
+            next: (1..=coords.len()).collect(),
+            removed: vec![false; coords.len()],
+            len: coords.len(),
+        })
+        .collect();
+    let mut heap = BinaryHeap::new();
+    for (r, ring) in state.iter().enumerate() {
+        for index in 1..ring.coords.len().saturating_sub(1) {
+            let area = ring.area(index);
+            heap.push(Candidate {
+                area,
+                ring: r,
+                index,
+            });
+        }
+    }
+    while let Some(Candidate {
+        area,
+        ring: r,
+        index,
+    }) = heap.pop()
+    {
+        if area >= area_threshold {
+            break;
+        }
+        let ring = &state[r];
+        if ring.removed[index] || ring.area(index) != area || ring.len <= min_len {
+            continue;
+        }
+        let (a, c) = (ring.prev[index], ring.next[index]);
+        if would_intersect(&state, r, a, index, c) {
+            // Retried when a neighbour is removed
+            continue;
+        }
+        let ring = &mut state[r];
+        ring.removed[index] = true;
+        ring.len -= 1;
+        ring.next[a] = c;
+        ring.prev[c] = a;
+        let last = ring.coords.len() - 1;
+        for &i in &[a, c] {
+            if i != 0 && i != last {
+                let area = ring.area(i);
+                heap.push(Candidate {
+                    area,
+                    ring: r,
+                    index: i,
+                });
+            }
+        }
+    }
+    state
+        .iter()
+        .map(|ring| {
+            (0..ring.coords.len())
+                .filter(|&i| !ring.removed[i])
+                .collect()
+        })
+        .collect()
+}
+
+// Whether replacing the segments a-b and b-c of ring r by a-c makes the
+// rings intersect
+fn would_intersect(state: &[Ring], r: usize, a: usize, b: usize, c: usize) -> bool {
+    let ring = &state[r];
+    let (pa, pb, pc) = (ring.coords[a], ring.coords[b], ring.coords[c]);
+    for (s, other) in state.iter().enumerate() {
+        for (j, k) in other.segments() {
+            if s == r && (j == b || k == b) {
+                continue;
+            }
+            let (pj, pk) = (other.coords[j], other.coords[k]);
+            // A vertex inside the removed triangle would end up on the other side
+            if !(s == r && (j == a || j == c))
+                && pj != pa
+                && pj != pc
+                && in_triangle(pj, pa, pb, pc)
+            {
+                return true;
+            }
+            let touches = [pj, pk].iter().any(|p| *p == pa || *p == pc);
+            if !touches && segments_intersect(pa, pc, pj, pk) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+fn triangle_area(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    cross(a, b, c).abs() / 2.0
+}
+
+fn in_triangle(p: (f64, f64), a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> bool {
+    let (d1, d2, d3) = (cross(a, b, p), cross(b, c, p), cross(c, a, p));
+    let has_neg = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_pos = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_neg && has_pos)
+}
+
+fn on_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> bool {
+    p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0) && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1)
+}
+
+fn segments_intersect(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    (d1 == 0.0 && on_segment(a, c, d))
+        || (d2 == 0.0 && on_segment(b, c, d))
+        || (d3 == 0.0 && on_segment(c, a, b))
+        || (d4 == 0.0 && on_segment(d, a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_simplify_vw_line() {
+        let line = LineString::from(vec![
+            (0.0, 0.0),
+            (1.0, 0.1),
+            (2.0, 0.0),
+            (3.0, 2.0),
+            (4.0, 2.0),
+        ]);
+        assert_eq!(line.simplify_vw(0.0).points.len(), 5);
+        let simplified = line.simplify_vw(0.5);
+        let xs: Vec<f64> = simplified.points.iter().map(|p| p.x).collect();
+        assert_eq!(xs, vec![0.0, 2.0, 3.0, 4.0]);
+        assert_eq!(line.simplify_vw(100.0).points.len(), 2);
+    }
+
+    #[test]
+    fn test_simplify_vw_keeps_topology() {
+        // Removing the apex would move the hole outside of the shell
+        let shell = LineString::from(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (5.0, 12.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]);
+        let hole = LineString::from(vec![
+            (4.0, 10.5),
+            (6.0, 10.5),
+            (6.0, 11.0),
+            (4.0, 11.0),
+            (4.0, 10.5),
+        ]);
+        let polygon = Polygon {
+            rings: vec![shell, hole].into_iter().collect(),
+            srid: Some(4326),
+        };
+        let simplified = polygon.simplify_vw(20.0);
+        assert_eq!(simplified.srid, Some(4326));
+        assert_eq!(simplified.rings[0].points.len(), 6);
+        assert_eq!(simplified.rings[1].points.len(), 4);
+
+        let without_hole = Polygon {
+            rings: vec![polygon.rings[0].clone()].into_iter().collect(),
+            srid: None,
+        };
+        assert_eq!(without_hole.simplify_vw(20.0).rings[0].points.len(), 5);
+
+        // Spiral, where shortcuts of outer corners cross inner turns
+        let line = LineString::from(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 2.0),
+            (8.0, 2.0),
+            (8.0, 8.0),
+            (2.0, 8.0),
+            (2.0, 4.0),
+            (6.0, 4.0),
+            (6.0, 6.0),
+        ]);
+        for &threshold in &[5.0, 20.0, 50.0, 1000.0] {
+            let coords: Vec<_> = line
+                .simplify_vw(threshold)
+                .points
+                .iter()
+                .map(|p| (p.x, p.y))
+                .collect();
+            let segments: Vec<_> = coords.windows(2).collect();
+            for i in 0..segments.len() {
+                for j in i + 2..segments.len() {
+                    let (a, b) = (segments[i], segments[j]);
+                    assert!(!segments_intersect(a[0], a[1], b[0], b[1]));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    algorithm::MapCoords,
+    ewkb::{self, EwkbRead, WritePrecision},
+    types as postgis,
+};
+
+/// Snap X and Y coordinates to a regular grid, like `ST_SnapToGrid`.
+///
+/// Consecutive vertices which collapse onto the same grid point are merged.
+/// Polygon rings with less than 4 remaining vertices are dropped, as are
+/// collapsed members (lines with less than 2 vertices, polygons without
+/// exterior ring) of multi geometries and collections. Z and M values are
+/// kept unchanged.
+///
+/// ```rust
+/// use postgis::{algorithm::SnapToGrid, ewkb};
+///
+/// let line: ewkb::LineString = vec![
+///     ewkb::Point::new(0.12, 0.0, None),
+///     ewkb::Point::new(0.09, 0.01, None),
+///     ewkb::Point::new(1.0, 1.0, None),
+/// ].into_iter().collect();
+/// let snapped = line.snap_to_grid(0.5);
+/// assert_eq!(snapped.points.len(), 2);
+/// ```
+pub trait SnapToGrid: Sized {
+    /// Snap coordinates to multiples of `size`. A non-positive size leaves
+    /// coordinates untouched but still removes repeated vertices.
+    fn snap_to_grid(&self, size: f64) -> Self {
+        self.snap_with(&|v: f64| {
+            if size > 0.0 {
+                (v / size).round() * size
+            } else {
+                v
+            }
+        })
+    }
+
+    /// Round coordinates to `decimals` decimal places, see
+    /// [`WritePrecision::apply`].
+    fn reduce_precision(&self, decimals: i32) -> Self {
+        let precision = WritePrecision::Decimals(decimals);
+        self.snap_with(&|v: f64| precision.apply(v))
+    }
+
+    #[doc(hidden)]
+    fn snap_with<F: Fn(f64) -> f64>(&self, f: &F) -> Self;
+
+    #[doc(hidden)]
+    fn is_collapsed(&self) -> bool {
+        false
+    }
+}
+
+macro_rules! impl_snap_for_point {
+    ($ptype:ident) => {
+        impl SnapToGrid for ewkb::$ptype {
+            fn snap_with<F: Fn(f64) -> f64>(&self, f: &F) -> Self {
+                self.map_coords(|x, y, z, m| (f(x), f(y), z, m))
+            }
+        }
+    };
+}
+
+impl_snap_for_point!(Point);
+impl_snap_for_point!(PointZ);
+impl_snap_for_point!(PointM);
+impl_snap_for_point!(PointZM);
+
+fn snap_points<P, F>(points: &[P], f: &F) -> Vec<P>
+where
+    P: SnapToGrid + PartialEq,
+    F: Fn(f64) -> f64,
+{
+    let mut snapped: Vec<P> = points.iter().map(|p| p.snap_with(f)).collect();
+    snapped.dedup();
+    snapped
+}
+
+impl<P> SnapToGrid for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + SnapToGrid + PartialEq,
+{
+    fn snap_with<F: Fn(f64) -> f64>(&self, f: &F) -> Self {
+        ewkb::LineStringT {
+            points: snap_points(&self.points, f),
+            srid: self.srid,
+        }
+    }
+    fn is_collapsed(&self) -> bool {
+        self.points.len() < 2
+    }
+}
+
+impl<P> SnapToGrid for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead + SnapToGrid + PartialEq,
+{
+    fn snap_with<F: Fn(f64) -> f64>(&self, f: &F) -> Self {
+        ewkb::MultiPointT {
+            points: snap_points(&self.points, f),
+            srid: self.srid,
+        }
+    }
+    fn is_collapsed(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+impl<P> SnapToGrid for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead + SnapToGrid + PartialEq,
+{
+    fn snap_with<F: Fn(f64) -> f64>(&self, f: &F) -> Self {
+        let mut rings = Vec::with_capacity(self.rings.len());
+        for (i, ring) in self.rings.iter().enumerate() {
+            let ring = ring.snap_with(f);
+            if ring.points.len() >= 4 {
+                rings.push(ring);
+            } else if i == 0 {
+                // collapsed exterior ring: holes are meaningless
+                break;
+            }
+        }
+        ewkb::PolygonT {
+            rings,
+            srid: self.srid,
+        }
+    }
+    fn is_collapsed(&self) -> bool {
+        self.rings.is_empty()
+    }
+}
+
+macro_rules! impl_snap_for_multi {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> SnapToGrid for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + SnapToGrid + PartialEq,
+        {
+            fn snap_with<F: Fn(f64) -> f64>(&self, f: &F) -> Self {
+                ewkb::$geotype {
+                    $itemname: self
+                        .$itemname
+                        .iter()
+                        .map(|item| item.snap_with(f))
+                        .filter(|item| !item.is_collapsed())
+                        .collect(),
+                    srid: self.srid,
+                }
+            }
+            fn is_collapsed(&self) -> bool {
+                self.$itemname.is_empty()
+            }
+        }
+    };
+}
+
+impl_snap_for_multi!(MultiLineStringT named lines);
+impl_snap_for_multi!(MultiPolygonT named polygons);
+impl_snap_for_multi!(GeometryCollectionT named geometries);
+
+impl<P> SnapToGrid for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + SnapToGrid + PartialEq,
+{
+    fn snap_with<F: Fn(f64) -> f64>(&self, f: &F) -> Self {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => G::Point(geom.snap_with(f)),
+            G::LineString(ref geom) => G::LineString(geom.snap_with(f)),
+            G::Polygon(ref geom) => G::Polygon(geom.snap_with(f)),
+            G::MultiPoint(ref geom) => G::MultiPoint(geom.snap_with(f)),
+            G::MultiLineString(ref geom) => G::MultiLineString(geom.snap_with(f)),
+            G::MultiPolygon(ref geom) => G::MultiPolygon(geom.snap_with(f)),
+            G::GeometryCollection(ref geom) => G::GeometryCollection(geom.snap_with(f)),
+        }
+    }
+    fn is_collapsed(&self) -> bool {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(_) => false,
+            G::LineString(ref geom) => geom.is_collapsed(),
+            G::Polygon(ref geom) => geom.is_collapsed(),
+            G::MultiPoint(ref geom) => geom.is_collapsed(),
+            G::MultiLineString(ref geom) => geom.is_collapsed(),
+            G::MultiPolygon(ref geom) => geom.is_collapsed(),
+            G::GeometryCollection(ref geom) => geom.is_collapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapToGrid;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_snap_line() {
+        let p = |x, y| Point::new(x, y, Some(4326));
+        let line = LineStringT::<Point> {
+            srid: Some(4326),
+            points: vec![p(0.1, 0.2), p(0.4, -0.1), p(1.6, 2.4), p(2.2, 1.9)],
+        };
+        let snapped = line.snap_to_grid(1.0);
+        assert_eq!(snapped.points, vec![p(0., 0.), p(2., 2.)]);
+        assert_eq!(snapped.srid, Some(4326));
+
+        let rounded = line.reduce_precision(0);
+        assert_eq!(rounded, snapped);
+    }
+
+    #[test]
+    fn test_reduce_precision() {
+        let p = PointZ::new(10.123456, -20.987654, 1.23456, None);
+        assert_eq!(
+            p.reduce_precision(2),
+            PointZ::new(10.12, -20.99, 1.23456, None)
+        );
+        assert_eq!(p.reduce_precision(400), p);
+        assert_eq!(p.reduce_precision(-400), p);
+        let far = Point::new(1e300, -1e300, None);
+        assert_eq!(far.reduce_precision(20), far);
+    }
+
+    #[test]
+    fn test_snap_collapses() {
+        let p = |x, y| Point::new(x, y, None);
+        let ring = |pts: Vec<Point>| LineStringT::<Point> {
+            srid: None,
+            points: pts,
+        };
+        let big = ring(vec![p(0., 0.), p(10., 0.), p(10., 10.), p(0., 0.)]);
+        let tiny = ring(vec![p(1., 1.), p(1.1, 1.), p(1.1, 1.1), p(1., 1.)]);
+        let poly = PolygonT::<Point> {
+            srid: None,
+            rings: vec![big.clone(), tiny.clone()],
+        };
+        let snapped = poly.snap_to_grid(1.0);
+        assert_eq!(snapped.rings, vec![big.clone()]);
+
+        let tiny_poly = PolygonT::<Point> {
+            srid: None,
+            rings: vec![tiny.clone()],
+        };
+        let multi = MultiPolygonT::<Point> {
+            srid: None,
+            polygons: vec![tiny_poly, poly],
+        };
+        let snapped = multi.snap_to_grid(1.0);
+        assert_eq!(snapped.polygons.len(), 1);
+
+        let multi = MultiLineStringT::<Point> {
+            srid: None,
+            lines: vec![tiny, big],
+        };
+        assert_eq!(multi.snap_to_grid(5.0).lines.len(), 1);
+    }
+}
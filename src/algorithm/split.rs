@@ -0,0 +1,155 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use super::linear_ref::{lerp, segment_length};
+use crate::{
+    coord::Coord,
+    error::Error,
+    ewkb::{self, EwkbRead, NewFromOptVals},
+    types as postgis,
+};
+
+/// Split long LineStrings into pieces, like `ST_Segmentize` followed by
+/// `ST_LineSubstring` calls.
+///
+/// Consecutive pieces share their end and start point, so the pieces
+/// together cover the whole line.
+///
+/// ```rust
+/// use postgis::{algorithm::SplitLine, ewkb};
+///
+/// let line = ewkb::LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 5.0)]);
+/// let pieces = line.split_by_length(6.0).unwrap();
+/// assert_eq!(pieces.lines.len(), 3);
+/// assert_eq!(pieces.lines[1], ewkb::LineString::from(vec![(6.0, 0.0), (10.0, 0.0), (10.0, 2.0)]));
+///
+/// let pieces = line.chunks(2).unwrap();
+/// assert_eq!(pieces.lines[1], ewkb::LineString::from(vec![(10.0, 0.0), (10.0, 5.0)]));
+/// ```
+pub trait SplitLine {
+    type Pieces;
+
+    /// Pieces with a 2D length of at most `max_len`. Z and M values of new
+    /// vertices are interpolated.
+    fn split_by_length(&self, max_len: f64) -> Result<Self::Pieces, Error>;
+
+    /// Pieces of at most `n_vertices` vertices.
+    fn chunks(&self, n_vertices: usize) -> Result<Self::Pieces, Error>;
+}
+
+impl<P> SplitLine for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals + Clone,
+{
+    type Pieces = ewkb::MultiLineStringT<P>;
+
+    fn split_by_length(&self, max_len: f64) -> Result<Self::Pieces, Error> {
+        if max_len.is_nan() || max_len <= 0.0 {
+            return Err(Error::Other(format!(
+                "maximum length {} is not positive",
+                max_len
+            )));
+        }
+        let coords: Vec<Coord> = self.points.iter().map(Coord::from_point).collect();
+        let mut pieces = ewkb::MultiLineStringT::new_with_srid(self.srid);
+        for piece in split_coords(&coords, max_len) {
+            pieces.lines.push(ewkb::LineStringT {
+                points: piece
+                    .iter()
+                    .map(|c| P::new_from_opt_vals(c.x, c.y, c.z, c.m, self.srid))
+                    .collect(),
+                srid: self.srid,
+            });
+        }
+        Ok(pieces)
+    }
+
+    fn chunks(&self, n_vertices: usize) -> Result<Self::Pieces, Error> {
+        if n_vertices < 2 {
+            return Err(Error::Other(format!(
+                "pieces need at least 2 vertices, got {}",
+                n_vertices
+            )));
+        }
+        let mut pieces = ewkb::MultiLineStringT::new_with_srid(self.srid);
+        let mut start = 0;
+        while start + 1 < self.points.len() {
+            let end = (start + n_vertices).min(self.points.len());
+            pieces.lines.push(ewkb::LineStringT {
+                points: Vec::from(&self.points[start..end]),
+                srid: self.srid,
+            });
+            start = end - 1;
+        }
+        Ok(pieces)
+    }
+}
+
+fn split_coords(coords: &[Coord], max_len: f64) -> Vec<Vec<Coord>> {
+    let mut pieces = Vec::new();
+    let mut piece = match coords.first() {
+        Some(c) => vec![*c],
+        None => return pieces,
+    };
+    let mut len = 0.0;
+    for w in coords.windows(2) {
+        let (mut a, b) = (w[0], w[1]);
+        let mut segment = segment_length(&a, &b);
+        while len + segment > max_len {
+            let cut = lerp(&a, &b, (max_len - len) / segment);
+            piece.push(cut);
+            pieces.push(std::mem::replace(&mut piece, vec![cut]));
+            a = cut;
+            segment = segment_length(&a, &b);
+            len = 0.0;
+        }
+        piece.push(b);
+        len += segment;
+    }
+    if piece.len() > 1 {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{LineString, LineStringZM, PointZM};
+
+    #[test]
+    fn test_split_by_length() {
+        let line = LineStringZM::from(vec![(0.0, 0.0, 0.0, 0.0), (3.0, 0.0, 30.0, 3.0)]);
+        let pieces = line.split_by_length(1.0).unwrap();
+        assert_eq!(pieces.lines.len(), 3);
+        assert_eq!(
+            pieces.lines[1].points[..],
+            [
+                PointZM::new(1.0, 0.0, 10.0, 1.0, None),
+                PointZM::new(2.0, 0.0, 20.0, 2.0, None)
+            ]
+        );
+        assert!(line.split_by_length(0.0).is_err());
+        assert!(LineString::new()
+            .split_by_length(1.0)
+            .unwrap()
+            .lines
+            .is_empty());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let line = LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0)]);
+        let pieces = line.chunks(3).unwrap();
+        assert_eq!(
+            pieces.lines,
+            vec![
+                LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]),
+                LineString::from(vec![(2.0, 0.0), (3.0, 0.0)]),
+            ]
+        );
+        assert_eq!(line.chunks(10).unwrap().lines, vec![line.clone()]);
+        assert!(line.chunks(1).is_err());
+    }
+}
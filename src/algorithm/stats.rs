@@ -0,0 +1,171 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::{
+    ewkb::{self, EwkbRead, PointType},
+    types::Point,
+};
+
+/// Geometry size metrics, like PostGIS `ST_NPoints`, `ST_NRings`,
+/// `ST_NumGeometries` and `ST_CoordDim`.
+///
+/// ```rust
+/// use postgis::{algorithm::GeometryStats, ewkb};
+///
+/// let poly = ewkb::PolygonZ::from(vec![vec![(0., 0., 1.), (1., 0., 1.), (0., 1., 1.), (0., 0., 1.)]]);
+/// assert_eq!(poly.num_points(), 4);
+/// assert_eq!(poly.num_rings(), 1);
+/// assert_eq!(poly.num_geometries(), 1);
+/// assert_eq!(poly.coord_dim(), 3);
+/// ```
+pub trait GeometryStats: EwkbRead {
+    /// Number of vertices.
+    fn num_points(&self) -> usize;
+
+    /// Number of polygon rings, including interior rings.
+    fn num_rings(&self) -> usize {
+        0
+    }
+
+    /// Number of members of multi geometries and collections, 1 otherwise.
+    fn num_geometries(&self) -> usize {
+        1
+    }
+
+    /// Number of coordinate dimensions (2, 3 or 4).
+    fn coord_dim(&self) -> u8 {
+        match Self::point_type() {
+            PointType::Point => 2,
+            PointType::PointZ | PointType::PointM => 3,
+            PointType::PointZM => 4,
+        }
+    }
+}
+
+macro_rules! impl_stats_for_point {
+    ($ptype:ident) => {
+        impl GeometryStats for ewkb::$ptype {
+            fn num_points(&self) -> usize {
+                1
+            }
+        }
+    };
+}
+
+impl_stats_for_point!(Point);
+impl_stats_for_point!(PointZ);
+impl_stats_for_point!(PointM);
+impl_stats_for_point!(PointZM);
+
+macro_rules! impl_stats_for_container {
+    ($geotype:ident named $itemname:ident, single) => {
+        impl_stats_for_container!($geotype named $itemname, |_geom| 1);
+    };
+    ($geotype:ident named $itemname:ident, multi) => {
+        impl_stats_for_container!($geotype named $itemname, |geom| geom.$itemname.len());
+    };
+    ($geotype:ident named $itemname:ident, |$geom:ident| $num_geometries:expr) => {
+        impl<P> GeometryStats for ewkb::$geotype<P>
+        where
+            P: Point + EwkbRead + GeometryStats,
+        {
+            fn num_points(&self) -> usize {
+                self.$itemname.iter().map(|item| item.num_points()).sum()
+            }
+            fn num_rings(&self) -> usize {
+                self.$itemname.iter().map(|item| item.num_rings()).sum()
+            }
+            fn num_geometries(&self) -> usize {
+                let $geom = self;
+                $num_geometries
+            }
+        }
+    };
+}
+
+impl_stats_for_container!(LineStringT named points, single);
+impl_stats_for_container!(MultiPointT named points, multi);
+impl_stats_for_container!(MultiLineStringT named lines, multi);
+impl_stats_for_container!(MultiPolygonT named polygons, multi);
+impl_stats_for_container!(GeometryCollectionT named geometries, multi);
+
+impl<P> GeometryStats for ewkb::PolygonT<P>
+where
+    P: Point + EwkbRead + GeometryStats,
+{
+    fn num_points(&self) -> usize {
+        self.rings.iter().map(|ring| ring.num_points()).sum()
+    }
+    fn num_rings(&self) -> usize {
+        self.rings.len()
+    }
+}
+
+impl<P> GeometryStats for ewkb::GeometryT<P>
+where
+    P: Point + EwkbRead + GeometryStats,
+{
+    fn num_points(&self) -> usize {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => geom.num_points(),
+            G::LineString(ref geom) => geom.num_points(),
+            G::Polygon(ref geom) => geom.num_points(),
+            G::MultiPoint(ref geom) => geom.num_points(),
+            G::MultiLineString(ref geom) => geom.num_points(),
+            G::MultiPolygon(ref geom) => geom.num_points(),
+            G::GeometryCollection(ref geom) => geom.num_points(),
+        }
+    }
+    fn num_rings(&self) -> usize {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Polygon(ref geom) => geom.num_rings(),
+            G::MultiPolygon(ref geom) => geom.num_rings(),
+            G::GeometryCollection(ref geom) => geom.num_rings(),
+            _ => 0,
+        }
+    }
+    fn num_geometries(&self) -> usize {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::MultiPoint(ref geom) => geom.num_geometries(),
+            G::MultiLineString(ref geom) => geom.num_geometries(),
+            G::MultiPolygon(ref geom) => geom.num_geometries(),
+            G::GeometryCollection(ref geom) => geom.num_geometries(),
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeometryStats;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_stats() {
+        let square = vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.), (0., 0.)];
+        let hole = vec![(1., 1.), (2., 1.), (1., 2.), (1., 1.)];
+        let multi = MultiPolygon::from(vec![vec![square.clone(), hole], vec![square]]);
+        assert_eq!(multi.num_points(), 14);
+        assert_eq!(multi.num_rings(), 3);
+        assert_eq!(multi.num_geometries(), 2);
+        assert_eq!(multi.coord_dim(), 2);
+
+        let collection = GeometryCollectionZM {
+            geometries: vec![
+                GeometryT::Point(PointZM::new(0., 0., 0., 0., None)),
+                GeometryT::LineString(LineStringZM::from(vec![(0., 0., 0., 0.), (1., 1., 1., 1.)])),
+            ],
+            srid: None,
+        };
+        let geom = GeometryT::GeometryCollection(collection);
+        assert_eq!(geom.num_points(), 3);
+        assert_eq!(geom.num_rings(), 0);
+        assert_eq!(geom.num_geometries(), 2);
+        assert_eq!(geom.coord_dim(), 4);
+        assert_eq!(PointM::new(0., 0., 0., None).coord_dim(), 3);
+    }
+}
@@ -0,0 +1,95 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+use crate::algorithm::MapCoords;
+
+/// Transformation of x/y coordinates.
+///
+/// Implemented for all functions and closures taking and returning a
+/// coordinate pair, which includes the converters in [`mars`](crate::mars)
+/// and [`tiles`](crate::tiles). Transformations are combined with
+/// [`then`](Transform::then).
+///
+/// ```rust
+/// use postgis::algorithm::{Transform, TransformCoords};
+/// use postgis::{ewkb, mars, tiles};
+///
+/// let gcj02_to_mercator = mars::to_wgs84.then(tiles::lonlat_to_mercator);
+/// let line = ewkb::LineString::from(vec![(116.4039, 39.9152), (116.4106, 39.9162)]);
+/// let line = line.transformed(&gcj02_to_mercator);
+/// assert!(line.points[0].x > 12_957_000.0);
+/// ```
+pub trait Transform {
+    fn transform(&self, x: f64, y: f64) -> (f64, f64);
+
+    /// Apply `next` to the output of this transformation.
+    fn then<T: Transform>(self, next: T) -> Chain<Self, T>
+    where
+        Self: Sized,
+    {
+        Chain(self, next)
+    }
+}
+
+impl<F> Transform for F
+where
+    F: Fn(f64, f64) -> (f64, f64),
+{
+    fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        self(x, y)
+    }
+}
+
+/// Two transformations applied in sequence, see [`Transform::then`].
+#[derive(Clone, Copy, Debug)]
+pub struct Chain<A, B>(A, B);
+
+impl<A: Transform, B: Transform> Transform for Chain<A, B> {
+    fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = self.0.transform(x, y);
+        self.1.transform(x, y)
+    }
+}
+
+/// Apply a [`Transform`] to every vertex of a geometry.
+///
+/// Z, M and SRIDs are kept unchanged.
+pub trait TransformCoords: MapCoords {
+    fn transformed<T: Transform + ?Sized>(&self, transform: &T) -> Self {
+        self.map_coords(|x, y, z, m| {
+            let (x, y) = transform.transform(x, y);
+            (x, y, z, m)
+        })
+    }
+}
+
+impl<G: MapCoords> TransformCoords for G {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ewkb, mars, tiles};
+
+    #[test]
+    fn test_transformed() {
+        let p = ewkb::PointZ::new(116.4039, 39.9152, 44.0, Some(4326));
+        let shift = |x, y| (x + 1.0, y * 2.0);
+        assert_eq!(
+            p.transformed(&shift),
+            ewkb::PointZ::new(117.4039, 79.8304, 44.0, Some(4326))
+        );
+
+        let to_bd09 = mars::from_wgs84.then(mars::gcj02_to_bd09);
+        let q = p.transformed(&to_bd09);
+        assert_eq!((q.x, q.y), mars::wgs84_to_bd09(p.x, p.y));
+
+        let pipeline: &dyn Transform = &to_bd09
+            .then(mars::bd09_to_wgs84)
+            .then(tiles::lonlat_to_mercator);
+        let line = ewkb::LineString::from(vec![(p.x, p.y)]).transformed(pipeline);
+        let (x, y) = tiles::lonlat_to_mercator(p.x, p.y);
+        assert!((line.points[0].x - x).abs() < 0.5);
+        assert!((line.points[0].y - y).abs() < 0.5);
+    }
+}
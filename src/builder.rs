@@ -0,0 +1,235 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Builders for composite [`ewkb`](crate::ewkb) geometries.
+//!
+//! The point type is inferred from the added points. Without an explicit
+//! SRID, the SRID of the first point (or polygon) is used.
+//!
+//! ```rust
+//! use postgis::{builder::PolygonBuilder, ewkb::Point};
+//!
+//! let p = |x, y| Point::new(x, y, Some(4326));
+//! let polygon = PolygonBuilder::new()
+//!     .exterior(vec![p(0., 0.), p(4., 0.), p(4., 4.), p(0., 4.)])
+//!     .interior(vec![p(1., 1.), p(1., 2.), p(2., 2.)])
+//!     .build();
+//! assert_eq!(polygon.srid, Some(4326));
+//! // rings are closed automatically
+//! assert_eq!(polygon.rings[0].points.len(), 5);
+//! ```
+
+use crate::{
+    ewkb::{self, EwkbRead, Srid},
+    types as postgis,
+};
+
+/// Builder for [`LineStringT`](crate::ewkb::LineStringT).
+#[derive(Clone, Debug)]
+pub struct LineStringBuilder<P: postgis::Point + EwkbRead> {
+    points: Vec<P>,
+    srid: Option<i32>,
+}
+
+impl<P> Default for LineStringBuilder<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn default() -> Self {
+        LineStringBuilder {
+            points: Vec::new(),
+            srid: None,
+        }
+    }
+}
+
+impl<P> LineStringBuilder<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn srid(mut self, srid: i32) -> Self {
+        self.srid = Some(srid);
+        self
+    }
+
+    pub fn point(mut self, point: P) -> Self {
+        self.points.push(point);
+        self
+    }
+
+    pub fn points<I: IntoIterator<Item = P>>(mut self, points: I) -> Self {
+        self.points.extend(points);
+        self
+    }
+
+    pub fn build(self) -> ewkb::LineStringT<P> {
+        let srid = self
+            .srid
+            .or_else(|| self.points.first().and_then(Srid::srid));
+        ewkb::LineStringT {
+            points: self.points,
+            srid,
+        }
+    }
+}
+
+/// Builder for [`PolygonT`](crate::ewkb::PolygonT). Rings are closed when built.
+#[derive(Clone, Debug)]
+pub struct PolygonBuilder<P: postgis::Point + EwkbRead> {
+    exterior: Vec<P>,
+    interiors: Vec<Vec<P>>,
+    srid: Option<i32>,
+}
+
+impl<P> Default for PolygonBuilder<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn default() -> Self {
+        PolygonBuilder {
+            exterior: Vec::new(),
+            interiors: Vec::new(),
+            srid: None,
+        }
+    }
+}
+
+impl<P> PolygonBuilder<P>
+where
+    P: postgis::Point + EwkbRead + Srid + PartialEq + Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn srid(mut self, srid: i32) -> Self {
+        self.srid = Some(srid);
+        self
+    }
+
+    pub fn exterior<I: IntoIterator<Item = P>>(mut self, points: I) -> Self {
+        self.exterior = points.into_iter().collect();
+        self
+    }
+
+    pub fn interior<I: IntoIterator<Item = P>>(mut self, points: I) -> Self {
+        self.interiors.push(points.into_iter().collect());
+        self
+    }
+
+    pub fn build(self) -> ewkb::PolygonT<P> {
+        let srid = self
+            .srid
+            .or_else(|| self.exterior.first().and_then(Srid::srid));
+        let mut rings = Vec::with_capacity(self.interiors.len() + 1);
+        if !self.exterior.is_empty() {
+            rings.push(self.exterior);
+        }
+        rings.extend(self.interiors);
+        ewkb::PolygonT {
+            rings: rings
+                .into_iter()
+                .map(|mut points| {
+                    if points.first() != points.last() {
+                        points.push(points[0].clone());
+                    }
+                    ewkb::LineStringT { points, srid }
+                })
+                .collect(),
+            srid,
+        }
+    }
+}
+
+/// Builder for [`MultiPolygonT`](crate::ewkb::MultiPolygonT).
+#[derive(Clone, Debug)]
+pub struct MultiPolygonBuilder<P: postgis::Point + EwkbRead> {
+    polygons: Vec<ewkb::PolygonT<P>>,
+    srid: Option<i32>,
+}
+
+impl<P> Default for MultiPolygonBuilder<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn default() -> Self {
+        MultiPolygonBuilder {
+            polygons: Vec::new(),
+            srid: None,
+        }
+    }
+}
+
+impl<P> MultiPolygonBuilder<P>
+where
+    P: postgis::Point + EwkbRead + Srid + PartialEq + Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn srid(mut self, srid: i32) -> Self {
+        self.srid = Some(srid);
+        self
+    }
+
+    pub fn polygon(mut self, polygon: ewkb::PolygonT<P>) -> Self {
+        self.polygons.push(polygon);
+        self
+    }
+
+    /// Add a polygon configured by `f`.
+    pub fn polygon_with<F>(self, f: F) -> Self
+    where
+        F: FnOnce(PolygonBuilder<P>) -> PolygonBuilder<P>,
+    {
+        self.polygon(f(PolygonBuilder::new()).build())
+    }
+
+    pub fn build(self) -> ewkb::MultiPolygonT<P> {
+        let srid = self
+            .srid
+            .or_else(|| self.polygons.first().and_then(|p| p.srid));
+        ewkb::MultiPolygonT {
+            polygons: self.polygons,
+            srid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{Point, PointZ};
+
+    #[test]
+    fn test_line_string_builder() {
+        let line = LineStringBuilder::new()
+            .point(PointZ::new(0., 0., 1., Some(3857)))
+            .points(vec![PointZ::new(1., 1., 2., Some(3857))])
+            .build();
+        assert_eq!(line.srid, Some(3857));
+        assert_eq!(line.points.len(), 2);
+        let line = LineStringBuilder::<Point>::new().srid(4326).build();
+        assert_eq!(line.srid, Some(4326));
+        assert!(line.points.is_empty());
+    }
+
+    #[test]
+    fn test_multi_polygon_builder() {
+        let p = |x, y| Point::new(x, y, None);
+        let multi = MultiPolygonBuilder::new()
+            .srid(4326)
+            .polygon_with(|b| b.exterior(vec![p(0., 0.), p(1., 0.), p(1., 1.), p(0., 0.)]))
+            .polygon(PolygonBuilder::new().build())
+            .build();
+        assert_eq!(multi.srid, Some(4326));
+        assert_eq!(multi.polygons[0].rings[0].points.len(), 4);
+        assert!(multi.polygons[1].rings.is_empty());
+    }
+}
@@ -0,0 +1,560 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Bulk loading of delimited text files with a geometry column.
+//!
+//! [`BulkReader`] reads one record per line and parses the geometry column,
+//! which may contain WKT, EWKT (`SRID=4326;POINT(1 2)`) or hex encoded EWKB.
+//! Empty geometry fields are NULL. Rows which can't be parsed are returned as
+//! [`RowError`] without stopping the import.
+//!
+//! ```rust
+//! use postgis::{bulk::BulkReader, copy::CopyWriter, ewkb::{self, AsEwkbGeometry}};
+//!
+//! let csv = "id,name,geom
+//! 1,Zurich,SRID=4326;POINT(8.54 47.37)
+//! 2,Bern,0101000020E61000009A99999999D91D40CDCCCCCCCC744740
+//! 3,Basel,POINT(7.59)
+//! 4,\"Lake, Zurich\",\"POLYGON((8.5 47.3,8.6 47.3,8.6 47.4,8.5 47.3))\"
+//! ";
+//! let reader = BulkReader::<_, ewkb::Point>::new(csv.as_bytes(), 2).header(true);
+//! let mut copy = CopyWriter::new(Vec::new()).unwrap();
+//! let errors = reader
+//!     .copy_to(&mut copy, |row, fields| {
+//!         let geom = row.geometry.as_ref().map(|g| g.as_ewkb());
+//!         fields.int4(row.fields[0].parse().unwrap()).text(&row.fields[1]).opt_geometry(geom.as_ref())
+//!     })
+//!     .unwrap();
+//! assert_eq!(errors.len(), 1);
+//! assert_eq!(errors[0].line, 4);
+//! ```
+
+use crate::{
+    copy::{CopyWriter, RowBuilder},
+    error::Error,
+    ewkb::{
+        EwkbRead, GeometryCollectionT, GeometryT, LineStringT, MultiLineStringT, MultiPointT,
+        MultiPolygonT, NewFromOptVals, PointType, PolygonT,
+    },
+    replication::decode_text,
+    types as postgis,
+};
+use std::fmt;
+use std::io::{self, prelude::*, BufReader};
+use std::marker::PhantomData;
+
+/// Parsed record.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Row<P: postgis::Point + EwkbRead> {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// All fields of the record, including the geometry column.
+    pub fields: Vec<String>,
+    /// Geometry, `None` for an empty field.
+    pub geometry: Option<GeometryT<P>>,
+}
+
+/// Record which couldn't be read or parsed.
+#[derive(Debug)]
+pub struct RowError {
+    /// Line number, starting at 1.
+    pub line: usize,
+    pub error: Error,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for RowError {}
+
+/// Reader for delimited text with a WKT, EWKT or hex EWKB geometry column.
+///
+/// Fields containing the delimiter, e.g. WKT with commas, have to be
+/// enclosed in double quotes. Quoted fields can't span multiple lines.
+pub struct BulkReader<R: Read, P: postgis::Point + EwkbRead> {
+    lines: io::Lines<BufReader<R>>,
+    column: usize,
+    delimiter: char,
+    header: bool,
+    line: usize,
+    done: bool,
+    point_type: PhantomData<P>,
+}
+
+impl<R: Read, P> BulkReader<R, P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    /// Reader for comma separated values with the geometry in column
+    /// `column` (starting at 0).
+    pub fn new(reader: R, column: usize) -> Self {
+        BulkReader {
+            lines: BufReader::new(reader).lines(),
+            column,
+            delimiter: ',',
+            header: false,
+            line: 0,
+            done: false,
+            point_type: PhantomData,
+        }
+    }
+
+    /// Field delimiter, e.g. `'\t'`. Default is `','`.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Skip the first line. Default is `false`.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Write the rows into `copy`, with the fields added by `fields`.
+    /// Returns the rows which couldn't be parsed, fails on write errors.
+    pub fn copy_to<W, F>(
+        self,
+        copy: &mut CopyWriter<W>,
+        mut fields: F,
+    ) -> Result<Vec<RowError>, Error>
+    where
+        W: Write,
+        F: for<'a> FnMut(&Row<P>, RowBuilder<'a, W>) -> RowBuilder<'a, W>,
+    {
+        let mut errors = Vec::new();
+        for row in self {
+            match row {
+                Ok(row) => fields(&row, copy.row()).finish()?,
+                Err(e) => errors.push(e),
+            }
+        }
+        Ok(errors)
+    }
+
+    fn parse_row(&self, line: &str) -> Result<Row<P>, Error> {
+        let fields = split_record(line, self.delimiter)?;
+        let value = fields.get(self.column).ok_or_else(|| {
+            Error::Read(format!(
+                "geometry column {} missing, record has {} fields",
+                self.column,
+                fields.len()
+            ))
+        })?;
+        let geometry = match value.trim() {
+            "" => None,
+            // hex EWKB starts with the byte order marker 00 or 01
+            value if value.starts_with("00") || value.starts_with("01") => {
+                Some(decode_text(value)?)
+            }
+            value => Some(parse_ewkt(value)?),
+        };
+        Ok(Row {
+            line: self.line,
+            fields,
+            geometry,
+        })
+    }
+}
+
+impl<R: Read, P> Iterator for BulkReader<R, P>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    type Item = Result<Row<P>, RowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    // I/O errors are not recoverable
+                    self.done = true;
+                    return Some(Err(RowError {
+                        line: self.line + 1,
+                        error: e.into(),
+                    }));
+                }
+                None => return None,
+            };
+            self.line += 1;
+            if self.line == 1 && self.header || line.trim().is_empty() {
+                continue;
+            }
+            return Some(self.parse_row(&line).map_err(|error| RowError {
+                line: self.line,
+                error,
+            }));
+        }
+    }
+}
+
+fn split_record(line: &str, delimiter: char) -> Result<Vec<String>, Error> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(Error::Read("unterminated quoted field".into()));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Parse WKT or EWKT with an optional `SRID=<srid>;` prefix.
+///
+/// Coordinates for dimensions the point type doesn't have are ignored,
+/// missing Z or M values are an error.
+pub fn parse_ewkt<P>(text: &str) -> Result<GeometryT<P>, Error>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals,
+{
+    let mut parser = Parser {
+        text: text.trim(),
+        pos: 0,
+    };
+    let mut srid = None;
+    if parser.text.len() > 5 && parser.text[..5].eq_ignore_ascii_case("SRID=") {
+        let end = parser
+            .text
+            .find(';')
+            .ok_or_else(|| Error::Read("missing ';' after SRID".into()))?;
+        srid = Some(
+            parser.text[5..end]
+                .trim()
+                .parse()
+                .map_err(|_| Error::Read(format!("invalid SRID {:?}", &parser.text[5..end])))?,
+        );
+        parser.pos = end + 1;
+    }
+    let geom = parser.geometry(srid)?;
+    parser.skip_whitespace();
+    if parser.pos < parser.text.len() {
+        return Err(parser.error("end of text"));
+    }
+    Ok(geom)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+// Dimensions given in the WKT tag, e.g. `POINT Z` or `LINESTRINGM`
+#[derive(Clone, Copy)]
+struct Dims {
+    z: bool,
+    m: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, expected: &str) -> Error {
+        Error::Read(format!(
+            "invalid WKT at position {}, expected {}",
+            self.pos, expected
+        ))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.text[self.pos..].chars().next()
+    }
+
+    fn consume(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.consume(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", c)))
+        }
+    }
+
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    // `(` or `EMPTY`, returns false for an empty geometry
+    fn open(&mut self) -> Result<bool, Error> {
+        if self.consume('(') {
+            return Ok(true);
+        }
+        let start = self.pos;
+        if self.word().eq_ignore_ascii_case("EMPTY") {
+            Ok(false)
+        } else {
+            self.pos = start;
+            Err(self.error("'(' or EMPTY"))
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, Error> {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let value = rest[..len].parse().map_err(|_| self.error("number"))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    fn point<P>(&mut self, dims: Dims, srid: Option<i32>) -> Result<P, Error>
+    where
+        P: postgis::Point + EwkbRead + NewFromOptVals,
+    {
+        let mut values = [0.0; 4];
+        let mut n = 0;
+        while n < 4
+            && matches!(self.peek(), Some(c) if c.is_ascii_digit() || matches!(c, '+' | '-' | '.'))
+        {
+            values[n] = self.number()?;
+            n += 1;
+        }
+        let (z, m) = match (n, dims.z, dims.m) {
+            (2, false, false) => (None, None),
+            (3, false, true) => (None, Some(values[2])),
+            (3, _, false) => (Some(values[2]), None),
+            (4, _, _) => (Some(values[2]), Some(values[3])),
+            _ => return Err(self.error("coordinate")),
+        };
+        let point_type = P::point_type();
+        let needs_z = matches!(point_type, PointType::PointZ | PointType::PointZM);
+        let needs_m = matches!(point_type, PointType::PointM | PointType::PointZM);
+        if needs_z && z.is_none() || needs_m && m.is_none() {
+            return Err(Error::Read(format!(
+                "coordinate at position {} has no values for {:?}",
+                self.pos, point_type
+            )));
+        }
+        Ok(P::new_from_opt_vals(values[0], values[1], z, m, srid))
+    }
+
+    // Comma separated list in parentheses, starting after the `(`
+    fn list<T, F>(&mut self, mut item: F) -> Result<Vec<T>, Error>
+    where
+        F: FnMut(&mut Self) -> Result<T, Error>,
+    {
+        let mut items = vec![item(self)?];
+        while self.consume(',') {
+            items.push(item(self)?);
+        }
+        self.expect(')')?;
+        Ok(items)
+    }
+
+    fn line_string<P>(&mut self, dims: Dims, srid: Option<i32>) -> Result<LineStringT<P>, Error>
+    where
+        P: postgis::Point + EwkbRead + NewFromOptVals,
+    {
+        let mut line = LineStringT::new_with_srid(srid);
+        if self.open()? {
+            for point in self.list(|p| p.point(dims, srid))? {
+                line.points.push(point);
+            }
+        }
+        Ok(line)
+    }
+
+    fn polygon<P>(&mut self, dims: Dims, srid: Option<i32>) -> Result<PolygonT<P>, Error>
+    where
+        P: postgis::Point + EwkbRead + NewFromOptVals,
+    {
+        let mut polygon = PolygonT::new_with_srid(srid);
+        if self.open()? {
+            for ring in self.list(|p| p.line_string(dims, srid))? {
+                polygon.rings.push(ring);
+            }
+        }
+        Ok(polygon)
+    }
+
+    fn geometry<P>(&mut self, srid: Option<i32>) -> Result<GeometryT<P>, Error>
+    where
+        P: postgis::Point + EwkbRead + NewFromOptVals,
+    {
+        let start = self.pos;
+        let tag = self.word().to_ascii_uppercase();
+        let (name, mut dims) = if let Some(name) = tag.strip_suffix("ZM") {
+            (name, Dims { z: true, m: true })
+        } else if let Some(name) = tag.strip_suffix('Z') {
+            (name, Dims { z: true, m: false })
+        } else if let Some(name) = tag.strip_suffix('M') {
+            (name, Dims { z: false, m: true })
+        } else {
+            (tag.as_str(), Dims { z: false, m: false })
+        };
+        // dimensions as separate word, e.g. `POINT ZM (1 2 3 4)`
+        let before_dims = self.pos;
+        match self.word().to_ascii_uppercase().as_str() {
+            "Z" => dims.z = true,
+            "M" => dims.m = true,
+            "ZM" => dims = Dims { z: true, m: true },
+            _ => self.pos = before_dims,
+        }
+        // like the EWKB reader, parts of multi geometries have no SRID
+        let geom = match name {
+            "POINT" => {
+                if self.open()? {
+                    let point = self.point(dims, srid)?;
+                    self.expect(')')?;
+                    GeometryT::Point(point)
+                } else {
+                    // PostGIS writes POINT EMPTY as NaN coordinates
+                    let nan = Some(f64::NAN);
+                    GeometryT::Point(P::new_from_opt_vals(f64::NAN, f64::NAN, nan, nan, srid))
+                }
+            }
+            "LINESTRING" => GeometryT::LineString(self.line_string(dims, srid)?),
+            "POLYGON" => GeometryT::Polygon(self.polygon(dims, srid)?),
+            "MULTIPOINT" => {
+                let mut multi = MultiPointT::new_with_srid(srid);
+                if self.open()? {
+                    // points with or without parentheses, e.g. `MULTIPOINT((1 2),(3 4))`
+                    let points = self.list(|p| {
+                        if p.consume('(') {
+                            let point = p.point(dims, None)?;
+                            p.expect(')')?;
+                            Ok(point)
+                        } else {
+                            p.point(dims, None)
+                        }
+                    })?;
+                    for point in points {
+                        multi.points.push(point);
+                    }
+                }
+                GeometryT::MultiPoint(multi)
+            }
+            "MULTILINESTRING" => {
+                let mut multi = MultiLineStringT::new_with_srid(srid);
+                if self.open()? {
+                    multi.lines = self.list(|p| p.line_string(dims, None))?;
+                }
+                GeometryT::MultiLineString(multi)
+            }
+            "MULTIPOLYGON" => {
+                let mut multi = MultiPolygonT::new_with_srid(srid);
+                if self.open()? {
+                    multi.polygons = self.list(|p| p.polygon(dims, None))?;
+                }
+                GeometryT::MultiPolygon(multi)
+            }
+            "GEOMETRYCOLLECTION" => {
+                let mut collection = GeometryCollectionT::new_with_srid(srid);
+                if self.open()? {
+                    collection.geometries = self.list(|p| p.geometry(None))?;
+                }
+                GeometryT::GeometryCollection(collection)
+            }
+            _ => {
+                self.pos = start;
+                return Err(self.error("geometry type"));
+            }
+        };
+        Ok(geom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{self, Point, PointZ, PointZM};
+
+    // EWKT and EWKB pairs created with PostGIS
+    fn assert_parsed<P>(ewkt: &str, hex: &str)
+    where
+        P: postgis::Point + EwkbRead + NewFromOptVals + PartialEq,
+    {
+        let expected: GeometryT<P> = decode_text(hex).unwrap();
+        assert_eq!(parse_ewkt::<P>(ewkt).unwrap(), expected, "{}", ewkt);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_parse_ewkt() {
+        assert_parsed::<Point>("POINT(10 -20)", "0101000000000000000000244000000000000034C0");
+        assert_parsed::<PointZ>("point z (10 -20 100)", "0101000080000000000000244000000000000034C00000000000005940");
+        assert_parsed::<ewkb::PointM>("POINTM(10 -20 1)", "0101000040000000000000244000000000000034C0000000000000F03F");
+        assert_parsed::<PointZM>("POINT(10 -20 100 1)", "01010000C0000000000000244000000000000034C00000000000005940000000000000F03F");
+        assert_parsed::<PointZ>("SRID=4326;LINESTRING (10 -20 100, 0 -0.5 101)", "01020000A0E610000002000000000000000000244000000000000034C000000000000059400000000000000000000000000000E0BF0000000000405940");
+        assert_parsed::<Point>("SRID=4326;POLYGON ((0 0, 2 0, 2 2, 0 2, 0 0))", "0103000020E610000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000");
+        assert_parsed::<PointZ>("SRID=4326;MULTIPOINT ((10 -20 100), (0 -0.5 101))", "01040000A0E6100000020000000101000080000000000000244000000000000034C0000000000000594001010000800000000000000000000000000000E0BF0000000000405940");
+        assert_parsed::<PointZ>("SRID=4326;MULTIPOINT (10 -20 100, 0 -0.5 101)", "01040000A0E6100000020000000101000080000000000000244000000000000034C0000000000000594001010000800000000000000000000000000000E0BF0000000000405940");
+        assert_parsed::<Point>("SRID=4326;MULTILINESTRING ((10 -20, 0 -0.5), (0 0, 2 0))", "0105000020E610000002000000010200000002000000000000000000244000000000000034C00000000000000000000000000000E0BF0102000000020000000000000000000000000000000000000000000000000000400000000000000000");
+        assert_parsed::<Point>("SRID=4326;MULTIPOLYGON (((0 0, 2 0, 2 2, 0 2, 0 0)), ((10 10, -2 10, -2 -2, 10 -2, 10 10)))", "0106000020E610000002000000010300000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000010300000001000000050000000000000000002440000000000000244000000000000000C0000000000000244000000000000000C000000000000000C0000000000000244000000000000000C000000000000024400000000000002440");
+        assert_parsed::<Point>("GeometryCollection(POINT (10 10),POINT (30 30),LINESTRING (15 15, 20 20))", "01070000000300000001010000000000000000002440000000000000244001010000000000000000003E400000000000003E400102000000020000000000000000002E400000000000002E4000000000000034400000000000003440");
+        assert_parsed::<Point>("GEOMETRYCOLLECTION EMPTY", "010700000000000000");
+
+        // extra dimensions are dropped, missing ones rejected
+        assert_eq!(parse_ewkt::<Point>("POINT Z (1 2 3)").unwrap(), GeometryT::Point(Point::new(1.0, 2.0, None)));
+        assert!(parse_ewkt::<PointZ>("POINT (1 2)").is_err());
+        assert!(parse_ewkt::<Point>("POINT (1)").is_err());
+        assert!(parse_ewkt::<Point>("LINESTRING (1 2, 3 4").is_err());
+        assert!(parse_ewkt::<Point>("POINT (1 2) x").is_err());
+        assert!(parse_ewkt::<Point>("CIRCLE (1 2)").is_err());
+        assert!(parse_ewkt::<Point>("SRID=x;POINT (1 2)").is_err());
+    }
+
+    #[test]
+    fn test_bulk_reader() {
+        let tsv = "1\tPOINT(1 2)\r\n\n2\t\n3\t\"POINT(\"\"1 2)\"\n4\t01010000\n";
+        let rows: Vec<_> = BulkReader::<_, Point>::new(tsv.as_bytes(), 1)
+            .delimiter('\t')
+            .collect();
+        assert_eq!(rows.len(), 4);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!((row.line, row.fields[0].as_str()), (1, "1"));
+        assert_eq!(
+            row.geometry,
+            Some(GeometryT::Point(Point::new(1.0, 2.0, None)))
+        );
+        assert_eq!(rows[1].as_ref().unwrap().geometry, None);
+        assert_eq!(rows[2].as_ref().unwrap_err().line, 4);
+        assert_eq!(rows[3].as_ref().unwrap_err().line, 5);
+
+        assert_eq!(
+            split_record(r#"a,"b,""c""",d"#, ',').unwrap(),
+            vec!["a", "b,\"c\"", "d"]
+        );
+        assert!(split_record(r#"a,"b"#, ',').is_err());
+    }
+}
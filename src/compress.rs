@@ -0,0 +1,166 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Compressed EWKB blobs for archives and message queue payloads.
+//!
+//! A blob starts with the magic bytes `EWZ` and one byte for the codec,
+//! followed by the compressed EWKB. Codecs are enabled with the `zstd` and
+//! `flate2` (gzip) features.
+//!
+//! ```rust
+//! # #[cfg(feature = "zstd")] {
+//! use postgis::compress::{Codec, FromCompressedEwkb, ToCompressedEwkb};
+//! use postgis::ewkb::{self, AsEwkbLineString};
+//!
+//! let line = ewkb::LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+//! let blob = line.as_ewkb().to_compressed_ewkb(Codec::Zstd).unwrap();
+//! assert_eq!(&blob[..4], b"EWZ\x01");
+//! assert_eq!(ewkb::LineString::from_compressed_ewkb(&blob).unwrap(), line);
+//! # }
+//! ```
+
+use crate::{
+    error::Error,
+    ewkb::{EwkbRead, EwkbWrite},
+};
+
+const MAGIC: &[u8; 3] = b"EWZ";
+
+/// Size of the header in front of the compressed EWKB.
+pub const HEADER_SIZE: usize = 4;
+
+/// Compression codec, stored in the fourth header byte.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Codec {
+    #[cfg(feature = "zstd")]
+    Zstd,
+    #[cfg(feature = "flate2")]
+    Gzip,
+}
+
+impl Codec {
+    /// Header byte of the codec.
+    pub fn id(self) -> u8 {
+        match self {
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => 1,
+            #[cfg(feature = "flate2")]
+            Codec::Gzip => 2,
+        }
+    }
+
+    /// Codec for a header byte. Fails for unknown codecs and codecs whose
+    /// feature is not enabled.
+    pub fn from_id(id: u8) -> Result<Codec, Error> {
+        match id {
+            #[cfg(feature = "zstd")]
+            1 => Ok(Codec::Zstd),
+            #[cfg(feature = "flate2")]
+            2 => Ok(Codec::Gzip),
+            _ => Err(Error::Read(format!("unsupported compression codec {}", id))),
+        }
+    }
+}
+
+/// Write geometries as compressed EWKB.
+pub trait ToCompressedEwkb {
+    fn to_compressed_ewkb(&self, codec: Codec) -> Result<Vec<u8>, Error>;
+}
+
+impl<T: EwkbWrite> ToCompressedEwkb for T {
+    fn to_compressed_ewkb(&self, codec: Codec) -> Result<Vec<u8>, Error> {
+        let mut blob = Vec::with_capacity(HEADER_SIZE + self.ewkb_size() / 2);
+        blob.extend_from_slice(MAGIC);
+        blob.push(codec.id());
+        match codec {
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => {
+                let mut encoder = zstd::Encoder::new(blob, 0)?;
+                self.write_ewkb(&mut encoder)?;
+                Ok(encoder.finish()?)
+            }
+            #[cfg(feature = "flate2")]
+            Codec::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(blob, flate2::Compression::default());
+                self.write_ewkb(&mut encoder)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+}
+
+/// Read geometries from compressed EWKB written with [`ToCompressedEwkb`].
+pub trait FromCompressedEwkb: Sized {
+    fn from_compressed_ewkb(blob: &[u8]) -> Result<Self, Error>;
+}
+
+/// Codec of a compressed EWKB blob.
+pub fn read_codec(blob: &[u8]) -> Result<Codec, Error> {
+    if blob.len() < HEADER_SIZE || &blob[..3] != MAGIC {
+        return Err(Error::Read("not a compressed EWKB blob".into()));
+    }
+    Codec::from_id(blob[3])
+}
+
+impl<T: EwkbRead> FromCompressedEwkb for T {
+    fn from_compressed_ewkb(blob: &[u8]) -> Result<Self, Error> {
+        let data = &blob[HEADER_SIZE.min(blob.len())..];
+        match read_codec(blob)? {
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => T::read_ewkb(&mut zstd::Decoder::new(data)?),
+            #[cfg(feature = "flate2")]
+            Codec::Gzip => T::read_ewkb(&mut flate2::read::GzDecoder::new(data)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{self, AsEwkbGeometry, AsEwkbPolygon};
+
+    fn codecs() -> Vec<Codec> {
+        vec![
+            #[cfg(feature = "zstd")]
+            Codec::Zstd,
+            #[cfg(feature = "flate2")]
+            Codec::Gzip,
+        ]
+    }
+
+    #[test]
+    fn test_compressed_roundtrip() {
+        let ring = ewkb::LineString::from(
+            (0..1000)
+                .map(|i| ((i % 100) as f64, (i / 100) as f64))
+                .collect::<Vec<_>>(),
+        );
+        let polygon = ewkb::Polygon {
+            rings: vec![ring],
+            srid: None,
+        };
+        let geom = ewkb::Geometry::Polygon(polygon.clone());
+        for codec in codecs() {
+            let blob = polygon.as_ewkb().to_compressed_ewkb(codec).unwrap();
+            assert_eq!(read_codec(&blob).unwrap(), codec);
+            assert!(blob.len() < polygon.as_ewkb().ewkb_size());
+            assert_eq!(ewkb::Polygon::from_compressed_ewkb(&blob).unwrap(), polygon);
+
+            let blob = geom.as_ewkb().to_compressed_ewkb(codec).unwrap();
+            assert_eq!(ewkb::Geometry::from_compressed_ewkb(&blob).unwrap(), geom);
+
+            // truncated payload
+            assert!(ewkb::Polygon::from_compressed_ewkb(&blob[..blob.len() / 2]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_compressed_header() {
+        assert!(ewkb::Point::from_compressed_ewkb(b"").is_err());
+        assert!(ewkb::Point::from_compressed_ewkb(b"EWZ").is_err());
+        assert!(ewkb::Point::from_compressed_ewkb(b"WKB\x01").is_err());
+        assert!(read_codec(b"EWZ\x09").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Dimension independent coordinates.
+//!
+//! [`Coord`] holds the values of any of the four ewkb point types, so code
+//! handling mixed dimensions doesn't have to be written four times.
+//!
+//! ```rust
+//! use postgis::{coord::{Coord, Coords}, ewkb};
+//!
+//! let line = ewkb::LineStringZ::from(vec![(0.0, 0.0, 10.0), (1.0, 1.0, 11.0)]);
+//! let zs: Vec<Option<f64>> = line.coords().map(|c| c.z).collect();
+//! assert_eq!(zs, vec![Some(10.0), Some(11.0)]);
+//!
+//! let p: ewkb::PointZ = Coord::new(1.0, 2.0).with_z(3.0).to_point(None).unwrap();
+//! assert_eq!(p, ewkb::PointZ::new(1.0, 2.0, 3.0, None));
+//! ```
+
+use crate::{
+    envelope::Envelope,
+    error::Error,
+    ewkb::{self, EwkbRead, NewFromOptVals, PointType},
+    twkb, types as postgis,
+};
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+
+/// Coordinate with optional Z and M values.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coord {
+    pub x: f64,
+    pub y: f64,
+    pub z: Option<f64>,
+    pub m: Option<f64>,
+}
+
+impl Coord {
+    pub fn new(x: f64, y: f64) -> Coord {
+        Coord {
+            x,
+            y,
+            z: None,
+            m: None,
+        }
+    }
+
+    pub fn with_z(self, z: f64) -> Coord {
+        Coord { z: Some(z), ..self }
+    }
+
+    pub fn with_m(self, m: f64) -> Coord {
+        Coord { m: Some(m), ..self }
+    }
+
+    pub fn from_point<P: postgis::Point>(point: &P) -> Coord {
+        Coord {
+            x: point.x(),
+            y: point.y(),
+            z: point.opt_z(),
+            m: point.opt_m(),
+        }
+    }
+
+    /// Create a point of type `P`. Fails if `P` requires a missing Z or M value.
+    pub fn to_point<P>(&self, srid: Option<i32>) -> Result<P, Error>
+    where
+        P: NewFromOptVals + EwkbRead,
+    {
+        let point_type = P::point_type();
+        if (point_type == PointType::PointZ || point_type == PointType::PointZM) && self.z.is_none()
+        {
+            return Err(Error::Other("missing Z value".into()));
+        }
+        if (point_type == PointType::PointM || point_type == PointType::PointZM) && self.m.is_none()
+        {
+            return Err(Error::Other("missing M value".into()));
+        }
+        Ok(P::new_from_opt_vals(self.x, self.y, self.z, self.m, srid))
+    }
+}
+
+impl postgis::Point for Coord {
+    fn x(&self) -> f64 {
+        self.x
+    }
+    fn y(&self) -> f64 {
+        self.y
+    }
+    fn opt_z(&self) -> Option<f64> {
+        self.z
+    }
+    fn opt_m(&self) -> Option<f64> {
+        self.m
+    }
+}
+
+macro_rules! impl_from_point_for_coord {
+    ($ptype:ident) => {
+        impl From<ewkb::$ptype> for Coord {
+            fn from(point: ewkb::$ptype) -> Coord {
+                Coord::from_point(&point)
+            }
+        }
+
+        impl<'a> From<&'a ewkb::$ptype> for Coord {
+            fn from(point: &'a ewkb::$ptype) -> Coord {
+                Coord::from_point(point)
+            }
+        }
+    };
+}
+
+impl_from_point_for_coord!(Point);
+impl_from_point_for_coord!(PointZ);
+impl_from_point_for_coord!(PointM);
+impl_from_point_for_coord!(PointZM);
+
+/// Iterator over all coordinates of a geometry.
+pub type CoordIter<'a> = Box<dyn Iterator<Item = Coord> + 'a>;
+
+/// Access the coordinates of all vertices of a geometry.
+///
+/// Implemented for all ewkb and twkb geometries, so algorithms working on
+/// plain coordinates can be written once for every geometry type.
+#[doc(alias = "CoordsIter")]
+pub trait Coords {
+    /// Iterate over all coordinates, descending into nested geometries.
+    fn coords(&self) -> CoordIter<'_>;
+
+    /// Number of coordinates returned by [`coords`](Coords::coords),
+    /// computed without iterating over the points.
+    fn coords_count(&self) -> usize {
+        self.coords().count()
+    }
+
+    /// Bounding box of all X/Y coordinates, `None` for empty geometries.
+    /// NaN coordinates (empty points) are ignored.
+    fn bbox(&self) -> Option<Envelope> {
+        self.coords()
+            .filter(|c| !c.x.is_nan() && !c.y.is_nan())
+            .map(|c| Envelope::new(c.x, c.y, c.x, c.y))
+            .reduce(|a, b| a.union(&b))
+    }
+}
+
+macro_rules! impl_coords_for_point {
+    ($ptype:ident) => {
+        impl Coords for ewkb::$ptype {
+            fn coords(&self) -> CoordIter<'_> {
+                Box::new(core::iter::once(Coord::from_point(self)))
+            }
+
+            fn coords_count(&self) -> usize {
+                1
+            }
+        }
+    };
+}
+
+impl_coords_for_point!(Point);
+impl_coords_for_point!(PointZ);
+impl_coords_for_point!(PointM);
+impl_coords_for_point!(PointZM);
+
+macro_rules! impl_coords_for_container {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> Coords for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + Coords,
+        {
+            fn coords(&self) -> CoordIter<'_> {
+                Box::new(self.$itemname.iter().flat_map(|item| item.coords()))
+            }
+
+            fn coords_count(&self) -> usize {
+                self.$itemname.iter().map(|item| item.coords_count()).sum()
+            }
+        }
+    };
+}
+
+impl_coords_for_container!(LineStringT named points);
+impl_coords_for_container!(PolygonT named rings);
+impl_coords_for_container!(MultiPointT named points);
+impl_coords_for_container!(MultiLineStringT named lines);
+impl_coords_for_container!(MultiPolygonT named polygons);
+impl_coords_for_container!(GeometryCollectionT named geometries);
+
+impl<P> Coords for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + Coords,
+{
+    fn coords(&self) -> CoordIter<'_> {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => geom.coords(),
+            G::LineString(ref geom) => geom.coords(),
+            G::Polygon(ref geom) => geom.coords(),
+            G::MultiPoint(ref geom) => geom.coords(),
+            G::MultiLineString(ref geom) => geom.coords(),
+            G::MultiPolygon(ref geom) => geom.coords(),
+            G::GeometryCollection(ref geom) => geom.coords(),
+        }
+    }
+
+    fn coords_count(&self) -> usize {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => geom.coords_count(),
+            G::LineString(ref geom) => geom.coords_count(),
+            G::Polygon(ref geom) => geom.coords_count(),
+            G::MultiPoint(ref geom) => geom.coords_count(),
+            G::MultiLineString(ref geom) => geom.coords_count(),
+            G::MultiPolygon(ref geom) => geom.coords_count(),
+            G::GeometryCollection(ref geom) => geom.coords_count(),
+        }
+    }
+}
+
+impl Coords for twkb::Point {
+    fn coords(&self) -> CoordIter<'_> {
+        Box::new(core::iter::once(Coord::from_point(self)))
+    }
+
+    fn coords_count(&self) -> usize {
+        1
+    }
+}
+
+macro_rules! impl_coords_for_twkb {
+    ($geotype:ident named $itemname:ident) => {
+        impl Coords for twkb::$geotype {
+            fn coords(&self) -> CoordIter<'_> {
+                Box::new(self.$itemname.iter().flat_map(|item| item.coords()))
+            }
+
+            fn coords_count(&self) -> usize {
+                self.$itemname.iter().map(|item| item.coords_count()).sum()
+            }
+        }
+    };
+}
+
+impl_coords_for_twkb!(LineString named points);
+impl_coords_for_twkb!(Polygon named rings);
+impl_coords_for_twkb!(MultiPoint named points);
+impl_coords_for_twkb!(MultiLineString named lines);
+impl_coords_for_twkb!(MultiPolygon named polygons);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_coord_points() {
+        let c = Coord::from(PointM::new(1.0, 2.0, 3.0, Some(4326)));
+        assert_eq!(c, Coord::new(1.0, 2.0).with_m(3.0));
+        assert_eq!(
+            c.to_point::<Point>(None).unwrap(),
+            Point::new(1.0, 2.0, None)
+        );
+        assert_eq!(
+            c.to_point::<PointM>(Some(4326)).unwrap(),
+            PointM::new(1.0, 2.0, 3.0, Some(4326))
+        );
+        assert!(c.to_point::<PointZM>(None).is_err());
+    }
+
+    #[test]
+    fn test_coords() {
+        let poly = Polygon::from(vec![
+            vec![(0., 0.), (4., 0.), (0., 4.), (0., 0.)],
+            vec![(1., 1.), (2., 1.), (1., 2.), (1., 1.)],
+        ]);
+        let geom = GeometryT::GeometryCollection(GeometryCollectionT {
+            geometries: vec![
+                GeometryT::Point(Point::new(9., 9., None)),
+                GeometryT::Polygon(poly),
+            ],
+            srid: None,
+        });
+        assert_eq!(geom.coords().count(), 9);
+        assert_eq!(geom.coords_count(), 9);
+        assert_eq!(geom.coords().last(), Some(Coord::new(1., 1.)));
+        let max_x = geom.coords().map(|c| c.x).fold(f64::MIN, f64::max);
+        assert_eq!(max_x, 9.0);
+        assert_eq!(geom.bbox(), Some(Envelope::new(0., 0., 9., 9.)));
+        assert_eq!(LineString::new().bbox(), None);
+    }
+
+    #[test]
+    fn test_twkb_coords() {
+        use crate::twkb;
+        let line = twkb::LineString {
+            points: vec![
+                twkb::Point {
+                    x: 1.0,
+                    y: 2.0,
+                    z: Some(3.0),
+                    m: None,
+                },
+                twkb::Point {
+                    x: 4.0,
+                    y: 5.0,
+                    z: Some(6.0),
+                    m: None,
+                },
+            ],
+        };
+        let multi = twkb::MultiLineString {
+            lines: vec![line.clone(), line],
+            ids: None,
+        };
+        assert_eq!(multi.coords_count(), 4);
+        assert_eq!(
+            multi.coords().nth(1),
+            Some(Coord::new(4.0, 5.0).with_z(6.0))
+        );
+        assert_eq!(multi.bbox(), Some(Envelope::new(1., 2., 4., 5.)));
+    }
+}
@@ -0,0 +1,166 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! PostgreSQL binary COPY output for bulk loading geometries.
+//!
+//! [`CopyWriter`] produces the data for `COPY ... FROM STDIN (FORMAT binary)`,
+//! e.g. written into the `CopyInWriter` of the `postgres` crate.
+//!
+//! ```rust
+//! use postgis::copy::CopyWriter;
+//! use postgis::ewkb::{AsEwkbPoint, Point};
+//!
+//! // client.copy_in("COPY places (id, geom) FROM STDIN (FORMAT binary)")?
+//! let mut sink = Vec::new();
+//! let mut copy = CopyWriter::new(&mut sink).unwrap();
+//! for (id, (x, y)) in vec![(10.0, -20.0), (0.0, -0.5)].into_iter().enumerate() {
+//!     let point = Point::new(x, y, Some(4326));
+//!     copy.row().int4(id as i32).geometry(&point.as_ewkb()).finish().unwrap();
+//! }
+//! copy.finish().unwrap();
+//! ```
+
+use crate::{error::Error, ewkb::EwkbWrite};
+use byteorder::{BigEndian, WriteBytesExt};
+use std::io::prelude::*;
+
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// Writer for the PostgreSQL binary COPY format.
+pub struct CopyWriter<W: Write> {
+    writer: W,
+    row: Vec<u8>,
+}
+
+impl<W: Write> CopyWriter<W> {
+    /// Create a writer and write the file header.
+    pub fn new(mut writer: W) -> Result<Self, Error> {
+        writer.write_all(SIGNATURE)?;
+        // flags and header extension length
+        writer.write_i32::<BigEndian>(0)?;
+        writer.write_i32::<BigEndian>(0)?;
+        Ok(CopyWriter {
+            writer,
+            row: Vec::new(),
+        })
+    }
+
+    /// Start a new row. Fields are written in column order.
+    pub fn row(&mut self) -> RowBuilder<'_, W> {
+        self.row.clear();
+        RowBuilder {
+            copy: self,
+            fields: 0,
+            error: None,
+        }
+    }
+
+    /// Write the file trailer and return the underlying writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.writer.write_i16::<BigEndian>(-1)?;
+        Ok(self.writer)
+    }
+}
+
+/// Fields of a row in binary COPY format.
+pub struct RowBuilder<'a, W: Write> {
+    copy: &'a mut CopyWriter<W>,
+    fields: i16,
+    error: Option<Error>,
+}
+
+impl<'a, W: Write> RowBuilder<'a, W> {
+    /// Field value in PostgreSQL binary representation.
+    pub fn bytes(mut self, value: &[u8]) -> Self {
+        self.field_len(value.len());
+        self.copy.row.extend_from_slice(value);
+        self
+    }
+
+    pub fn null(mut self) -> Self {
+        self.fields += 1;
+        self.copy.row.extend_from_slice(&(-1i32).to_be_bytes());
+        self
+    }
+
+    /// `geometry` or `geography` field.
+    pub fn geometry<T: EwkbWrite>(mut self, geom: &T) -> Self {
+        self.field_len(geom.ewkb_size());
+        if let Err(e) = geom.write_ewkb(&mut self.copy.row) {
+            self.error.get_or_insert(e);
+        }
+        self
+    }
+
+    /// Optional `geometry` or `geography` field.
+    pub fn opt_geometry<T: EwkbWrite>(self, geom: Option<&T>) -> Self {
+        match geom {
+            Some(geom) => self.geometry(geom),
+            None => self.null(),
+        }
+    }
+
+    pub fn int4(self, value: i32) -> Self {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    pub fn int8(self, value: i64) -> Self {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    pub fn float8(self, value: f64) -> Self {
+        self.bytes(&value.to_bits().to_be_bytes())
+    }
+
+    /// `text` or `varchar` field.
+    pub fn text(self, value: &str) -> Self {
+        self.bytes(value.as_bytes())
+    }
+
+    /// Write the row.
+    pub fn finish(self) -> Result<(), Error> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        self.copy.writer.write_i16::<BigEndian>(self.fields)?;
+        self.copy.writer.write_all(&self.copy.row)?;
+        Ok(())
+    }
+
+    fn field_len(&mut self, len: usize) {
+        self.fields += 1;
+        self.copy.row.extend_from_slice(&(len as i32).to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{AsEwkbPoint, Point};
+
+    #[test]
+    fn test_copy_binary() {
+        let point = Point::new(10.0, -20.0, None);
+        let mut copy = CopyWriter::new(Vec::new()).unwrap();
+        copy.row()
+            .int4(7)
+            .geometry(&point.as_ewkb())
+            .finish()
+            .unwrap();
+        copy.row()
+            .int4(8)
+            .opt_geometry::<crate::ewkb::EwkbPoint>(None)
+            .finish()
+            .unwrap();
+        let out = copy.finish().unwrap();
+
+        let mut expected = SIGNATURE.to_vec();
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[0, 2, 0, 0, 0, 4, 0, 0, 0, 7, 0, 0, 0, 21]);
+        point.as_ewkb().write_ewkb(&mut expected).unwrap();
+        expected.extend_from_slice(&[0, 2, 0, 0, 0, 4, 0, 0, 0, 8, 0xff, 0xff, 0xff, 0xff]);
+        expected.extend_from_slice(&[0xff, 0xff]);
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,450 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Compact deltas between two revisions of a geometry.
+//!
+//! A delta describes the new revision as edits of the vertex sequences
+//! (points, lines and polygon rings) of the old revision: runs of copied,
+//! skipped and inserted vertices. Inserted vertices are quantized and
+//! delta encoded as zigzag varints like in TWKB, unchanged vertices cost
+//! nothing, so a small edit of a large polygon is a few bytes.
+//!
+//! The number and kind of parts must not change between the revisions,
+//! e.g. a polygon ring can be edited, but not added. Send the full geometry
+//! in that case.
+//!
+//! ```rust
+//! use postgis::{delta, ewkb};
+//!
+//! let old = ewkb::Geometry::LineString(ewkb::LineString::from(
+//!     (0..1000).map(|i| (i as f64, 0.0)).collect::<Vec<_>>(),
+//! ));
+//! let mut new = old.clone();
+//! new.as_line_string_mut().unwrap().points[500].y = 1.25;
+//!
+//! let bytes = delta::encode_delta(&old, &new, 2).unwrap();
+//! assert!(bytes.len() < 20);
+//! assert_eq!(delta::apply_delta(&old, &bytes).unwrap(), new);
+//! ```
+//!
+//! # Format
+//!
+//! * zigzag encoded precision (byte), number of parts (varint)
+//! * per part: number of vertices in the old revision (varint), then
+//!   operations (varint `count << 2 | code`) up to an end marker:
+//!   `0` copy `count` old vertices, `1` skip `count` old vertices,
+//!   `2` insert `count` vertices, followed by their coordinates, `3` end.
+//!
+//! Inserted coordinates are scaled by `10^precision` and written as
+//! differences to the previously inserted vertex, X, Y and the Z and M
+//! values of the point type.
+
+use crate::{
+    diff::Parts,
+    error::Error,
+    ewkb::{self, EwkbRead, NewFromOptVals, PointType, Srid},
+    twkb::{decode_zig_zag_64, read_raw_varint64, write_int64, write_raw_varint64},
+    types as postgis,
+};
+use std::collections::HashMap;
+
+const COPY: u64 = 0;
+const SKIP: u64 = 1;
+const INSERT: u64 = 2;
+const END: u64 = 3;
+
+/// Encode `new` as changes to `old`, quantizing coordinates to `precision`
+/// decimal places (-8 to 7).
+pub fn encode_delta<P>(
+    old: &ewkb::GeometryT<P>,
+    new: &ewkb::GeometryT<P>,
+    precision: i8,
+) -> Result<Vec<u8>, Error>
+where
+    P: postgis::Point + EwkbRead,
+{
+    if !(-8..=7).contains(&precision) {
+        return Err(Error::Write("delta precision out of range".into()));
+    }
+    if !same_structure(old, new) {
+        return Err(Error::Write(
+            "geometry structure changed, delta not possible".into(),
+        ));
+    }
+    let (mut old_parts, mut new_parts) = (Vec::new(), Vec::new());
+    old.parts(&mut old_parts);
+    new.parts(&mut new_parts);
+
+    let mut encoder = Encoder {
+        out: vec![encode_zig_zag_8(precision)],
+        scale: 10f64.powi(precision as i32),
+        dims: dims::<P>(),
+        last: [0; 4],
+    };
+    write_raw_varint64(&mut encoder.out, old_parts.len() as u64);
+    for (old_part, new_part) in old_parts.iter().zip(&new_parts) {
+        encoder.write_part(old_part, new_part);
+    }
+    Ok(encoder.out)
+}
+
+/// Apply a delta created by [`encode_delta`] to `old`.
+pub fn apply_delta<P>(old: &ewkb::GeometryT<P>, delta: &[u8]) -> Result<ewkb::GeometryT<P>, Error>
+where
+    P: postgis::Point + EwkbRead + NewFromOptVals + Srid + Clone,
+{
+    let mut raw = delta;
+    let precision = match raw.split_first() {
+        Some((&byte, rest)) => {
+            raw = rest;
+            decode_zig_zag_64(byte as u64) as i32
+        }
+        None => return Err(Error::Read("empty delta".into())),
+    };
+    let scale = 10f64.powi(precision);
+    let dims = dims::<P>();
+    let mut geom = old.clone();
+    let mut parts = Vec::new();
+    parts_mut(&mut geom, &mut parts);
+    let num_parts = read_raw_varint64(&mut raw)? as usize;
+    if num_parts != parts.len() {
+        return Err(Error::Read(format!(
+            "delta for {} parts applied to geometry with {} parts",
+            num_parts,
+            parts.len()
+        )));
+    }
+
+    let mut last = [0i64; 4];
+    for part in parts.iter_mut() {
+        let old_points: Vec<P> = part.points().to_vec();
+        if read_raw_varint64(&mut raw)? as usize != old_points.len() {
+            return Err(Error::Read("delta doesn't match the old geometry".into()));
+        }
+        let srid = old_points.first().and_then(|p| p.srid());
+        let mut points = Vec::with_capacity(old_points.len());
+        let mut i = 0;
+        loop {
+            let op = read_raw_varint64(&mut raw)?;
+            let count = (op >> 2) as usize;
+            match op & 3 {
+                COPY | SKIP if i + count > old_points.len() => {
+                    return Err(Error::Read("delta exceeds the old vertices".into()));
+                }
+                COPY => {
+                    points.extend_from_slice(&old_points[i..i + count]);
+                    i += count;
+                }
+                SKIP => i += count,
+                INSERT => {
+                    for _ in 0..count {
+                        let mut values = [0.0; 4];
+                        for &d in &dims {
+                            last[d] += decode_zig_zag_64(read_raw_varint64(&mut raw)?);
+                            values[d] = last[d] as f64 / scale;
+                        }
+                        let (z, m) = (Some(values[2]), Some(values[3]));
+                        points.push(P::new_from_opt_vals(values[0], values[1], z, m, srid));
+                    }
+                }
+                _ => break,
+            }
+        }
+        if i != old_points.len() {
+            return Err(Error::Read("delta doesn't match the old geometry".into()));
+        }
+        part.set_points(points)?;
+    }
+    if !raw.is_empty() {
+        return Err(Error::Read(format!(
+            "{} trailing bytes after delta",
+            raw.len()
+        )));
+    }
+    Ok(geom)
+}
+
+fn encode_zig_zag_8(n: i8) -> u8 {
+    ((n << 1) ^ (n >> 7)) as u8
+}
+
+fn dims<P: EwkbRead>() -> Vec<usize> {
+    match P::point_type() {
+        PointType::Point => vec![0, 1],
+        PointType::PointZ => vec![0, 1, 2],
+        PointType::PointM => vec![0, 1, 3],
+        PointType::PointZM => vec![0, 1, 2, 3],
+    }
+}
+
+// Same geometry types and number of parts at every level
+fn same_structure<P>(a: &ewkb::GeometryT<P>, b: &ewkb::GeometryT<P>) -> bool
+where
+    P: postgis::Point + EwkbRead,
+{
+    use crate::ewkb::GeometryT as G;
+    match (a, b) {
+        (G::Point(_), G::Point(_))
+        | (G::LineString(_), G::LineString(_))
+        | (G::MultiPoint(_), G::MultiPoint(_)) => true,
+        (G::Polygon(a), G::Polygon(b)) => a.rings.len() == b.rings.len(),
+        (G::MultiLineString(a), G::MultiLineString(b)) => a.lines.len() == b.lines.len(),
+        (G::MultiPolygon(a), G::MultiPolygon(b)) => {
+            a.polygons.len() == b.polygons.len()
+                && a.polygons
+                    .iter()
+                    .zip(&b.polygons)
+                    .all(|(a, b)| a.rings.len() == b.rings.len())
+        }
+        (G::GeometryCollection(a), G::GeometryCollection(b)) => {
+            a.geometries.len() == b.geometries.len()
+                && a.geometries
+                    .iter()
+                    .zip(&b.geometries)
+                    .all(|(a, b)| same_structure(a, b))
+        }
+        _ => false,
+    }
+}
+
+struct Encoder {
+    out: Vec<u8>,
+    scale: f64,
+    dims: Vec<usize>,
+    // last inserted vertex
+    last: [i64; 4],
+}
+
+impl Encoder {
+    fn quantize<P: postgis::Point>(&self, p: &P) -> [i64; 4] {
+        let values = [
+            p.x(),
+            p.y(),
+            p.opt_z().unwrap_or(0.0),
+            p.opt_m().unwrap_or(0.0),
+        ];
+        let mut q = [0; 4];
+        for &d in &self.dims {
+            q[d] = (values[d] * self.scale).round() as i64;
+        }
+        q
+    }
+
+    fn write_part<P: postgis::Point>(&mut self, old: &[P], new: &[P]) {
+        let old: Vec<[i64; 4]> = old.iter().map(|p| self.quantize(p)).collect();
+        let new: Vec<[i64; 4]> = new.iter().map(|p| self.quantize(p)).collect();
+        let (old_index, new_index) = (positions(&old), positions(&new));
+        write_raw_varint64(&mut self.out, old.len() as u64);
+
+        // pending run of copied vertices, or skipped and inserted vertices
+        let (mut copy, mut skip, mut insert) = (0, 0, 0..0);
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() && j < new.len() {
+            if old[i] == new[j] {
+                if skip > 0 || !insert.is_empty() {
+                    self.write_changes(skip, &new[insert.clone()]);
+                    skip = 0;
+                    insert = j..j;
+                }
+                copy += 1;
+                i += 1;
+                j += 1;
+                continue;
+            }
+            self.write_op(COPY, copy);
+            copy = 0;
+            if insert.is_empty() {
+                insert = j..j;
+            }
+            // resynchronize at the nearest next occurrence of either vertex
+            let inserted = next_position(&new_index, &old[i], j).map(|p| p - j);
+            let skipped = next_position(&old_index, &new[j], i).map(|p| p - i);
+            match (inserted, skipped) {
+                (Some(k), s) if s.is_none_or(|s| k <= s) => j += k,
+                (_, Some(k)) => {
+                    skip += k;
+                    i += k;
+                }
+                _ => {
+                    skip += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+            insert.end = j;
+        }
+        self.write_op(COPY, copy);
+        if insert.is_empty() {
+            insert = j..j;
+        }
+        self.write_changes(skip + old.len() - i, &new[insert.start..]);
+        self.write_op(END, 0);
+    }
+
+    fn write_changes(&mut self, skip: usize, insert: &[[i64; 4]]) {
+        self.write_op(SKIP, skip);
+        self.write_op(INSERT, insert.len());
+        for q in insert {
+            for &d in &self.dims {
+                write_int64(&mut self.out, q[d] - self.last[d]);
+                self.last[d] = q[d];
+            }
+        }
+    }
+
+    fn write_op(&mut self, code: u64, count: usize) {
+        if count > 0 || code == END {
+            write_raw_varint64(&mut self.out, (count as u64) << 2 | code);
+        }
+    }
+}
+
+// Positions of the vertices in a sequence
+fn positions(points: &[[i64; 4]]) -> HashMap<[i64; 4], Vec<usize>> {
+    let mut index: HashMap<_, Vec<_>> = HashMap::new();
+    for (pos, q) in points.iter().enumerate() {
+        index.entry(*q).or_default().push(pos);
+    }
+    index
+}
+
+fn next_position(
+    index: &HashMap<[i64; 4], Vec<usize>>,
+    q: &[i64; 4],
+    after: usize,
+) -> Option<usize> {
+    let positions = index.get(q)?;
+    positions
+        .get(positions.partition_point(|&pos| pos <= after))
+        .copied()
+}
+
+// Mutable vertex sequence of a geometry
+enum PartMut<'a, P> {
+    Point(&'a mut P),
+    Points(&'a mut Vec<P>),
+}
+
+impl<P: Clone> PartMut<'_, P> {
+    fn points(&self) -> &[P] {
+        match *self {
+            PartMut::Point(ref p) => core::slice::from_ref(&**p),
+            PartMut::Points(ref points) => points,
+        }
+    }
+
+    fn set_points(&mut self, mut points: Vec<P>) -> Result<(), Error> {
+        match *self {
+            PartMut::Point(ref mut p) => match points.pop() {
+                Some(point) if points.is_empty() => **p = point,
+                _ => return Err(Error::Read("delta changes the number of points".into())),
+            },
+            PartMut::Points(ref mut items) => **items = points.into_iter().collect(),
+        }
+        Ok(())
+    }
+}
+
+fn parts_mut<'a, P>(geom: &'a mut ewkb::GeometryT<P>, parts: &mut Vec<PartMut<'a, P>>)
+where
+    P: postgis::Point + EwkbRead,
+{
+    use crate::ewkb::GeometryT as G;
+    match *geom {
+        G::Point(ref mut p) => parts.push(PartMut::Point(p)),
+        G::LineString(ref mut line) => parts.push(PartMut::Points(&mut line.points)),
+        G::Polygon(ref mut polygon) => {
+            for ring in polygon.rings.iter_mut() {
+                parts.push(PartMut::Points(&mut ring.points));
+            }
+        }
+        G::MultiPoint(ref mut multi) => parts.push(PartMut::Points(&mut multi.points)),
+        G::MultiLineString(ref mut multi) => {
+            for line in multi.lines.iter_mut() {
+                parts.push(PartMut::Points(&mut line.points));
+            }
+        }
+        G::MultiPolygon(ref mut multi) => {
+            for ring in multi.polygons.iter_mut().flat_map(|p| p.rings.iter_mut()) {
+                parts.push(PartMut::Points(&mut ring.points));
+            }
+        }
+        G::GeometryCollection(ref mut collection) => {
+            for member in collection.geometries.iter_mut() {
+                parts_mut(member, parts);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{Geometry, GeometryZ, LineStringZ, Point, Polygon};
+
+    fn square(size: f64) -> Vec<(f64, f64)> {
+        vec![
+            (0.0, 0.0),
+            (size, 0.0),
+            (size, size),
+            (0.0, size),
+            (0.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn test_delta_polygon() {
+        let old = Geometry::Polygon(Polygon::from(vec![square(10.0), square(2.0)]));
+        let mut new = old.clone();
+        {
+            let rings = &mut new.as_polygon_mut().unwrap().rings;
+            rings[0].points.insert(2, Point::new(10.5, 5.0, None));
+            rings[1].points.remove(2);
+            rings[1].points[2] = Point::new(0.0, 2.25, None);
+        }
+        let delta = encode_delta(&old, &new, 2).unwrap();
+        assert_eq!(apply_delta(&old, &delta).unwrap(), new);
+        assert_eq!(
+            apply_delta(&old, &encode_delta(&old, &old, 0).unwrap()).unwrap(),
+            old
+        );
+
+        // coordinates are quantized
+        let delta = encode_delta(&old, &new, 0).unwrap();
+        let rounded = apply_delta(&old, &delta).unwrap();
+        assert_eq!(
+            rounded.as_polygon().unwrap().rings[0].points[2],
+            Point::new(11.0, 5.0, None)
+        );
+
+        let changed = Geometry::Polygon(Polygon::from(vec![square(10.0)]));
+        assert!(encode_delta(&old, &changed, 2).is_err());
+        assert!(apply_delta(&changed, &delta).is_err());
+        assert!(apply_delta(&old, &delta[..delta.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_delta_line_z() {
+        let points: Vec<_> = (0..100).map(|i| (i as f64, 0.0, i as f64)).collect();
+        let old = GeometryZ::LineString(LineStringZ::from(points));
+        let mut new = old.clone();
+        {
+            let points = &mut new.as_line_string_mut().unwrap().points;
+            points.drain(10..40);
+            points[50].z = 0.5;
+            points.push(ewkb::PointZ::new(200.0, 1.0, 2.0, None));
+        }
+        let delta = encode_delta(&old, &new, 1).unwrap();
+        assert!(delta.len() < 32);
+        assert_eq!(apply_delta(&old, &delta).unwrap(), new);
+    }
+
+    #[test]
+    fn test_delta_point() {
+        let old = Geometry::Point(Point::new(1.0, 2.0, Some(4326)));
+        let new = Geometry::Point(Point::new(1.5, 2.0, Some(4326)));
+        let delta = encode_delta(&old, &new, 1).unwrap();
+        assert_eq!(apply_delta(&old, &delta).unwrap(), new);
+    }
+}
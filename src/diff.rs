@@ -0,0 +1,302 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Vertex differences between two revisions of a geometry.
+//!
+//! Vertices are compared part by part (points, lines and polygon rings in
+//! storage order). Vertices closer than the tolerance in X/Y are unchanged,
+//! single inserted or deleted vertices are reported as added or removed,
+//! other differing vertices as moved.
+//!
+//! ```rust
+//! use postgis::{diff::{Diff, VertexChange}, ewkb};
+//!
+//! let old = ewkb::LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]);
+//! let new = ewkb::LineString::from(vec![(0.0, 0.0), (1.0, 0.5), (1.5, 0.0), (2.0, 0.001)]);
+//! let changes = old.diff(&new, 0.01);
+//! assert_eq!(changes.len(), 2);
+//! assert!(matches!(changes[0], VertexChange::Moved { part: 0, index: 1, .. }));
+//! assert!(matches!(changes[1], VertexChange::Added { part: 0, index: 2, .. }));
+//! assert!(old.equivalent_within(&ewkb::LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.001)]), 0.01));
+//! ```
+
+use crate::{
+    coord::Coord,
+    ewkb::{self, EwkbRead},
+    types as postgis,
+};
+use std::slice;
+
+/// Change of a single vertex.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum VertexChange {
+    /// Vertex only in the new geometry, `index` in the new part.
+    Added {
+        part: usize,
+        index: usize,
+        coord: Coord,
+    },
+    /// Vertex only in the old geometry, `index` in the old part.
+    Removed {
+        part: usize,
+        index: usize,
+        coord: Coord,
+    },
+    /// Vertex moved further than the tolerance, `index` in the new part.
+    Moved {
+        part: usize,
+        index: usize,
+        from: Coord,
+        to: Coord,
+    },
+}
+
+/// Compare geometries vertex by vertex.
+pub trait Diff {
+    /// Vertex changes from `self` to `other`. Parts missing in one of the
+    /// geometries are reported as added or removed vertices.
+    fn diff(&self, other: &Self, tolerance: f64) -> Vec<VertexChange>;
+
+    /// Same structure with all vertices within `eps` in X/Y.
+    fn equivalent_within(&self, other: &Self, eps: f64) -> bool;
+}
+
+impl<G: Parts> Diff for G {
+    fn diff(&self, other: &Self, tolerance: f64) -> Vec<VertexChange> {
+        let (mut old, mut new) = (Vec::new(), Vec::new());
+        self.parts(&mut old);
+        other.parts(&mut new);
+        let mut changes = Vec::new();
+        for part in 0..old.len().max(new.len()) {
+            let a = old.get(part).copied().unwrap_or(&[]);
+            let b = new.get(part).copied().unwrap_or(&[]);
+            diff_part(part, a, b, tolerance, &mut changes);
+        }
+        changes
+    }
+
+    fn equivalent_within(&self, other: &Self, eps: f64) -> bool {
+        let (mut old, mut new) = (Vec::new(), Vec::new());
+        self.parts(&mut old);
+        other.parts(&mut new);
+        old.len() == new.len()
+            && old.iter().zip(&new).all(|(a, b)| {
+                a.len() == b.len() && a.iter().zip(*b).all(|(p, q)| within(p, q, eps))
+            })
+    }
+}
+
+fn within<P: postgis::Point>(a: &P, b: &P, eps: f64) -> bool {
+    (a.x() - b.x()).hypot(a.y() - b.y()) <= eps
+}
+
+// Greedy alignment, looking one vertex ahead for insertions and deletions
+fn diff_part<P: postgis::Point>(
+    part: usize,
+    a: &[P],
+    b: &[P],
+    tolerance: f64,
+    changes: &mut Vec<VertexChange>,
+) {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if within(&a[i], &b[j], tolerance) {
+            i += 1;
+            j += 1;
+        } else if j + 1 < b.len() && within(&a[i], &b[j + 1], tolerance) {
+            changes.push(VertexChange::Added {
+                part,
+                index: j,
+                coord: Coord::from_point(&b[j]),
+            });
+            j += 1;
+        } else if i + 1 < a.len() && within(&a[i + 1], &b[j], tolerance) {
+            changes.push(VertexChange::Removed {
+                part,
+                index: i,
+                coord: Coord::from_point(&a[i]),
+            });
+            i += 1;
+        } else {
+            changes.push(VertexChange::Moved {
+                part,
+                index: j,
+                from: Coord::from_point(&a[i]),
+                to: Coord::from_point(&b[j]),
+            });
+            i += 1;
+            j += 1;
+        }
+    }
+    for (index, point) in a.iter().enumerate().skip(i) {
+        changes.push(VertexChange::Removed {
+            part,
+            index,
+            coord: Coord::from_point(point),
+        });
+    }
+    for (index, point) in b.iter().enumerate().skip(j) {
+        changes.push(VertexChange::Added {
+            part,
+            index,
+            coord: Coord::from_point(point),
+        });
+    }
+}
+
+/// Vertex sequences of a geometry.
+#[doc(hidden)]
+pub trait Parts {
+    type Point: postgis::Point;
+
+    fn parts<'a>(&'a self, parts: &mut Vec<&'a [Self::Point]>);
+}
+
+macro_rules! impl_parts_for_point {
+    ($ptype:ident) => {
+        impl Parts for ewkb::$ptype {
+            type Point = Self;
+
+            fn parts<'a>(&'a self, parts: &mut Vec<&'a [Self]>) {
+                parts.push(slice::from_ref(self));
+            }
+        }
+    };
+}
+
+impl_parts_for_point!(Point);
+impl_parts_for_point!(PointZ);
+impl_parts_for_point!(PointM);
+impl_parts_for_point!(PointZM);
+
+macro_rules! impl_parts_for_points {
+    ($geotype:ident) => {
+        impl<P: postgis::Point + EwkbRead> Parts for ewkb::$geotype<P> {
+            type Point = P;
+
+            fn parts<'a>(&'a self, parts: &mut Vec<&'a [P]>) {
+                parts.push(&self.points);
+            }
+        }
+    };
+}
+
+impl_parts_for_points!(LineStringT);
+impl_parts_for_points!(MultiPointT);
+
+macro_rules! impl_parts_for_container {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P: postgis::Point + EwkbRead> Parts for ewkb::$geotype<P> {
+            type Point = P;
+
+            fn parts<'a>(&'a self, parts: &mut Vec<&'a [P]>) {
+                for item in self.$itemname.iter() {
+                    item.parts(parts);
+                }
+            }
+        }
+    };
+}
+
+impl_parts_for_container!(PolygonT named rings);
+impl_parts_for_container!(MultiLineStringT named lines);
+impl_parts_for_container!(MultiPolygonT named polygons);
+impl_parts_for_container!(GeometryCollectionT named geometries);
+
+impl<P: postgis::Point + EwkbRead> Parts for ewkb::GeometryT<P> {
+    type Point = P;
+
+    fn parts<'a>(&'a self, parts: &mut Vec<&'a [P]>) {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => parts.push(slice::from_ref(geom)),
+            G::LineString(ref geom) => geom.parts(parts),
+            G::Polygon(ref geom) => geom.parts(parts),
+            G::MultiPoint(ref geom) => geom.parts(parts),
+            G::MultiLineString(ref geom) => geom.parts(parts),
+            G::MultiPolygon(ref geom) => geom.parts(parts),
+            G::GeometryCollection(ref geom) => geom.parts(parts),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{MultiPolygon, Point, Polygon};
+
+    fn coord(x: f64, y: f64) -> Coord {
+        Coord::new(x, y)
+    }
+
+    #[test]
+    fn test_diff_polygon() {
+        let square = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+        let hole = vec![(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 1.0)];
+        let old = Polygon::from(vec![square.clone(), hole]);
+        let new = Polygon::from(vec![vec![(0.0, 0.0), (4.0, 0.0), (0.0, 4.0), (0.0, 0.0)]]);
+        assert_eq!(
+            old.diff(&new, 0.0),
+            vec![
+                VertexChange::Removed {
+                    part: 0,
+                    index: 2,
+                    coord: coord(4.0, 4.0)
+                },
+                VertexChange::Removed {
+                    part: 1,
+                    index: 0,
+                    coord: coord(1.0, 1.0)
+                },
+                VertexChange::Removed {
+                    part: 1,
+                    index: 1,
+                    coord: coord(2.0, 1.0)
+                },
+                VertexChange::Removed {
+                    part: 1,
+                    index: 2,
+                    coord: coord(2.0, 2.0)
+                },
+                VertexChange::Removed {
+                    part: 1,
+                    index: 3,
+                    coord: coord(1.0, 1.0)
+                },
+            ]
+        );
+        assert!(old.diff(&old, 0.0).is_empty());
+        assert!(!old.equivalent_within(&new, 10.0));
+
+        let multi = MultiPolygon::from(vec![Polygon::from(vec![square])]);
+        let mut moved = multi.clone();
+        moved.polygons[0].rings[0].points[2] = Point::new(4.0, 4.5, None);
+        assert!(multi.equivalent_within(&moved, 0.5));
+        assert!(!multi.equivalent_within(&moved, 0.4));
+        assert_eq!(
+            multi.diff(&moved, 0.4),
+            vec![VertexChange::Moved {
+                part: 0,
+                index: 2,
+                from: coord(4.0, 4.0),
+                to: coord(4.0, 4.5)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_geometry() {
+        let old = ewkb::Geometry::Point(Point::new(1.0, 1.0, None));
+        let new = ewkb::Geometry::LineString(ewkb::LineString::from(vec![(1.0, 1.0), (2.0, 2.0)]));
+        assert_eq!(
+            old.diff(&new, 0.0),
+            vec![VertexChange::Added {
+                part: 0,
+                index: 1,
+                coord: coord(2.0, 2.0)
+            }]
+        );
+        assert!(!old.equivalent_within(&new, 0.0));
+    }
+}
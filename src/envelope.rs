@@ -0,0 +1,122 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Axis-aligned bounding boxes.
+
+use crate::{ewkb, types as postgis};
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+/// 2D bounding box with minimum and maximum coordinates.
+#[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Envelope {
+    pub xmin: f64,
+    pub ymin: f64,
+    pub xmax: f64,
+    pub ymax: f64,
+}
+
+impl Envelope {
+    pub fn new(xmin: f64, ymin: f64, xmax: f64, ymax: f64) -> Self {
+        Envelope {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.xmax - self.xmin
+    }
+
+    pub fn height(&self) -> f64 {
+        self.ymax - self.ymin
+    }
+
+    /// Center of the box as `(x, y)`.
+    pub fn center(&self) -> (f64, f64) {
+        ((self.xmin + self.xmax) / 2.0, (self.ymin + self.ymax) / 2.0)
+    }
+
+    /// Box grown by `by` on every side. Negative values shrink the box.
+    pub fn expand(&self, by: f64) -> Self {
+        Envelope::new(
+            self.xmin - by,
+            self.ymin - by,
+            self.xmax + by,
+            self.ymax + by,
+        )
+    }
+
+    /// Smallest box containing both boxes.
+    pub fn union(&self, other: &Envelope) -> Self {
+        Envelope::new(
+            self.xmin.min(other.xmin),
+            self.ymin.min(other.ymin),
+            self.xmax.max(other.xmax),
+            self.ymax.max(other.ymax),
+        )
+    }
+
+    /// Whether the boxes share at least one point, including touching edges.
+    pub fn intersects(&self, other: &Envelope) -> bool {
+        self.xmin <= other.xmax
+            && other.xmin <= self.xmax
+            && self.ymin <= other.ymax
+            && other.ymin <= self.ymax
+    }
+
+    /// Whether the point lies inside or on the boundary of the box.
+    pub fn contains_point<P: postgis::Point>(&self, p: &P) -> bool {
+        p.x() >= self.xmin && p.x() <= self.xmax && p.y() >= self.ymin && p.y() <= self.ymax
+    }
+
+    /// Box as counter-clockwise polygon.
+    pub fn to_polygon(&self, srid: Option<i32>) -> ewkb::Polygon {
+        let p = |x, y| ewkb::Point::new(x, y, srid);
+        let ring = ewkb::LineString {
+            points: vec![
+                p(self.xmin, self.ymin),
+                p(self.xmax, self.ymin),
+                p(self.xmax, self.ymax),
+                p(self.xmin, self.ymax),
+                p(self.xmin, self.ymin),
+            ]
+            .into_iter()
+            .collect(),
+            srid,
+        };
+        ewkb::Polygon {
+            rings: core::iter::once(ring).collect(),
+            srid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_ops() {
+        let a = Envelope::new(0.0, 0.0, 2.0, 1.0);
+        let b = Envelope::new(2.0, 1.0, 3.0, 4.0);
+        assert_eq!(a.expand(1.0), Envelope::new(-1.0, -1.0, 3.0, 2.0));
+        assert_eq!(a.union(&b), Envelope::new(0.0, 0.0, 3.0, 4.0));
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&b.expand(-0.5)));
+        assert!(a.contains_point(&ewkb::Point::new(2.0, 0.5, None)));
+        assert!(!a.contains_point(&ewkb::Point::new(2.1, 0.5, None)));
+
+        let poly = a.to_polygon(Some(3857));
+        assert_eq!(poly.srid, Some(3857));
+        assert_eq!(poly.rings[0].points.len(), 5);
+        assert_eq!(
+            poly.rings[0].points[2],
+            ewkb::Point::new(2.0, 1.0, Some(3857))
+        );
+    }
+}
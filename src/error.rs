@@ -5,8 +5,8 @@
 //  Description : PostGIS helper
 //  Time-stamp: <2015-06-13 19:21:08 andelf>
 
-use std;
-use std::fmt;
+use alloc::string::String;
+use core::fmt;
 
 #[derive(Debug)]
 pub enum Error {
@@ -21,6 +21,7 @@ impl fmt::Display for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
@@ -5,17 +5,30 @@
 //!
 //! Support for SRID information according to [PostGIS EWKB extensions](https://git.osgeo.org/gitea/postgis/postgis/src/branch/master/doc/ZMSgeoms.txt)
 
-use crate::{error::Error, types as postgis};
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
-use std;
-use std::fmt;
-use std::io::prelude::*;
-use std::iter::FromIterator;
-use std::slice::Iter;
+use crate::io::{Read, ReadBytesExt, Write, WriteBytesExt};
+use crate::hex::{self, HexCase};
+use crate::{coord::Coord, envelope::Envelope, error::Error, types as postgis};
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec, vec::Vec};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use core::ops::{Index, IndexMut};
+use core::slice::{Iter, IterMut};
+
+pub mod soa;
+
+#[cfg(all(feature = "std", feature = "tokio"))]
+mod async_write;
+#[cfg(all(feature = "std", feature = "tokio"))]
+pub use self::async_write::{AsyncEwkbWrite, WriteEwkbFuture};
 
 // --- Structs for reading PostGIS geometries into
 
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -23,6 +36,7 @@ pub struct Point {
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointZ {
     pub x: f64,
     pub y: f64,
@@ -31,6 +45,7 @@ pub struct PointZ {
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointM {
     pub x: f64,
     pub y: f64,
@@ -39,6 +54,7 @@ pub struct PointM {
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointZM {
     pub x: f64,
     pub y: f64,
@@ -47,6 +63,120 @@ pub struct PointZM {
     pub srid: Option<i32>,
 }
 
+
+/// Bit-exact comparison and hashing of coordinates, see [`BitExact`].
+#[doc(hidden)]
+pub trait BitEq {
+    fn bit_eq(&self, other: &Self) -> bool;
+    fn bit_hash<H: Hasher>(&self, state: &mut H);
+}
+
+impl BitEq for f64 {
+    fn bit_eq(&self, other: &Self) -> bool {
+        self.to_bits() == other.to_bits()
+    }
+
+    fn bit_hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bits().hash(state);
+    }
+}
+
+impl<T: BitEq> BitEq for Option<T> {
+    fn bit_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.bit_eq(b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+
+    fn bit_hash<H: Hasher>(&self, state: &mut H) {
+        self.is_some().hash(state);
+        if let Some(v) = self {
+            v.bit_hash(state);
+        }
+    }
+}
+
+impl<T: BitEq> BitEq for [T] {
+    fn bit_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.bit_eq(b))
+    }
+
+    fn bit_hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for item in self {
+            item.bit_hash(state);
+        }
+    }
+}
+
+impl<T: BitEq + ?Sized> BitEq for &T {
+    fn bit_eq(&self, other: &Self) -> bool {
+        (**self).bit_eq(*other)
+    }
+
+    fn bit_hash<H: Hasher>(&self, state: &mut H) {
+        (**self).bit_hash(state);
+    }
+}
+
+macro_rules! impl_point_bit_eq {
+    ($ptype:ident: $($field:ident),+) => {
+        impl BitEq for $ptype {
+            fn bit_eq(&self, other: &Self) -> bool {
+                $(self.$field.bit_eq(&other.$field) &&)+ self.srid == other.srid
+            }
+
+            fn bit_hash<H: Hasher>(&self, state: &mut H) {
+                $(self.$field.bit_hash(state);)+
+                self.srid.hash(state);
+            }
+        }
+    };
+}
+
+impl_point_bit_eq!(Point: x, y);
+impl_point_bit_eq!(PointZ: x, y, z);
+impl_point_bit_eq!(PointM: x, y, m);
+impl_point_bit_eq!(PointZM: x, y, z, m);
+
+/// Wrapper comparing and hashing geometries bit-exactly, so that they can be
+/// used as HashMap/HashSet keys. Unlike `==` on the geometry types, 0.0 and
+/// -0.0 are distinct and NaN equals NaN.
+///
+/// ```rust
+/// use postgis::ewkb::{BitExact, Point};
+/// use std::collections::HashSet;
+///
+/// let mut set = HashSet::new();
+/// assert!(set.insert(BitExact(Point::new(1.0, f64::NAN, None))));
+/// assert!(!set.insert(BitExact(Point::new(1.0, f64::NAN, None))));
+/// assert!(set.insert(BitExact(Point::new(-0.0, 2.0, None))));
+/// assert!(set.insert(BitExact(Point::new(0.0, 2.0, None))));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BitExact<T>(pub T);
+
+impl<T> BitExact<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: BitEq> PartialEq for BitExact<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.bit_eq(&other.0)
+    }
+}
+
+impl<T: BitEq> Eq for BitExact<T> {}
+
+impl<T: BitEq> Hash for BitExact<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.bit_hash(state);
+    }
+}
+
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum PointType {
     Point,
@@ -60,16 +190,74 @@ pub enum PointType {
 pub trait EwkbRead: fmt::Debug + Sized {
     fn point_type() -> PointType;
 
+    /// Read an EWKB geometry.
+    ///
+    /// Coordinates are read value by value. Unbuffered readers like files or
+    /// sockets should be wrapped in a `BufReader`. Byte slices are read faster
+    /// with [`read_ewkb_from_slice`](EwkbRead::read_ewkb_from_slice).
     fn read_ewkb<R: Read>(raw: &mut R) -> Result<Self, Error> {
-        let byte_order = raw.read_i8()?;
-        let is_be = byte_order == 0i8;
+        Self::read_ewkb_with(raw, &ReadOptions::default())
+    }
+
+    /// Read an EWKB geometry with the given options.
+    ///
+    /// ```rust
+    /// use postgis::ewkb::{CoordPolicy, EwkbRead, Point, ReadOptions};
+    ///
+    /// // 'POINT EMPTY'
+    /// let empty = [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xf8, 0x7f, 0, 0, 0, 0, 0, 0, 0xf8, 0x7f];
+    /// let options = ReadOptions { coord_policy: CoordPolicy::Reject };
+    /// assert!(Point::read_ewkb_with(&mut &empty[..], &options).is_err());
+    /// let options = ReadOptions { coord_policy: CoordPolicy::Replace(0.0) };
+    /// let point = Point::read_ewkb_with(&mut &empty[..], &options).unwrap();
+    /// assert_eq!(point, Point::new(0.0, 0.0, None));
+    /// ```
+    fn read_ewkb_with<R: Read>(raw: &mut R, options: &ReadOptions) -> Result<Self, Error> {
+        let (is_be, type_id, srid) = read_type_and_srid(raw)?;
+        Self::read_ewkb_body(raw, is_be, type_id, srid, options)
+    }
+
+    /// Read an EWKB geometry from a byte slice.
+    ///
+    /// The coordinates of points, lines and rings are decoded directly from
+    /// the slice after checking the length of the whole block once.
+    fn read_ewkb_from_slice(mut data: &[u8]) -> Result<Self, Error> {
+        Self::read_ewkb_slice_with(&mut data, &ReadOptions::default())
+    }
 
-        let type_id = read_u32(raw, is_be)?;
-        let mut srid: Option<i32> = None;
-        if type_id & 0x20000000 == 0x20000000 {
-            srid = Some(read_i32(raw, is_be)?);
+    /// Read an EWKB geometry which has to fill the whole slice.
+    /// Fails if bytes are left after the geometry.
+    fn read_ewkb_exact(mut data: &[u8]) -> Result<Self, Error> {
+        let geom = Self::read_ewkb_slice_with(&mut data, &ReadOptions::default())?;
+        if !data.is_empty() {
+            return Err(Error::Read(format!(
+                "{} trailing bytes after geometry",
+                data.len()
+            )));
         }
-        Self::read_ewkb_body(raw, is_be, type_id, srid)
+        Ok(geom)
+    }
+
+    /// Read the geometry following a header already read with [`read_header`],
+    /// e.g. for dispatching on the geometry type.
+    ///
+    /// `raw` has to be positioned behind the header, see [`EwkbHeader::size`].
+    ///
+    /// ```rust
+    /// use postgis::ewkb::{self, AsEwkbPoint, EwkbRead, EwkbWrite, Point};
+    ///
+    /// let mut buf = Vec::new();
+    /// Point::new(10.0, -20.0, Some(4326)).as_ewkb().write_ewkb(&mut buf).unwrap();
+    /// let header = ewkb::read_header(&buf).unwrap();
+    /// if header.geom_type == ewkb::WKB_POINT {
+    ///     let point = Point::read_ewkb_body_with_header(&header, &mut &buf[header.size()..]).unwrap();
+    ///     assert_eq!(point, Point::new(10.0, -20.0, Some(4326)));
+    /// }
+    /// ```
+    fn read_ewkb_body_with_header<R: Read>(header: &EwkbHeader, raw: &mut R) -> Result<Self, Error> {
+        let type_id = header.geom_type | dims_flags(header.point_type(), header.srid.is_some());
+        let is_be = header.byte_order == Endianness::BigEndian;
+        Self::read_ewkb_body(raw, is_be, type_id, header.srid, &ReadOptions::default())
     }
 
     #[doc(hidden)]
@@ -78,7 +266,42 @@ pub trait EwkbRead: fmt::Debug + Sized {
         is_be: bool,
         type_id: u32,
         srid: Option<i32>,
+        options: &ReadOptions,
     ) -> Result<Self, Error>;
+
+    #[doc(hidden)]
+    fn read_ewkb_slice_with(raw: &mut &[u8], options: &ReadOptions) -> Result<Self, Error> {
+        let (is_be, type_id, srid) = read_type_and_srid(raw)?;
+        Self::read_ewkb_body_slice(raw, is_be, type_id, srid, options)
+    }
+
+    // Types with point blocks decode them from the slice, others read the
+    // slice like any reader.
+    #[doc(hidden)]
+    fn read_ewkb_body_slice(
+        raw: &mut &[u8],
+        is_be: bool,
+        type_id: u32,
+        srid: Option<i32>,
+        options: &ReadOptions,
+    ) -> Result<Self, Error> {
+        Self::read_ewkb_body(raw, is_be, type_id, srid, options)
+    }
+
+    // `npoints` consecutive point bodies, as in LineStrings and rings
+    #[doc(hidden)]
+    fn read_points_slice(
+        raw: &mut &[u8],
+        npoints: usize,
+        is_be: bool,
+        type_id: u32,
+        srid: Option<i32>,
+        options: &ReadOptions,
+    ) -> Result<Vec<Self>, Error> {
+        (0..npoints)
+            .map(|_| Self::read_ewkb_body(raw, is_be, type_id, srid, options))
+            .collect()
+    }
 }
 
 pub trait EwkbWrite: fmt::Debug + Sized {
@@ -87,46 +310,180 @@ pub trait EwkbWrite: fmt::Debug + Sized {
     }
 
     fn wkb_type_id(point_type: &PointType, srid: Option<i32>) -> u32 {
-        let mut type_ = 0;
-        if srid.is_some() {
-            type_ |= 0x20000000;
-        }
-        if *point_type == PointType::PointZ || *point_type == PointType::PointZM {
-            type_ |= 0x80000000;
-        }
-        if *point_type == PointType::PointM || *point_type == PointType::PointZM {
-            type_ |= 0x40000000;
-        }
-        type_
+        dims_flags(*point_type, srid.is_some())
     }
 
     fn type_id(&self) -> u32;
 
+    /// Write little endian EWKB. Coordinates are written bit for bit, keeping
+    /// the sign of zero and NaN payloads, so geometries read from PostGIS are
+    /// written back byte-identical.
     fn write_ewkb<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        self.write_ewkb_with(w, &WriteOptions::default())
+    }
+
+    /// Write little endian EWKB with the given options.
+    ///
+    /// ```rust
+    /// use postgis::ewkb::{AsEwkbPoint, EwkbRead, EwkbWrite, Point, WriteOptions, WritePrecision};
+    ///
+    /// let point = Point::new(0.1 + 0.2, 7.123456789, None);
+    /// let options = WriteOptions { precision: WritePrecision::Decimals(3), ..Default::default() };
+    /// let mut buf = Vec::new();
+    /// point.as_ewkb().write_ewkb_with(&mut buf, &options).unwrap();
+    /// assert_eq!(Point::read_ewkb(&mut buf.as_slice()).unwrap(), Point::new(0.3, 7.123, None));
+    /// ```
+    fn write_ewkb_with<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), Error> {
         // use LE
         w.write_u8(0x01)?;
         let type_id = self.type_id();
         w.write_u32::<LittleEndian>(type_id)?;
         self.opt_srid()
             .map(|srid| w.write_i32::<LittleEndian>(srid));
-        self.write_ewkb_body(w)?;
+        self.write_ewkb_body(w, options)?;
         Ok(())
     }
     #[doc(hidden)]
-    fn write_ewkb_body<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error>;
+    fn write_ewkb_body<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), Error>;
+
+    /// Exact number of bytes written by `write_ewkb`.
+    fn ewkb_size(&self) -> usize {
+        self.ewkb_size_with(&WriteOptions::default())
+    }
+
+    /// Exact number of bytes written by `write_ewkb_with`.
+    fn ewkb_size_with(&self, options: &WriteOptions) -> usize {
+        let srid_size = if self.opt_srid().is_some() { 4 } else { 0 };
+        1 + 4 + srid_size + self.ewkb_body_size(options)
+    }
+
+    // Writers of this crate compute the size from the point and item counts.
+    // This fallback writes the body to count its bytes.
+    #[doc(hidden)]
+    fn ewkb_body_size(&self, options: &WriteOptions) -> usize {
+        let mut counter = ByteCounter(0);
+        let _ = self.write_ewkb_body(&mut counter, options);
+        counter.0
+    }
+
+    /// Write into a preallocated buffer and return the number of bytes written.
+    fn write_ewkb_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let size = self.ewkb_size();
+        if buf.len() < size {
+            return Err(Error::Write(format!(
+                "buffer too small: {} bytes needed, {} available",
+                size,
+                buf.len()
+            )));
+        }
+        self.write_ewkb(&mut &mut buf[..size])?;
+        Ok(size)
+    }
 
     fn to_hex_ewkb(&self) -> String {
-        let mut buf: Vec<u8> = Vec::new();
-        let _ = self.write_ewkb(&mut buf).unwrap();
-        let hex: String = buf
-            .iter()
-            .fold(String::new(), |s, &b| s + &format!("{:02X}", b));
+        let mut hex = String::with_capacity(2 * self.ewkb_size());
+        self.write_hex_ewkb(&mut hex).unwrap();
         hex
     }
+
+    /// Write EWKB as upper case hex string.
+    fn write_hex_ewkb<W: fmt::Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        self.write_hex_ewkb_with(w, HexCase::Upper)
+    }
+
+    /// Write EWKB as hex string with digits in the given case.
+    fn write_hex_ewkb_with<W: fmt::Write + ?Sized>(
+        &self,
+        w: &mut W,
+        case: HexCase,
+    ) -> Result<(), Error> {
+        self.write_ewkb(&mut HexWriter(w, case))
+    }
+}
+
+/// Point types which can be created from optional Z and M values.
+pub trait NewFromOptVals: Sized {
+    /// Create a point, ignoring values for dimensions the point type doesn't have.
+    /// Panics if a value for a dimension of the point type is `None`.
+    fn new_from_opt_vals(
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        m: Option<f64>,
+        srid: Option<i32>,
+    ) -> Self;
+}
+
+/// SRID access for all ewkb geometry types.
+pub trait Srid {
+    fn srid(&self) -> Option<i32>;
+
+    /// Set the SRID of the geometry and all its parts.
+    fn set_srid(&mut self, srid: Option<i32>);
 }
 
 // --- helpers
 
+// Writer encoding the bytes written into it as hex characters
+struct HexWriter<'a, W: fmt::Write + ?Sized>(&'a mut W, HexCase);
+
+impl<'a, W: fmt::Write + ?Sized> HexWriter<'a, W> {
+    fn encode(&mut self, buf: &[u8]) -> fmt::Result {
+        hex::encode(self.0, buf, self.1)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, W: fmt::Write + ?Sized> Write for HexWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.encode(buf)
+            .map_err(|_| std::io::Error::other("formatter error"))?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<'a, W: fmt::Write + ?Sized> Write for HexWriter<'a, W> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
+        self.encode(buf)
+            .map_err(|_| Error::Write("formatter error".into()))
+    }
+}
+
+// Writer counting the bytes written into it
+struct ByteCounter(usize);
+
+#[cfg(feature = "std")]
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Write for ByteCounter {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
+        self.0 += buf.len();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Error {
         Error::Read(format!("error while reading: {:?}", e))
@@ -157,13 +514,274 @@ fn read_f64<R: Read>(raw: &mut R, is_be: bool) -> Result<f64, Error> {
     })
 }
 
+// Coordinate value with the coordinate policy applied
+fn read_coord<R: Read>(raw: &mut R, is_be: bool, options: &ReadOptions) -> Result<f64, Error> {
+    options.coord_policy.apply(read_f64(raw, is_be)?)
+}
+
+// Byte order, type id and SRID of an EWKB header
+fn read_type_and_srid<R: Read>(raw: &mut R) -> Result<(bool, u32, Option<i32>), Error> {
+    let byte_order = raw.read_i8()?;
+    let is_be = byte_order == 0i8;
+
+    let type_id = read_u32(raw, is_be)?;
+    let mut srid: Option<i32> = None;
+    if type_id & EWKB_SRID_FLAG == EWKB_SRID_FLAG {
+        srid = Some(read_i32(raw, is_be)?);
+    }
+    Ok((is_be, type_id, srid))
+}
+
+// Split off the coordinates of `npoints` points, checking the length once
+fn take_points<'a>(raw: &mut &'a [u8], npoints: usize, type_id: u32) -> Result<&'a [u8], Error> {
+    let point_size = point_size(type_id);
+    let len = npoints
+        .checked_mul(point_size)
+        .filter(|&len| len <= raw.len())
+        .ok_or_else(|| {
+            Error::Read(format!(
+                "{} points of {} bytes exceed the remaining {} bytes",
+                npoints,
+                point_size,
+                raw.len()
+            ))
+        })?;
+    let (block, rest) = raw.split_at(len);
+    *raw = rest;
+    Ok(block)
+}
+
+fn point_size(type_id: u32) -> usize {
+    8 * (2 + has_z(type_id) as usize + has_m(type_id) as usize)
+}
+
+// Decode a point from its `point_size` bytes. Also returns whether all
+// values are finite, the coordinate policy is not applied.
+#[inline]
+fn decode_point<P: NewFromOptVals>(
+    chunk: &[u8],
+    is_be: bool,
+    type_id: u32,
+    srid: Option<i32>,
+) -> (P, bool) {
+    let mut values = chunk.chunks_exact(8).map(|b| {
+        let b: [u8; 8] = b.try_into().unwrap();
+        if is_be {
+            f64::from_be_bytes(b)
+        } else {
+            f64::from_le_bytes(b)
+        }
+    });
+    let mut next = || values.next().unwrap_or(f64::NAN);
+    let (x, y) = (next(), next());
+    let z = if has_z(type_id) { Some(next()) } else { None };
+    let m = if has_m(type_id) { Some(next()) } else { None };
+    let finite = |v: Option<f64>| v.is_none_or(f64::is_finite);
+    let all_finite = x.is_finite() && y.is_finite() && finite(z) && finite(m);
+    (P::new_from_opt_vals(x, y, z, m, srid), all_finite)
+}
+
+// --- Non-finite coordinates
+
+/// Handling of NaN and infinite coordinates, see [`ReadOptions`] and
+/// [`WriteOptions`].
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum CoordPolicy {
+    /// Keep the values. `POINT EMPTY` is read as point with NaN coordinates.
+    #[default]
+    Accept,
+    /// Fail with an error.
+    Reject,
+    /// Replace non-finite values with the given value.
+    Replace(f64),
+}
+
+impl CoordPolicy {
+    /// Apply the policy to a coordinate value.
+    pub fn apply(&self, value: f64) -> Result<f64, Error> {
+        if value.is_finite() {
+            return Ok(value);
+        }
+        match *self {
+            CoordPolicy::Accept => Ok(value),
+            CoordPolicy::Reject => Err(Error::Other(format!(
+                "non-finite coordinate value {}",
+                value
+            ))),
+            CoordPolicy::Replace(replacement) => Ok(replacement),
+        }
+    }
+}
+
+/// Options for [`EwkbRead::read_ewkb_with`]. The default reads geometries
+/// unchanged, like [`EwkbRead::read_ewkb`].
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct ReadOptions {
+    /// Handling of NaN and infinite coordinates.
+    pub coord_policy: CoordPolicy,
+}
+
+// --- Precision of written coordinates
+
+/// Rounding of X and Y when writing EWKB, see [`WriteOptions`].
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum WritePrecision {
+    /// Write coordinates unchanged.
+    #[default]
+    Exact,
+    /// Round to the given number of decimal places.
+    Decimals(i32),
+    /// Snap to multiples of the grid size, like `ST_SnapToGrid`.
+    Grid(f64),
+}
+
+impl WritePrecision {
+    /// Round a coordinate value. Non-finite values are kept, as are values
+    /// which overflow when scaled to the number of decimals.
+    pub fn apply(&self, value: f64) -> f64 {
+        if !value.is_finite() {
+            return value;
+        }
+        match *self {
+            WritePrecision::Exact => value,
+            WritePrecision::Decimals(decimals) => {
+                let factor = 10f64.powi(decimals);
+                let scaled = value * factor;
+                if factor == 0.0 || !factor.is_finite() || !scaled.is_finite() {
+                    return value;
+                }
+                scaled.round() / factor
+            }
+            WritePrecision::Grid(size) if size > 0.0 => (value / size).round() * size,
+            WritePrecision::Grid(_) => value,
+        }
+    }
+}
+
+/// Options for [`EwkbWrite::write_ewkb_with`]. The default writes geometries
+/// unchanged, like [`EwkbWrite::write_ewkb`].
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct WriteOptions {
+    /// Rounding of X and Y, e.g. to drop the noise of floating point
+    /// computations before storing geometries. Z and M values are written
+    /// unchanged.
+    pub precision: WritePrecision,
+    /// Write the SRID of Multi* geometries and GeometryCollections also on
+    /// each nested geometry. By default children are written without SRID
+    /// flag like PostGIS does. Some importers of other databases expect the
+    /// SRID on every element.
+    ///
+    /// ```rust
+    /// use postgis::ewkb::{AsEwkbMultiPoint, EwkbWrite, MultiPoint, Point, WriteOptions};
+    /// use postgis::hex::ToHex;
+    ///
+    /// let mut points = MultiPoint::new_with_srid(Some(4326));
+    /// points.push(Point::new(1.0, 2.0, None));
+    /// let options = WriteOptions { child_srid: true, ..Default::default() };
+    /// let mut buf = Vec::new();
+    /// points.as_ewkb().write_ewkb_with(&mut buf, &options).unwrap();
+    /// assert_eq!(buf.to_hex(), "0104000020E6100000010000000101000020E6100000000000000000F03F0000000000000040");
+    /// assert_eq!(points.as_ewkb().ewkb_size_with(&options), buf.len());
+    /// assert_eq!(points.as_ewkb().to_hex_ewkb(), "0104000020E6100000010000000101000000000000000000F03F0000000000000040");
+    /// ```
+    pub child_srid: bool,
+    /// Handling of NaN and infinite coordinates.
+    pub coord_policy: CoordPolicy,
+}
+
+impl WriteOptions {
+    // SRID written on nested geometries of a geometry with `srid`
+    #[inline]
+    fn child_srid(&self, srid: Option<i32>) -> Option<i32> {
+        if self.child_srid {
+            srid
+        } else {
+            None
+        }
+    }
+}
+
+// --- Type codes and flags
+
+/// WKB type code of Point.
+pub const WKB_POINT: u32 = 0x01;
+/// WKB type code of LineString.
+pub const WKB_LINESTRING: u32 = 0x02;
+/// WKB type code of Polygon.
+pub const WKB_POLYGON: u32 = 0x03;
+/// WKB type code of MultiPoint.
+pub const WKB_MULTIPOINT: u32 = 0x04;
+/// WKB type code of MultiLineString.
+pub const WKB_MULTILINESTRING: u32 = 0x05;
+/// WKB type code of MultiPolygon.
+pub const WKB_MULTIPOLYGON: u32 = 0x06;
+/// WKB type code of GeometryCollection.
+pub const WKB_GEOMETRYCOLLECTION: u32 = 0x07;
+/// WKB type code of CircularString, not supported by this crate.
+pub const WKB_CIRCULARSTRING: u32 = 0x08;
+/// WKB type code of CompoundCurve, not supported by this crate.
+pub const WKB_COMPOUNDCURVE: u32 = 0x09;
+/// WKB type code of CurvePolygon, not supported by this crate.
+pub const WKB_CURVEPOLYGON: u32 = 0x0a;
+/// WKB type code of MultiCurve, not supported by this crate.
+pub const WKB_MULTICURVE: u32 = 0x0b;
+/// WKB type code of MultiSurface, not supported by this crate.
+pub const WKB_MULTISURFACE: u32 = 0x0c;
+/// WKB type code of Curve, not supported by this crate.
+pub const WKB_CURVE: u32 = 0x0d;
+/// WKB type code of Surface, not supported by this crate.
+pub const WKB_SURFACE: u32 = 0x0e;
+/// WKB type code of PolyhedralSurface, not supported by this crate.
+pub const WKB_POLYHEDRALSURFACE: u32 = 0x0f;
+/// WKB type code of TIN, not supported by this crate.
+pub const WKB_TIN: u32 = 0x10;
+/// WKB type code of Triangle, not supported by this crate.
+pub const WKB_TRIANGLE: u32 = 0x11;
+
+/// EWKB type flag of geometries with Z values.
+pub const EWKB_Z_FLAG: u32 = 0x80000000;
+/// EWKB type flag of geometries with M values.
+pub const EWKB_M_FLAG: u32 = 0x40000000;
+/// EWKB type flag of geometries with a SRID following the type.
+pub const EWKB_SRID_FLAG: u32 = 0x20000000;
+
+/// EWKB type id as written by this crate.
+///
+/// ```rust
+/// use postgis::ewkb::{self, GeometryType, PointType};
+///
+/// let type_id = ewkb::type_id_for(GeometryType::LineString, PointType::PointZ, true);
+/// assert_eq!(type_id, ewkb::WKB_LINESTRING | ewkb::EWKB_Z_FLAG | ewkb::EWKB_SRID_FLAG);
+/// ```
+pub fn type_id_for(kind: GeometryType, dims: PointType, has_srid: bool) -> u32 {
+    kind.code() | dims_flags(dims, has_srid)
+}
+
+fn dims_flags(dims: PointType, has_srid: bool) -> u32 {
+    let mut flags = 0;
+    if has_srid {
+        flags |= EWKB_SRID_FLAG;
+    }
+    if dims == PointType::PointZ || dims == PointType::PointZM {
+        flags |= EWKB_Z_FLAG;
+    }
+    if dims == PointType::PointM || dims == PointType::PointZM {
+        flags |= EWKB_M_FLAG;
+    }
+    flags
+}
+
 // --- Point
 
+// EWKB flags or ISO WKB type codes (1000 + type for Z, 2000 for M, 3000 for ZM)
 fn has_z(type_id: u32) -> bool {
-    type_id & 0x80000000 == 0x80000000
+    type_id & EWKB_Z_FLAG == EWKB_Z_FLAG || matches!((type_id & 0xffff) / 1000, 1 | 3)
 }
 fn has_m(type_id: u32) -> bool {
-    type_id & 0x40000000 == 0x40000000
+    type_id & EWKB_M_FLAG == EWKB_M_FLAG || matches!((type_id & 0xffff) / 1000, 2 | 3)
+}
+fn geometry_type_code(type_id: u32) -> u32 {
+    (type_id & 0xffff) % 1000
 }
 
 impl Point {
@@ -294,6 +912,25 @@ impl postgis::Point for PointZM {
     }
 }
 
+impl From<(f64, f64)> for Point {
+    fn from((x, y): (f64, f64)) -> Self {
+        Self::new(x, y, None)
+    }
+}
+
+impl From<(f64, f64, f64)> for PointZ {
+    fn from((x, y, z): (f64, f64, f64)) -> Self {
+        Self::new(x, y, z, None)
+    }
+}
+
+/// `PointM` has no tuple conversion, since `(x, y, z)` is already taken by `PointZ`.
+impl From<(f64, f64, f64, f64)> for PointZM {
+    fn from((x, y, z, m): (f64, f64, f64, f64)) -> Self {
+        Self::new(x, y, z, m, None)
+    }
+}
+
 macro_rules! impl_point_read_traits {
     ($ptype:ident) => {
         impl EwkbRead for $ptype {
@@ -305,21 +942,71 @@ macro_rules! impl_point_read_traits {
                 is_be: bool,
                 type_id: u32,
                 srid: Option<i32>,
+                options: &ReadOptions,
             ) -> Result<Self, Error> {
-                let x = read_f64(raw, is_be)?;
-                let y = read_f64(raw, is_be)?;
+                let x = read_coord(raw, is_be, options)?;
+                let y = read_coord(raw, is_be, options)?;
                 let z = if has_z(type_id) {
-                    Some(read_f64(raw, is_be)?)
+                    Some(read_coord(raw, is_be, options)?)
                 } else {
                     None
                 };
                 let m = if has_m(type_id) {
-                    Some(read_f64(raw, is_be)?)
+                    Some(read_coord(raw, is_be, options)?)
                 } else {
                     None
                 };
                 Ok(Self::new_from_opt_vals(x, y, z, m, srid))
             }
+            fn read_ewkb_body_slice(
+                raw: &mut &[u8],
+                is_be: bool,
+                type_id: u32,
+                srid: Option<i32>,
+                options: &ReadOptions,
+            ) -> Result<Self, Error> {
+                let mut block = take_points(raw, 1, type_id)?;
+                match decode_point(block, is_be, type_id, srid) {
+                    (point, true) => Ok(point),
+                    _ => Self::read_ewkb_body(&mut block, is_be, type_id, srid, options),
+                }
+            }
+            fn read_points_slice(
+                raw: &mut &[u8],
+                npoints: usize,
+                is_be: bool,
+                type_id: u32,
+                srid: Option<i32>,
+                options: &ReadOptions,
+            ) -> Result<Vec<Self>, Error> {
+                let mut block = take_points(raw, npoints, type_id)?;
+                let mut points = Vec::with_capacity(npoints);
+                let mut all_finite = true;
+                for chunk in block.chunks_exact(point_size(type_id)) {
+                    let (point, finite) = decode_point(chunk, is_be, type_id, srid);
+                    points.push(point);
+                    all_finite &= finite;
+                }
+                if !all_finite && options.coord_policy != CoordPolicy::Accept {
+                    // read again, applying the coordinate policy value by value
+                    return (0..npoints)
+                        .map(|_| Self::read_ewkb_body(&mut block, is_be, type_id, srid, options))
+                        .collect();
+                }
+                Ok(points)
+            }
+        }
+
+        impl NewFromOptVals for $ptype {
+            fn new_from_opt_vals(
+                x: f64,
+                y: f64,
+                z: Option<f64>,
+                m: Option<f64>,
+                srid: Option<i32>,
+            ) -> Self {
+                $ptype::new_from_opt_vals(x, y, z, m, srid)
+            }
         }
 
         impl<'a> AsEwkbPoint<'a> for $ptype {
@@ -363,92 +1050,298 @@ impl<'a> EwkbWrite for EwkbPoint<'a> {
     fn opt_srid(&self) -> Option<i32> {
         self.srid
     }
-    fn write_ewkb_body<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
-        w.write_f64::<LittleEndian>(self.geom.x())?;
-        w.write_f64::<LittleEndian>(self.geom.y())?;
-        self.geom.opt_z().map(|z| w.write_f64::<LittleEndian>(z));
-        self.geom.opt_m().map(|m| w.write_f64::<LittleEndian>(m));
+    fn write_ewkb_body<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), Error> {
+        // collect the coordinates for a single write call
+        let mut buf = [0u8; 32];
+        let mut len = 0;
+        let values = [
+            Some(options.precision.apply(self.geom.x())),
+            Some(options.precision.apply(self.geom.y())),
+            self.geom.opt_z(),
+            self.geom.opt_m(),
+        ];
+        for value in values.iter().flatten() {
+            let value = options.coord_policy.apply(*value)?;
+            LittleEndian::write_f64(&mut buf[len..len + 8], value);
+            len += 8;
+        }
+        w.write_all(&buf[..len])?;
         Ok(())
     }
+    fn ewkb_body_size(&self, _options: &WriteOptions) -> usize {
+        let dims = 2 + self.geom.opt_z().iter().count() + self.geom.opt_m().iter().count();
+        8 * dims
+    }
 }
 
-macro_rules! point_container_type {
-    // geometries containing points
-    ($geotypetrait:ident for $geotype:ident) => {
-        /// $geotypetrait
-        #[derive(PartialEq, Clone, Debug)]
-        pub struct $geotype<P: postgis::Point + EwkbRead> {
-            pub points: Vec<P>,
-            pub srid: Option<i32>,
-        }
-
-        impl<P: postgis::Point + EwkbRead> $geotype<P> {
-            pub fn new() -> $geotype<P> {
-                $geotype {
-                    points: Vec::new(),
-                    srid: None,
-                }
+macro_rules! impl_into_iterator {
+    ($geotype:ident, $itemname:ident: $item:ty) => {
+        impl_into_iterator!($geotype, $itemname: $item, Vec);
+    };
+    ($geotype:ident, $itemname:ident: $item:ty, $storage:ident) => {
+        impl<P> IntoIterator for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            type Item = $item;
+            type IntoIter = <$storage<$item> as IntoIterator>::IntoIter;
+            fn into_iter(self) -> Self::IntoIter {
+                self.$itemname.into_iter()
             }
         }
 
-        impl<P> FromIterator<P> for $geotype<P>
+        impl<'a, P> IntoIterator for &'a $geotype<P>
         where
             P: postgis::Point + EwkbRead,
         {
-            #[inline]
-            fn from_iter<I: IntoIterator<Item = P>>(iterable: I) -> $geotype<P> {
-                let iterator = iterable.into_iter();
-                let (lower, _) = iterator.size_hint();
-                let mut ret = $geotype::new();
-                ret.points.reserve(lower);
-                for item in iterator {
-                    ret.points.push(item);
-                }
-                ret
+            type Item = &'a $item;
+            type IntoIter = Iter<'a, $item>;
+            fn into_iter(self) -> Self::IntoIter {
+                self.$itemname.iter()
             }
         }
 
-        impl<'a, P> postgis::$geotypetrait<'a> for $geotype<P>
+        impl<'a, P> IntoIterator for &'a mut $geotype<P>
         where
-            P: 'a + postgis::Point + EwkbRead,
+            P: postgis::Point + EwkbRead,
         {
-            type ItemType = P;
-            type Iter = Iter<'a, Self::ItemType>;
-            fn points(&'a self) -> Self::Iter {
-                self.points.iter()
+            type Item = &'a mut $item;
+            type IntoIter = IterMut<'a, $item>;
+            fn into_iter(self) -> Self::IntoIter {
+                self.$itemname.iter_mut()
             }
         }
     };
 }
 
-macro_rules! geometry_container_type {
-    // geometries containing lines and polygons
-    ($geotypetrait:ident for $geotype:ident contains $itemtype:ident named $itemname:ident) => {
-        #[derive(PartialEq, Clone, Debug)]
-        pub struct $geotype<P: postgis::Point + EwkbRead> {
-            pub $itemname: Vec<$itemtype<P>>,
-            pub srid: Option<i32>,
+macro_rules! impl_iter_mut {
+    ($geotype:ident, $method:ident => $itemname:ident: $item:ty) => {
+        impl<P> $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            /// Mutable iterator over the
+            #[doc = concat!("`", stringify!($itemname), "`")]
+            /// for in-place editing.
+            pub fn $method(&mut self) -> IterMut<'_, $item> {
+                self.$itemname.iter_mut()
+            }
         }
+    };
+}
 
+macro_rules! impl_container_access {
+    ($geotype:ident, $itemname:ident: $item:ty) => {
         impl<P> $geotype<P>
         where
             P: postgis::Point + EwkbRead,
         {
-            pub fn new() -> $geotype<P> {
-                $geotype {
-                    $itemname: Vec::new(),
-                    srid: None,
-                }
+            /// Number of
+            #[doc = concat!("`", stringify!($itemname), "`.")]
+            pub fn len(&self) -> usize {
+                self.$itemname.len()
+            }
+            pub fn is_empty(&self) -> bool {
+                self.$itemname.is_empty()
+            }
+            pub fn get(&self, index: usize) -> Option<&$item> {
+                self.$itemname.get(index)
+            }
+            pub fn get_mut(&mut self, index: usize) -> Option<&mut $item> {
+                self.$itemname.get_mut(index)
+            }
+            pub fn push(&mut self, item: $item) {
+                self.$itemname.push(item)
+            }
+            pub fn pop(&mut self) -> Option<$item> {
+                self.$itemname.pop()
+            }
+            /// Insert at `index`. Panics if `index > len`.
+            pub fn insert(&mut self, index: usize, item: $item) {
+                self.$itemname.insert(index, item)
+            }
+            pub fn extend<I: IntoIterator<Item = $item>>(&mut self, iter: I) {
+                self.$itemname.extend(iter)
             }
         }
 
-        impl<P> FromIterator<$itemtype<P>> for $geotype<P>
+        impl<P> Extend<$item> for $geotype<P>
         where
             P: postgis::Point + EwkbRead,
         {
-            #[inline]
-            fn from_iter<I: IntoIterator<Item = $itemtype<P>>>(iterable: I) -> $geotype<P> {
-                let iterator = iterable.into_iter();
+            fn extend<I: IntoIterator<Item = $item>>(&mut self, iter: I) {
+                self.$itemname.extend(iter)
+            }
+        }
+
+        impl<P> Index<usize> for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            type Output = $item;
+            fn index(&self, index: usize) -> &$item {
+                &self.$itemname[index]
+            }
+        }
+
+        impl<P> IndexMut<usize> for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            fn index_mut(&mut self, index: usize) -> &mut $item {
+                &mut self.$itemname[index]
+            }
+        }
+    };
+}
+
+macro_rules! point_container_type {
+    // geometries containing points
+    ($geotypetrait:ident for $geotype:ident) => {
+        point_container_type!($geotypetrait for $geotype in Vec);
+    };
+    ($geotypetrait:ident for $geotype:ident in $storage:ident) => {
+        /// $geotypetrait
+        #[derive(PartialEq, Clone, Debug)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $geotype<P: postgis::Point + EwkbRead> {
+            pub points: $storage<P>,
+            pub srid: Option<i32>,
+        }
+
+        impl<P: postgis::Point + EwkbRead> $geotype<P> {
+            pub fn new() -> $geotype<P> {
+                $geotype {
+                    points: $storage::new(),
+                    srid: None,
+                }
+            }
+            pub fn new_with_srid(srid: Option<i32>) -> $geotype<P> {
+                $geotype {
+                    points: $storage::new(),
+                    srid,
+                }
+            }
+            pub fn with_capacity_and_srid(capacity: usize, srid: Option<i32>) -> $geotype<P> {
+                $geotype {
+                    points: $storage::with_capacity(capacity),
+                    srid,
+                }
+            }
+        }
+
+        impl<P: postgis::Point + EwkbRead + BitEq> BitEq for $geotype<P> {
+            fn bit_eq(&self, other: &Self) -> bool {
+                self.points[..].bit_eq(&other.points[..]) && self.srid == other.srid
+            }
+
+            fn bit_hash<H: Hasher>(&self, state: &mut H) {
+                self.points[..].bit_hash(state);
+                self.srid.hash(state);
+            }
+        }
+
+        impl<P> FromIterator<P> for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            #[inline]
+            fn from_iter<I: IntoIterator<Item = P>>(iterable: I) -> $geotype<P> {
+                let iterator = iterable.into_iter();
+                let (lower, _) = iterator.size_hint();
+                let mut ret = $geotype::new();
+                ret.points.reserve(lower);
+                for item in iterator {
+                    ret.points.push(item);
+                }
+                ret
+            }
+        }
+
+        impl_into_iterator!($geotype, points: P, $storage);
+        impl_container_access!($geotype, points: P);
+
+        impl<P, T> From<Vec<T>> for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead + From<T>,
+        {
+            fn from(points: Vec<T>) -> $geotype<P> {
+                points.into_iter().map(P::from).collect()
+            }
+        }
+
+        impl<'a, P> postgis::$geotypetrait<'a> for $geotype<P>
+        where
+            P: 'a + postgis::Point + EwkbRead,
+        {
+            type ItemType = P;
+            type Iter = Iter<'a, Self::ItemType>;
+            fn points(&'a self) -> Self::Iter {
+                self.points.iter()
+            }
+        }
+    };
+}
+
+macro_rules! geometry_container_type {
+    // geometries containing lines and polygons
+    ($geotypetrait:ident for $geotype:ident contains $itemtype:ident named $itemname:ident) => {
+        geometry_container_type!($geotypetrait for $geotype contains $itemtype named $itemname in Vec);
+    };
+    ($geotypetrait:ident for $geotype:ident contains $itemtype:ident named $itemname:ident in $storage:ident) => {
+        #[derive(PartialEq, Clone, Debug)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $geotype<P: postgis::Point + EwkbRead> {
+            pub $itemname: $storage<$itemtype<P>>,
+            pub srid: Option<i32>,
+        }
+
+        impl<P> $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            pub fn new() -> $geotype<P> {
+                $geotype {
+                    $itemname: $storage::new(),
+                    srid: None,
+                }
+            }
+            pub fn new_with_srid(srid: Option<i32>) -> $geotype<P> {
+                $geotype {
+                    $itemname: $storage::new(),
+                    srid,
+                }
+            }
+            pub fn with_capacity_and_srid(capacity: usize, srid: Option<i32>) -> $geotype<P> {
+                $geotype {
+                    $itemname: $storage::with_capacity(capacity),
+                    srid,
+                }
+            }
+        }
+
+        impl<P: postgis::Point + EwkbRead + BitEq> BitEq for $geotype<P> {
+            fn bit_eq(&self, other: &Self) -> bool {
+                self.$itemname[..].bit_eq(&other.$itemname[..]) && self.srid == other.srid
+            }
+
+            fn bit_hash<H: Hasher>(&self, state: &mut H) {
+                self.$itemname[..].bit_hash(state);
+                self.srid.hash(state);
+            }
+        }
+
+        impl<P> FromIterator<$itemtype<P>> for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            #[inline]
+            fn from_iter<I: IntoIterator<Item = $itemtype<P>>>(iterable: I) -> $geotype<P> {
+                let iterator = iterable.into_iter();
                 let (lower, _) = iterator.size_hint();
                 let mut ret = $geotype::new();
                 ret.$itemname.reserve(lower);
@@ -459,6 +1352,19 @@ macro_rules! geometry_container_type {
             }
         }
 
+        impl_into_iterator!($geotype, $itemname: $itemtype<P>, $storage);
+        impl_container_access!($geotype, $itemname: $itemtype<P>);
+
+        impl<P, T> From<Vec<T>> for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+            $itemtype<P>: From<T>,
+        {
+            fn from($itemname: Vec<T>) -> $geotype<P> {
+                $itemname.into_iter().map($itemtype::from).collect()
+            }
+        }
+
         impl<'a, P> postgis::$geotypetrait<'a> for $geotype<P>
         where
             P: 'a + postgis::Point + EwkbRead,
@@ -486,16 +1392,27 @@ macro_rules! impl_read_for_point_container_type {
                 is_be: bool,
                 type_id: u32,
                 srid: Option<i32>,
+                options: &ReadOptions,
             ) -> Result<Self, Error> {
-                let mut points: Vec<P> = vec![];
+                let mut geom = $geotype::<P>::new_with_srid(srid);
                 let size = read_u32(raw, is_be)? as usize;
                 for _ in 0..size {
-                    points.push(P::read_ewkb_body(raw, is_be, type_id, srid)?);
+                    geom.points.push(P::read_ewkb_body(raw, is_be, type_id, srid, options)?);
                 }
-                Ok($geotype::<P> {
-                    points: points,
-                    srid: srid,
-                })
+                Ok(geom)
+            }
+            fn read_ewkb_body_slice(
+                raw: &mut &[u8],
+                is_be: bool,
+                type_id: u32,
+                srid: Option<i32>,
+                options: &ReadOptions,
+            ) -> Result<Self, Error> {
+                let mut geom = $geotype::<P>::new_with_srid(srid);
+                let size = read_u32(raw, is_be)? as usize;
+                let points = P::read_points_slice(raw, size, is_be, type_id, srid, options)?;
+                geom.points = points.into_iter().collect();
+                Ok(geom)
             }
         }
     };
@@ -512,16 +1429,28 @@ macro_rules! impl_read_for_point_container_type {
                 is_be: bool,
                 _type_id: u32,
                 srid: Option<i32>,
+                options: &ReadOptions,
             ) -> Result<Self, Error> {
-                let mut points: Vec<P> = vec![];
+                let mut geom = $geotype::<P>::new_with_srid(srid);
                 let size = read_u32(raw, is_be)? as usize;
                 for _ in 0..size {
-                    points.push(P::read_ewkb(raw)?);
+                    geom.points.push(P::read_ewkb_with(raw, options)?);
                 }
-                Ok($geotype::<P> {
-                    points: points,
-                    srid: srid,
-                })
+                Ok(geom)
+            }
+            fn read_ewkb_body_slice(
+                raw: &mut &[u8],
+                is_be: bool,
+                _type_id: u32,
+                srid: Option<i32>,
+                options: &ReadOptions,
+            ) -> Result<Self, Error> {
+                let mut geom = $geotype::<P>::new_with_srid(srid);
+                let size = read_u32(raw, is_be)? as usize;
+                for _ in 0..size {
+                    geom.points.push(P::read_ewkb_slice_with(raw, options)?);
+                }
+                Ok(geom)
             }
         }
     };
@@ -541,16 +1470,28 @@ macro_rules! impl_read_for_geometry_container_type {
                 is_be: bool,
                 type_id: u32,
                 srid: Option<i32>,
+                options: &ReadOptions,
             ) -> Result<Self, Error> {
-                let mut $itemname: Vec<$itemtype<P>> = vec![];
+                let mut geom = $geotype::<P>::new_with_srid(srid);
                 let size = read_u32(raw, is_be)? as usize;
                 for _ in 0..size {
-                    $itemname.push($itemtype::read_ewkb_body(raw, is_be, type_id, srid)?);
+                    geom.$itemname.push($itemtype::read_ewkb_body(raw, is_be, type_id, srid, options)?);
                 }
-                Ok($geotype::<P> {
-                    $itemname: $itemname,
-                    srid: srid,
-                })
+                Ok(geom)
+            }
+            fn read_ewkb_body_slice(
+                raw: &mut &[u8],
+                is_be: bool,
+                type_id: u32,
+                srid: Option<i32>,
+                options: &ReadOptions,
+            ) -> Result<Self, Error> {
+                let mut geom = $geotype::<P>::new_with_srid(srid);
+                let size = read_u32(raw, is_be)? as usize;
+                for _ in 0..size {
+                    geom.$itemname.push($itemtype::read_ewkb_body_slice(raw, is_be, type_id, srid, options)?);
+                }
+                Ok(geom)
             }
         }
     };
@@ -567,23 +1508,38 @@ macro_rules! impl_read_for_geometry_container_type {
                 is_be: bool,
                 _type_id: u32,
                 srid: Option<i32>,
+                options: &ReadOptions,
             ) -> Result<Self, Error> {
                 let mut $itemname: Vec<$itemtype<P>> = vec![];
                 let size = read_u32(raw, is_be)? as usize;
                 for _ in 0..size {
-                    $itemname.push($itemtype::read_ewkb(raw)?);
+                    $itemname.push($itemtype::read_ewkb_with(raw, options)?);
                 }
                 Ok($geotype::<P> {
                     $itemname: $itemname,
                     srid: srid,
                 })
             }
+            fn read_ewkb_body_slice(
+                raw: &mut &[u8],
+                is_be: bool,
+                _type_id: u32,
+                srid: Option<i32>,
+                options: &ReadOptions,
+            ) -> Result<Self, Error> {
+                let mut $itemname: Vec<$itemtype<P>> = vec![];
+                let size = read_u32(raw, is_be)? as usize;
+                for _ in 0..size {
+                    $itemname.push($itemtype::read_ewkb_slice_with(raw, options)?);
+                }
+                Ok($geotype::<P> { $itemname, srid })
+            }
         }
     };
 }
 
 macro_rules! point_container_write {
-    ($geotypetrait:ident and $asewkbtype:ident for $geotype:ident to $ewkbtype:ident with type code $typecode:expr, command $writecmd:ident) => {
+    ($geotypetrait:ident and $asewkbtype:ident for $geotype:ident to $ewkbtype:ident with type code $typecode:expr, command $writecmd:ident, size $sizecmd:ident) => {
         pub struct $ewkbtype<'a, P, I>
         where
             P: 'a + postgis::Point,
@@ -625,18 +1581,37 @@ macro_rules! point_container_write {
                 $typecode | Self::wkb_type_id(&self.point_type, self.srid)
             }
 
-            fn write_ewkb_body<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+            fn write_ewkb_body<W: Write + ?Sized>(
+                &self,
+                w: &mut W,
+                options: &WriteOptions,
+            ) -> Result<(), Error> {
                 w.write_u32::<LittleEndian>(self.geom.points().len() as u32)?;
                 for geom in self.geom.points() {
                     let wkb = EwkbPoint {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.$writecmd(w)?;
+                    wkb.$writecmd(w, options)?;
                 }
                 Ok(())
             }
+
+            fn ewkb_body_size(&self, options: &WriteOptions) -> usize {
+                // all points have the same size
+                let mut points = self.geom.points();
+                let count = points.len();
+                let point_size = points.next().map_or(0, |geom| {
+                    EwkbPoint {
+                        geom,
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
+                    }
+                    .$sizecmd(options)
+                });
+                4 + count * point_size
+            }
         }
 
         impl<'a, P> $asewkbtype<'a> for $geotype<P>
@@ -657,7 +1632,7 @@ macro_rules! point_container_write {
 }
 
 macro_rules! geometry_container_write {
-    ($geotypetrait:ident and $asewkbtype:ident for $geotype:ident to $ewkbtype:ident with type code $typecode:expr, contains $ewkbitemtype:ident, $itemtype:ident as $itemtypetrait:ident named $itemname:ident, command $writecmd:ident) => {
+    ($geotypetrait:ident and $asewkbtype:ident for $geotype:ident to $ewkbtype:ident with type code $typecode:expr, contains $ewkbitemtype:ident, $itemtype:ident as $itemtypetrait:ident named $itemname:ident, command $writecmd:ident, size $sizecmd:ident) => {
         pub struct $ewkbtype<'a, P, I, T, J>
         where
             P: 'a + postgis::Point,
@@ -711,18 +1686,34 @@ macro_rules! geometry_container_write {
                 $typecode | Self::wkb_type_id(&self.point_type, self.srid)
             }
 
-            fn write_ewkb_body<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+            fn write_ewkb_body<W: Write + ?Sized>(
+                &self,
+                w: &mut W,
+                options: &WriteOptions,
+            ) -> Result<(), Error> {
                 w.write_u32::<LittleEndian>(self.geom.$itemname().len() as u32)?;
                 for geom in self.geom.$itemname() {
                     let wkb = $ewkbitemtype {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.$writecmd(w)?;
+                    wkb.$writecmd(w, options)?;
                 }
                 Ok(())
             }
+
+            fn ewkb_body_size(&self, options: &WriteOptions) -> usize {
+                let items = self.geom.$itemname().map(|geom| {
+                    $ewkbitemtype {
+                        geom,
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
+                    }
+                    .$sizecmd(options)
+                });
+                4 + items.sum::<usize>()
+            }
         }
 
         impl<'a, P> $asewkbtype<'a> for $geotype<P>
@@ -744,7 +1735,7 @@ macro_rules! geometry_container_write {
             }
         }
     };
-    (multipoly $geotypetrait:ident and $asewkbtype:ident for $geotype:ident to $ewkbtype:ident with type code $typecode:expr, contains $ewkbitemtype:ident, $itemtype:ident as $itemtypetrait:ident named $itemname:ident, command $writecmd:ident) => {
+    (multipoly $geotypetrait:ident and $asewkbtype:ident for $geotype:ident to $ewkbtype:ident with type code $typecode:expr, contains $ewkbitemtype:ident, $itemtype:ident as $itemtypetrait:ident named $itemname:ident, command $writecmd:ident, size $sizecmd:ident) => {
         pub struct $ewkbtype<'a, P, I, L, K, T, J>
         where
             P: 'a + postgis::Point,
@@ -816,18 +1807,34 @@ macro_rules! geometry_container_write {
                 $typecode | Self::wkb_type_id(&self.point_type, self.srid)
             }
 
-            fn write_ewkb_body<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+            fn write_ewkb_body<W: Write + ?Sized>(
+                &self,
+                w: &mut W,
+                options: &WriteOptions,
+            ) -> Result<(), Error> {
                 w.write_u32::<LittleEndian>(self.geom.$itemname().len() as u32)?;
                 for geom in self.geom.$itemname() {
                     let wkb = $ewkbitemtype {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.$writecmd(w)?;
+                    wkb.$writecmd(w, options)?;
                 }
                 Ok(())
             }
+
+            fn ewkb_body_size(&self, options: &WriteOptions) -> usize {
+                let items = self.geom.$itemname().map(|geom| {
+                    $ewkbitemtype {
+                        geom,
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
+                    }
+                    .$sizecmd(options)
+                });
+                4 + items.sum::<usize>()
+            }
         }
 
         impl<'a, P> $asewkbtype<'a> for $geotype<P>
@@ -863,9 +1870,10 @@ macro_rules! geometry_container_write {
 
 point_container_type!(LineString for LineStringT);
 impl_read_for_point_container_type!(singletype LineStringT);
+impl_iter_mut!(LineStringT, points_mut => points: P);
 point_container_write!(LineString and AsEwkbLineString for LineStringT
                        to EwkbLineString with type code 0x02,
-                       command write_ewkb_body);
+                       command write_ewkb_body, size ewkb_body_size);
 
 /// OGC LineString type
 pub type LineString = LineStringT<Point>;
@@ -878,10 +1886,11 @@ pub type LineStringZM = LineStringT<PointZM>;
 
 geometry_container_type!(Polygon for PolygonT contains LineStringT named rings);
 impl_read_for_geometry_container_type!(singletype PolygonT contains LineStringT named rings);
+impl_iter_mut!(PolygonT, rings_mut => rings: LineStringT<P>);
 geometry_container_write!(Polygon and AsEwkbPolygon for PolygonT
                           to EwkbPolygon with type code 0x03,
                           contains EwkbLineString,LineStringT as LineString named rings,
-                          command write_ewkb_body);
+                          command write_ewkb_body, size ewkb_body_size);
 
 /// OGC Polygon type
 pub type Polygon = PolygonT<Point>;
@@ -892,11 +1901,39 @@ pub type PolygonM = PolygonT<PointM>;
 /// OGC PolygonZM type
 pub type PolygonZM = PolygonT<PointZM>;
 
+impl<P> PolygonT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    /// Polygon with SRID of the exterior ring.
+    pub fn from_exterior_and_holes<I>(exterior: LineStringT<P>, holes: I) -> Self
+    where
+        I: IntoIterator<Item = LineStringT<P>>,
+    {
+        let srid = exterior.srid;
+        PolygonT {
+            rings: core::iter::once(exterior).chain(holes).collect(),
+            srid,
+        }
+    }
+
+    /// The first ring, `None` for empty polygons.
+    pub fn exterior(&self) -> Option<&LineStringT<P>> {
+        self.rings.first()
+    }
+
+    /// All rings after the exterior ring.
+    pub fn interiors(&self) -> &[LineStringT<P>] {
+        self.rings.get(1..).unwrap_or(&[])
+    }
+}
+
 point_container_type!(MultiPoint for MultiPointT);
 impl_read_for_point_container_type!(multitype MultiPointT);
+impl_iter_mut!(MultiPointT, points_mut => points: P);
 point_container_write!(MultiPoint and AsEwkbMultiPoint for MultiPointT
                        to EwkbMultiPoint with type code 0x04,
-                       command write_ewkb);
+                       command write_ewkb_with, size ewkb_size_with);
 
 /// OGC MultiPoint type
 pub type MultiPoint = MultiPointT<Point>;
@@ -909,10 +1946,11 @@ pub type MultiPointZM = MultiPointT<PointZM>;
 
 geometry_container_type!(MultiLineString for MultiLineStringT contains LineStringT named lines);
 impl_read_for_geometry_container_type!(multitype MultiLineStringT contains LineStringT named lines);
+impl_iter_mut!(MultiLineStringT, lines_mut => lines: LineStringT<P>);
 geometry_container_write!(MultiLineString and AsEwkbMultiLineString for MultiLineStringT
                           to EwkbMultiLineString with type code 0x05,
                           contains EwkbLineString,LineStringT as LineString named lines,
-                          command write_ewkb);
+                          command write_ewkb_with, size ewkb_size_with);
 
 /// OGC MultiLineString type
 pub type MultiLineString = MultiLineStringT<Point>;
@@ -925,10 +1963,11 @@ pub type MultiLineStringZM = MultiLineStringT<PointZM>;
 
 geometry_container_type!(MultiPolygon for MultiPolygonT contains PolygonT named polygons);
 impl_read_for_geometry_container_type!(multitype MultiPolygonT contains PolygonT named polygons);
+impl_iter_mut!(MultiPolygonT, polygons_mut => polygons: PolygonT<P>);
 geometry_container_write!(multipoly MultiPolygon and AsEwkbMultiPolygon for MultiPolygonT
                           to EwkbMultiPolygon with type code 0x06,
                           contains EwkbPolygon,PolygonT as Polygon named polygons,
-                          command write_ewkb);
+                          command write_ewkb_with, size ewkb_size_with);
 
 /// OGC MultiPolygon type
 pub type MultiPolygon = MultiPolygonT<Point>;
@@ -939,87 +1978,299 @@ pub type MultiPolygonM = MultiPolygonT<PointM>;
 /// OGC MultiPolygonZM type
 pub type MultiPolygonZM = MultiPolygonT<PointZM>;
 
-/// Generic Geometry Data Type
-#[derive(Clone, Debug)]
-pub enum GeometryT<P: postgis::Point + EwkbRead> {
-    Point(P),
-    LineString(LineStringT<P>),
-    Polygon(PolygonT<P>),
-    MultiPoint(MultiPointT<P>),
-    MultiLineString(MultiLineStringT<P>),
-    MultiPolygon(MultiPolygonT<P>),
-    GeometryCollection(GeometryCollectionT<P>),
+// --- Inline storage for small geometries
+
+/// Storage of [`SmallLineStringT`] points and [`SmallPolygonT`] rings. Up to
+/// 4 items are stored inline without heap allocation.
+#[cfg(feature = "smallvec")]
+pub type InlineVec<T> = smallvec::SmallVec<[T; 4]>;
+
+#[cfg(feature = "smallvec")]
+point_container_type!(LineString for SmallLineStringT in InlineVec);
+#[cfg(feature = "smallvec")]
+impl_read_for_point_container_type!(singletype SmallLineStringT);
+#[cfg(feature = "smallvec")]
+impl_iter_mut!(SmallLineStringT, points_mut => points: P);
+
+#[cfg(feature = "smallvec")]
+geometry_container_type!(Polygon for SmallPolygonT contains SmallLineStringT named rings in InlineVec);
+#[cfg(feature = "smallvec")]
+impl_read_for_geometry_container_type!(singletype SmallPolygonT contains SmallLineStringT named rings);
+#[cfg(feature = "smallvec")]
+impl_iter_mut!(SmallPolygonT, rings_mut => rings: SmallLineStringT<P>);
+
+#[cfg(feature = "smallvec")]
+impl<'a, P> AsEwkbLineString<'a> for SmallLineStringT<P>
+where
+    P: 'a + postgis::Point + EwkbRead,
+{
+    type PointType = P;
+    type Iter = Iter<'a, P>;
+    fn as_ewkb(&'a self) -> EwkbLineString<'a, Self::PointType, Self::Iter> {
+        EwkbLineString {
+            geom: self,
+            srid: self.srid,
+            point_type: P::point_type(),
+        }
+    }
 }
 
-impl<'a, P> postgis::Geometry<'a> for GeometryT<P>
+#[cfg(feature = "smallvec")]
+impl<'a, P> AsEwkbPolygon<'a> for SmallPolygonT<P>
 where
     P: 'a + postgis::Point + EwkbRead,
 {
-    type Point = P;
-    type LineString = LineStringT<P>;
-    type Polygon = PolygonT<P>;
-    type MultiPoint = MultiPointT<P>;
-    type MultiLineString = MultiLineStringT<P>;
-    type MultiPolygon = MultiPolygonT<P>;
-    type GeometryCollection = GeometryCollectionT<P>;
-    fn as_type(
+    type PointType = P;
+    type PointIter = Iter<'a, P>;
+    type ItemType = SmallLineStringT<P>;
+    type Iter = Iter<'a, Self::ItemType>;
+    fn as_ewkb(
         &'a self,
-    ) -> postgis::GeometryType<
-        'a,
-        P,
-        LineStringT<P>,
-        PolygonT<P>,
-        MultiPointT<P>,
-        MultiLineStringT<P>,
-        MultiPolygonT<P>,
-        GeometryCollectionT<P>,
-    > {
-        use crate::ewkb::GeometryT as A;
-        use crate::types::GeometryType as B;
-        match *self {
-            A::Point(ref geom) => B::Point(geom),
-            A::LineString(ref geom) => B::LineString(geom),
-            A::Polygon(ref geom) => B::Polygon(geom),
-            A::MultiPoint(ref geom) => B::MultiPoint(geom),
-            A::MultiLineString(ref geom) => B::MultiLineString(geom),
-            A::MultiPolygon(ref geom) => B::MultiPolygon(geom),
-            A::GeometryCollection(ref geom) => B::GeometryCollection(geom),
+    ) -> EwkbPolygon<'a, Self::PointType, Self::PointIter, Self::ItemType, Self::Iter> {
+        EwkbPolygon {
+            geom: self,
+            srid: self.srid,
+            point_type: P::point_type(),
         }
     }
 }
 
-impl<P> EwkbRead for GeometryT<P>
-where
-    P: postgis::Point + EwkbRead,
-{
-    fn point_type() -> PointType {
-        P::point_type()
+#[cfg(feature = "smallvec")]
+impl<P: postgis::Point + EwkbRead> From<LineStringT<P>> for SmallLineStringT<P> {
+    fn from(line: LineStringT<P>) -> Self {
+        SmallLineStringT {
+            points: line.points.into_iter().collect(),
+            srid: line.srid,
+        }
     }
-    fn read_ewkb<R: Read>(raw: &mut R) -> Result<Self, Error> {
-        let byte_order = raw.read_i8()?;
-        let is_be = byte_order == 0i8;
+}
 
-        let type_id = read_u32(raw, is_be)?;
-        let mut srid: Option<i32> = None;
-        if type_id & 0x20000000 == 0x20000000 {
-            srid = Some(read_i32(raw, is_be)?);
+#[cfg(feature = "smallvec")]
+impl<P: postgis::Point + EwkbRead> From<SmallLineStringT<P>> for LineStringT<P> {
+    fn from(line: SmallLineStringT<P>) -> Self {
+        LineStringT {
+            points: line.points.into_vec(),
+            srid: line.srid,
         }
+    }
+}
 
-        let geom = match type_id & 0xff {
-            0x01 => GeometryT::Point(P::read_ewkb_body(raw, is_be, type_id, srid)?),
-            0x02 => {
-                GeometryT::LineString(LineStringT::<P>::read_ewkb_body(raw, is_be, type_id, srid)?)
-            }
-            0x03 => GeometryT::Polygon(PolygonT::read_ewkb_body(raw, is_be, type_id, srid)?),
-            0x04 => GeometryT::MultiPoint(MultiPointT::read_ewkb_body(raw, is_be, type_id, srid)?),
-            0x05 => GeometryT::MultiLineString(MultiLineStringT::read_ewkb_body(
-                raw, is_be, type_id, srid,
-            )?),
-            0x06 => {
-                GeometryT::MultiPolygon(MultiPolygonT::read_ewkb_body(raw, is_be, type_id, srid)?)
+#[cfg(feature = "smallvec")]
+impl<P: postgis::Point + EwkbRead> From<PolygonT<P>> for SmallPolygonT<P> {
+    fn from(polygon: PolygonT<P>) -> Self {
+        SmallPolygonT {
+            rings: polygon.rings.into_iter().map(SmallLineStringT::from).collect(),
+            srid: polygon.srid,
+        }
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<P: postgis::Point + EwkbRead> From<SmallPolygonT<P>> for PolygonT<P> {
+    fn from(polygon: SmallPolygonT<P>) -> Self {
+        PolygonT {
+            rings: polygon.rings.into_iter().map(LineStringT::from).collect(),
+            srid: polygon.srid,
+        }
+    }
+}
+
+/// Generic Geometry Data Type
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GeometryT<P: postgis::Point + EwkbRead> {
+    Point(P),
+    LineString(LineStringT<P>),
+    Polygon(PolygonT<P>),
+    MultiPoint(MultiPointT<P>),
+    MultiLineString(MultiLineStringT<P>),
+    MultiPolygon(MultiPolygonT<P>),
+    GeometryCollection(GeometryCollectionT<P>),
+}
+
+impl<P: postgis::Point + EwkbRead + BitEq> BitEq for GeometryT<P> {
+    fn bit_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (GeometryT::Point(a), GeometryT::Point(b)) => a.bit_eq(b),
+            (GeometryT::LineString(a), GeometryT::LineString(b)) => a.bit_eq(b),
+            (GeometryT::Polygon(a), GeometryT::Polygon(b)) => a.bit_eq(b),
+            (GeometryT::MultiPoint(a), GeometryT::MultiPoint(b)) => a.bit_eq(b),
+            (GeometryT::MultiLineString(a), GeometryT::MultiLineString(b)) => a.bit_eq(b),
+            (GeometryT::MultiPolygon(a), GeometryT::MultiPolygon(b)) => a.bit_eq(b),
+            (GeometryT::GeometryCollection(a), GeometryT::GeometryCollection(b)) => a.bit_eq(b),
+            _ => false,
+        }
+    }
+
+    fn bit_hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            GeometryT::Point(geom) => geom.bit_hash(state),
+            GeometryT::LineString(geom) => geom.bit_hash(state),
+            GeometryT::Polygon(geom) => geom.bit_hash(state),
+            GeometryT::MultiPoint(geom) => geom.bit_hash(state),
+            GeometryT::MultiLineString(geom) => geom.bit_hash(state),
+            GeometryT::MultiPolygon(geom) => geom.bit_hash(state),
+            GeometryT::GeometryCollection(geom) => geom.bit_hash(state),
+        }
+    }
+}
+
+macro_rules! impl_geometry_accessors {
+    ($($variant:ident($itemtype:ty): $as_fn:ident, $as_mut_fn:ident, $into_fn:ident;)*) => {
+        impl<P> GeometryT<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            /// Name of the geometry type, e.g. `"Polygon"`.
+            pub fn type_name(&self) -> &'static str {
+                match *self {
+                    $(GeometryT::$variant(_) => stringify!($variant),)*
+                }
+            }
+            $(
+                pub fn $as_fn(&self) -> Option<&$itemtype> {
+                    match *self {
+                        GeometryT::$variant(ref geom) => Some(geom),
+                        _ => None,
+                    }
+                }
+                pub fn $as_mut_fn(&mut self) -> Option<&mut $itemtype> {
+                    match *self {
+                        GeometryT::$variant(ref mut geom) => Some(geom),
+                        _ => None,
+                    }
+                }
+                /// Unwrap the geometry, returning `self` for other geometry types.
+                pub fn $into_fn(self) -> Result<$itemtype, Self> {
+                    match self {
+                        GeometryT::$variant(geom) => Ok(geom),
+                        other => Err(other),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+impl_geometry_accessors! {
+    Point(P): as_point, as_point_mut, into_point;
+    LineString(LineStringT<P>): as_line_string, as_line_string_mut, into_line_string;
+    Polygon(PolygonT<P>): as_polygon, as_polygon_mut, into_polygon;
+    MultiPoint(MultiPointT<P>): as_multi_point, as_multi_point_mut, into_multi_point;
+    MultiLineString(MultiLineStringT<P>): as_multi_line_string, as_multi_line_string_mut, into_multi_line_string;
+    MultiPolygon(MultiPolygonT<P>): as_multi_polygon, as_multi_polygon_mut, into_multi_polygon;
+    GeometryCollection(GeometryCollectionT<P>): as_geometry_collection, as_geometry_collection_mut, into_geometry_collection;
+}
+
+fn unexpected_geometry<P>(expected: &str, geom: &GeometryT<P>) -> Error
+where
+    P: postgis::Point + EwkbRead,
+{
+    Error::Other(format!("expected {}, found {}", expected, geom.type_name()))
+}
+
+macro_rules! impl_try_from_geometry {
+    (point $ptype:ident) => {
+        impl TryFrom<GeometryT<$ptype>> for $ptype {
+            type Error = Error;
+            fn try_from(geom: GeometryT<$ptype>) -> Result<Self, Error> {
+                geom.into_point()
+                    .map_err(|geom| unexpected_geometry("Point", &geom))
+            }
+        }
+    };
+    ($variant:ident($geotype:ident): $into_fn:ident) => {
+        impl<P> TryFrom<GeometryT<P>> for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            type Error = Error;
+            fn try_from(geom: GeometryT<P>) -> Result<Self, Error> {
+                geom.$into_fn()
+                    .map_err(|geom| unexpected_geometry(stringify!($variant), &geom))
+            }
+        }
+    };
+}
+
+impl_try_from_geometry!(point Point);
+impl_try_from_geometry!(point PointZ);
+impl_try_from_geometry!(point PointM);
+impl_try_from_geometry!(point PointZM);
+impl_try_from_geometry!(LineString(LineStringT): into_line_string);
+impl_try_from_geometry!(Polygon(PolygonT): into_polygon);
+impl_try_from_geometry!(MultiPoint(MultiPointT): into_multi_point);
+impl_try_from_geometry!(MultiLineString(MultiLineStringT): into_multi_line_string);
+impl_try_from_geometry!(MultiPolygon(MultiPolygonT): into_multi_polygon);
+impl_try_from_geometry!(GeometryCollection(GeometryCollectionT): into_geometry_collection);
+
+impl<'a, P> postgis::Geometry<'a> for GeometryT<P>
+where
+    P: 'a + postgis::Point + EwkbRead,
+{
+    type Point = P;
+    type LineString = LineStringT<P>;
+    type Polygon = PolygonT<P>;
+    type MultiPoint = MultiPointT<P>;
+    type MultiLineString = MultiLineStringT<P>;
+    type MultiPolygon = MultiPolygonT<P>;
+    type GeometryCollection = GeometryCollectionT<P>;
+    fn as_type(
+        &'a self,
+    ) -> postgis::GeometryType<
+        'a,
+        P,
+        LineStringT<P>,
+        PolygonT<P>,
+        MultiPointT<P>,
+        MultiLineStringT<P>,
+        MultiPolygonT<P>,
+        GeometryCollectionT<P>,
+    > {
+        use crate::ewkb::GeometryT as A;
+        use crate::types::GeometryType as B;
+        match *self {
+            A::Point(ref geom) => B::Point(geom),
+            A::LineString(ref geom) => B::LineString(geom),
+            A::Polygon(ref geom) => B::Polygon(geom),
+            A::MultiPoint(ref geom) => B::MultiPoint(geom),
+            A::MultiLineString(ref geom) => B::MultiLineString(geom),
+            A::MultiPolygon(ref geom) => B::MultiPolygon(geom),
+            A::GeometryCollection(ref geom) => B::GeometryCollection(geom),
+        }
+    }
+}
+
+impl<P> EwkbRead for GeometryT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn point_type() -> PointType {
+        P::point_type()
+    }
+    fn read_ewkb_body<R: Read>(
+        raw: &mut R,
+        is_be: bool,
+        type_id: u32,
+        srid: Option<i32>,
+        options: &ReadOptions,
+    ) -> Result<Self, Error> {
+        let geom = match geometry_type_code(type_id) {
+            0x01 => GeometryT::Point(P::read_ewkb_body(raw, is_be, type_id, srid, options)?),
+            0x02 => {
+                GeometryT::LineString(LineStringT::<P>::read_ewkb_body(raw, is_be, type_id, srid, options)?)
+            }
+            0x03 => GeometryT::Polygon(PolygonT::read_ewkb_body(raw, is_be, type_id, srid, options)?),
+            0x04 => GeometryT::MultiPoint(MultiPointT::read_ewkb_body(raw, is_be, type_id, srid, options)?),
+            0x05 => GeometryT::MultiLineString(MultiLineStringT::read_ewkb_body(
+                raw, is_be, type_id, srid, options,
+            )?),
+            0x06 => {
+                GeometryT::MultiPolygon(MultiPolygonT::read_ewkb_body(raw, is_be, type_id, srid, options)?)
             }
             0x07 => GeometryT::GeometryCollection(GeometryCollectionT::read_ewkb_body(
-                raw, is_be, type_id, srid,
+                raw, is_be, type_id, srid, options,
             )?),
             _ => {
                 return Err(Error::Read(format!(
@@ -1030,13 +2281,37 @@ where
         };
         Ok(geom)
     }
-    fn read_ewkb_body<R: Read>(
-        _raw: &mut R,
-        _is_be: bool,
-        _type_id: u32,
-        _srid: Option<i32>,
+    fn read_ewkb_body_slice(
+        raw: &mut &[u8],
+        is_be: bool,
+        type_id: u32,
+        srid: Option<i32>,
+        options: &ReadOptions,
     ) -> Result<Self, Error> {
-        panic!("Not used for generic geometry type")
+        let geom = match geometry_type_code(type_id) {
+            0x01 => GeometryT::Point(P::read_ewkb_body_slice(raw, is_be, type_id, srid, options)?),
+            0x02 => {
+                GeometryT::LineString(LineStringT::<P>::read_ewkb_body_slice(raw, is_be, type_id, srid, options)?)
+            }
+            0x03 => GeometryT::Polygon(PolygonT::read_ewkb_body_slice(raw, is_be, type_id, srid, options)?),
+            0x04 => GeometryT::MultiPoint(MultiPointT::read_ewkb_body_slice(raw, is_be, type_id, srid, options)?),
+            0x05 => GeometryT::MultiLineString(MultiLineStringT::read_ewkb_body_slice(
+                raw, is_be, type_id, srid, options,
+            )?),
+            0x06 => {
+                GeometryT::MultiPolygon(MultiPolygonT::read_ewkb_body_slice(raw, is_be, type_id, srid, options)?)
+            }
+            0x07 => GeometryT::GeometryCollection(GeometryCollectionT::read_ewkb_body_slice(
+                raw, is_be, type_id, srid, options,
+            )?),
+            _ => {
+                return Err(Error::Read(format!(
+                    "Error reading generic geometry type - unsupported type id {}.",
+                    type_id
+                )))
+            }
+        };
+        Ok(geom)
     }
 }
 
@@ -1206,15 +2481,31 @@ where
         }
     }
 
-    fn write_ewkb_body<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+    fn write_ewkb_body<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), Error> {
+        match *self {
+            EwkbGeometry::Point(ref ewkb) => ewkb.write_ewkb_body(w, options),
+            EwkbGeometry::LineString(ref ewkb) => ewkb.write_ewkb_body(w, options),
+            EwkbGeometry::Polygon(ref ewkb) => ewkb.write_ewkb_body(w, options),
+            EwkbGeometry::MultiPoint(ref ewkb) => ewkb.write_ewkb_body(w, options),
+            EwkbGeometry::MultiLineString(ref ewkb) => ewkb.write_ewkb_body(w, options),
+            EwkbGeometry::MultiPolygon(ref ewkb) => ewkb.write_ewkb_body(w, options),
+            EwkbGeometry::GeometryCollection(ref ewkb) => ewkb.write_ewkb_body(w, options),
+        }
+    }
+
+    fn ewkb_body_size(&self, options: &WriteOptions) -> usize {
         match *self {
-            EwkbGeometry::Point(ref ewkb) => ewkb.write_ewkb_body(w),
-            EwkbGeometry::LineString(ref ewkb) => ewkb.write_ewkb_body(w),
-            EwkbGeometry::Polygon(ref ewkb) => ewkb.write_ewkb_body(w),
-            EwkbGeometry::MultiPoint(ref ewkb) => ewkb.write_ewkb_body(w),
-            EwkbGeometry::MultiLineString(ref ewkb) => ewkb.write_ewkb_body(w),
-            EwkbGeometry::MultiPolygon(ref ewkb) => ewkb.write_ewkb_body(w),
-            EwkbGeometry::GeometryCollection(ref ewkb) => ewkb.write_ewkb_body(w),
+            EwkbGeometry::Point(ref ewkb) => ewkb.ewkb_body_size(options),
+            EwkbGeometry::LineString(ref ewkb) => ewkb.ewkb_body_size(options),
+            EwkbGeometry::Polygon(ref ewkb) => ewkb.ewkb_body_size(options),
+            EwkbGeometry::MultiPoint(ref ewkb) => ewkb.ewkb_body_size(options),
+            EwkbGeometry::MultiLineString(ref ewkb) => ewkb.ewkb_body_size(options),
+            EwkbGeometry::MultiPolygon(ref ewkb) => ewkb.ewkb_body_size(options),
+            EwkbGeometry::GeometryCollection(ref ewkb) => ewkb.ewkb_body_size(options),
         }
     }
 }
@@ -1275,7 +2566,8 @@ pub type GeometryM = GeometryT<PointM>;
 /// OGC GeometryZM type
 pub type GeometryZM = GeometryT<PointZM>;
 
-#[derive(Clone, Debug)]
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GeometryCollectionT<P: postgis::Point + EwkbRead> {
     pub geometries: Vec<GeometryT<P>>,
     pub srid: Option<i32>,
@@ -1291,8 +2583,114 @@ where
             srid: None,
         }
     }
+    pub fn new_with_srid(srid: Option<i32>) -> GeometryCollectionT<P> {
+        GeometryCollectionT {
+            geometries: Vec::new(),
+            srid,
+        }
+    }
+    pub fn with_capacity_and_srid(capacity: usize, srid: Option<i32>) -> GeometryCollectionT<P> {
+        GeometryCollectionT {
+            geometries: Vec::with_capacity(capacity),
+            srid,
+        }
+    }
+}
+
+impl<P: postgis::Point + EwkbRead + BitEq> BitEq for GeometryCollectionT<P> {
+    fn bit_eq(&self, other: &Self) -> bool {
+        self.geometries[..].bit_eq(&other.geometries[..]) && self.srid == other.srid
+    }
+
+    fn bit_hash<H: Hasher>(&self, state: &mut H) {
+        self.geometries[..].bit_hash(state);
+        self.srid.hash(state);
+    }
+}
+
+impl<P> GeometryCollectionT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    /// Iterate over all non-collection members, recursing into nested collections.
+    pub fn flatten(&self) -> FlattenIter<'_, P> {
+        FlattenIter {
+            stack: vec![self.geometries.iter()],
+        }
+    }
+
+    /// Group the (flattened) members into a MultiPoint, MultiLineString or
+    /// MultiPolygon. If members of different dimensions are present, a
+    /// collection of the resulting multi geometries is returned.
+    pub fn collect_into_multi(&self) -> GeometryT<P>
+    where
+        P: Clone,
+    {
+        let mut points = MultiPointT::new_with_srid(self.srid);
+        let mut lines = MultiLineStringT::new_with_srid(self.srid);
+        let mut polygons = MultiPolygonT::new_with_srid(self.srid);
+        for geom in self.flatten() {
+            match *geom {
+                GeometryT::Point(ref p) => points.push(p.clone()),
+                GeometryT::MultiPoint(ref mp) => points.extend(mp.points.iter().cloned()),
+                GeometryT::LineString(ref l) => lines.push(l.clone()),
+                GeometryT::MultiLineString(ref ml) => lines.extend(ml.lines.iter().cloned()),
+                GeometryT::Polygon(ref p) => polygons.push(p.clone()),
+                GeometryT::MultiPolygon(ref mp) => polygons.extend(mp.polygons.iter().cloned()),
+                GeometryT::GeometryCollection(_) => unreachable!(),
+            }
+        }
+        let mut multis = Vec::new();
+        if !points.is_empty() {
+            multis.push(GeometryT::MultiPoint(points));
+        }
+        if !lines.is_empty() {
+            multis.push(GeometryT::MultiLineString(lines));
+        }
+        if !polygons.is_empty() {
+            multis.push(GeometryT::MultiPolygon(polygons));
+        }
+        if multis.len() == 1 {
+            multis.pop().unwrap()
+        } else {
+            GeometryT::GeometryCollection(GeometryCollectionT {
+                geometries: multis,
+                srid: self.srid,
+            })
+        }
+    }
+}
+
+/// Iterator returned by [`GeometryCollectionT::flatten`].
+pub struct FlattenIter<'a, P: postgis::Point + EwkbRead> {
+    stack: Vec<Iter<'a, GeometryT<P>>>,
+}
+
+impl<'a, P> Iterator for FlattenIter<'a, P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    type Item = &'a GeometryT<P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(GeometryT::GeometryCollection(collection)) => {
+                    self.stack.push(collection.geometries.iter())
+                }
+                Some(geom) => return Some(geom),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
 }
 
+impl_into_iterator!(GeometryCollectionT, geometries: GeometryT<P>);
+impl_container_access!(GeometryCollectionT, geometries: GeometryT<P>);
+impl_iter_mut!(GeometryCollectionT, geometries_mut => geometries: GeometryT<P>);
+
 impl<'a, P> postgis::GeometryCollection<'a> for GeometryCollectionT<P>
 where
     P: 'a + postgis::Point + EwkbRead,
@@ -1316,44 +2714,30 @@ where
         raw: &mut R,
         is_be: bool,
         _type_id: u32,
-        _srid: Option<i32>,
+        srid: Option<i32>,
+        options: &ReadOptions,
     ) -> Result<Self, Error> {
         let mut ret = GeometryCollectionT::new();
+        ret.srid = srid;
         let size = read_u32(raw, is_be)? as usize;
         for _ in 0..size {
-            let is_be = raw.read_i8()? == 0i8;
-
-            let type_id = read_u32(raw, is_be)?;
-            let mut srid: Option<i32> = None;
-            if type_id & 0x20000000 == 0x20000000 {
-                srid = Some(read_i32(raw, is_be)?);
-            }
-            let geom = match type_id & 0xff {
-                0x01 => GeometryT::Point(P::read_ewkb_body(raw, is_be, type_id, srid)?),
-                0x02 => GeometryT::LineString(LineStringT::<P>::read_ewkb_body(
-                    raw, is_be, type_id, srid,
-                )?),
-                0x03 => GeometryT::Polygon(PolygonT::read_ewkb_body(raw, is_be, type_id, srid)?),
-                0x04 => {
-                    GeometryT::MultiPoint(MultiPointT::read_ewkb_body(raw, is_be, type_id, srid)?)
-                }
-                0x05 => GeometryT::MultiLineString(MultiLineStringT::read_ewkb_body(
-                    raw, is_be, type_id, srid,
-                )?),
-                0x06 => GeometryT::MultiPolygon(MultiPolygonT::read_ewkb_body(
-                    raw, is_be, type_id, srid,
-                )?),
-                0x07 => GeometryT::GeometryCollection(GeometryCollectionT::read_ewkb_body(
-                    raw, is_be, type_id, srid,
-                )?),
-                _ => {
-                    return Err(Error::Read(format!(
-                        "Error reading generic geometry type - unsupported type id {}.",
-                        type_id
-                    )))
-                }
-            };
-            ret.geometries.push(geom);
+            ret.geometries.push(GeometryT::read_ewkb_with(raw, options)?);
+        }
+        Ok(ret)
+    }
+
+    fn read_ewkb_body_slice(
+        raw: &mut &[u8],
+        is_be: bool,
+        _type_id: u32,
+        srid: Option<i32>,
+        options: &ReadOptions,
+    ) -> Result<Self, Error> {
+        let mut ret = GeometryCollectionT::new();
+        ret.srid = srid;
+        let size = read_u32(raw, is_be)? as usize;
+        for _ in 0..size {
+            ret.geometries.push(GeometryT::read_ewkb_slice_with(raw, options)?);
         }
         Ok(ret)
     }
@@ -1505,7 +2889,11 @@ where
         0x07 | Self::wkb_type_id(&self.point_type, self.srid)
     }
 
-    fn write_ewkb_body<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+    fn write_ewkb_body<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), Error> {
         w.write_u32::<LittleEndian>(self.geom.geometries().len() as u32)?;
 
         for geom in self.geom.geometries() {
@@ -1513,63 +2901,112 @@ where
                 postgis::GeometryType::Point(geom) => {
                     let wkb = EwkbPoint {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.write_ewkb(w)?;
+                    wkb.write_ewkb_with(w, options)?;
                 }
                 postgis::GeometryType::LineString(geom) => {
                     let wkb = EwkbLineString {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.write_ewkb(w)?;
+                    wkb.write_ewkb_with(w, options)?;
                 }
                 postgis::GeometryType::Polygon(geom) => {
                     let wkb = EwkbPolygon {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.write_ewkb(w)?;
+                    wkb.write_ewkb_with(w, options)?;
                 }
                 postgis::GeometryType::MultiPoint(geom) => {
                     let wkb = EwkbMultiPoint {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.write_ewkb(w)?;
+                    wkb.write_ewkb_with(w, options)?;
                 }
                 postgis::GeometryType::MultiLineString(geom) => {
                     let wkb = EwkbMultiLineString {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.write_ewkb(w)?;
+                    wkb.write_ewkb_with(w, options)?;
                 }
                 postgis::GeometryType::MultiPolygon(geom) => {
                     let wkb = EwkbMultiPolygon {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.write_ewkb(w)?;
+                    wkb.write_ewkb_with(w, options)?;
                 }
                 postgis::GeometryType::GeometryCollection(geom) => {
                     let wkb = EwkbGeometryCollection {
                         geom: geom,
-                        srid: None,
-                        point_type: self.point_type.clone(),
+                        srid: options.child_srid(self.srid),
+                        point_type: self.point_type,
                     };
-                    wkb.write_ewkb(w)?;
+                    wkb.write_ewkb_with(w, options)?;
                 }
             }
         }
         Ok(())
     }
+
+    fn ewkb_body_size(&self, options: &WriteOptions) -> usize {
+        let point_type = &self.point_type;
+        let items = self.geom.geometries().map(|geom| match geom.as_type() {
+            postgis::GeometryType::Point(geom) => EwkbPoint {
+                geom,
+                srid: options.child_srid(self.srid),
+                point_type: *point_type,
+            }
+            .ewkb_size_with(options),
+            postgis::GeometryType::LineString(geom) => EwkbLineString {
+                geom,
+                srid: options.child_srid(self.srid),
+                point_type: *point_type,
+            }
+            .ewkb_size_with(options),
+            postgis::GeometryType::Polygon(geom) => EwkbPolygon {
+                geom,
+                srid: options.child_srid(self.srid),
+                point_type: *point_type,
+            }
+            .ewkb_size_with(options),
+            postgis::GeometryType::MultiPoint(geom) => EwkbMultiPoint {
+                geom,
+                srid: options.child_srid(self.srid),
+                point_type: *point_type,
+            }
+            .ewkb_size_with(options),
+            postgis::GeometryType::MultiLineString(geom) => EwkbMultiLineString {
+                geom,
+                srid: options.child_srid(self.srid),
+                point_type: *point_type,
+            }
+            .ewkb_size_with(options),
+            postgis::GeometryType::MultiPolygon(geom) => EwkbMultiPolygon {
+                geom,
+                srid: options.child_srid(self.srid),
+                point_type: *point_type,
+            }
+            .ewkb_size_with(options),
+            postgis::GeometryType::GeometryCollection(geom) => EwkbGeometryCollection {
+                geom,
+                srid: options.child_srid(self.srid),
+                point_type: *point_type,
+            }
+            .ewkb_size_with(options),
+        });
+        4 + items.sum::<usize>()
+    }
 }
 
 impl<'a, P> AsEwkbGeometryCollection<'a> for GeometryCollectionT<P>
@@ -1622,44 +3059,1221 @@ pub type GeometryCollectionM = GeometryCollectionT<PointM>;
 /// OGC GeometryCollectionZM type
 pub type GeometryCollectionZM = GeometryCollectionT<PointZM>;
 
-#[test]
-#[cfg_attr(rustfmt, rustfmt_skip)]
-fn test_point_write() {
-    // 'POINT (10 -20)'
-    let point = Point { x: 10.0, y: -20.0, srid: None };
-    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000000000000000000244000000000000034C0");
-
-    // 'POINT (10 -20 100)'
-    let point = PointZ { x: 10.0, y: -20.0, z: 100.0, srid: None };
-    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000080000000000000244000000000000034C00000000000005940");
+// --- Srid
 
-    // 'POINTM (10 -20 1)'
-    let point = PointM { x: 10.0, y: -20.0, m: 1.0, srid: None };
-    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000040000000000000244000000000000034C0000000000000F03F");
+macro_rules! impl_srid_for_point {
+    ($ptype:ident) => {
+        impl Srid for $ptype {
+            fn srid(&self) -> Option<i32> {
+                self.srid
+            }
+            fn set_srid(&mut self, srid: Option<i32>) {
+                self.srid = srid;
+            }
+        }
+    };
+}
 
-    // 'POINT (10 -20 100 1)'
-    let point = PointZM { x: 10.0, y: -20.0, z: 100.0, m: 1.0, srid: None };
-    assert_eq!(point.as_ewkb().to_hex_ewkb(), "01010000C0000000000000244000000000000034C00000000000005940000000000000F03F");
+impl_srid_for_point!(Point);
+impl_srid_for_point!(PointZ);
+impl_srid_for_point!(PointM);
+impl_srid_for_point!(PointZM);
 
-    // 'POINT (-0 -1)'
-    let point = Point { x: 0.0, y: -1.0, srid: None };
-    assert_eq!(point.as_ewkb().to_hex_ewkb(), "01010000000000000000000000000000000000F0BF");
-    // TODO: -0 in PostGIS gives 01010000000000000000000080000000000000F0BF
+macro_rules! impl_srid_for_container {
+    ($geotype:ident named $itemname:ident) => {
+        impl<P> Srid for $geotype<P>
+        where
+            P: postgis::Point + EwkbRead + Srid,
+        {
+            fn srid(&self) -> Option<i32> {
+                self.srid
+            }
+            fn set_srid(&mut self, srid: Option<i32>) {
+                self.srid = srid;
+                for item in self.$itemname.iter_mut() {
+                    item.set_srid(srid);
+                }
+            }
+        }
+    };
+}
 
-    // 'SRID=4326;POINT (10 -20)'
-    let point = Point { x: 10.0, y: -20.0, srid: Some(4326) };
-    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000020E6100000000000000000244000000000000034C0");
+impl_srid_for_container!(LineStringT named points);
+impl_srid_for_container!(PolygonT named rings);
+impl_srid_for_container!(MultiPointT named points);
+impl_srid_for_container!(MultiLineStringT named lines);
+impl_srid_for_container!(MultiPolygonT named polygons);
+impl_srid_for_container!(GeometryCollectionT named geometries);
+#[cfg(feature = "smallvec")]
+impl_srid_for_container!(SmallLineStringT named points);
+#[cfg(feature = "smallvec")]
+impl_srid_for_container!(SmallPolygonT named rings);
+
+impl<P> Srid for GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    fn srid(&self) -> Option<i32> {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => geom.srid(),
+            G::LineString(ref geom) => geom.srid(),
+            G::Polygon(ref geom) => geom.srid(),
+            G::MultiPoint(ref geom) => geom.srid(),
+            G::MultiLineString(ref geom) => geom.srid(),
+            G::MultiPolygon(ref geom) => geom.srid(),
+            G::GeometryCollection(ref geom) => geom.srid(),
+        }
+    }
+    fn set_srid(&mut self, srid: Option<i32>) {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref mut geom) => geom.set_srid(srid),
+            G::LineString(ref mut geom) => geom.set_srid(srid),
+            G::Polygon(ref mut geom) => geom.set_srid(srid),
+            G::MultiPoint(ref mut geom) => geom.set_srid(srid),
+            G::MultiLineString(ref mut geom) => geom.set_srid(srid),
+            G::MultiPolygon(ref mut geom) => geom.set_srid(srid),
+            G::GeometryCollection(ref mut geom) => geom.set_srid(srid),
+        }
+    }
 }
 
-#[test]
-#[cfg_attr(rustfmt, rustfmt_skip)]
-fn test_line_write() {
-    let p = |x, y| Point { x: x, y: y, srid: None };
-    // 'LINESTRING (10 -20, 0 -0.5)'
-    let line = LineStringT::<Point> {srid: None, points: vec![p(10.0, -20.0), p(0., -0.5)]};
-    assert_eq!(line.as_ewkb().to_hex_ewkb(), "010200000002000000000000000000244000000000000034C00000000000000000000000000000E0BF");
+// --- Streaming point reader
+
+/// Pull-based reader yielding the points of an EWKB LineString or MultiPoint
+/// one at a time, without allocating the whole point vector.
+///
+/// ```rust
+/// use postgis::ewkb::{AsEwkbLineString, EwkbPointStream, EwkbWrite, LineString, Point};
+///
+/// let line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+/// let mut buf = Vec::new();
+/// line.as_ewkb().write_ewkb(&mut buf).unwrap();
+///
+/// let stream = EwkbPointStream::<_, Point>::new(buf.as_slice()).unwrap();
+/// assert_eq!(stream.len(), 2);
+/// let xs: Vec<f64> = stream.map(|p| p.unwrap().x).collect();
+/// assert_eq!(xs, vec![10.0, 0.0]);
+/// ```
+pub struct EwkbPointStream<R: Read, P: postgis::Point + EwkbRead> {
+    raw: R,
+    is_be: bool,
+    type_id: u32,
+    srid: Option<i32>,
+    multi: bool,
+    remaining: usize,
+    failed: bool,
+    point_type: core::marker::PhantomData<P>,
+}
 
-    // 'SRID=4326;LINESTRING (10 -20, 0 -0.5)'
+impl<R: Read, P: postgis::Point + EwkbRead> EwkbPointStream<R, P> {
+    /// Parse the geometry header and point count.
+    pub fn new(mut raw: R) -> Result<Self, Error> {
+        let is_be = raw.read_i8()? == 0i8;
+        let type_id = read_u32(&mut raw, is_be)?;
+        let srid = if type_id & EWKB_SRID_FLAG == EWKB_SRID_FLAG {
+            Some(read_i32(&mut raw, is_be)?)
+        } else {
+            None
+        };
+        let multi = match geometry_type_code(type_id) {
+            0x02 => false,
+            0x04 => true,
+            code => {
+                return Err(Error::Read(format!(
+                    "cannot stream points of geometry type {}",
+                    code
+                )))
+            }
+        };
+        let remaining = read_u32(&mut raw, is_be)? as usize;
+        Ok(EwkbPointStream {
+            raw,
+            is_be,
+            type_id,
+            srid,
+            multi,
+            remaining,
+            failed: false,
+            point_type: core::marker::PhantomData,
+        })
+    }
+
+    /// SRID of the geometry.
+    pub fn srid(&self) -> Option<i32> {
+        self.srid
+    }
+
+    /// Number of points not read yet.
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl<R: Read, P: postgis::Point + EwkbRead> Iterator for EwkbPointStream<R, P> {
+    type Item = Result<P, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.failed {
+            return None;
+        }
+        self.remaining -= 1;
+        let point = if self.multi {
+            P::read_ewkb(&mut self.raw)
+        } else {
+            P::read_ewkb_body(
+                &mut self.raw,
+                self.is_be,
+                self.type_id,
+                self.srid,
+                &ReadOptions::default(),
+            )
+        };
+        self.failed = point.is_err();
+        Some(point)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            (0, Some(0))
+        } else {
+            (0, Some(self.remaining))
+        }
+    }
+}
+
+// --- Concatenated EWKB
+
+#[cfg(feature = "std")]
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> std::io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// Iterator over EWKB geometries stored back to back in a byte stream.
+///
+/// Iteration ends at the end of the stream. A truncated or invalid geometry
+/// yields an error with its byte offset and stops the iteration.
+///
+/// ```rust
+/// use postgis::ewkb::{AsEwkbPoint, EwkbIterator, EwkbWrite, Geometry, Point};
+///
+/// let mut dump = Vec::new();
+/// Point::new(1.0, 2.0, None).as_ewkb().write_ewkb(&mut dump).unwrap();
+/// Point::new(3.0, 4.0, Some(4326)).as_ewkb().write_ewkb(&mut dump).unwrap();
+///
+/// let geoms = EwkbIterator::<_, Geometry>::new(dump.as_slice());
+/// assert_eq!(geoms.collect::<Result<Vec<_>, _>>().unwrap().len(), 2);
+/// ```
+#[cfg(feature = "std")]
+pub struct EwkbIterator<R: std::io::Read, G: EwkbRead> {
+    raw: CountingReader<R>,
+    failed: bool,
+    geom_type: core::marker::PhantomData<G>,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read, G: EwkbRead> EwkbIterator<R, G> {
+    pub fn new(raw: R) -> Self {
+        EwkbIterator {
+            raw: CountingReader {
+                inner: raw,
+                count: 0,
+            },
+            failed: false,
+            geom_type: core::marker::PhantomData,
+        }
+    }
+
+    /// Number of bytes read so far.
+    pub fn offset(&self) -> u64 {
+        self.raw.count
+    }
+
+    pub fn into_inner(self) -> R {
+        self.raw.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read, G: EwkbRead> Iterator for EwkbIterator<R, G> {
+    type Item = Result<G, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use std::io::Read as _;
+
+        if self.failed {
+            return None;
+        }
+        let offset = self.raw.count;
+        // the stream may only end before the byte order of the next geometry
+        let mut byte_order = [0u8];
+        let geom = loop {
+            match self.raw.read(&mut byte_order) {
+                Ok(0) => return None,
+                Ok(_) => break G::read_ewkb(&mut (&byte_order[..]).chain(&mut self.raw)),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => break Err(e.into()),
+            }
+        };
+        self.failed = geom.is_err();
+        Some(geom.map_err(|e| {
+            let msg = match e {
+                Error::Read(msg) | Error::Write(msg) | Error::Other(msg) => msg,
+            };
+            Error::Read(format!("geometry at offset {}: {}", offset, msg))
+        }))
+    }
+}
+
+// --- Raw EWKB
+
+/// Pre-encoded EWKB, sent to and received from PostgreSQL untouched.
+///
+/// The bytes are neither validated nor re-encoded, so geometries can be
+/// passed between databases without decoding costs and byte-identical,
+/// e.g. keeping big endian EWKB as is.
+///
+/// ```rust
+/// use postgis::ewkb::Ewkb;
+///
+/// // big endian POINT(10 -20)
+/// let raw = Ewkb(vec![0, 0, 0, 0, 1, 64, 36, 0, 0, 0, 0, 0, 0, 192, 52, 0, 0, 0, 0, 0, 0]);
+/// assert_eq!(raw.view().unwrap().geometry_type(), 1);
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
+pub struct Ewkb(pub Vec<u8>);
+
+impl Ewkb {
+    /// Borrowed view of the bytes, see [`EwkbSlice`].
+    pub fn view(&self) -> Result<EwkbSlice<'_>, Error> {
+        EwkbSlice::new(&self.0)
+    }
+}
+
+impl From<Vec<u8>> for Ewkb {
+    fn from(bytes: Vec<u8>) -> Self {
+        Ewkb(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Ewkb {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Borrowed raw EWKB, e.g. from the buffer of a row, see [`Ewkb`].
+///
+/// Unlike [`EwkbSlice`] nothing is validated, so getting it from a row
+/// costs nothing and decoding can be deferred or skipped.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct EwkbBytes<'a>(pub &'a [u8]);
+
+impl<'a> EwkbBytes<'a> {
+    /// Borrowed view of the bytes, see [`EwkbSlice`].
+    pub fn view(&self) -> Result<EwkbSlice<'a>, Error> {
+        EwkbSlice::new(self.0)
+    }
+}
+
+impl<'a> From<EwkbBytes<'a>> for Ewkb {
+    fn from(bytes: EwkbBytes<'a>) -> Self {
+        Ewkb(bytes.0.to_vec())
+    }
+}
+
+impl AsRef<[u8]> for EwkbBytes<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+// --- Borrowed EWKB view
+
+/// Borrowed view of an EWKB encoded geometry.
+///
+/// The header is validated on creation, everything else is read from the
+/// underlying bytes on demand. Use [`decode`](EwkbSlice::decode) to
+/// materialize the geometry.
+///
+/// ```rust
+/// use postgis::ewkb::{AsEwkbLineString, EwkbSlice, EwkbWrite, LineString};
+///
+/// let mut line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+/// line.srid = Some(4326);
+/// let mut buf = Vec::new();
+/// line.as_ewkb().write_ewkb(&mut buf).unwrap();
+///
+/// let view = EwkbSlice::new(&buf).unwrap();
+/// assert_eq!(view.geometry_type(), 2);
+/// assert_eq!(view.srid(), Some(4326));
+/// assert_eq!(view.point_n(1).unwrap().map(|c| c.y), Some(-0.5));
+/// let bbox = view.bbox().unwrap().unwrap();
+/// assert_eq!((bbox.xmin, bbox.ymax), (0.0, -0.5));
+/// assert_eq!(view.decode::<LineString>().unwrap().points.len(), 2);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct EwkbSlice<'a> {
+    data: &'a [u8],
+    type_id: u32,
+    srid: Option<i32>,
+}
+
+impl<'a> EwkbSlice<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
+        let mut raw = data;
+        let (type_id, srid) = parse_header(&mut raw)?;
+        Ok(EwkbSlice {
+            data,
+            type_id,
+            srid,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Geometry type code (1 = Point, ..., 7 = GeometryCollection).
+    pub fn geometry_type(&self) -> u32 {
+        geometry_type_code(self.type_id)
+    }
+
+    pub fn point_type(&self) -> PointType {
+        point_type_of(self.type_id)
+    }
+
+    pub fn srid(&self) -> Option<i32> {
+        self.srid
+    }
+
+    /// 2D bounding box of all points, `None` for empty geometries.
+    pub fn bbox(&self) -> Result<Option<Envelope>, Error> {
+        let mut bbox: Option<Envelope> = None;
+        scan_coords(&mut &self.data[..], 0, &mut |c: Coord| {
+            if c.x.is_nan() || c.y.is_nan() {
+                return true;
+            }
+            let point = Envelope::new(c.x, c.y, c.x, c.y);
+            bbox = Some(bbox.map_or(point, |b| b.union(&point)));
+            true
+        })?;
+        Ok(bbox)
+    }
+
+    /// The `n`-th point of the geometry in storage order, `None` if out of range.
+    pub fn point_n(&self, n: usize) -> Result<Option<Coord>, Error> {
+        let mut index = 0;
+        let mut found = None;
+        scan_coords(&mut &self.data[..], 0, &mut |c: Coord| {
+            if index == n {
+                found = Some(c);
+                return false;
+            }
+            index += 1;
+            true
+        })?;
+        Ok(found)
+    }
+
+    /// Read the geometry into an owned value.
+    pub fn decode<T: EwkbRead>(&self) -> Result<T, Error> {
+        T::read_ewkb(&mut &self.data[..])
+    }
+
+    /// The `i`-th member of a multi geometry or collection, `None` if out of
+    /// range. Preceding members are skipped without decoding them.
+    pub fn member(&self, i: usize) -> Result<Option<EwkbSlice<'a>>, Error> {
+        let mut raw = self.data;
+        let is_be = raw.first() == Some(&0);
+        parse_header(&mut raw)?;
+        match self.geometry_type() {
+            0x04..=0x07 => {}
+            code => {
+                return Err(Error::Read(format!(
+                    "geometry type {} has no members",
+                    code
+                )))
+            }
+        }
+        if i >= read_u32(&mut raw, is_be)? as usize {
+            return Ok(None);
+        }
+        for _ in 0..i {
+            skip_geometry(&mut raw, 1)?;
+        }
+        let start = raw;
+        skip_geometry(&mut raw, 1)?;
+        EwkbSlice::new(&start[..start.len() - raw.len()]).map(Some)
+    }
+
+    /// Read only the exterior ring of a Polygon, `None` for an empty Polygon.
+    pub fn exterior_ring<P>(&self) -> Result<Option<LineStringT<P>>, Error>
+    where
+        P: postgis::Point + EwkbRead,
+    {
+        if self.geometry_type() != 0x03 {
+            return Err(Error::Read(format!(
+                "expected geometry type 3, found {}",
+                self.geometry_type()
+            )));
+        }
+        if self.point_type() != P::point_type() {
+            return Err(Error::Read(format!(
+                "expected {:?} coordinates, found {:?}",
+                P::point_type(),
+                self.point_type()
+            )));
+        }
+        let mut raw = self.data;
+        let is_be = raw.first() == Some(&0);
+        parse_header(&mut raw)?;
+        if read_u32(&mut raw, is_be)? == 0 {
+            return Ok(None);
+        }
+        LineStringT::read_ewkb_body(&mut raw, is_be, self.type_id, self.srid, &ReadOptions::default())
+            .map(Some)
+    }
+}
+
+/// Byte order of EWKB data.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Endianness {
+    BigEndian,
+    LittleEndian,
+}
+
+/// Geometry type, dimensions, SRID and byte order of EWKB data.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct EwkbHeader {
+    /// Geometry type code (1 = Point, ..., 7 = GeometryCollection).
+    pub geom_type: u32,
+    pub has_z: bool,
+    pub has_m: bool,
+    pub srid: Option<i32>,
+    pub byte_order: Endianness,
+}
+
+impl EwkbHeader {
+    pub fn point_type(&self) -> PointType {
+        dims_point_type(self.has_z, self.has_m)
+    }
+
+    /// Number of bytes of the header, including the SRID.
+    pub fn size(&self) -> usize {
+        if self.srid.is_some() {
+            9
+        } else {
+            5
+        }
+    }
+}
+
+/// Read the header of EWKB data without decoding the geometry body.
+///
+/// ```rust
+/// use postgis::ewkb::{self, AsEwkbPoint, EwkbWrite, Endianness, PointZ};
+///
+/// let mut buf = Vec::new();
+/// PointZ::new(1.0, 2.0, 3.0, Some(4326)).as_ewkb().write_ewkb(&mut buf).unwrap();
+/// let header = ewkb::read_header(&buf).unwrap();
+/// assert_eq!((header.geom_type, header.has_z, header.has_m), (1, true, false));
+/// assert_eq!(header.srid, Some(4326));
+/// assert_eq!(header.byte_order, Endianness::LittleEndian);
+/// ```
+pub fn read_header(data: &[u8]) -> Result<EwkbHeader, Error> {
+    let byte_order = match data.first() {
+        Some(0) => Endianness::BigEndian,
+        _ => Endianness::LittleEndian,
+    };
+    let (type_id, srid) = parse_header(&mut &data[..])?;
+    Ok(EwkbHeader {
+        geom_type: geometry_type_code(type_id),
+        has_z: has_z(type_id),
+        has_m: has_m(type_id),
+        srid,
+        byte_order,
+    })
+}
+
+/// OGC geometry type.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum GeometryType {
+    Point = 1,
+    LineString = 2,
+    Polygon = 3,
+    MultiPoint = 4,
+    MultiLineString = 5,
+    MultiPolygon = 6,
+    GeometryCollection = 7,
+}
+
+impl GeometryType {
+    /// Geometry type for a type code (1 = Point, ..., 7 = GeometryCollection).
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(GeometryType::Point),
+            2 => Some(GeometryType::LineString),
+            3 => Some(GeometryType::Polygon),
+            4 => Some(GeometryType::MultiPoint),
+            5 => Some(GeometryType::MultiLineString),
+            6 => Some(GeometryType::MultiPolygon),
+            7 => Some(GeometryType::GeometryCollection),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+}
+
+/// Geometry type and dimensions of EWKB data, see [`kind_of`].
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct GeometryKind {
+    pub geometry_type: GeometryType,
+    pub has_z: bool,
+    pub has_m: bool,
+}
+
+impl GeometryKind {
+    pub fn point_type(&self) -> PointType {
+        dims_point_type(self.has_z, self.has_m)
+    }
+}
+
+/// Geometry type and dimensions of EWKB data, without allocating.
+///
+/// Only the header is read, so rows can be dispatched before decoding.
+///
+/// ```rust
+/// use postgis::ewkb::{self, AsEwkbLineString, EwkbWrite, GeometryType, LineStringM, PointM};
+///
+/// let mut buf = Vec::new();
+/// LineStringM::from(vec![PointM::new(0.0, 0.0, 1.0, None)]).as_ewkb().write_ewkb(&mut buf).unwrap();
+/// let kind = ewkb::kind_of(&buf).unwrap();
+/// assert_eq!(kind.geometry_type, GeometryType::LineString);
+/// assert_eq!((kind.has_z, kind.has_m), (false, true));
+/// ```
+pub fn kind_of(data: &[u8]) -> Result<GeometryKind, Error> {
+    let (type_id, _) = parse_header(&mut &data[..])?;
+    Ok(GeometryKind {
+        // checked by `parse_header`
+        geometry_type: GeometryType::from_code(geometry_type_code(type_id)).unwrap(),
+        has_z: has_z(type_id),
+        has_m: has_m(type_id),
+    })
+}
+
+fn parse_header(raw: &mut &[u8]) -> Result<(u32, Option<i32>), Error> {
+    let is_be = match raw.read_u8()? {
+        0 => true,
+        1 => false,
+        b => return Err(Error::Read(format!("invalid byte order {}", b))),
+    };
+    let type_id = read_u32(raw, is_be)?;
+    match geometry_type_code(type_id) {
+        0x01..=0x07 => {}
+        code => return Err(Error::Read(format!("unknown geometry type {}", code))),
+    }
+    let srid = if type_id & EWKB_SRID_FLAG == EWKB_SRID_FLAG {
+        Some(read_i32(raw, is_be)?)
+    } else {
+        None
+    };
+    Ok((type_id, srid))
+}
+
+// Calls `f` for every point until it returns false. Returns false if stopped early.
+fn scan_coords(
+    raw: &mut &[u8],
+    depth: usize,
+    f: &mut dyn FnMut(Coord) -> bool,
+) -> Result<bool, Error> {
+    let is_be = raw.first() == Some(&0);
+    let (type_id, _) = parse_header(raw)?;
+    let read_point = |raw: &mut &[u8]| -> Result<Coord, Error> {
+        let mut c = Coord::new(read_f64(raw, is_be)?, read_f64(raw, is_be)?);
+        if has_z(type_id) {
+            c.z = Some(read_f64(raw, is_be)?);
+        }
+        if has_m(type_id) {
+            c.m = Some(read_f64(raw, is_be)?);
+        }
+        Ok(c)
+    };
+    match geometry_type_code(type_id) {
+        0x01 => return Ok(f(read_point(raw)?)),
+        0x02 => {
+            for _ in 0..read_u32(raw, is_be)? {
+                if !f(read_point(raw)?) {
+                    return Ok(false);
+                }
+            }
+        }
+        0x03 => {
+            for _ in 0..read_u32(raw, is_be)? {
+                for _ in 0..read_u32(raw, is_be)? {
+                    if !f(read_point(raw)?) {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        _ => {
+            if depth >= MAX_NESTING_DEPTH {
+                return Err(Error::Read("geometry collections nested too deeply".into()));
+            }
+            for _ in 0..read_u32(raw, is_be)? {
+                if !scan_coords(raw, depth + 1, f)? {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+// --- Statistics
+
+/// Complexity and memory usage of a geometry, see [`GeometryT::stats`].
+///
+/// The counts of single geometries are also available from
+/// [`GeometryStats`](crate::algorithm::GeometryStats) when the `std`
+/// feature is enabled.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct ComplexityReport {
+    /// Number of points, including empty points.
+    pub num_vertices: usize,
+    /// Number of polygon rings.
+    pub num_rings: usize,
+    /// Number of points, lines and polygons. Collections are counted by their members.
+    pub num_parts: usize,
+    /// Estimated heap memory of the decoded geometry in bytes.
+    pub heap_bytes: usize,
+    /// Bounding box of the X/Y coordinates, `None` for empty geometries.
+    pub bbox: Option<Envelope>,
+}
+
+impl ComplexityReport {
+    fn add_point<P: postgis::Point>(&mut self, point: &P) {
+        self.num_vertices += 1;
+        let (x, y) = (point.x(), point.y());
+        if !x.is_nan() && !y.is_nan() {
+            let bbox = Envelope::new(x, y, x, y);
+            self.bbox = Some(self.bbox.map_or(bbox, |b| b.union(&bbox)));
+        }
+    }
+
+    fn add_points<'a, P, I>(&mut self, points: I)
+    where
+        P: postgis::Point + 'a,
+        I: IntoIterator<Item = &'a P>,
+    {
+        for point in points {
+            self.add_point(point);
+        }
+    }
+
+    fn add_line<P: postgis::Point + EwkbRead>(&mut self, line: &LineStringT<P>) {
+        self.num_parts += 1;
+        self.heap_bytes += item_vec_heap_bytes(&line.points);
+        self.add_points(&line.points);
+    }
+
+    fn add_polygon<P: postgis::Point + EwkbRead>(&mut self, polygon: &PolygonT<P>) {
+        self.num_parts += 1;
+        self.num_rings += polygon.rings.len();
+        self.heap_bytes += item_vec_heap_bytes(&polygon.rings);
+        for ring in &polygon.rings {
+            self.heap_bytes += item_vec_heap_bytes(&ring.points);
+            self.add_points(&ring.points);
+        }
+    }
+
+    fn add_geometry<P: postgis::Point + EwkbRead>(&mut self, geom: &GeometryT<P>) {
+        match *geom {
+            GeometryT::Point(ref point) => {
+                self.num_parts += 1;
+                self.add_point(point);
+            }
+            GeometryT::LineString(ref line) => self.add_line(line),
+            GeometryT::Polygon(ref polygon) => self.add_polygon(polygon),
+            GeometryT::MultiPoint(ref multi) => {
+                self.num_parts += multi.points.len();
+                self.heap_bytes += item_vec_heap_bytes(&multi.points);
+                self.add_points(&multi.points);
+            }
+            GeometryT::MultiLineString(ref multi) => {
+                self.heap_bytes += multi.lines.capacity() * core::mem::size_of::<LineStringT<P>>();
+                for line in &multi.lines {
+                    self.add_line(line);
+                }
+            }
+            GeometryT::MultiPolygon(ref multi) => {
+                self.heap_bytes += multi.polygons.capacity() * core::mem::size_of::<PolygonT<P>>();
+                for polygon in &multi.polygons {
+                    self.add_polygon(polygon);
+                }
+            }
+            GeometryT::GeometryCollection(ref collection) => {
+                self.heap_bytes +=
+                    collection.geometries.capacity() * core::mem::size_of::<GeometryT<P>>();
+                for geom in &collection.geometries {
+                    self.add_geometry(geom);
+                }
+            }
+        }
+    }
+}
+
+// Heap memory of a point or ring list
+fn item_vec_heap_bytes<T>(items: &Vec<T>) -> usize {
+    items.capacity() * core::mem::size_of::<T>()
+}
+
+impl<P> GeometryT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    /// Vertex, ring and part counts, estimated heap memory and bounding box,
+    /// computed in a single pass.
+    ///
+    /// ```rust
+    /// use postgis::ewkb::{Geometry, Polygon};
+    ///
+    /// let polygon = Polygon::from(vec![vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 0.0)]]);
+    /// let stats = Geometry::Polygon(polygon).stats();
+    /// assert_eq!((stats.num_vertices, stats.num_rings, stats.num_parts), (4, 1, 1));
+    /// assert_eq!(stats.bbox.unwrap().xmax, 2.0);
+    /// ```
+    pub fn stats(&self) -> ComplexityReport {
+        let mut stats = ComplexityReport::default();
+        stats.add_geometry(self);
+        stats
+    }
+}
+
+// --- Validation
+
+/// Summary of a validated EWKB geometry.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct GeometryMeta {
+    /// Geometry type code (1 = Point, ..., 7 = GeometryCollection).
+    pub geometry_type: u32,
+    pub point_type: PointType,
+    pub srid: Option<i32>,
+    /// Number of points, including empty points.
+    pub num_points: usize,
+    /// Number of members of multi geometries and collections, 1 otherwise.
+    pub num_geometries: usize,
+}
+
+/// Maximum nesting depth of geometry collections accepted by [`validate`]
+/// and the [`EwkbSlice`] accessors.
+pub const MAX_NESTING_DEPTH: usize = 32;
+
+/// Check the structure of EWKB data without decoding it.
+///
+/// Verifies byte order markers, type codes, counts against the data length,
+/// member types of multi geometries, consistent dimensions of all members and
+/// that no bytes follow the geometry. Nothing is allocated, so this is cheap
+/// enough to reject untrusted input before decoding.
+///
+/// ```rust
+/// use postgis::ewkb::{self, AsEwkbLineString, EwkbWrite, LineString};
+///
+/// let line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+/// let mut buf = Vec::new();
+/// line.as_ewkb().write_ewkb(&mut buf).unwrap();
+///
+/// let meta = ewkb::validate(&buf).unwrap();
+/// assert_eq!((meta.geometry_type, meta.num_points), (2, 2));
+/// assert!(ewkb::validate(&buf[..buf.len() - 1]).is_err());
+/// ```
+pub fn validate(data: &[u8]) -> Result<GeometryMeta, Error> {
+    let mut raw = data;
+    let is_be = raw.first() == Some(&0);
+    let (type_id, srid) = parse_header(&mut raw)?;
+    let mut meta = GeometryMeta {
+        geometry_type: geometry_type_code(type_id),
+        point_type: point_type_of(type_id),
+        srid,
+        num_points: 0,
+        num_geometries: 1,
+    };
+    validate_body(&mut raw, is_be, type_id, &mut meta, 0)?;
+    if !raw.is_empty() {
+        return Err(Error::Read(format!(
+            "{} trailing bytes after EWKB geometry",
+            raw.len()
+        )));
+    }
+    Ok(meta)
+}
+
+fn point_type_of(type_id: u32) -> PointType {
+    dims_point_type(has_z(type_id), has_m(type_id))
+}
+
+fn dims_point_type(has_z: bool, has_m: bool) -> PointType {
+    match (has_z, has_m) {
+        (false, false) => PointType::Point,
+        (true, false) => PointType::PointZ,
+        (false, true) => PointType::PointM,
+        (true, true) => PointType::PointZM,
+    }
+}
+
+// Count which fits into the remaining data with at least `min_size` bytes per item
+fn read_count(raw: &mut &[u8], is_be: bool, min_size: usize) -> Result<usize, Error> {
+    let count = read_u32(raw, is_be)? as usize;
+    if count > raw.len() / min_size {
+        return Err(Error::Read(format!(
+            "count {} exceeds the remaining EWKB data",
+            count
+        )));
+    }
+    Ok(count)
+}
+
+fn skip_points(raw: &mut &[u8], count: usize, point_size: usize) -> Result<(), Error> {
+    let len = count * point_size;
+    if raw.len() < len {
+        return Err(Error::Read("unexpected end of EWKB data".into()));
+    }
+    *raw = &raw[len..];
+    Ok(())
+}
+
+fn skip_bytes(raw: &mut &[u8], len: usize) -> Result<(), Error> {
+    if raw.len() < len {
+        return Err(Error::Read("unexpected end of EWKB data".into()));
+    }
+    *raw = &raw[len..];
+    Ok(())
+}
+
+// Advance `raw` behind the next geometry without decoding it
+fn skip_geometry(raw: &mut &[u8], depth: usize) -> Result<(), Error> {
+    let is_be = raw.first() == Some(&0);
+    let (type_id, _) = parse_header(raw)?;
+    let point_size = 8 * (2 + has_z(type_id) as usize + has_m(type_id) as usize);
+    match geometry_type_code(type_id) {
+        0x01 => skip_bytes(raw, point_size),
+        0x02 => {
+            let size = read_u32(raw, is_be)? as usize;
+            skip_bytes(raw, size * point_size)
+        }
+        0x03 => {
+            for _ in 0..read_u32(raw, is_be)? {
+                let size = read_u32(raw, is_be)? as usize;
+                skip_bytes(raw, size * point_size)?;
+            }
+            Ok(())
+        }
+        _ => {
+            if depth >= MAX_NESTING_DEPTH {
+                return Err(Error::Read("geometry collections nested too deeply".into()));
+            }
+            for _ in 0..read_u32(raw, is_be)? {
+                skip_geometry(raw, depth + 1)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn validate_body(
+    raw: &mut &[u8],
+    is_be: bool,
+    type_id: u32,
+    meta: &mut GeometryMeta,
+    depth: usize,
+) -> Result<(), Error> {
+    let point_size = 8 * (2 + has_z(type_id) as usize + has_m(type_id) as usize);
+    let member_type = match geometry_type_code(type_id) {
+        0x01 => {
+            meta.num_points += 1;
+            return skip_points(raw, 1, point_size);
+        }
+        0x02 => {
+            let count = read_count(raw, is_be, point_size)?;
+            meta.num_points += count;
+            return skip_points(raw, count, point_size);
+        }
+        0x03 => {
+            for _ in 0..read_count(raw, is_be, 4)? {
+                let count = read_count(raw, is_be, point_size)?;
+                meta.num_points += count;
+                skip_points(raw, count, point_size)?;
+            }
+            return Ok(());
+        }
+        0x04 => Some(0x01),
+        0x05 => Some(0x02),
+        0x06 => Some(0x03),
+        _ => None,
+    };
+    if depth >= MAX_NESTING_DEPTH {
+        return Err(Error::Read("geometry collections nested too deeply".into()));
+    }
+    // byte order and type of each member
+    let count = read_count(raw, is_be, 5)?;
+    if depth == 0 {
+        meta.num_geometries = count;
+    }
+    for _ in 0..count {
+        let is_be = raw.first() == Some(&0);
+        let (member_id, _) = parse_header(raw)?;
+        let code = geometry_type_code(member_id);
+        if member_type.is_some_and(|expected| code != expected) {
+            return Err(Error::Read(format!(
+                "unexpected member type {} in geometry type {}",
+                code,
+                geometry_type_code(type_id)
+            )));
+        }
+        if has_z(member_id) != has_z(type_id) || has_m(member_id) != has_m(type_id) {
+            return Err(Error::Read(
+                "member dimensions differ from geometry dimensions".into(),
+            ));
+        }
+        validate_body(raw, is_be, member_id, meta, depth + 1)?;
+    }
+    Ok(())
+}
+
+// --- Parallel decoding
+
+// Decode the members of a multi geometry or collection in parallel
+#[cfg(feature = "parallel")]
+fn read_members_par<T>(data: &[u8], type_code: u32) -> Result<(Vec<T>, Option<i32>), Error>
+where
+    T: EwkbRead + Send,
+{
+    use rayon::prelude::*;
+
+    let mut raw = data;
+    let is_be = raw.first() == Some(&0);
+    let (type_id, srid) = parse_header(&mut raw)?;
+    if geometry_type_code(type_id) != type_code {
+        return Err(Error::Read(format!(
+            "expected geometry type {}, found {}",
+            type_code,
+            geometry_type_code(type_id)
+        )));
+    }
+    let size = read_u32(&mut raw, is_be)? as usize;
+    let mut members = Vec::with_capacity(size.min(raw.len()));
+    for _ in 0..size {
+        let start = raw;
+        skip_geometry(&mut raw, 1)?;
+        members.push(&start[..start.len() - raw.len()]);
+    }
+    let items = members
+        .par_iter()
+        .map(|member| T::read_ewkb(&mut &member[..]))
+        .collect::<Result<Vec<T>, Error>>()?;
+    Ok((items, srid))
+}
+
+#[cfg(feature = "parallel")]
+impl<P> MultiPolygonT<P>
+where
+    P: postgis::Point + EwkbRead + Send,
+{
+    /// Read an EWKB MultiPolygon, decoding its polygons in parallel.
+    pub fn read_ewkb_par(data: &[u8]) -> Result<Self, Error> {
+        let (polygons, srid) = read_members_par(data, 0x06)?;
+        Ok(MultiPolygonT { polygons, srid })
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<P> GeometryCollectionT<P>
+where
+    P: postgis::Point + EwkbRead + Send,
+{
+    /// Read an EWKB GeometryCollection, decoding its members in parallel.
+    pub fn read_ewkb_par(data: &[u8]) -> Result<Self, Error> {
+        let (geometries, srid) = read_members_par(data, 0x07)?;
+        Ok(GeometryCollectionT { geometries, srid })
+    }
+}
+
+// --- Promotion of single geometries to multi geometries
+
+impl<P> MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    pub fn from_point(point: P) -> Self {
+        let mut multi = MultiPointT::new_with_srid(point.srid());
+        multi.push(point);
+        multi
+    }
+}
+
+impl<P> MultiLineStringT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    pub fn from_line_string(line: LineStringT<P>) -> Self {
+        MultiLineStringT {
+            srid: line.srid,
+            lines: vec![line],
+        }
+    }
+}
+
+impl<P> MultiPolygonT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    pub fn from_polygon(polygon: PolygonT<P>) -> Self {
+        MultiPolygonT {
+            srid: polygon.srid,
+            polygons: vec![polygon],
+        }
+    }
+}
+
+impl<P> From<LineStringT<P>> for MultiLineStringT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn from(line: LineStringT<P>) -> Self {
+        Self::from_line_string(line)
+    }
+}
+
+impl<P> From<PolygonT<P>> for MultiPolygonT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn from(polygon: PolygonT<P>) -> Self {
+        Self::from_polygon(polygon)
+    }
+}
+
+/// Multi geometry which is also read from the corresponding single geometry,
+/// e.g. a `Promoted<MultiPolygon>` accepts both Polygons and MultiPolygons.
+///
+/// ```rust
+/// use postgis::ewkb::{AsEwkbPolygon, EwkbRead, EwkbWrite, MultiPolygon, Polygon, Promoted};
+///
+/// let polygon = Polygon::from(vec![vec![(0., 0.), (1., 0.), (0., 1.), (0., 0.)]]);
+/// let mut buf = Vec::new();
+/// polygon.as_ewkb().write_ewkb(&mut buf).unwrap();
+/// let multi = Promoted::<MultiPolygon>::read_ewkb(&mut buf.as_slice()).unwrap().into_inner();
+/// assert_eq!(multi.polygons, vec![polygon]);
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Promoted<T>(pub T);
+
+impl<T> Promoted<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+macro_rules! impl_read_for_promoted {
+    ($multitype:ident from $single:ty, $type_code:expr, $from_fn:ident) => {
+        impl<P> EwkbRead for Promoted<$multitype<P>>
+        where
+            P: postgis::Point + EwkbRead + Srid,
+        {
+            fn point_type() -> PointType {
+                P::point_type()
+            }
+            fn read_ewkb_body<R: Read>(
+                raw: &mut R,
+                is_be: bool,
+                type_id: u32,
+                srid: Option<i32>,
+                options: &ReadOptions,
+            ) -> Result<Self, Error> {
+                match geometry_type_code(type_id) {
+                    $type_code => {
+                        let single = <$single>::read_ewkb_body(raw, is_be, type_id, srid, options)?;
+                        Ok(Promoted($multitype::$from_fn(single)))
+                    }
+                    // multi geometry codes follow the single ones by 3
+                    code if code == $type_code + 3 => {
+                        $multitype::read_ewkb_body(raw, is_be, type_id, srid, options).map(Promoted)
+                    }
+                    code => Err(Error::Read(format!(
+                        "cannot read geometry type {} into {}",
+                        code,
+                        stringify!($multitype)
+                    ))),
+                }
+            }
+        }
+    };
+}
+
+impl_read_for_promoted!(MultiPointT from P, 0x01, from_point);
+impl_read_for_promoted!(MultiLineStringT from LineStringT<P>, 0x02, from_line_string);
+impl_read_for_promoted!(MultiPolygonT from PolygonT<P>, 0x03, from_polygon);
+
+#[test]
+#[cfg_attr(rustfmt, rustfmt_skip)]
+fn test_point_write() {
+    // 'POINT (10 -20)'
+    let point = Point { x: 10.0, y: -20.0, srid: None };
+    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000000000000000000244000000000000034C0");
+
+    // 'POINT (10 -20 100)'
+    let point = PointZ { x: 10.0, y: -20.0, z: 100.0, srid: None };
+    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000080000000000000244000000000000034C00000000000005940");
+
+    // 'POINTM (10 -20 1)'
+    let point = PointM { x: 10.0, y: -20.0, m: 1.0, srid: None };
+    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000040000000000000244000000000000034C0000000000000F03F");
+
+    // 'POINT (10 -20 100 1)'
+    let point = PointZM { x: 10.0, y: -20.0, z: 100.0, m: 1.0, srid: None };
+    assert_eq!(point.as_ewkb().to_hex_ewkb(), "01010000C0000000000000244000000000000034C00000000000005940000000000000F03F");
+
+    // 'POINT (0 -1)'
+    let point = Point { x: 0.0, y: -1.0, srid: None };
+    assert_eq!(point.as_ewkb().to_hex_ewkb(), "01010000000000000000000000000000000000F0BF");
+
+    // 'POINT (-0 -1)'
+    let point = Point { x: -0.0, y: -1.0, srid: None };
+    assert_eq!(point.as_ewkb().to_hex_ewkb(), "01010000000000000000000080000000000000F0BF");
+
+    // 'SRID=4326;POINT (10 -20)'
+    let point = Point { x: 10.0, y: -20.0, srid: Some(4326) };
+    assert_eq!(point.as_ewkb().to_hex_ewkb(), "0101000020E6100000000000000000244000000000000034C0");
+}
+
+#[test]
+#[cfg_attr(rustfmt, rustfmt_skip)]
+fn test_line_write() {
+    let p = |x, y| Point { x: x, y: y, srid: None };
+    // 'LINESTRING (10 -20, 0 -0.5)'
+    let line = LineStringT::<Point> {srid: None, points: vec![p(10.0, -20.0), p(0., -0.5)]};
+    assert_eq!(line.as_ewkb().to_hex_ewkb(), "010200000002000000000000000000244000000000000034C00000000000000000000000000000E0BF");
+
+    // 'SRID=4326;LINESTRING (10 -20, 0 -0.5)'
     let line = LineStringT::<Point> {srid: Some(4326), points: vec![p(10.0, -20.0), p(0., -0.5)]};
     assert_eq!(line.as_ewkb().to_hex_ewkb(), "0102000020E610000002000000000000000000244000000000000034C00000000000000000000000000000E0BF");
 
@@ -1731,6 +4345,17 @@ fn hex_to_vec(hexstr: &str) -> Vec<u8> {
     }).collect::<Vec<_>>()
 }
 
+// GeometryCollections nested `depth` levels deep, each with a single member
+#[cfg(test)]
+fn nested_collection(depth: usize) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for _ in 0..depth {
+        buf.extend_from_slice(&[0x01, 0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+    }
+    buf.extend_from_slice(&[0x01, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    buf
+}
+
 #[test]
 #[cfg_attr(rustfmt, rustfmt_skip)]
 fn test_point_read() {
@@ -1883,3 +4508,766 @@ fn test_iterators() {
     let line = self::LineStringT::<Point> {srid: Some(4326), points: vec![p(10.0, -20.0), p(0., -0.5)]};
     assert_eq!(line.points().last(), Some(&Point { x: 0., y: -0.5, srid: None }));
 }
+
+#[test]
+#[rustfmt::skip]
+fn test_from_tuples() {
+    assert_eq!(Point::from((1.0, 2.0)), Point { x: 1.0, y: 2.0, srid: None });
+    let line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+    assert_eq!(line.as_ewkb().to_hex_ewkb(), "010200000002000000000000000000244000000000000034C00000000000000000000000000000E0BF");
+    let line = LineStringZ::from(vec![(10.0, -20.0, 100.0), (0., -0.5, 101.0)]);
+    assert_eq!(line.points[1], PointZ { x: 0., y: -0.5, z: 101.0, srid: None });
+    let poly = PolygonZM::from(vec![vec![(0., 0., 1., 2.), (2., 0., 1., 2.), (0., 2., 1., 2.), (0., 0., 1., 2.)]]);
+    assert_eq!(poly.rings[0].points.len(), 4);
+    let multipoly = MultiPolygon::from(vec![vec![vec![(0., 0.), (2., 0.), (0., 2.), (0., 0.)]]]);
+    assert_eq!(multipoly.polygons[0].rings[0].points[1], Point { x: 2., y: 0., srid: None });
+}
+
+#[test]
+fn test_into_iterator() {
+    let mut line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+    for p in &mut line {
+        p.x += 1.0;
+    }
+    let xs: Vec<f64> = (&line).into_iter().map(|p| p.x).collect();
+    assert_eq!(xs, vec![11.0, 1.0]);
+    let multipoint: MultiPoint = line.into_iter().collect();
+    assert_eq!(multipoint.points.len(), 2);
+
+    let collection = GeometryCollection {
+        geometries: vec![GeometryT::Point(Point::new(1.0, 2.0, None))],
+        srid: None,
+    };
+    let mut count = 0;
+    for geom in collection {
+        assert!(matches!(geom, GeometryT::Point(_)));
+        count += 1;
+    }
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_iter_mut() {
+    let ring = LineStringZ::from(vec![(0., 0., 0.), (1., 0., 0.), (0., 1., 0.), (0., 0., 0.)]);
+    let mut multipoly = MultiPolygonZ::from(vec![PolygonZ::from(vec![ring])]);
+    for poly in multipoly.polygons_mut() {
+        for ring in poly.rings_mut() {
+            for p in ring.points_mut() {
+                p.z = 10.0;
+            }
+        }
+    }
+    assert!(multipoly.polygons[0].rings[0].points.iter().all(|p| p.z == 10.0));
+
+    let mut lines = MultiLineString::from(vec![vec![(0., 0.), (1., 1.)]]);
+    lines.lines_mut().for_each(|line| line.points_mut().for_each(|p| p.y += 1.0));
+    assert_eq!(lines.lines[0].points[1], Point::new(1., 2., None));
+}
+
+#[test]
+fn test_index() {
+    let mut line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+    assert_eq!(line.len(), 2);
+    assert!(!line.is_empty());
+    assert_eq!(line[1], Point::new(0., -0.5, None));
+    assert_eq!(line.get(2), None);
+    line[0].x = 1.0;
+    line.get_mut(1).unwrap().y = 2.0;
+    assert_eq!(line.points, vec![Point::new(1., -20., None), Point::new(0., 2., None)]);
+
+    let poly = Polygon::from(vec![vec![(0., 0.), (1., 0.), (0., 1.), (0., 0.)]]);
+    assert_eq!(poly[0][2], Point::new(0., 1., None));
+    assert!(GeometryCollection::new().is_empty());
+}
+
+#[test]
+fn test_push_extend() {
+    let mut line = LineString::new();
+    line.push(Point::new(0., 0., None));
+    line.insert(0, Point::new(-1., 0., None));
+    line.extend(vec![Point::new(1., 1., None), Point::new(2., 2., None)]);
+    assert_eq!(line.len(), 4);
+    assert_eq!(line.pop(), Some(Point::new(2., 2., None)));
+    assert_eq!(line[0], Point::new(-1., 0., None));
+
+    let mut multipoint = MultiPoint::new();
+    Extend::extend(&mut multipoint, line);
+    assert_eq!(multipoint.len(), 3);
+}
+
+#[test]
+fn test_new_with_srid() {
+    let mut line = LineStringZ::with_capacity_and_srid(2, Some(4326));
+    assert!(line.points.capacity() >= 2);
+    line.push(PointZ::new(10.0, -20.0, 100.0, Some(4326)));
+    assert_eq!(line.as_ewkb().opt_srid(), Some(4326));
+    assert_eq!(MultiPolygon::new_with_srid(Some(3857)).srid, Some(3857));
+    assert_eq!(GeometryCollection::new_with_srid(None).srid, None);
+}
+
+#[test]
+fn test_bit_exact() {
+    use std::collections::HashSet;
+
+    // numeric equality
+    assert_eq!(Point::new(-0.0, 2.0, None), Point::new(0.0, 2.0, None));
+    assert_ne!(PointM::new(1.0, f64::NAN, 0.0, None), PointM::new(1.0, f64::NAN, 0.0, None));
+
+    let mut set = HashSet::new();
+    assert!(set.insert(BitExact(Point::new(1.0, 2.0, None))));
+    assert!(!set.insert(BitExact(Point::new(1.0, 2.0, None))));
+    assert!(set.insert(BitExact(Point::new(1.0, 2.0, Some(4326)))));
+    assert!(set.insert(BitExact(Point::new(-0.0, 2.0, None))));
+    assert!(set.insert(BitExact(Point::new(0.0, 2.0, None))));
+    assert_eq!(
+        BitExact(PointM::new(1.0, f64::NAN, 0.0, None)),
+        BitExact(PointM::new(1.0, f64::NAN, 0.0, None))
+    );
+
+    let mut set = HashSet::new();
+    let line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+    let geom = GeometryT::LineString(line.clone());
+    assert!(set.insert(BitExact(geom.clone())));
+    assert!(!set.insert(BitExact(geom.clone())));
+    assert!(set.insert(BitExact(GeometryT::MultiPoint(MultiPoint {
+        points: line.points.clone(),
+        srid: None,
+    }))));
+    let mut coll = GeometryCollection::new();
+    coll.geometries.push(geom);
+    assert!(set.insert(BitExact(GeometryT::GeometryCollection(coll.clone()))));
+    assert!(!set.insert(BitExact(GeometryT::GeometryCollection(coll))));
+    assert_eq!(set.len(), 3);
+
+    // borrowed keys
+    let polygon = Polygon {
+        rings: vec![line.clone()],
+        srid: None,
+    };
+    let copy = polygon.clone();
+    let mut set = HashSet::new();
+    assert!(set.insert(BitExact(&polygon)));
+    assert!(!set.insert(BitExact(&copy)));
+
+    let line = crate::twkb::LineString {
+        points: vec![crate::twkb::Point {
+            x: f64::NAN,
+            y: -0.0,
+            z: None,
+            m: Some(1.0),
+        }],
+    };
+    assert_ne!(line, line.clone());
+    assert_eq!(BitExact(&line), BitExact(&line.clone()));
+}
+
+#[test]
+fn test_geometry_accessors() {
+    let mut geom = GeometryT::LineString(LineString::from(vec![(10.0, -20.0), (0., -0.5)]));
+    assert_eq!(geom.type_name(), "LineString");
+    assert!(geom.as_point().is_none());
+    assert_eq!(geom.as_line_string().map(|line| line.len()), Some(2));
+    geom.as_line_string_mut().unwrap().push(Point::new(1., 1., None));
+    let geom = geom.into_polygon().unwrap_err();
+    let line = geom.clone().into_line_string().unwrap();
+    assert_eq!(line.len(), 3);
+
+    assert_eq!(LineString::try_from(geom.clone()).unwrap(), line);
+    let err = Polygon::try_from(geom).unwrap_err();
+    assert_eq!(format!("{}", err), "Other(\"expected Polygon, found LineString\")");
+    let point = PointZ::new(1.0, 2.0, 3.0, None);
+    assert_eq!(PointZ::try_from(GeometryT::Point(point)).unwrap(), point);
+}
+
+#[test]
+fn test_srid() {
+    fn srid_of<T: Srid>(geom: &T) -> Option<i32> {
+        geom.srid()
+    }
+    let mut geom = GeometryT::LineString(LineString::from(vec![(10.0, -20.0), (0., -0.5)]));
+    assert_eq!(srid_of(&geom), None);
+    geom.set_srid(Some(4326));
+    assert_eq!(srid_of(&geom), Some(4326));
+    let line = geom.into_line_string().unwrap();
+    assert_eq!(Srid::srid(&line.points[1]), Some(4326));
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_read_promoted() {
+    // SELECT 'SRID=4326;LINESTRING (10 -20 100, 0 -0.5 101)'::geometry
+    let ewkb = hex_to_vec("01020000A0E610000002000000000000000000244000000000000034C000000000000059400000000000000000000000000000E0BF0000000000405940");
+    let multi = Promoted::<MultiLineStringZ>::read_ewkb(&mut ewkb.as_slice()).unwrap().into_inner();
+    assert_eq!(multi.srid, Some(4326));
+    assert_eq!(multi.lines[0].points[1], PointZ::new(0., -0.5, 101.0, Some(4326)));
+    // SELECT 'SRID=4326;MULTIPOINT ((10 -20 100), (0 -0.5 101))'::geometry
+    let ewkb = hex_to_vec("01040000A0E6100000020000000101000080000000000000244000000000000034C0000000000000594001010000800000000000000000000000000000E0BF0000000000405940");
+    let multi = Promoted::<MultiPointZ>::read_ewkb(&mut ewkb.as_slice()).unwrap().into_inner();
+    assert_eq!(multi.points.len(), 2);
+    // 'POINT (10 -20)'
+    let ewkb = hex_to_vec("0101000000000000000000244000000000000034C0");
+    let multi = Promoted::<MultiPoint>::read_ewkb(&mut ewkb.as_slice()).unwrap().into_inner();
+    assert_eq!(multi.points, vec![Point::new(10.0, -20.0, None)]);
+    assert!(Promoted::<MultiPolygon>::read_ewkb(&mut ewkb.as_slice()).is_err());
+}
+
+#[test]
+fn test_flatten_collection() {
+    let point = |x, y| GeometryT::Point(Point::new(x, y, None));
+    let line = GeometryT::LineString(LineString::from(vec![(10.0, -20.0), (0., -0.5)]));
+    let nested = GeometryCollection {
+        geometries: vec![point(2., 2.), GeometryT::GeometryCollection(GeometryCollection::new())],
+        srid: None,
+    };
+    let collection = GeometryCollection {
+        geometries: vec![point(1., 1.), GeometryT::GeometryCollection(nested), point(3., 3.)],
+        srid: Some(4326),
+    };
+    let leaves: Vec<_> = collection.flatten().collect();
+    assert_eq!(leaves, vec![&point(1., 1.), &point(2., 2.), &point(3., 3.)]);
+
+    match collection.collect_into_multi() {
+        GeometryT::MultiPoint(mp) => {
+            assert_eq!(mp.srid, Some(4326));
+            assert_eq!(mp.len(), 3);
+        }
+        _ => panic!("expected MultiPoint"),
+    }
+
+    let mut mixed = collection.clone();
+    mixed.push(line);
+    match mixed.collect_into_multi() {
+        GeometryT::GeometryCollection(c) => {
+            assert_eq!(c.geometries[0].as_multi_point().map(|mp| mp.len()), Some(3));
+            assert_eq!(c.geometries[1].as_multi_line_string().map(|ml| ml.len()), Some(1));
+        }
+        _ => panic!("expected GeometryCollection"),
+    }
+    assert!(GeometryCollection::new().collect_into_multi().as_geometry_collection().unwrap().is_empty());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_point_stream() {
+    // SELECT 'SRID=4326;MULTIPOINT ((10 -20 100), (0 -0.5 101))'::geometry
+    let ewkb = hex_to_vec("01040000A0E6100000020000000101000080000000000000244000000000000034C0000000000000594001010000800000000000000000000000000000E0BF0000000000405940");
+    let stream = EwkbPointStream::<_, PointZ>::new(ewkb.as_slice()).unwrap();
+    assert_eq!(stream.srid(), Some(4326));
+    let points: Vec<PointZ> = stream.collect::<Result<_, _>>().unwrap();
+    assert_eq!(points[1], PointZ::new(0., -0.5, 101.0, None));
+
+    // truncated LINESTRING
+    let ewkb = hex_to_vec("010200000002000000000000000000244000000000000034C000000000");
+    let mut stream = EwkbPointStream::<_, Point>::new(ewkb.as_slice()).unwrap();
+    assert_eq!(stream.next().unwrap().unwrap(), Point::new(10.0, -20.0, None));
+    assert!(stream.next().unwrap().is_err());
+    assert!(stream.next().is_none());
+
+    // 'POINT (10 -20)'
+    let ewkb = hex_to_vec("0101000000000000000000244000000000000034C0");
+    assert!(EwkbPointStream::<_, Point>::new(ewkb.as_slice()).is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_ewkb_iterator() {
+    // reader returning one byte per call
+    struct Trickle<'a>(&'a [u8]);
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    // 'POINT (10 -20)', 'SRID=4326;LINESTRING (10 -20, 0 -0.5)'
+    let mut dump = hex_to_vec("0101000000000000000000244000000000000034C0");
+    dump.extend(hex_to_vec("0102000020E610000002000000000000000000244000000000000034C00000000000000000000000000000E0BF"));
+    let mut geoms = EwkbIterator::<_, Geometry>::new(Trickle(&dump));
+    assert_eq!(geoms.next().unwrap().unwrap(), GeometryT::Point(Point::new(10.0, -20.0, None)));
+    assert_eq!(geoms.offset(), 21);
+    match geoms.next().unwrap().unwrap() {
+        GeometryT::LineString(line) => assert_eq!(line.srid, Some(4326)),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(geoms.next().is_none());
+    assert!(EwkbIterator::<_, Geometry>::new(&[][..]).next().is_none());
+
+    // truncated second geometry
+    let mut geoms = EwkbIterator::<_, Point>::new(&dump[..30]);
+    assert!(geoms.next().unwrap().is_ok());
+    match geoms.next().unwrap() {
+        Err(Error::Read(msg)) => assert!(msg.starts_with("geometry at offset 21: "), "{}", msg),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(geoms.next().is_none());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_ewkb_slice() {
+    // SELECT 'SRID=4326;MULTIPOINT ((10 -20 100), (0 -0.5 101))'::geometry
+    let ewkb = hex_to_vec("01040000A0E6100000020000000101000080000000000000244000000000000034C0000000000000594001010000800000000000000000000000000000E0BF0000000000405940");
+    let view = EwkbSlice::new(&ewkb).unwrap();
+    assert_eq!(view.geometry_type(), 4);
+    assert_eq!(view.point_type(), PointType::PointZ);
+    assert_eq!(view.srid(), Some(4326));
+    assert_eq!(view.bbox().unwrap(), Some(Envelope::new(0.0, -20.0, 10.0, -0.5)));
+    assert_eq!(view.point_n(1).unwrap(), Some(Coord::new(0.0, -0.5).with_z(101.0)));
+    assert_eq!(view.point_n(2).unwrap(), None);
+    assert_eq!(view.decode::<MultiPointZ>().unwrap().len(), 2);
+
+    // SELECT 'GEOMETRYCOLLECTION EMPTY'::geometry
+    let ewkb = hex_to_vec("010700000000000000");
+    assert_eq!(EwkbSlice::new(&ewkb).unwrap().bbox().unwrap(), None);
+
+    assert!(EwkbSlice::new(&[0x01, 0x09, 0x00, 0x00, 0x00]).is_err());
+    assert!(EwkbSlice::new(&[0x01, 0x01]).is_err());
+    // truncated body is only detected when read
+    let ewkb = hex_to_vec("0101000000000000000000244000000000");
+    assert!(EwkbSlice::new(&ewkb).unwrap().bbox().is_err());
+
+    let nested = nested_collection(1000);
+    assert!(EwkbSlice::new(&nested).unwrap().bbox().is_err());
+    assert!(EwkbSlice::new(&nested).unwrap().point_n(0).is_err());
+}
+
+#[test]
+fn test_ewkb_size() {
+    let p = |x, y| Point::new(x, y, None);
+    let poly = PolygonT::<Point> {
+        rings: vec![LineStringT::<Point> {
+            points: vec![p(0., 0.), p(2., 0.), p(2., 2.), p(0., 2.), p(0., 0.)],
+            srid: None,
+        }],
+        srid: Some(4326),
+    };
+    let multipoly = MultiPolygonT::<Point> {
+        polygons: vec![poly.clone(), poly],
+        srid: Some(4326),
+    };
+    let coll = GeometryCollectionT::<PointZ> {
+        geometries: vec![
+            GeometryT::Point(PointZ::new(1.0, 2.0, 3.0, None)),
+            GeometryT::LineString(LineStringZ::from(vec![(0., 0., 0.), (1., 1., 1.)])),
+        ],
+        srid: None,
+    };
+    let mut buf = Vec::new();
+    multipoly.as_ewkb().write_ewkb(&mut buf).unwrap();
+    assert_eq!(multipoly.as_ewkb().ewkb_size(), buf.len());
+    let mut exact = vec![0u8; buf.len()];
+    assert_eq!(multipoly.as_ewkb().write_ewkb_into(&mut exact).unwrap(), buf.len());
+    assert_eq!(exact, buf);
+    assert!(multipoly.as_ewkb().write_ewkb_into(&mut exact[1..]).is_err());
+
+    let options = WriteOptions { child_srid: true, ..Default::default() };
+    let mut buf = Vec::new();
+    multipoly.as_ewkb().write_ewkb_with(&mut buf, &options).unwrap();
+    assert_eq!(multipoly.as_ewkb().ewkb_size_with(&options), buf.len());
+    assert_eq!(buf.len(), multipoly.as_ewkb().ewkb_size() + 2 * 4);
+
+    let mut buf = Vec::new();
+    coll.as_ewkb().write_ewkb(&mut buf).unwrap();
+    assert_eq!(coll.as_ewkb().ewkb_size(), buf.len());
+    let geom = GeometryT::GeometryCollection(coll);
+    assert_eq!(geom.as_ewkb().ewkb_size(), buf.len());
+    assert_eq!(PointZM::new(0., 0., 0., 0., Some(4326)).as_ewkb().ewkb_size(), 41);
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+#[rustfmt::skip]
+fn test_read_par() {
+    // SELECT 'SRID=4326;MULTIPOLYGON (((0 0,2 0,2 2,0 2,0 0)),((10 10,-2 10,-2 -2,10 -2,10 10),(2 2,5 2,5 5,2 5,2 2)))'::geometry
+    let ewkb = hex_to_vec("0106000020E610000002000000010300000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000010300000002000000050000000000000000002440000000000000244000000000000000C0000000000000244000000000000000C000000000000000C0000000000000244000000000000000C000000000000024400000000000002440050000000000000000000040000000000000004000000000000014400000000000000040000000000000144000000000000014400000000000000040000000000000144000000000000000400000000000000040");
+    let par = MultiPolygon::read_ewkb_par(&ewkb).unwrap();
+    assert_eq!(par, MultiPolygon::read_ewkb(&mut ewkb.as_slice()).unwrap());
+    assert_eq!(par.srid, Some(4326));
+    assert_eq!(par.polygons[1].rings[1].points[2], Point::new(5.0, 5.0, None));
+    assert!(MultiPolygon::read_ewkb_par(&ewkb[..ewkb.len() - 1]).is_err());
+    assert!(GeometryCollection::read_ewkb_par(&ewkb).is_err());
+
+    // SELECT 'GeometryCollection(POINT (10 10),POINT (30 30),LINESTRING (15 15, 20 20))'::geometry
+    let ewkb = hex_to_vec("01070000000300000001010000000000000000002440000000000000244001010000000000000000003E400000000000003E400102000000020000000000000000002E400000000000002E4000000000000034400000000000003440");
+    let par = GeometryCollection::read_ewkb_par(&ewkb).unwrap();
+    assert_eq!(par, GeometryCollection::read_ewkb(&mut ewkb.as_slice()).unwrap());
+
+    assert!(GeometryCollection::read_ewkb_par(&nested_collection(1000)).is_err());
+}
+
+#[test]
+fn test_write_hex_ewkb() {
+    let point = PointZ::new(10.0, -20.0, 100.0, None);
+    let mut out = String::from("\\x");
+    point.as_ewkb().write_hex_ewkb(&mut out).unwrap();
+    assert_eq!(out, "\\x0101000080000000000000244000000000000034C00000000000005940");
+    assert_eq!(&out[2..], point.as_ewkb().to_hex_ewkb());
+}
+
+#[test]
+fn test_read_long_line() {
+    let line: LineStringZ = (0..500).map(|i| PointZ::new(i as f64, 0.0, -(i as f64), None)).collect();
+    let mut buf = Vec::new();
+    line.as_ewkb().write_ewkb(&mut buf).unwrap();
+    let mut reader = std::io::BufReader::new(buf.as_slice());
+    assert_eq!(LineStringZ::read_ewkb(&mut reader).unwrap(), line);
+    assert!(LineStringZ::read_ewkb(&mut &buf[..buf.len() - 8]).is_err());
+}
+
+#[test]
+fn test_read_ewkb_from_slice() {
+    let line: LineStringZ = (0..500).map(|i| PointZ::new(i as f64, 0.0, -(i as f64), None)).collect();
+    let mut buf = Vec::new();
+    line.as_ewkb().write_ewkb(&mut buf).unwrap();
+    assert_eq!(LineStringZ::read_ewkb_from_slice(&buf).unwrap(), line);
+    assert!(LineStringZ::read_ewkb_from_slice(&buf[..buf.len() - 8]).is_err());
+    assert!(LineStringZ::read_ewkb_exact(&buf[..buf.len() - 1]).is_err());
+    // point count far beyond the data
+    let mut huge = buf.clone();
+    huge[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(LineStringZ::read_ewkb_from_slice(&huge).is_err());
+
+    // SELECT 'LINESTRING(1 2,3 4)'::geometry in big endian (XDR)
+    let be = hex_to_vec("0000000002000000023FF0000000000000400000000000000040080000000000004010000000000000");
+    let line = LineString::read_ewkb_from_slice(&be).unwrap();
+    assert_eq!(line, LineString::read_ewkb(&mut be.as_slice()).unwrap());
+    assert_eq!((line.points[1].x, line.points[1].y), (3.0, 4.0));
+
+    let ewkt = "SRID=4326;GEOMETRYCOLLECTION(POINT(1 2 3 4),LINESTRING(0 0 1 2,1 1 3 4),\
+                POLYGON((0 0 0 0,2 0 0 0,2 2 0 0,0 0 0 0)),MULTIPOINT(1 1 1 1,2 2 2 2),\
+                MULTILINESTRING((0 0 0 0,1 1 1 1)),MULTIPOLYGON(((0 0 0 0,1 0 0 0,1 1 0 0,0 0 0 0))))";
+    let geom = crate::bulk::parse_ewkt::<PointZM>(ewkt).unwrap();
+    let mut buf = Vec::new();
+    geom.as_ewkb().write_ewkb(&mut buf).unwrap();
+    let read = GeometryT::<PointZM>::read_ewkb_from_slice(&buf).unwrap();
+    assert_eq!(read, GeometryT::<PointZM>::read_ewkb(&mut buf.as_slice()).unwrap());
+    assert_eq!(read, geom);
+    for len in 0..buf.len() {
+        assert!(GeometryT::<PointZM>::read_ewkb_from_slice(&buf[..len]).is_err());
+    }
+
+    // the coordinate policy applies to decoded blocks
+    let line = LineString::from(vec![(1.0, 2.0), (f64::NAN, 3.0)]);
+    let mut buf = Vec::new();
+    line.as_ewkb().write_ewkb(&mut buf).unwrap();
+    assert!(LineString::read_ewkb_from_slice(&buf).unwrap().points[1].x.is_nan());
+    let reject = ReadOptions { coord_policy: CoordPolicy::Reject };
+    assert!(LineString::read_ewkb_slice_with(&mut buf.as_slice(), &reject).is_err());
+    let replace = ReadOptions { coord_policy: CoordPolicy::Replace(0.0) };
+    let read = LineString::read_ewkb_slice_with(&mut buf.as_slice(), &replace).unwrap();
+    assert_eq!(read, LineString::from(vec![(1.0, 2.0), (0.0, 3.0)]));
+    let multi = MultiPoint { points: line.points.clone(), srid: None };
+    let mut buf = Vec::new();
+    multi.as_ewkb().write_ewkb(&mut buf).unwrap();
+    assert!(MultiPoint::read_ewkb_slice_with(&mut buf.as_slice(), &reject).is_err());
+    let read = MultiPoint::read_ewkb_slice_with(&mut buf.as_slice(), &replace).unwrap();
+    assert_eq!(read.points[1], Point::new(0.0, 3.0, None));
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_polygon_exterior_interiors() {
+    let exterior = LineString { points: vec![Point::new(0., 0., None), Point::new(4., 0., None), Point::new(0., 4., None), Point::new(0., 0., None)].into_iter().collect(), srid: Some(4326) };
+    let hole = LineString { points: vec![Point::new(1., 1., None), Point::new(2., 1., None), Point::new(1., 2., None), Point::new(1., 1., None)].into_iter().collect(), srid: Some(4326) };
+    let poly = Polygon::from_exterior_and_holes(exterior.clone(), vec![hole.clone()]);
+    assert_eq!(poly.srid, Some(4326));
+    assert_eq!(poly.exterior(), Some(&exterior));
+    assert_eq!(poly.interiors(), &[hole.clone()][..]);
+    assert_eq!(postgis::Polygon::exterior(&poly), Some(&exterior));
+    assert_eq!(postgis::Polygon::interiors(&poly).collect::<Vec<_>>(), vec![&hole]);
+
+    let empty = Polygon::new();
+    assert_eq!(empty.exterior(), None);
+    assert!(empty.interiors().is_empty());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_validate() {
+    let multi = MultiLineStringZ { lines: vec![
+        LineStringZ::from(vec![(0., 0., 1.), (1., 1., 1.)]),
+        LineStringZ::from(vec![(2., 2., 1.), (3., 3., 1.), (4., 4., 1.)]),
+    ].into_iter().collect(), srid: Some(4326) };
+    let ewkb = hex_to_vec(&multi.as_ewkb().to_hex_ewkb());
+    let meta = validate(&ewkb).unwrap();
+    assert_eq!(meta, GeometryMeta { geometry_type: 5, point_type: PointType::PointZ, srid: Some(4326), num_points: 5, num_geometries: 2 });
+
+    // truncated, trailing bytes, huge count
+    assert!(validate(&ewkb[..ewkb.len() - 8]).is_err());
+    let mut trailing = ewkb.clone();
+    trailing.push(0);
+    assert!(validate(&trailing).is_err());
+    assert!(validate(&[0x01, 0x02, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0x7f]).is_err());
+
+    // member dimensions
+    let mut ewkb = hex_to_vec(&multi.as_ewkb().to_hex_ewkb());
+    // clear Z flag of the second member
+    let pos = ewkb.len() - 3 * 24 - 4 - 4 + 3;
+    assert_eq!(ewkb[pos], 0x80);
+    ewkb[pos] = 0;
+    assert!(validate(&ewkb).is_err());
+
+    // member type
+    let ewkb = hex_to_vec("0104000000010000000102000000000000000000");
+    assert!(validate(&ewkb).is_err());
+
+    let geom: Geometry = GeometryT::GeometryCollection(GeometryCollection {
+        geometries: vec![GeometryT::Point(Point::new(1., 1., None)), GeometryT::Point(Point::new(f64::NAN, f64::NAN, None))],
+        srid: None,
+    });
+    let meta = validate(&hex_to_vec(&geom.as_ewkb().to_hex_ewkb())).unwrap();
+    assert_eq!((meta.geometry_type, meta.num_points, meta.num_geometries), (7, 2, 2));
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_read_header() {
+    // SELECT 'SRID=4326;MULTIPOINTM(1 2 3)'::geometry
+    let ewkb = hex_to_vec("0104000060E6100000010000000101000040000000000000F03F00000000000000400000000000000840");
+    let header = read_header(&ewkb).unwrap();
+    assert_eq!(header, EwkbHeader { geom_type: 4, has_z: false, has_m: true, srid: Some(4326), byte_order: Endianness::LittleEndian });
+    assert_eq!(header.point_type(), PointType::PointM);
+
+    // SELECT ST_AsBinary('POINT(1 2)'::geometry, 'XDR')
+    let ewkb = hex_to_vec("00000000013FF00000000000004000000000000000");
+    let header = read_header(&ewkb).unwrap();
+    assert_eq!((header.geom_type, header.srid, header.byte_order), (1, None, Endianness::BigEndian));
+
+    assert!(read_header(&ewkb[..3]).is_err());
+    assert!(read_header(&[]).is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_read_ewkb_exact() {
+    // SELECT 'POINT(10 -20)'::geometry
+    let mut ewkb = hex_to_vec("0101000000000000000000244000000000000034C0");
+    assert_eq!(Point::read_ewkb_exact(&ewkb).unwrap(), Point::new(10.0, -20.0, None));
+    ewkb.extend_from_slice(&[0xde, 0xad]);
+    assert!(Point::read_ewkb_from_slice(&ewkb).is_ok());
+    match Point::read_ewkb_exact(&ewkb) {
+        Err(Error::Read(msg)) => assert_eq!(msg, "2 trailing bytes after geometry"),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(LineString::read_ewkb_exact(&ewkb[..4]).is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_special_values_roundtrip() {
+    let fixtures = [
+        // 'POINT (-0 -1)'
+        "01010000000000000000000080000000000000F0BF",
+        // 'POINT EMPTY'
+        "0101000000000000000000F87F000000000000F87F",
+        // 'SRID=4326;LINESTRING (-0 -0, 1 -0)'
+        "0102000020E61000000200000000000000000000800000000000000080000000000000F03F0000000000000080",
+    ];
+    for hex in fixtures.iter() {
+        let geom = Geometry::read_ewkb(&mut hex_to_vec(hex).as_slice()).unwrap();
+        assert_eq!(&geom.as_ewkb().to_hex_ewkb(), hex);
+    }
+    // 'MULTIPOINT Z (-0 1 -0)'
+    let hex = "01040000800100000001010000800000000000000080000000000000F03F0000000000000080";
+    let geom = GeometryZ::read_ewkb(&mut hex_to_vec(hex).as_slice()).unwrap();
+    assert_eq!(geom.as_ewkb().to_hex_ewkb(), hex);
+
+    // NaN payloads and sign
+    for bits in [0x7ff8000000000001u64, 0xfff8000000000000, 0x7ff4000000000000].iter() {
+        let mut ewkb = hex_to_vec("0101000000000000000000F87F000000000000F03F");
+        ewkb[5..13].copy_from_slice(&bits.to_le_bytes());
+        let point = Point::read_ewkb(&mut ewkb.as_slice()).unwrap();
+        assert_eq!(point.x.to_bits(), *bits);
+        let mut out = Vec::new();
+        point.as_ewkb().write_ewkb(&mut out).unwrap();
+        assert_eq!(out, ewkb);
+    }
+}
+
+#[test]
+fn test_coord_policy() {
+    assert_eq!(CoordPolicy::Accept.apply(1.5).unwrap(), 1.5);
+    assert!(CoordPolicy::Accept.apply(f64::NAN).unwrap().is_nan());
+    assert_eq!(CoordPolicy::Reject.apply(-2.0).unwrap(), -2.0);
+    assert!(CoordPolicy::Reject.apply(f64::NAN).is_err());
+    assert!(CoordPolicy::Reject.apply(f64::INFINITY).is_err());
+    assert_eq!(CoordPolicy::Replace(0.0).apply(f64::NEG_INFINITY).unwrap(), 0.0);
+
+    let geom: Geometry = GeometryT::GeometryCollection(GeometryCollection {
+        geometries: vec![GeometryT::LineString(LineString::from(vec![(1.0, 2.0), (f64::INFINITY, 3.0)]))],
+        srid: None,
+    });
+    let mut buf = Vec::new();
+    geom.as_ewkb().write_ewkb(&mut buf).unwrap();
+    let read = |coord_policy| Geometry::read_ewkb_with(&mut buf.as_slice(), &ReadOptions { coord_policy });
+    assert_eq!(read(CoordPolicy::Accept).unwrap(), geom);
+    assert!(read(CoordPolicy::Reject).is_err());
+    let replaced: Geometry = GeometryT::GeometryCollection(GeometryCollection {
+        geometries: vec![GeometryT::LineString(LineString::from(vec![(1.0, 2.0), (-1.0, 3.0)]))],
+        srid: None,
+    });
+    assert_eq!(read(CoordPolicy::Replace(-1.0)).unwrap(), replaced);
+
+    let write = |coord_policy| {
+        let mut buf = Vec::new();
+        let options = WriteOptions { coord_policy, ..Default::default() };
+        geom.as_ewkb().write_ewkb_with(&mut buf, &options).map(|_| buf)
+    };
+    assert!(write(CoordPolicy::Reject).is_err());
+    let written = write(CoordPolicy::Replace(-1.0)).unwrap();
+    assert_eq!(Geometry::read_ewkb(&mut written.as_slice()).unwrap(), replaced);
+}
+
+#[test]
+#[rustfmt::skip]
+#[cfg(feature = "serde")]
+fn test_serde() {
+    let mut poly = PolygonZ::from(vec![vec![(0.0, 0.0, 1.0), (1.0, 0.0, 2.0), (0.0, 1.0, 3.0), (0.0, 0.0, 1.0)]]);
+    poly.srid = Some(4326);
+    let geom = GeometryZ::Polygon(poly);
+    let json = serde_json::to_string(&geom).unwrap();
+    assert!(json.starts_with(r#"{"Polygon":{"rings":[{"points":[{"x":0.0,"y":0.0,"z":1.0,"srid":null}"#));
+    assert_eq!(serde_json::from_str::<GeometryZ>(&json).unwrap(), geom);
+}
+
+#[test]
+#[cfg(feature = "smallvec")]
+fn test_small_geometries() {
+    let square = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)];
+    let mut poly = Polygon::from(vec![square]);
+    poly.set_srid(Some(4326));
+    let small = SmallPolygonT::from(poly.clone());
+    assert!(!small.rings.spilled() && !small.rings[0].points.spilled());
+    assert_eq!(small.as_ewkb().to_hex_ewkb(), poly.as_ewkb().to_hex_ewkb());
+
+    let mut buf = Vec::new();
+    poly.as_ewkb().write_ewkb(&mut buf).unwrap();
+    let read = SmallPolygonT::<Point>::read_ewkb(&mut buf.as_slice()).unwrap();
+    assert_eq!(read, small);
+    assert_eq!(PolygonT::from(read), poly);
+
+    let line = SmallLineStringT::<Point>::from(vec![(0.0, 0.0), (1.0, 1.0)]);
+    assert_eq!(line.as_ewkb().ewkb_size(), LineString::from(line.clone()).as_ewkb().ewkb_size());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_geometry_stats() {
+    // SELECT 'SRID=4326;MULTIPOLYGON (((0 0,2 0,2 2,0 2,0 0)),((10 10,-2 10,-2 -2,10 -2,10 10),(2 2,5 2,5 5,2 5,2 2)))'::geometry
+    let ewkb = hex_to_vec("0106000020E610000002000000010300000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000010300000002000000050000000000000000002440000000000000244000000000000000C0000000000000244000000000000000C000000000000000C0000000000000244000000000000000C000000000000024400000000000002440050000000000000000000040000000000000004000000000000014400000000000000040000000000000144000000000000014400000000000000040000000000000144000000000000000400000000000000040");
+    let multi = MultiPolygon::read_ewkb(&mut ewkb.as_slice()).unwrap();
+    let geom = Geometry::GeometryCollection(GeometryCollection {
+        geometries: vec![
+            Geometry::MultiPolygon(multi),
+            Geometry::Point(Point::new(20.0, 1.0, None)),
+            Geometry::MultiPoint(MultiPoint::from(vec![(f64::NAN, f64::NAN), (3.0, 4.0)])),
+        ],
+        srid: None,
+    });
+    let stats = geom.stats();
+    assert_eq!((stats.num_vertices, stats.num_rings, stats.num_parts), (18, 3, 5));
+    assert_eq!(stats.bbox, Some(Envelope::new(-2.0, -2.0, 20.0, 10.0)));
+    assert!(stats.heap_bytes >= 15 * core::mem::size_of::<Point>());
+
+    assert_eq!(Geometry::Point(Point::new(f64::NAN, f64::NAN, None)).stats().bbox, None);
+}
+
+#[test]
+fn test_write_precision() {
+    assert_eq!(WritePrecision::Decimals(2).apply(1.23456), 1.23);
+    assert_eq!(WritePrecision::Decimals(-2).apply(1234.5), 1200.0);
+    assert_eq!(WritePrecision::Grid(0.5).apply(1.3), 1.5);
+    assert_eq!(WritePrecision::Grid(0.0).apply(1.3), 1.3);
+    assert!(WritePrecision::Decimals(2).apply(f64::NAN).is_nan());
+    // factors or scaled values out of range keep the value
+    assert_eq!(WritePrecision::Decimals(400).apply(1.23456), 1.23456);
+    assert_eq!(WritePrecision::Decimals(-400).apply(1234.5), 1234.5);
+    assert_eq!(WritePrecision::Decimals(20).apply(1e300), 1e300);
+    assert_eq!(WritePrecision::Decimals(20).apply(-1e300), -1e300);
+
+    let line = LineString::from(vec![(1.26, -0.74), (2.0, f64::NAN)]);
+    let options = WriteOptions { precision: WritePrecision::Grid(0.5), ..Default::default() };
+    let mut buf = Vec::new();
+    line.as_ewkb().write_ewkb_with(&mut buf, &options).unwrap();
+    let read = LineString::read_ewkb(&mut buf.as_slice()).unwrap();
+    assert_eq!((read.points[0].x, read.points[0].y), (1.5, -0.5));
+    assert!(read.points[1].y.is_nan());
+    // default is lossless
+    let mut buf = Vec::new();
+    line.as_ewkb().write_ewkb(&mut buf).unwrap();
+    assert_eq!(LineString::read_ewkb(&mut buf.as_slice()).unwrap().points[0], line.points[0]);
+}
+
+#[test]
+fn test_ewkb_slice_member() {
+    let square = |x: f64| vec![(x, 0.0), (x + 1.0, 0.0), (x + 1.0, 1.0), (x, 0.0)];
+    let mut multi = MultiPolygon::from(vec![
+        Polygon::from(vec![square(0.0), square(0.5)]),
+        Polygon::new(),
+        Polygon::from(vec![square(10.0)]),
+    ]);
+    multi.srid = Some(4326);
+    let mut buf = Vec::new();
+    multi.as_ewkb().write_ewkb(&mut buf).unwrap();
+    let view = EwkbSlice::new(&buf).unwrap();
+
+    let last = view.member(2).unwrap().unwrap();
+    assert_eq!(last.geometry_type(), 3);
+    let ring = last.exterior_ring::<Point>().unwrap().unwrap();
+    assert_eq!(ring, LineString::from(square(10.0)));
+    assert_eq!(view.member(1).unwrap().unwrap().exterior_ring::<Point>().unwrap(), None);
+    assert!(view.member(3).unwrap().is_none());
+    assert!(last.exterior_ring::<PointZ>().is_err());
+    assert!(last.member(0).is_err());
+    assert!(view.exterior_ring::<Point>().is_err());
+    // truncated member
+    assert!(EwkbSlice::new(&buf[..buf.len() - 8]).unwrap().member(2).is_err());
+
+    let nested = nested_collection(1000);
+    assert!(EwkbSlice::new(&nested).unwrap().member(0).is_err());
+    let nested = nested_collection(MAX_NESTING_DEPTH - 1);
+    assert!(validate(&nested).is_ok());
+    assert!(EwkbSlice::new(&nested).unwrap().member(0).unwrap().is_some());
+}
+
+#[test]
+fn test_kind_of() {
+    let mut buf = Vec::new();
+    let collection = GeometryCollectionZ::new();
+    collection.as_ewkb().write_ewkb(&mut buf).unwrap();
+    let kind = kind_of(&buf).unwrap();
+    assert_eq!(kind.geometry_type, GeometryType::GeometryCollection);
+    assert_eq!(kind.point_type(), PointType::PointZ);
+    assert_eq!(kind.geometry_type.code(), 7);
+    assert!(kind_of(&[]).is_err());
+    assert!(kind_of(&[1, 8, 0, 0, 0]).is_err());
+    assert_eq!(GeometryType::from_code(8), None);
+}
+
+#[test]
+fn test_type_id_for() {
+    let point = PointZM::new(1.0, 2.0, 3.0, 4.0, Some(4326));
+    let type_id = type_id_for(GeometryType::Point, PointType::PointZM, true);
+    assert_eq!(type_id, point.as_ewkb().type_id());
+    assert_eq!(type_id, 0xE0000001);
+    assert_eq!(type_id_for(GeometryType::GeometryCollection, PointType::Point, false), WKB_GEOMETRYCOLLECTION);
+}
+
+#[test]
+fn test_read_ewkb_body_with_header() {
+    // big endian 'SRID=4326;LINESTRING(10 -20,0 -0.5)'
+    let ewkb = hex_to_vec("0020000002000010E6000000024024000000000000C0340000000000000000000000000000BFE0000000000000");
+    let header = read_header(&ewkb).unwrap();
+    assert_eq!(header.size(), 9);
+    let mut raw = &ewkb[header.size()..];
+    let geom = Geometry::read_ewkb_body_with_header(&header, &mut raw).unwrap();
+    assert!(raw.is_empty());
+    let mut line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+    line.set_srid(Some(4326));
+    assert_eq!(geom, GeometryT::LineString(line));
+}
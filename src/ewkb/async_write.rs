@@ -0,0 +1,96 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Writing EWKB to tokio `AsyncWrite` sinks.
+
+use super::EwkbWrite;
+use crate::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::AsyncWrite;
+
+/// Asynchronous EWKB output, available for all [`EwkbWrite`] adapters.
+///
+/// The geometry is encoded into an exactly sized buffer, which is then
+/// written to the sink without blocking.
+///
+/// ```rust,ignore
+/// use postgis::ewkb::{AsEwkbPoint, AsyncEwkbWrite, Point};
+///
+/// let point = Point::new(10.0, -20.0, Some(4326));
+/// point.as_ewkb().write_ewkb_async(&mut socket).await?;
+/// ```
+pub trait AsyncEwkbWrite: EwkbWrite {
+    fn write_ewkb_async<'a, W>(&self, w: &'a mut W) -> WriteEwkbFuture<'a, W>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let mut buf = Vec::with_capacity(self.ewkb_size());
+        let error = self.write_ewkb(&mut buf).err();
+        WriteEwkbFuture {
+            writer: w,
+            buf,
+            pos: 0,
+            error,
+        }
+    }
+}
+
+impl<T: EwkbWrite> AsyncEwkbWrite for T {}
+
+/// Future returned by [`AsyncEwkbWrite::write_ewkb_async`].
+pub struct WriteEwkbFuture<'a, W: ?Sized> {
+    writer: &'a mut W,
+    buf: Vec<u8>,
+    pos: usize,
+    error: Option<Error>,
+}
+
+impl<'a, W> Future for WriteEwkbFuture<'a, W>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    type Output = Result<(), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(err) = this.error.take() {
+            return Poll::Ready(Err(err));
+        }
+        while this.pos < this.buf.len() {
+            match Pin::new(&mut *this.writer).poll_write(cx, &this.buf[this.pos..]) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(Error::Write("failed to write whole EWKB".into())))
+                }
+                Poll::Ready(Ok(n)) => this.pos += n,
+                Poll::Ready(Err(e)) => {
+                    return Poll::Ready(Err(Error::Write(format!("error while writing: {:?}", e))))
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{AsEwkbLineString, AsEwkbPoint, LineString, Point};
+
+    #[tokio::test]
+    async fn test_write_ewkb_async() {
+        let mut out: Vec<u8> = Vec::new();
+        let point = Point::new(10.0, -20.0, None);
+        point.as_ewkb().write_ewkb_async(&mut out).await.unwrap();
+        let line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+        line.as_ewkb().write_ewkb_async(&mut out).await.unwrap();
+
+        let mut expected = Vec::new();
+        point.as_ewkb().write_ewkb(&mut expected).unwrap();
+        line.as_ewkb().write_ewkb(&mut expected).unwrap();
+        assert_eq!(out, expected);
+    }
+}
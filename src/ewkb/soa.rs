@@ -0,0 +1,281 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Struct-of-arrays geometry representation.
+//!
+//! Coordinates are stored in contiguous `Vec<f64>` arrays per dimension,
+//! the geometry structure in Arrow style offset arrays. This layout suits
+//! vectorized processing and can be handed over to columnar formats without
+//! copying coordinates point by point.
+//!
+//! ```rust
+//! use postgis::ewkb::{soa::SoaGeometry, AsEwkbMultiLineString, EwkbWrite, MultiLineString};
+//! use postgis::Point;
+//!
+//! let multi = MultiLineString::from(vec![vec![(0., 0.), (1., 1.)], vec![(5., 5.), (6., 6.), (7., 7.)]]);
+//! let mut buf = Vec::new();
+//! multi.as_ewkb().write_ewkb(&mut buf).unwrap();
+//!
+//! let soa = SoaGeometry::from_ewkb(&buf).unwrap();
+//! assert_eq!(soa.coords.x, vec![0., 1., 5., 6., 7.]);
+//! assert_eq!(soa.ring_offsets, vec![0, 2, 5]);
+//! assert_eq!(soa.coords.point(3).x(), 6.0);
+//! ```
+
+use super::{geometry_type_code, has_m, has_z, read_f64, read_i32, read_u32, EWKB_SRID_FLAG};
+use crate::io::{Read, ReadBytesExt};
+use crate::{error::Error, types as postgis};
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec::Vec};
+
+/// Coordinate values stored per dimension.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct CoordArrays {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub z: Option<Vec<f64>>,
+    pub m: Option<Vec<f64>>,
+}
+
+impl CoordArrays {
+    pub fn new(has_z: bool, has_m: bool) -> Self {
+        CoordArrays {
+            x: Vec::new(),
+            y: Vec::new(),
+            z: if has_z { Some(Vec::new()) } else { None },
+            m: if has_m { Some(Vec::new()) } else { None },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    /// View of the point at `index`. Panics if out of range.
+    pub fn point(&self, index: usize) -> PointView<'_> {
+        assert!(index < self.len(), "point index out of range");
+        PointView {
+            coords: self,
+            index,
+        }
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = PointView<'_>> {
+        (0..self.len()).map(move |index| PointView {
+            coords: self,
+            index,
+        })
+    }
+
+    fn read_point<R: Read>(&mut self, raw: &mut R, is_be: bool, type_id: u32) -> Result<(), Error> {
+        self.x.push(read_f64(raw, is_be)?);
+        self.y.push(read_f64(raw, is_be)?);
+        // missing dimensions of mixed input are stored as NaN
+        let z = if has_z(type_id) {
+            read_f64(raw, is_be)?
+        } else {
+            f64::NAN
+        };
+        let m = if has_m(type_id) {
+            read_f64(raw, is_be)?
+        } else {
+            f64::NAN
+        };
+        if let Some(ref mut zs) = self.z {
+            zs.push(z);
+        }
+        if let Some(ref mut ms) = self.m {
+            ms.push(m);
+        }
+        Ok(())
+    }
+}
+
+/// Borrowed point of a [`CoordArrays`].
+#[derive(Clone, Copy, Debug)]
+pub struct PointView<'a> {
+    coords: &'a CoordArrays,
+    index: usize,
+}
+
+impl<'a> PointView<'a> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'a> postgis::Point for PointView<'a> {
+    fn x(&self) -> f64 {
+        self.coords.x[self.index]
+    }
+    fn y(&self) -> f64 {
+        self.coords.y[self.index]
+    }
+    fn opt_z(&self) -> Option<f64> {
+        self.coords.z.as_ref().map(|z| z[self.index])
+    }
+    fn opt_m(&self) -> Option<f64> {
+        self.coords.m.as_ref().map(|m| m[self.index])
+    }
+}
+
+/// Point, LineString or Polygon geometry and their multi variants in struct-of-arrays layout.
+///
+/// Offset arrays hold the start index of each element followed by the total
+/// count, so element `i` spans `offsets[i]..offsets[i + 1]`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct SoaGeometry {
+    /// EWKB geometry type code (1 = Point, ..., 6 = MultiPolygon).
+    pub geometry_type: u32,
+    pub srid: Option<i32>,
+    pub coords: CoordArrays,
+    /// Coordinate offsets of lines and rings. Empty for points.
+    pub ring_offsets: Vec<usize>,
+    /// Ring offsets of polygons. Empty for points and lines.
+    pub polygon_offsets: Vec<usize>,
+}
+
+impl SoaGeometry {
+    pub fn from_ewkb(mut data: &[u8]) -> Result<Self, Error> {
+        Self::read_ewkb(&mut data)
+    }
+
+    /// Decode EWKB. GeometryCollections are not supported.
+    pub fn read_ewkb<R: Read>(raw: &mut R) -> Result<Self, Error> {
+        let is_be = raw.read_i8()? == 0i8;
+        let type_id = read_u32(raw, is_be)?;
+        let srid = if type_id & EWKB_SRID_FLAG == EWKB_SRID_FLAG {
+            Some(read_i32(raw, is_be)?)
+        } else {
+            None
+        };
+        let geometry_type = geometry_type_code(type_id);
+        let mut geom = SoaGeometry {
+            geometry_type,
+            srid,
+            coords: CoordArrays::new(has_z(type_id), has_m(type_id)),
+            ring_offsets: Vec::new(),
+            polygon_offsets: Vec::new(),
+        };
+        if geometry_type == 0x02 || geometry_type == 0x05 {
+            geom.ring_offsets.push(0);
+        }
+        if geometry_type == 0x03 || geometry_type == 0x06 {
+            geom.ring_offsets.push(0);
+            geom.polygon_offsets.push(0);
+        }
+        match geometry_type {
+            0x01..=0x03 => geom.read_body(raw, is_be, type_id)?,
+            0x04..=0x06 => {
+                for _ in 0..read_u32(raw, is_be)? {
+                    let is_be = raw.read_i8()? == 0i8;
+                    let type_id = read_u32(raw, is_be)?;
+                    if type_id & EWKB_SRID_FLAG == EWKB_SRID_FLAG {
+                        read_i32(raw, is_be)?;
+                    }
+                    if geometry_type_code(type_id) != geometry_type - 3 {
+                        return Err(Error::Read(format!(
+                            "unexpected member type {} in geometry type {}",
+                            geometry_type_code(type_id),
+                            geometry_type
+                        )));
+                    }
+                    geom.read_body(raw, is_be, type_id)?;
+                }
+            }
+            code => {
+                return Err(Error::Read(format!(
+                    "geometry type {} not supported in struct-of-arrays layout",
+                    code
+                )))
+            }
+        }
+        Ok(geom)
+    }
+
+    // Read the body of a Point, LineString or Polygon
+    fn read_body<R: Read>(&mut self, raw: &mut R, is_be: bool, type_id: u32) -> Result<(), Error> {
+        match geometry_type_code(type_id) {
+            0x01 => self.coords.read_point(raw, is_be, type_id)?,
+            0x02 => self.read_ring(raw, is_be, type_id)?,
+            _ => {
+                for _ in 0..read_u32(raw, is_be)? {
+                    self.read_ring(raw, is_be, type_id)?;
+                }
+                self.polygon_offsets.push(self.ring_offsets.len() - 1);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_ring<R: Read>(&mut self, raw: &mut R, is_be: bool, type_id: u32) -> Result<(), Error> {
+        for _ in 0..read_u32(raw, is_be)? {
+            self.coords.read_point(raw, is_be, type_id)?;
+        }
+        self.ring_offsets.push(self.coords.len());
+        Ok(())
+    }
+
+    /// Number of lines or rings.
+    pub fn num_rings(&self) -> usize {
+        self.ring_offsets.len().saturating_sub(1)
+    }
+
+    /// Points of the `i`-th line or ring.
+    pub fn ring_points(&self, i: usize) -> impl Iterator<Item = PointView<'_>> {
+        let coords = &self.coords;
+        (self.ring_offsets[i]..self.ring_offsets[i + 1]).map(move |index| coords.point(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{AsEwkbMultiPolygon, AsEwkbPoint, EwkbWrite, MultiPolygonZ, PointM};
+    use crate::types::Point;
+
+    fn ewkb<T: EwkbWrite>(geom: T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        geom.write_ewkb(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_soa_multi_polygon() {
+        let square = vec![
+            (0., 0., 1.),
+            (4., 0., 1.),
+            (4., 4., 1.),
+            (0., 4., 1.),
+            (0., 0., 1.),
+        ];
+        let hole = vec![(1., 1., 2.), (2., 1., 2.), (1., 2., 2.), (1., 1., 2.)];
+        let mut multi = MultiPolygonZ::from(vec![vec![square.clone(), hole], vec![square]]);
+        multi.srid = Some(3857);
+        let soa = SoaGeometry::from_ewkb(&ewkb(multi.as_ewkb())).unwrap();
+        assert_eq!(soa.geometry_type, 6);
+        assert_eq!(soa.srid, Some(3857));
+        assert_eq!(soa.coords.len(), 14);
+        assert_eq!(soa.coords.m, None);
+        assert_eq!(soa.ring_offsets, vec![0, 5, 9, 14]);
+        assert_eq!(soa.polygon_offsets, vec![0, 2, 3]);
+        let zs: Vec<f64> = soa.ring_points(1).map(|p| p.opt_z().unwrap()).collect();
+        assert_eq!(zs, vec![2., 2., 2., 2.]);
+    }
+
+    #[test]
+    fn test_soa_point() {
+        let point = PointM::new(1.0, 2.0, 3.0, None);
+        let soa = SoaGeometry::from_ewkb(&ewkb(point.as_ewkb())).unwrap();
+        assert_eq!(soa.coords.m, Some(vec![3.0]));
+        assert!(soa.ring_offsets.is_empty());
+        assert_eq!(soa.coords.point(0).opt_z(), None);
+        // GeometryCollection
+        assert!(SoaGeometry::from_ewkb(&[1, 7, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Write geometries as EWKT, the text format of `ST_AsEWKT`.
+
+use crate::{
+    ewkb::{self, EwkbRead, PointType, Srid},
+    types as postgis,
+};
+use core::fmt;
+
+/// Format geometries as EWKT, like `ST_AsEWKT`.
+///
+/// The output has an `SRID=...;` prefix if the geometry has a SRID and
+/// can be read back with `ST_GeomFromEWKT`. As in PostGIS, geometries with
+/// Z values have no dimension tag and geometries with only M values are
+/// tagged with an `M` suffix.
+///
+/// ```rust
+/// use postgis::{ewkb, ewkt::AsEwkt};
+///
+/// let line = ewkb::LineString {
+///     points: vec![ewkb::Point::new(10.0, -20.0, None), ewkb::Point::new(0.0, -0.5, None)].into(),
+///     srid: Some(4326),
+/// };
+/// assert_eq!(line.as_ewkt().to_string(), "SRID=4326;LINESTRING(10 -20,0 -0.5)");
+///
+/// let point = ewkb::PointM::new(1.0, 2.0, 3.0, None);
+/// assert_eq!(point.as_ewkt().to_string(), "POINTM(1 2 3)");
+/// ```
+pub trait AsEwkt: Srid {
+    fn as_ewkt(&self) -> Ewkt<'_, Self> {
+        Ewkt(self)
+    }
+
+    /// Write the WKT text without SRID prefix.
+    #[doc(hidden)]
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+/// EWKT `Display` wrapper returned by [`AsEwkt::as_ewkt`].
+pub struct Ewkt<'a, G: ?Sized>(&'a G);
+
+impl<G: AsEwkt + ?Sized> fmt::Display for Ewkt<'_, G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(srid) = self.0.srid() {
+            write!(f, "SRID={};", srid)?;
+        }
+        self.0.write_wkt(f)
+    }
+}
+
+fn write_tag<P: EwkbRead>(f: &mut fmt::Formatter, tag: &str, empty: bool) -> fmt::Result {
+    f.write_str(tag)?;
+    if P::point_type() == PointType::PointM {
+        f.write_str("M")?;
+    }
+    if empty {
+        f.write_str(" ")?;
+    }
+    Ok(())
+}
+
+fn write_coords<P: postgis::Point>(f: &mut fmt::Formatter, p: &P) -> fmt::Result {
+    write!(f, "{} {}", p.x(), p.y())?;
+    if let Some(z) = p.opt_z() {
+        write!(f, " {}", z)?;
+    }
+    if let Some(m) = p.opt_m() {
+        write!(f, " {}", m)?;
+    }
+    Ok(())
+}
+
+// Comma separated list in parentheses, or `EMPTY`
+fn write_list<T>(
+    f: &mut fmt::Formatter,
+    items: &[T],
+    mut write_item: impl FnMut(&mut fmt::Formatter, &T) -> fmt::Result,
+) -> fmt::Result {
+    if items.is_empty() {
+        return f.write_str("EMPTY");
+    }
+    f.write_str("(")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write_item(f, item)?;
+    }
+    f.write_str(")")
+}
+
+fn write_points<P: postgis::Point>(f: &mut fmt::Formatter, points: &[P]) -> fmt::Result {
+    write_list(f, points, |f, p| write_coords(f, p))
+}
+
+fn write_rings<P: postgis::Point + EwkbRead>(
+    f: &mut fmt::Formatter,
+    polygon: &ewkb::PolygonT<P>,
+) -> fmt::Result {
+    write_list(f, &polygon.rings, |f, ring| write_points(f, &ring.points))
+}
+
+macro_rules! impl_ewkt_for_point {
+    ($ptype:ident) => {
+        impl AsEwkt for ewkb::$ptype {
+            fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                // NaN coordinates are the EWKB encoding of an empty point
+                let empty = self.x.is_nan() && self.y.is_nan();
+                write_tag::<Self>(f, "POINT", empty)?;
+                if empty {
+                    f.write_str("EMPTY")
+                } else {
+                    f.write_str("(")?;
+                    write_coords(f, self)?;
+                    f.write_str(")")
+                }
+            }
+        }
+    };
+}
+
+impl_ewkt_for_point!(Point);
+impl_ewkt_for_point!(PointZ);
+impl_ewkt_for_point!(PointM);
+impl_ewkt_for_point!(PointZM);
+
+impl<P> AsEwkt for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tag::<P>(f, "LINESTRING", self.points.is_empty())?;
+        write_points(f, &self.points)
+    }
+}
+
+impl<P> AsEwkt for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tag::<P>(f, "POLYGON", self.rings.is_empty())?;
+        write_rings(f, self)
+    }
+}
+
+impl<P> AsEwkt for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tag::<P>(f, "MULTIPOINT", self.points.is_empty())?;
+        write_points(f, &self.points)
+    }
+}
+
+impl<P> AsEwkt for ewkb::MultiLineStringT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tag::<P>(f, "MULTILINESTRING", self.lines.is_empty())?;
+        write_list(f, &self.lines, |f, line| write_points(f, &line.points))
+    }
+}
+
+impl<P> AsEwkt for ewkb::MultiPolygonT<P>
+where
+    P: postgis::Point + EwkbRead + Srid,
+{
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tag::<P>(f, "MULTIPOLYGON", self.polygons.is_empty())?;
+        write_list(f, &self.polygons, write_rings)
+    }
+}
+
+impl<P> AsEwkt for ewkb::GeometryCollectionT<P>
+where
+    P: postgis::Point + EwkbRead + Srid + AsEwkt,
+{
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tag::<P>(f, "GEOMETRYCOLLECTION", self.geometries.is_empty())?;
+        write_list(f, &self.geometries, |f, geom| geom.write_wkt(f))
+    }
+}
+
+impl<P> AsEwkt for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + Srid + AsEwkt,
+{
+    fn write_wkt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => geom.write_wkt(f),
+            G::LineString(ref geom) => geom.write_wkt(f),
+            G::Polygon(ref geom) => geom.write_wkt(f),
+            G::MultiPoint(ref geom) => geom.write_wkt(f),
+            G::MultiLineString(ref geom) => geom.write_wkt(f),
+            G::MultiPolygon(ref geom) => geom.write_wkt(f),
+            G::GeometryCollection(ref geom) => geom.write_wkt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bulk::parse_ewkt, ewkb::*};
+
+    fn roundtrip<P>(ewkt: &str)
+    where
+        P: postgis::Point + EwkbRead + NewFromOptVals + Srid + AsEwkt,
+    {
+        let geom = parse_ewkt::<P>(ewkt).unwrap();
+        assert_eq!(geom.as_ewkt().to_string(), ewkt);
+    }
+
+    #[test]
+    fn test_ewkt_roundtrip() {
+        roundtrip::<Point>("SRID=4326;POINT(10 -20)");
+        roundtrip::<Point>("POINT EMPTY");
+        roundtrip::<PointZ>("SRID=3857;LINESTRING(0 0 1,1.5 2 3)");
+        roundtrip::<PointM>("POLYGONM((0 0 1,1 0 2,1 1 3,0 0 1))");
+        roundtrip::<PointZM>("MULTIPOINT(1 2 3 4,5 6 7 8)");
+        roundtrip::<Point>("MULTILINESTRING((0 0,1 1),EMPTY)");
+        roundtrip::<Point>("SRID=4326;MULTIPOLYGON(((0 0,1 0,1 1,0 0)),((5 5,6 5,6 6,5 5),(5.1 5.1,5.2 5.1,5.2 5.2,5.1 5.1)))");
+        roundtrip::<Point>(
+            "SRID=4326;GEOMETRYCOLLECTION(POINT(1 2),LINESTRING EMPTY,GEOMETRYCOLLECTION EMPTY)",
+        );
+    }
+}
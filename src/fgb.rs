@@ -0,0 +1,507 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Conversion between [`ewkb`](crate::ewkb) geometries and the
+//! [FlatGeobuf](https://flatgeobuf.org/) geometry representation.
+//!
+//! FlatGeobuf stores coordinates column-major in flat `xy`, `z` and `m` arrays.
+//! Multi-part LineStrings and Polygons are split by `ends` (exclusive end
+//! index of every part in points), MultiPolygons and GeometryCollections
+//! are stored as nested `parts`.
+//!
+//! ```rust
+//! use postgis::{ewkb, fgb::{AsFgb, FgbGeometry, FromFgb, GeometryType}};
+//!
+//! let line = ewkb::LineString {
+//!     points: vec![ewkb::Point::new(0.0, 0.0, None), ewkb::Point::new(1.0, 2.0, None)],
+//!     srid: None,
+//! };
+//! let geom = line.to_fgb();
+//! assert_eq!(geom.geometry_type, GeometryType::LineString);
+//! assert_eq!(geom.xy, vec![0.0, 0.0, 1.0, 2.0]);
+//! assert_eq!(ewkb::LineString::from_fgb(&geom).unwrap(), line);
+//! ```
+
+use crate::{
+    error::Error,
+    ewkb::{self, EwkbRead, NewFromOptVals, PointType},
+    types as postgis,
+};
+
+/// FlatGeobuf geometry types.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GeometryType {
+    Unknown = 0,
+    Point = 1,
+    LineString = 2,
+    Polygon = 3,
+    MultiPoint = 4,
+    MultiLineString = 5,
+    MultiPolygon = 6,
+    GeometryCollection = 7,
+}
+
+/// FlatGeobuf geometry with column-major coordinates.
+#[derive(PartialEq, Clone, Debug)]
+pub struct FgbGeometry {
+    pub geometry_type: GeometryType,
+    pub ends: Vec<u32>,
+    pub xy: Vec<f64>,
+    pub z: Vec<f64>,
+    pub m: Vec<f64>,
+    pub parts: Vec<FgbGeometry>,
+}
+
+impl FgbGeometry {
+    pub fn new(geometry_type: GeometryType) -> FgbGeometry {
+        FgbGeometry {
+            geometry_type,
+            ends: Vec::new(),
+            xy: Vec::new(),
+            z: Vec::new(),
+            m: Vec::new(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Number of points in the coordinate arrays.
+    pub fn num_points(&self) -> usize {
+        self.xy.len() / 2
+    }
+
+    fn push_point<P: postgis::Point + EwkbRead>(&mut self, p: &P) {
+        self.xy.push(p.x());
+        self.xy.push(p.y());
+        let point_type = P::point_type();
+        if point_type == PointType::PointZ || point_type == PointType::PointZM {
+            self.z.push(p.opt_z().unwrap_or(0.0));
+        }
+        if point_type == PointType::PointM || point_type == PointType::PointZM {
+            self.m.push(p.opt_m().unwrap_or(0.0));
+        }
+    }
+
+    fn push_points<'a, P, I>(&mut self, points: I)
+    where
+        P: 'a + postgis::Point + EwkbRead,
+        I: Iterator<Item = &'a P>,
+    {
+        for p in points {
+            self.push_point(p);
+        }
+    }
+
+    /// Close the current part, `ends` is only written for more than one part.
+    fn end_part(&mut self, count: &mut usize) {
+        self.ends.push(self.num_points() as u32);
+        *count += 1;
+    }
+
+    fn finish_parts(&mut self, count: usize) {
+        if count <= 1 {
+            self.ends.clear();
+        }
+    }
+
+    fn point<P: NewFromOptVals + EwkbRead>(&self, i: usize) -> Result<P, Error> {
+        let point_type = P::point_type();
+        let needs_z = point_type == PointType::PointZ || point_type == PointType::PointZM;
+        let needs_m = point_type == PointType::PointM || point_type == PointType::PointZM;
+        let z = self.z.get(i).cloned();
+        let m = self.m.get(i).cloned();
+        if needs_z && z.is_none() {
+            return Err(Error::Read("FlatGeobuf geometry has no Z values".into()));
+        }
+        if needs_m && m.is_none() {
+            return Err(Error::Read("FlatGeobuf geometry has no M values".into()));
+        }
+        Ok(P::new_from_opt_vals(
+            self.xy[2 * i],
+            self.xy[2 * i + 1],
+            z,
+            m,
+            None,
+        ))
+    }
+
+    fn points<P: NewFromOptVals + EwkbRead>(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Result<Vec<P>, Error> {
+        (start..end).map(|i| self.point(i)).collect()
+    }
+
+    /// Point index ranges of all parts.
+    fn part_ranges(&self) -> Result<Vec<(usize, usize)>, Error> {
+        let npoints = self.num_points();
+        if self.ends.is_empty() {
+            return Ok(vec![(0, npoints)]);
+        }
+        let mut start = 0;
+        let mut ranges = Vec::with_capacity(self.ends.len());
+        for &end in self.ends.iter() {
+            let end = end as usize;
+            if end < start || end > npoints {
+                return Err(Error::Read("invalid FlatGeobuf ends".into()));
+            }
+            ranges.push((start, end));
+            start = end;
+        }
+        Ok(ranges)
+    }
+
+    fn check_type(&self, expected: GeometryType) -> Result<(), Error> {
+        if self.geometry_type != expected {
+            return Err(Error::Read(format!(
+                "expected {:?}, found {:?}",
+                expected, self.geometry_type
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Convert a geometry to its FlatGeobuf representation.
+pub trait AsFgb {
+    fn to_fgb(&self) -> FgbGeometry;
+}
+
+/// Create a geometry from its FlatGeobuf representation.
+pub trait FromFgb: Sized {
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error>;
+}
+
+macro_rules! impl_fgb_for_point {
+    ($ptype:ident) => {
+        impl AsFgb for ewkb::$ptype {
+            fn to_fgb(&self) -> FgbGeometry {
+                let mut geom = FgbGeometry::new(GeometryType::Point);
+                geom.push_point(self);
+                geom
+            }
+        }
+
+        impl FromFgb for ewkb::$ptype {
+            fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+                geom.check_type(GeometryType::Point)?;
+                if geom.num_points() == 0 {
+                    return Err(Error::Read("empty FlatGeobuf point".into()));
+                }
+                geom.point(0)
+            }
+        }
+    };
+}
+
+impl_fgb_for_point!(Point);
+impl_fgb_for_point!(PointZ);
+impl_fgb_for_point!(PointM);
+impl_fgb_for_point!(PointZM);
+
+impl<P> AsFgb for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn to_fgb(&self) -> FgbGeometry {
+        let mut geom = FgbGeometry::new(GeometryType::LineString);
+        geom.push_points(self.points.iter());
+        geom
+    }
+}
+
+impl<P> FromFgb for ewkb::LineStringT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+        geom.check_type(GeometryType::LineString)?;
+        Ok(ewkb::LineStringT {
+            points: geom.points(0, geom.num_points())?,
+            srid: None,
+        })
+    }
+}
+
+impl<P> AsFgb for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn to_fgb(&self) -> FgbGeometry {
+        let mut geom = FgbGeometry::new(GeometryType::Polygon);
+        let mut count = 0;
+        for ring in self.rings.iter() {
+            geom.push_points(ring.points.iter());
+            geom.end_part(&mut count);
+        }
+        geom.finish_parts(count);
+        geom
+    }
+}
+
+impl<P> FromFgb for ewkb::PolygonT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+        geom.check_type(GeometryType::Polygon)?;
+        let mut rings = Vec::new();
+        if geom.num_points() > 0 {
+            for (start, end) in geom.part_ranges()? {
+                rings.push(ewkb::LineStringT {
+                    points: geom.points(start, end)?,
+                    srid: None,
+                });
+            }
+        }
+        Ok(ewkb::PolygonT { rings, srid: None })
+    }
+}
+
+impl<P> AsFgb for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn to_fgb(&self) -> FgbGeometry {
+        let mut geom = FgbGeometry::new(GeometryType::MultiPoint);
+        geom.push_points(self.points.iter());
+        geom
+    }
+}
+
+impl<P> FromFgb for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+        geom.check_type(GeometryType::MultiPoint)?;
+        Ok(ewkb::MultiPointT {
+            points: geom.points(0, geom.num_points())?,
+            srid: None,
+        })
+    }
+}
+
+impl<P> AsFgb for ewkb::MultiLineStringT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn to_fgb(&self) -> FgbGeometry {
+        let mut geom = FgbGeometry::new(GeometryType::MultiLineString);
+        let mut count = 0;
+        for line in self.lines.iter() {
+            geom.push_points(line.points.iter());
+            geom.end_part(&mut count);
+        }
+        geom.finish_parts(count);
+        geom
+    }
+}
+
+impl<P> FromFgb for ewkb::MultiLineStringT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+        geom.check_type(GeometryType::MultiLineString)?;
+        let mut lines = Vec::new();
+        if geom.num_points() > 0 {
+            for (start, end) in geom.part_ranges()? {
+                lines.push(ewkb::LineStringT {
+                    points: geom.points(start, end)?,
+                    srid: None,
+                });
+            }
+        }
+        Ok(ewkb::MultiLineStringT { lines, srid: None })
+    }
+}
+
+impl<P> AsFgb for ewkb::MultiPolygonT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn to_fgb(&self) -> FgbGeometry {
+        let mut geom = FgbGeometry::new(GeometryType::MultiPolygon);
+        geom.parts = self.polygons.iter().map(|poly| poly.to_fgb()).collect();
+        geom
+    }
+}
+
+impl<P> FromFgb for ewkb::MultiPolygonT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+        geom.check_type(GeometryType::MultiPolygon)?;
+        Ok(ewkb::MultiPolygonT {
+            polygons: geom
+                .parts
+                .iter()
+                .map(ewkb::PolygonT::from_fgb)
+                .collect::<Result<_, _>>()?,
+            srid: None,
+        })
+    }
+}
+
+impl<P> AsFgb for ewkb::GeometryCollectionT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn to_fgb(&self) -> FgbGeometry {
+        let mut geom = FgbGeometry::new(GeometryType::GeometryCollection);
+        geom.parts = self.geometries.iter().map(|g| g.to_fgb()).collect();
+        geom
+    }
+}
+
+impl<P> FromFgb for ewkb::GeometryCollectionT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+        geom.check_type(GeometryType::GeometryCollection)?;
+        Ok(ewkb::GeometryCollectionT {
+            geometries: geom
+                .parts
+                .iter()
+                .map(ewkb::GeometryT::from_fgb)
+                .collect::<Result<_, _>>()?,
+            srid: None,
+        })
+    }
+}
+
+impl<P> AsFgb for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn to_fgb(&self) -> FgbGeometry {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => {
+                let mut fgb = FgbGeometry::new(GeometryType::Point);
+                fgb.push_point(geom);
+                fgb
+            }
+            G::LineString(ref geom) => geom.to_fgb(),
+            G::Polygon(ref geom) => geom.to_fgb(),
+            G::MultiPoint(ref geom) => geom.to_fgb(),
+            G::MultiLineString(ref geom) => geom.to_fgb(),
+            G::MultiPolygon(ref geom) => geom.to_fgb(),
+            G::GeometryCollection(ref geom) => geom.to_fgb(),
+        }
+    }
+}
+
+impl<P> FromFgb for ewkb::GeometryT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn from_fgb(geom: &FgbGeometry) -> Result<Self, Error> {
+        use crate::ewkb::GeometryT as G;
+        Ok(match geom.geometry_type {
+            GeometryType::Point => {
+                if geom.num_points() == 0 {
+                    return Err(Error::Read("empty FlatGeobuf point".into()));
+                }
+                G::Point(geom.point(0)?)
+            }
+            GeometryType::LineString => G::LineString(FromFgb::from_fgb(geom)?),
+            GeometryType::Polygon => G::Polygon(FromFgb::from_fgb(geom)?),
+            GeometryType::MultiPoint => G::MultiPoint(FromFgb::from_fgb(geom)?),
+            GeometryType::MultiLineString => G::MultiLineString(FromFgb::from_fgb(geom)?),
+            GeometryType::MultiPolygon => G::MultiPolygon(FromFgb::from_fgb(geom)?),
+            GeometryType::GeometryCollection => G::GeometryCollection(FromFgb::from_fgb(geom)?),
+            GeometryType::Unknown => {
+                return Err(Error::Read("unknown FlatGeobuf geometry type".into()))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::GeometryType;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_polygon() {
+        let p = |x, y, z| PointZ::new(x, y, z, None);
+        let ring = |pts: Vec<PointZ>| LineStringT::<PointZ> {
+            points: pts,
+            srid: None,
+        };
+        let shell = ring(vec![
+            p(0., 0., 1.),
+            p(4., 0., 1.),
+            p(4., 4., 1.),
+            p(0., 0., 1.),
+        ]);
+        let hole = ring(vec![
+            p(1., 1., 2.),
+            p(2., 1., 2.),
+            p(2., 2., 2.),
+            p(1., 1., 2.),
+        ]);
+        let poly = PolygonT::<PointZ> {
+            rings: vec![shell.clone()],
+            srid: None,
+        };
+        let fgb = poly.to_fgb();
+        assert!(fgb.ends.is_empty());
+        assert_eq!(fgb.z, vec![1.0; 4]);
+        assert!(fgb.m.is_empty());
+
+        let poly = PolygonT::<PointZ> {
+            rings: vec![shell, hole],
+            srid: None,
+        };
+        let fgb = poly.to_fgb();
+        assert_eq!(fgb.ends, vec![4, 8]);
+        assert_eq!(PolygonT::<PointZ>::from_fgb(&fgb).unwrap(), poly);
+        assert_eq!(
+            PolygonT::<Point>::from_fgb(&fgb).unwrap().rings[1].points[1],
+            Point::new(2., 1., None)
+        );
+        assert!(PolygonT::<PointM>::from_fgb(&fgb).is_err());
+        assert!(LineStringT::<PointZ>::from_fgb(&fgb).is_err());
+    }
+
+    #[test]
+    fn test_collection() {
+        let line = LineStringT::<Point> {
+            points: vec![Point::new(0., 0., None), Point::new(1., 1., None)],
+            srid: None,
+        };
+        let multi = MultiLineStringT::<Point> {
+            lines: vec![line.clone(), line.clone()],
+            srid: None,
+        };
+        let coll = GeometryCollectionT::<Point> {
+            geometries: vec![
+                GeometryT::Point(Point::new(5., 6., None)),
+                GeometryT::MultiLineString(multi),
+                GeometryT::MultiPolygon(MultiPolygonT {
+                    polygons: vec![PolygonT {
+                        rings: vec![],
+                        srid: None,
+                    }],
+                    srid: None,
+                }),
+            ],
+            srid: None,
+        };
+        let fgb = GeometryT::GeometryCollection(coll.clone()).to_fgb();
+        assert_eq!(fgb.geometry_type, GeometryType::GeometryCollection);
+        assert_eq!(fgb.parts[1].ends, vec![2, 4]);
+        assert_eq!(fgb.parts[2].parts[0].num_points(), 0);
+        match GeometryT::<Point>::from_fgb(&fgb).unwrap() {
+            GeometryT::GeometryCollection(read) => assert_eq!(read, coll),
+            _ => panic!("expected GeometryCollection"),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! PostGIS generated EWKB and TWKB samples for round-trip and regression tests.
+//!
+//! The samples are keyed by the EWKT they were created from, e.g. with
+//! `SELECT 'POINT(10 -20)'::geometry` or
+//! `SELECT encode(ST_AsTWKB('POINT(10 -20)'::geometry), 'hex')`.
+//!
+//! ```rust
+//! use postgis::{ewkb::{self, EwkbRead}, fixtures};
+//!
+//! let bytes = fixtures::ewkb("POINT(10 -20)").unwrap();
+//! let point = ewkb::Point::read_ewkb(&mut bytes.as_slice()).unwrap();
+//! assert_eq!(point, ewkb::Point::new(10.0, -20.0, None));
+//! ```
+
+use crate::replication::hex_to_ewkb;
+
+/// EWKB sample.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct EwkbFixture {
+    pub ewkt: &'static str,
+    /// Hex encoded EWKB as returned by PostGIS
+    pub hex: &'static str,
+}
+
+impl EwkbFixture {
+    pub fn bytes(&self) -> Vec<u8> {
+        hex_to_ewkb(self.hex).expect("invalid fixture")
+    }
+}
+
+/// TWKB sample.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TwkbFixture {
+    pub ewkt: &'static str,
+    /// XY precision of the encoding
+    pub precision: i8,
+    /// Hex encoded TWKB as returned by `ST_AsTWKB`
+    pub hex: &'static str,
+}
+
+impl TwkbFixture {
+    pub fn bytes(&self) -> Vec<u8> {
+        hex_to_ewkb(self.hex).expect("invalid fixture")
+    }
+}
+
+macro_rules! ewkb_fixtures {
+    ($($ewkt:expr => $hex:expr,)*) => {
+        &[$(EwkbFixture { ewkt: $ewkt, hex: $hex },)*]
+    };
+}
+
+macro_rules! twkb_fixtures {
+    ($($ewkt:expr, $precision:expr => $hex:expr,)*) => {
+        &[$(TwkbFixture { ewkt: $ewkt, precision: $precision, hex: $hex },)*]
+    };
+}
+
+/// All EWKB samples.
+#[rustfmt::skip]
+pub const EWKB: &[EwkbFixture] = ewkb_fixtures![
+    "POINT(10 -20)" => "0101000000000000000000244000000000000034C0",
+    "POINT(10 -20 100)" => "0101000080000000000000244000000000000034C00000000000005940",
+    "POINTM(10 -20 1)" => "0101000040000000000000244000000000000034C0000000000000F03F",
+    "POINT(10 -20 100 1)" => "01010000C0000000000000244000000000000034C00000000000005940000000000000F03F",
+    "LINESTRING (10 -20, 0 -0.5)" => "010200000002000000000000000000244000000000000034C00000000000000000000000000000E0BF",
+    "SRID=4326;LINESTRING (10 -20 100, 0 -0.5 101)" => "01020000A0E610000002000000000000000000244000000000000034C000000000000059400000000000000000000000000000E0BF0000000000405940",
+    "SRID=4326;POLYGON ((0 0, 2 0, 2 2, 0 2, 0 0))" => "0103000020E610000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000",
+    "SRID=4326;MULTIPOINT ((10 -20 100), (0 -0.5 101))" => "01040000A0E6100000020000000101000080000000000000244000000000000034C0000000000000594001010000800000000000000000000000000000E0BF0000000000405940",
+    "SRID=4326;MULTIPOINTM(1 2 3)" => "0104000060E6100000010000000101000040000000000000F03F00000000000000400000000000000840",
+    "SRID=4326;MULTILINESTRING ((10 -20, 0 -0.5), (0 0, 2 0))" => "0105000020E610000002000000010200000002000000000000000000244000000000000034C00000000000000000000000000000E0BF0102000000020000000000000000000000000000000000000000000000000000400000000000000000",
+    "SRID=4326;MULTIPOLYGON (((0 0, 2 0, 2 2, 0 2, 0 0)), ((10 10, -2 10, -2 -2, 10 -2, 10 10)))" => "0106000020E610000002000000010300000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000010300000001000000050000000000000000002440000000000000244000000000000000C0000000000000244000000000000000C000000000000000C0000000000000244000000000000000C000000000000024400000000000002440",
+    "SRID=4326;MULTIPOLYGON (((0 0,2 0,2 2,0 2,0 0)),((10 10,-2 10,-2 -2,10 -2,10 10),(2 2,5 2,5 5,2 5,2 2)))" => "0106000020E610000002000000010300000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000010300000002000000050000000000000000002440000000000000244000000000000000C0000000000000244000000000000000C000000000000000C0000000000000244000000000000000C000000000000024400000000000002440050000000000000000000040000000000000004000000000000014400000000000000040000000000000144000000000000014400000000000000040000000000000144000000000000000400000000000000040",
+    "GeometryCollection(POINT (10 10),POINT (30 30),LINESTRING (15 15, 20 20))" => "01070000000300000001010000000000000000002440000000000000244001010000000000000000003E400000000000003E400102000000020000000000000000002E400000000000002E4000000000000034400000000000003440",
+    "GEOMETRYCOLLECTION EMPTY" => "010700000000000000",
+];
+
+/// All TWKB samples.
+#[rustfmt::skip]
+pub const TWKB: &[TwkbFixture] = twkb_fixtures![
+    "POINT(10 -20)", 0 => "01001427",
+    "POINT(10 -20 99)", 0 => "0108011427c601",
+    "POINT(10.12 -20.34)", 1 => "2100ca019503",
+    "POINT(11.12 -22.34)", -1 => "11000203",
+    "POINT EMPTY", 0 => "0110",
+    "SRID=4326;POINT(10 -20)", 5 => "a10080897aff91f401",
+    "LINESTRING (10 -20, -0 -0.5)", 0 => "02000214271326",
+    "LINESTRING (10 -20, -0 -0.5)", 1 => "220002c8018f03c7018603",
+    "LINESTRING EMPTY", 0 => "0210",
+    "POLYGON ((0 0, 2 0, 2 2, 0 2, 0 0),(10 10, -2 10, -2 -2, 10 -2, 10 10))", 0 => "03000205000004000004030000030514141700001718000018",
+    "MULTIPOINT ((10 -20), (0 -0.5))", 0 => "04000214271326",
+    "MULTILINESTRING ((10 -20, 0 -0.5), (0 0, 2 0))", 0 => "05000202142713260200020400",
+    "MULTIPOLYGON (((0 0, 2 0, 2 2, 0 2, 0 0)), ((10 10, -2 10, -2 -2, 10 -2, 10 10)))", 0 => "060002010500000400000403000003010514141700001718000018",
+];
+
+/// EWKB bytes of the sample created from `ewkt`.
+pub fn ewkb(ewkt: &str) -> Option<Vec<u8>> {
+    EWKB.iter().find(|f| f.ewkt == ewkt).map(EwkbFixture::bytes)
+}
+
+/// TWKB bytes of the sample created from `ewkt` with the given precision.
+pub fn twkb(ewkt: &str, precision: i8) -> Option<Vec<u8>> {
+    TWKB.iter()
+        .find(|f| f.ewkt == ewkt && f.precision == precision)
+        .map(TwkbFixture::bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ewkb,
+        twkb::{self, TwkbGeom},
+    };
+
+    #[test]
+    fn test_fixtures_decode() {
+        for fixture in EWKB {
+            let meta = ewkb::validate(&fixture.bytes());
+            assert!(meta.is_ok(), "{}: {:?}", fixture.ewkt, meta);
+        }
+        for fixture in TWKB {
+            let geom = twkb::Geometry::read_twkb(&mut fixture.bytes().as_slice());
+            assert!(geom.is_ok(), "{}: {:?}", fixture.ewkt, geom);
+        }
+    }
+
+    #[test]
+    fn test_fixture_lookup() {
+        assert_eq!(twkb("LINESTRING (10 -20, -0 -0.5)", 1).unwrap().len(), 11);
+        assert!(twkb("LINESTRING (10 -20, -0 -0.5)", 2).is_none());
+        assert!(ewkb("POINT(1 2)").is_none());
+    }
+}
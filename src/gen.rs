@@ -0,0 +1,146 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Random geometries for load tests and benchmark data.
+//!
+//! ```rust
+//! use postgis::{envelope::Envelope, gen::Generator};
+//! use rand::{rngs::StdRng, SeedableRng};
+//!
+//! let bbox = Envelope::new(5.9, 45.8, 10.5, 47.8);
+//! let mut gen = Generator::new(StdRng::seed_from_u64(42), bbox).srid(Some(4326));
+//! let points = gen.points(100);
+//! assert!(points.points.iter().all(|p| bbox.contains_point(p)));
+//! let track = gen.random_walk(500, 0.01);
+//! assert_eq!(track.points.len(), 500);
+//! let polygon = gen.convex_polygon(8);
+//! assert_eq!(polygon.rings[0].points.len(), 9);
+//! ```
+
+use crate::{
+    envelope::Envelope,
+    ewkb::{LineString, MultiPoint, Point, Polygon},
+};
+use rand::{Rng, RngExt};
+use std::f64::consts::PI;
+
+/// Generator for random geometries within a bounding box.
+pub struct Generator<R: Rng> {
+    rng: R,
+    bbox: Envelope,
+    srid: Option<i32>,
+}
+
+impl<R: Rng> Generator<R> {
+    pub fn new(rng: R, bbox: Envelope) -> Self {
+        Generator {
+            rng,
+            bbox,
+            srid: None,
+        }
+    }
+
+    /// SRID of the generated geometries. Default is `None`.
+    pub fn srid(mut self, srid: Option<i32>) -> Self {
+        self.srid = srid;
+        self
+    }
+
+    /// Uniformly distributed point.
+    pub fn point(&mut self) -> Point {
+        let x = self.bbox.xmin + self.rng.random::<f64>() * self.bbox.width();
+        let y = self.bbox.ymin + self.rng.random::<f64>() * self.bbox.height();
+        Point::new(x, y, self.srid)
+    }
+
+    /// MultiPoint of `count` uniformly distributed points.
+    pub fn points(&mut self, count: usize) -> MultiPoint {
+        let mut multi = MultiPoint::new_with_srid(self.srid);
+        for _ in 0..count {
+            multi.points.push(self.point());
+        }
+        multi
+    }
+
+    /// LineString with `vertices` vertices, starting at a random point and
+    /// moving `step` into a random direction at each vertex. Vertices are
+    /// kept within the bounding box.
+    pub fn random_walk(&mut self, vertices: usize, step: f64) -> LineString {
+        let mut line = LineString::new_with_srid(self.srid);
+        if vertices == 0 {
+            return line;
+        }
+        let mut p = self.point();
+        line.points.push(p);
+        for _ in 1..vertices {
+            let angle = self.rng.random::<f64>() * 2.0 * PI;
+            p.x = (p.x + step * angle.cos()).clamp(self.bbox.xmin, self.bbox.xmax);
+            p.y = (p.y + step * angle.sin()).clamp(self.bbox.ymin, self.bbox.ymax);
+            line.points.push(p);
+        }
+        line
+    }
+
+    /// Counterclockwise convex polygon with `vertices` distinct vertices on
+    /// an ellipse inside the bounding box.
+    ///
+    /// Panics if `vertices` is less than 3.
+    pub fn convex_polygon(&mut self, vertices: usize) -> Polygon {
+        assert!(vertices >= 3, "a polygon needs at least 3 vertices");
+        let (cx, cy) = self.bbox.center();
+        let scale = 0.5 + self.rng.random::<f64>() / 2.0;
+        let rx = self.bbox.width() / 2.0 * scale;
+        let ry = self.bbox.height() / 2.0 * scale;
+        let mut angles: Vec<f64> = (0..vertices)
+            .map(|_| self.rng.random::<f64>() * 2.0 * PI)
+            .collect();
+        angles.sort_by(f64::total_cmp);
+        let mut ring = LineString::new_with_srid(self.srid);
+        for angle in &angles {
+            ring.points.push(Point::new(
+                cx + rx * angle.cos(),
+                cy + ry * angle.sin(),
+                self.srid,
+            ));
+        }
+        ring.points.push(ring.points[0]);
+        let mut polygon = Polygon::new_with_srid(self.srid);
+        polygon.rings.push(ring);
+        polygon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_generator() {
+        let bbox = Envelope::new(0.0, 0.0, 10.0, 5.0);
+        let mut gen = Generator::new(StdRng::seed_from_u64(1), bbox).srid(Some(3857));
+
+        let walk = gen.random_walk(1000, 1.0);
+        assert_eq!(walk.srid, Some(3857));
+        assert!(walk.points.iter().all(|p| bbox.contains_point(p)));
+        assert!(walk
+            .points
+            .windows(2)
+            .all(|w| (w[1].x - w[0].x).hypot(w[1].y - w[0].y) <= 1.0 + 1e-9));
+        assert!(gen.random_walk(0, 1.0).points.is_empty());
+
+        for vertices in 3..10 {
+            let polygon = gen.convex_polygon(vertices);
+            let ring = &polygon.rings[0].points;
+            assert_eq!(ring.len(), vertices + 1);
+            assert!(ring.iter().all(|p| bbox.contains_point(p)));
+            // all turns are left turns
+            for i in 0..vertices {
+                let (a, b, c) = (ring[i], ring[i + 1], ring[(i + 2) % vertices]);
+                let cross = (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x);
+                assert!(cross >= 0.0);
+            }
+        }
+    }
+}
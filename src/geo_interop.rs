@@ -0,0 +1,747 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! [`geo-traits`](https://docs.rs/geo-traits) implementations for the ewkb and twkb types.
+//!
+//! Algorithms written against the geo-traits read the geometries in place.
+//! Coordinates are returned as [`Coord`] values.
+//!
+//! ```rust
+//! use geo_traits::{CoordTrait, LineStringTrait, PolygonTrait};
+//! use postgis::ewkb;
+//!
+//! fn ring_length<L: LineStringTrait<T = f64>>(ring: &L) -> f64 {
+//!     let coords: Vec<_> = ring.coords().collect();
+//!     coords.windows(2).map(|w| (w[1].x() - w[0].x()).hypot(w[1].y() - w[0].y())).sum()
+//! }
+//!
+//! let poly = ewkb::Polygon::from(vec![vec![(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (0.0, 0.0)]]);
+//! let exterior = PolygonTrait::exterior(&poly).unwrap();
+//! assert_eq!(ring_length(&exterior), 3.0 + 5f64.sqrt());
+//! ```
+
+use crate::{
+    coord::Coord,
+    ewkb::{self, EwkbRead},
+    twkb, types as postgis,
+};
+use geo_traits::{
+    CoordTrait, Dimensions, GeometryCollectionTrait, GeometryTrait, GeometryType, LineStringTrait,
+    MultiLineStringTrait, MultiPointTrait, MultiPolygonTrait, PointTrait, PolygonTrait,
+    UnimplementedGeometryCollection, UnimplementedLine, UnimplementedRect, UnimplementedTriangle,
+};
+
+// --- Coordinates
+
+impl CoordTrait for Coord {
+    type T = f64;
+
+    fn dim(&self) -> Dimensions {
+        match (self.z.is_some(), self.m.is_some()) {
+            (false, false) => Dimensions::Xy,
+            (true, false) => Dimensions::Xyz,
+            (false, true) => Dimensions::Xym,
+            (true, true) => Dimensions::Xyzm,
+        }
+    }
+
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+
+    fn nth_or_panic(&self, n: usize) -> f64 {
+        let value = match n {
+            0 => Some(self.x),
+            1 => Some(self.y),
+            2 => self.z.or(self.m),
+            3 => self.z.and(self.m),
+            _ => None,
+        };
+        value.unwrap_or_else(|| panic!("coordinate has no dimension {}", n))
+    }
+}
+
+fn dimensions(point_type: ewkb::PointType) -> Dimensions {
+    match point_type {
+        ewkb::PointType::Point => Dimensions::Xy,
+        ewkb::PointType::PointZ => Dimensions::Xyz,
+        ewkb::PointType::PointM => Dimensions::Xym,
+        ewkb::PointType::PointZM => Dimensions::Xyzm,
+    }
+}
+
+// Empty points have NaN coordinates
+fn point_coord<P: postgis::Point>(point: &P) -> Option<Coord> {
+    if point.x().is_nan() && point.y().is_nan() {
+        None
+    } else {
+        Some(Coord::from_point(point))
+    }
+}
+
+// Associated geometry types of `GeometryTrait`
+macro_rules! geometry_types {
+    ($point:ty, $line:ty, $polygon:ty, $multipoint:ty, $multiline:ty, $multipolygon:ty, $collection:ty) => {
+        type T = f64;
+        type PointType<'b>
+            = $point
+        where
+            Self: 'b;
+        type LineStringType<'b>
+            = $line
+        where
+            Self: 'b;
+        type PolygonType<'b>
+            = $polygon
+        where
+            Self: 'b;
+        type MultiPointType<'b>
+            = $multipoint
+        where
+            Self: 'b;
+        type MultiLineStringType<'b>
+            = $multiline
+        where
+            Self: 'b;
+        type MultiPolygonType<'b>
+            = $multipolygon
+        where
+            Self: 'b;
+        type GeometryCollectionType<'b>
+            = $collection
+        where
+            Self: 'b;
+        type RectType<'b>
+            = UnimplementedRect<f64>
+        where
+            Self: 'b;
+        type TriangleType<'b>
+            = UnimplementedTriangle<f64>
+        where
+            Self: 'b;
+        type LineType<'b>
+            = UnimplementedLine<f64>
+        where
+            Self: 'b;
+    };
+}
+
+// --- ewkb
+
+type EwkbGeometryType<'a, P> = GeometryType<
+    'a,
+    P,
+    ewkb::LineStringT<P>,
+    ewkb::PolygonT<P>,
+    ewkb::MultiPointT<P>,
+    ewkb::MultiLineStringT<P>,
+    ewkb::MultiPolygonT<P>,
+    ewkb::GeometryCollectionT<P>,
+    UnimplementedRect<f64>,
+    UnimplementedTriangle<f64>,
+    UnimplementedLine<f64>,
+>;
+
+macro_rules! ewkb_geometry_types {
+    ($point:ty) => {
+        geometry_types!(
+            $point,
+            ewkb::LineStringT<$point>,
+            ewkb::PolygonT<$point>,
+            ewkb::MultiPointT<$point>,
+            ewkb::MultiLineStringT<$point>,
+            ewkb::MultiPolygonT<$point>,
+            ewkb::GeometryCollectionT<$point>
+        );
+    };
+}
+
+macro_rules! impl_geo_traits_for_point {
+    ($ptype:ident) => {
+        impl GeometryTrait for ewkb::$ptype {
+            ewkb_geometry_types!(ewkb::$ptype);
+
+            fn dim(&self) -> Dimensions {
+                dimensions(ewkb::$ptype::point_type())
+            }
+
+            fn as_type(&self) -> EwkbGeometryType<'_, ewkb::$ptype> {
+                GeometryType::Point(self)
+            }
+        }
+
+        impl PointTrait for ewkb::$ptype {
+            type CoordType<'b>
+                = Coord
+            where
+                Self: 'b;
+
+            fn coord(&self) -> Option<Coord> {
+                point_coord(self)
+            }
+        }
+    };
+}
+
+impl_geo_traits_for_point!(Point);
+impl_geo_traits_for_point!(PointZ);
+impl_geo_traits_for_point!(PointM);
+impl_geo_traits_for_point!(PointZM);
+
+// `GeometryTrait` for an ewkb container and references to it
+macro_rules! impl_geometry_trait {
+    ($geotype:ident => $variant:ident) => {
+        impl<P> GeometryTrait for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+        {
+            ewkb_geometry_types!(P);
+
+            fn dim(&self) -> Dimensions {
+                dimensions(P::point_type())
+            }
+
+            fn as_type(&self) -> EwkbGeometryType<'_, P> {
+                GeometryType::$variant(self)
+            }
+        }
+
+        impl<P> GeometryTrait for &ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+        {
+            ewkb_geometry_types!(P);
+
+            fn dim(&self) -> Dimensions {
+                dimensions(P::point_type())
+            }
+
+            fn as_type(&self) -> EwkbGeometryType<'_, P> {
+                GeometryType::$variant(*self)
+            }
+        }
+    };
+}
+
+impl_geometry_trait!(LineStringT => LineString);
+impl_geometry_trait!(PolygonT => Polygon);
+impl_geometry_trait!(MultiPointT => MultiPoint);
+impl_geometry_trait!(MultiLineStringT => MultiLineString);
+impl_geometry_trait!(MultiPolygonT => MultiPolygon);
+impl_geometry_trait!(GeometryCollectionT => GeometryCollection);
+
+impl<P> GeometryTrait for ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+{
+    ewkb_geometry_types!(P);
+
+    fn dim(&self) -> Dimensions {
+        dimensions(P::point_type())
+    }
+
+    fn as_type(&self) -> EwkbGeometryType<'_, P> {
+        use crate::ewkb::GeometryT as G;
+        match *self {
+            G::Point(ref geom) => GeometryType::Point(geom),
+            G::LineString(ref geom) => GeometryType::LineString(geom),
+            G::Polygon(ref geom) => GeometryType::Polygon(geom),
+            G::MultiPoint(ref geom) => GeometryType::MultiPoint(geom),
+            G::MultiLineString(ref geom) => GeometryType::MultiLineString(geom),
+            G::MultiPolygon(ref geom) => GeometryType::MultiPolygon(geom),
+            G::GeometryCollection(ref geom) => GeometryType::GeometryCollection(geom),
+        }
+    }
+}
+
+impl<P> GeometryTrait for &ewkb::GeometryT<P>
+where
+    P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+{
+    ewkb_geometry_types!(P);
+
+    fn dim(&self) -> Dimensions {
+        dimensions(P::point_type())
+    }
+
+    fn as_type(&self) -> EwkbGeometryType<'_, P> {
+        (*self).as_type()
+    }
+}
+
+// Implement a geo-traits container trait for an ewkb type and references to it
+macro_rules! impl_container_trait {
+    ($geotype:ident: $traitname:ident {
+        type $inner:ident = $innertype:ty;
+        fn $count:ident => $itemname:ident $(+ $offset:literal)?;
+        fn $get:ident;
+        $($extra:item)*
+    }) => {
+        impl<P> $traitname for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+        {
+            type $inner<'b> = $innertype where Self: 'b;
+
+            fn $count(&self) -> usize {
+                self.$itemname.len()$(.saturating_sub($offset))?
+            }
+
+            unsafe fn $get(&self, i: usize) -> Self::$inner<'_> {
+                &self.$itemname[i $(+ $offset)?]
+            }
+
+            $($extra)*
+        }
+
+        impl<P> $traitname for &ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+        {
+            type $inner<'b> = $innertype where Self: 'b;
+
+            fn $count(&self) -> usize {
+                (**self).$count()
+            }
+
+            unsafe fn $get(&self, i: usize) -> Self::$inner<'_> {
+                (**self).$get(i)
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+impl_container_trait!(PolygonT: PolygonTrait {
+    type RingType = &'b ewkb::LineStringT<P>;
+    fn num_interiors => rings + 1;
+    fn interior_unchecked;
+    fn exterior(&self) -> Option<Self::RingType<'_>> {
+        self.rings.first()
+    }
+});
+impl_container_trait!(MultiLineStringT: MultiLineStringTrait {
+    type InnerLineStringType = &'b ewkb::LineStringT<P>;
+    fn num_line_strings => lines;
+    fn line_string_unchecked;
+});
+impl_container_trait!(MultiPolygonT: MultiPolygonTrait {
+    type InnerPolygonType = &'b ewkb::PolygonT<P>;
+    fn num_polygons => polygons;
+    fn polygon_unchecked;
+});
+impl_container_trait!(GeometryCollectionT: GeometryCollectionTrait {
+    type GeometryType = &'b ewkb::GeometryT<P>;
+    fn num_geometries => geometries;
+    fn geometry_unchecked;
+});
+
+impl<P> LineStringTrait for ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+{
+    type CoordType<'b>
+        = Coord
+    where
+        Self: 'b;
+
+    fn num_coords(&self) -> usize {
+        self.points.len()
+    }
+
+    unsafe fn coord_unchecked(&self, i: usize) -> Coord {
+        Coord::from_point(&self.points[i])
+    }
+}
+
+impl<P> LineStringTrait for &ewkb::LineStringT<P>
+where
+    P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+{
+    type CoordType<'b>
+        = Coord
+    where
+        Self: 'b;
+
+    fn num_coords(&self) -> usize {
+        self.points.len()
+    }
+
+    unsafe fn coord_unchecked(&self, i: usize) -> Coord {
+        Coord::from_point(&self.points[i])
+    }
+}
+
+impl<P> MultiPointTrait for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+{
+    type InnerPointType<'b>
+        = P
+    where
+        Self: 'b;
+
+    fn num_points(&self) -> usize {
+        self.points.len()
+    }
+
+    unsafe fn point_unchecked(&self, i: usize) -> P {
+        self.points[i]
+    }
+}
+
+impl<P> MultiPointTrait for &ewkb::MultiPointT<P>
+where
+    P: postgis::Point + EwkbRead + PointTrait<T = f64> + Copy,
+{
+    type InnerPointType<'b>
+        = P
+    where
+        Self: 'b;
+
+    fn num_points(&self) -> usize {
+        self.points.len()
+    }
+
+    unsafe fn point_unchecked(&self, i: usize) -> P {
+        self.points[i]
+    }
+}
+
+// --- twkb
+
+// TWKB geometries have no fixed dimensions, they are taken from the first point
+fn twkb_dimensions<'a, I: IntoIterator<Item = &'a twkb::Point>>(points: I) -> Dimensions {
+    points
+        .into_iter()
+        .next()
+        .map_or(Dimensions::Xy, |p| Coord::from_point(p).dim())
+}
+
+type TwkbGeometryType<'a> = GeometryType<
+    'a,
+    twkb::Point,
+    twkb::LineString,
+    twkb::Polygon,
+    twkb::MultiPoint,
+    twkb::MultiLineString,
+    twkb::MultiPolygon,
+    UnimplementedGeometryCollection<f64>,
+    UnimplementedRect<f64>,
+    UnimplementedTriangle<f64>,
+    UnimplementedLine<f64>,
+>;
+
+macro_rules! impl_geometry_trait_for_twkb {
+    ($geotype:ident => |$geom:ident| $points:expr) => {
+        impl GeometryTrait for twkb::$geotype {
+            geometry_types!(
+                twkb::Point,
+                twkb::LineString,
+                twkb::Polygon,
+                twkb::MultiPoint,
+                twkb::MultiLineString,
+                twkb::MultiPolygon,
+                UnimplementedGeometryCollection<f64>
+            );
+
+            fn dim(&self) -> Dimensions {
+                let $geom = self;
+                twkb_dimensions($points)
+            }
+
+            fn as_type(&self) -> TwkbGeometryType<'_> {
+                GeometryType::$geotype(self)
+            }
+        }
+    };
+}
+
+impl_geometry_trait_for_twkb!(Point => |p| Some(p));
+impl_geometry_trait_for_twkb!(LineString => |line| &line.points);
+impl_geometry_trait_for_twkb!(Polygon => |poly| poly.rings.iter().flat_map(|r| &r.points));
+impl_geometry_trait_for_twkb!(MultiPoint => |multi| &multi.points);
+impl_geometry_trait_for_twkb!(MultiLineString => |multi| multi.lines.iter().flat_map(|l| &l.points));
+impl_geometry_trait_for_twkb!(MultiPolygon => |multi| multi
+    .polygons
+    .iter()
+    .flat_map(|p| &p.rings)
+    .flat_map(|r| &r.points));
+
+impl GeometryTrait for twkb::Geometry {
+    geometry_types!(
+        twkb::Point,
+        twkb::LineString,
+        twkb::Polygon,
+        twkb::MultiPoint,
+        twkb::MultiLineString,
+        twkb::MultiPolygon,
+        UnimplementedGeometryCollection<f64>
+    );
+
+    fn dim(&self) -> Dimensions {
+        match *self {
+            twkb::Geometry::Point(ref geom) => geom.dim(),
+            twkb::Geometry::LineString(ref geom) => geom.dim(),
+            twkb::Geometry::Polygon(ref geom) => geom.dim(),
+            twkb::Geometry::MultiPoint(ref geom) => geom.dim(),
+            twkb::Geometry::MultiLineString(ref geom) => geom.dim(),
+            twkb::Geometry::MultiPolygon(ref geom) => geom.dim(),
+        }
+    }
+
+    fn as_type(&self) -> TwkbGeometryType<'_> {
+        match *self {
+            twkb::Geometry::Point(ref geom) => GeometryType::Point(geom),
+            twkb::Geometry::LineString(ref geom) => GeometryType::LineString(geom),
+            twkb::Geometry::Polygon(ref geom) => GeometryType::Polygon(geom),
+            twkb::Geometry::MultiPoint(ref geom) => GeometryType::MultiPoint(geom),
+            twkb::Geometry::MultiLineString(ref geom) => GeometryType::MultiLineString(geom),
+            twkb::Geometry::MultiPolygon(ref geom) => GeometryType::MultiPolygon(geom),
+        }
+    }
+}
+
+impl PointTrait for twkb::Point {
+    type CoordType<'b>
+        = Coord
+    where
+        Self: 'b;
+
+    fn coord(&self) -> Option<Coord> {
+        point_coord(self)
+    }
+}
+
+impl LineStringTrait for twkb::LineString {
+    type CoordType<'b>
+        = Coord
+    where
+        Self: 'b;
+
+    fn num_coords(&self) -> usize {
+        self.points.len()
+    }
+
+    unsafe fn coord_unchecked(&self, i: usize) -> Coord {
+        Coord::from_point(&self.points[i])
+    }
+}
+
+impl PolygonTrait for twkb::Polygon {
+    type RingType<'b>
+        = &'b twkb::LineString
+    where
+        Self: 'b;
+
+    fn exterior(&self) -> Option<&twkb::LineString> {
+        self.rings.first()
+    }
+
+    fn num_interiors(&self) -> usize {
+        self.rings.len().saturating_sub(1)
+    }
+
+    unsafe fn interior_unchecked(&self, i: usize) -> &twkb::LineString {
+        &self.rings[i + 1]
+    }
+}
+
+impl MultiPointTrait for twkb::MultiPoint {
+    type InnerPointType<'b>
+        = twkb::Point
+    where
+        Self: 'b;
+
+    fn num_points(&self) -> usize {
+        self.points.len()
+    }
+
+    unsafe fn point_unchecked(&self, i: usize) -> twkb::Point {
+        self.points[i]
+    }
+}
+
+impl MultiLineStringTrait for twkb::MultiLineString {
+    type InnerLineStringType<'b>
+        = &'b twkb::LineString
+    where
+        Self: 'b;
+
+    fn num_line_strings(&self) -> usize {
+        self.lines.len()
+    }
+
+    unsafe fn line_string_unchecked(&self, i: usize) -> &twkb::LineString {
+        &self.lines[i]
+    }
+}
+
+impl MultiPolygonTrait for twkb::MultiPolygon {
+    type InnerPolygonType<'b>
+        = &'b twkb::Polygon
+    where
+        Self: 'b;
+
+    fn num_polygons(&self) -> usize {
+        self.polygons.len()
+    }
+
+    unsafe fn polygon_unchecked(&self, i: usize) -> &twkb::Polygon {
+        &self.polygons[i]
+    }
+}
+
+// Rings and members are handed out by reference
+macro_rules! impl_geometry_trait_for_twkb_ref {
+    ($geotype:ident) => {
+        impl GeometryTrait for &twkb::$geotype {
+            geometry_types!(
+                twkb::Point,
+                twkb::LineString,
+                twkb::Polygon,
+                twkb::MultiPoint,
+                twkb::MultiLineString,
+                twkb::MultiPolygon,
+                UnimplementedGeometryCollection<f64>
+            );
+
+            fn dim(&self) -> Dimensions {
+                (**self).dim()
+            }
+
+            fn as_type(&self) -> TwkbGeometryType<'_> {
+                GeometryType::$geotype(*self)
+            }
+        }
+    };
+}
+
+impl_geometry_trait_for_twkb_ref!(LineString);
+impl_geometry_trait_for_twkb_ref!(Polygon);
+
+impl LineStringTrait for &twkb::LineString {
+    type CoordType<'b>
+        = Coord
+    where
+        Self: 'b;
+
+    fn num_coords(&self) -> usize {
+        (**self).num_coords()
+    }
+
+    unsafe fn coord_unchecked(&self, i: usize) -> Coord {
+        (**self).coord_unchecked(i)
+    }
+}
+
+impl PolygonTrait for &twkb::Polygon {
+    type RingType<'b>
+        = &'b twkb::LineString
+    where
+        Self: 'b;
+
+    fn exterior(&self) -> Option<&twkb::LineString> {
+        (**self).exterior()
+    }
+
+    fn num_interiors(&self) -> usize {
+        (**self).num_interiors()
+    }
+
+    unsafe fn interior_unchecked(&self, i: usize) -> &twkb::LineString {
+        (**self).interior_unchecked(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{coord::Coord, ewkb, twkb};
+    use geo_traits::*;
+
+    fn coords<L: LineStringTrait<T = f64>>(line: L) -> Vec<(f64, f64)> {
+        line.coords().map(|c| (c.x(), c.y())).collect()
+    }
+
+    #[test]
+    fn test_coord() {
+        let coord = Coord::new(1.0, 2.0).with_m(4.0);
+        assert_eq!(coord.dim(), Dimensions::Xym);
+        assert_eq!(coord.nth_or_panic(2), 4.0);
+        assert_eq!(coord.x_y(), (1.0, 2.0));
+        assert_eq!(
+            Coord::new(1.0, 2.0).with_z(3.0).with_m(4.0).nth_or_panic(3),
+            4.0
+        );
+    }
+
+    #[test]
+    fn test_ewkb() {
+        let point = ewkb::PointZ::new(1.0, 2.0, 3.0, None);
+        assert_eq!(point.dim(), Dimensions::Xyz);
+        assert_eq!(PointTrait::coord(&point).unwrap().nth_or_panic(2), 3.0);
+        assert!(PointTrait::coord(&ewkb::Point::new(f64::NAN, f64::NAN, None)).is_none());
+
+        let poly = ewkb::Polygon::from(vec![
+            vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 0.0)],
+            vec![(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 1.0)],
+        ]);
+        assert_eq!(poly.num_interiors(), 1);
+        assert_eq!(
+            coords(PolygonTrait::interiors(&poly).next().unwrap())[1],
+            (2.0, 1.0)
+        );
+
+        let geom = ewkb::Geometry::MultiPolygon(ewkb::MultiPolygon::from(vec![poly]));
+        match geom.as_type() {
+            GeometryType::MultiPolygon(multi) => {
+                let exterior = multi.polygon(0).unwrap().exterior().unwrap();
+                assert_eq!(exterior.num_coords(), 4);
+            }
+            _ => panic!("MultiPolygon expected"),
+        }
+
+        let multi = ewkb::MultiPoint::from(vec![(1.0, 2.0), (3.0, 4.0)]);
+        let xs: Vec<f64> = multi
+            .points()
+            .map(|p| PointTrait::coord(&p).unwrap().x())
+            .collect();
+        assert_eq!(xs, vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_twkb() {
+        let line = twkb::LineString {
+            points: vec![
+                twkb::Point {
+                    x: 1.0,
+                    y: 2.0,
+                    z: Some(3.0),
+                    m: None,
+                },
+                twkb::Point {
+                    x: 4.0,
+                    y: 5.0,
+                    z: Some(6.0),
+                    m: None,
+                },
+            ],
+        };
+        assert_eq!(coords(&line), vec![(1.0, 2.0), (4.0, 5.0)]);
+        let geom = twkb::Geometry::MultiLineString(twkb::MultiLineString {
+            lines: vec![line],
+            ids: None,
+        });
+        assert_eq!(geom.dim(), Dimensions::Xyz);
+        match geom.as_type() {
+            GeometryType::MultiLineString(multi) => assert_eq!(multi.num_line_strings(), 1),
+            _ => panic!("MultiLineString expected"),
+        }
+    }
+}
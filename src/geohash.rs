@@ -0,0 +1,155 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! [Geohash](https://en.wikipedia.org/wiki/Geohash) encoding and decoding.
+//!
+//! Coordinates are interpreted as WGS-84 longitude (x) and latitude (y).
+//!
+//! ```rust
+//! use postgis::{ewkb, geohash};
+//!
+//! let point = ewkb::Point::new_wgs84(-5.6, 42.6);
+//! let hash = geohash::encode_point(&point, 5);
+//! assert_eq!(hash, "ezs42");
+//! let center = geohash::decode(&hash).unwrap();
+//! assert!((center.x - -5.6).abs() < 0.03 && (center.y - 42.6).abs() < 0.03);
+//! ```
+
+use crate::{envelope::Envelope, error::Error, ewkb, types as postgis};
+
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Maximum supported geohash length (60 bits).
+pub const MAX_PRECISION: usize = 12;
+
+/// Encode a longitude/latitude pair into a geohash of `precision` characters.
+pub fn encode(x: f64, y: f64, precision: usize) -> String {
+    let precision = precision.min(MAX_PRECISION);
+    let (mut xmin, mut xmax) = (-180.0, 180.0);
+    let (mut ymin, mut ymax) = (-90.0, 90.0);
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+    let mut bits = 0;
+    let mut idx = 0;
+    while hash.len() < precision {
+        if even {
+            let mid = (xmin + xmax) / 2.0;
+            if x >= mid {
+                idx = idx * 2 + 1;
+                xmin = mid;
+            } else {
+                idx *= 2;
+                xmax = mid;
+            }
+        } else {
+            let mid = (ymin + ymax) / 2.0;
+            if y >= mid {
+                idx = idx * 2 + 1;
+                ymin = mid;
+            } else {
+                idx *= 2;
+                ymax = mid;
+            }
+        }
+        even = !even;
+        bits += 1;
+        if bits == 5 {
+            hash.push(BASE32[idx] as char);
+            bits = 0;
+            idx = 0;
+        }
+    }
+    hash
+}
+
+/// Encode a point into a geohash of `precision` characters.
+pub fn encode_point<P: postgis::Point>(point: &P, precision: usize) -> String {
+    encode(point.x(), point.y(), precision)
+}
+
+/// Longest geohash (up to `max_precision` characters) whose cell contains the whole envelope.
+pub fn encode_envelope(envelope: &Envelope, max_precision: usize) -> String {
+    let min = encode(envelope.xmin, envelope.ymin, max_precision);
+    let max = encode(envelope.xmax, envelope.ymax, max_precision);
+    min.chars()
+        .zip(max.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect()
+}
+
+/// Decode a geohash into the envelope of its cell.
+pub fn decode_envelope(hash: &str) -> Result<Envelope, Error> {
+    let (mut xmin, mut xmax) = (-180.0, 180.0);
+    let (mut ymin, mut ymax) = (-90.0, 90.0);
+    let mut even = true;
+    for c in hash.chars() {
+        let idx = BASE32
+            .iter()
+            .position(|&b| b as char == c.to_ascii_lowercase())
+            .ok_or_else(|| Error::Read(format!("invalid geohash character '{}'", c)))?;
+        for shift in (0..5).rev() {
+            let bit = (idx >> shift) & 1 == 1;
+            if even {
+                let mid = (xmin + xmax) / 2.0;
+                if bit {
+                    xmin = mid;
+                } else {
+                    xmax = mid;
+                }
+            } else {
+                let mid = (ymin + ymax) / 2.0;
+                if bit {
+                    ymin = mid;
+                } else {
+                    ymax = mid;
+                }
+            }
+            even = !even;
+        }
+    }
+    Ok(Envelope::new(xmin, ymin, xmax, ymax))
+}
+
+/// Decode a geohash into the center point of its cell, with SRID 4326.
+pub fn decode(hash: &str) -> Result<ewkb::Point, Error> {
+    let (x, y) = decode_envelope(hash)?.center();
+    Ok(ewkb::Point::new_wgs84(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(-5.6, 42.6, 5), "ezs42");
+        assert_eq!(encode(10.40744, 57.64911, 11), "u4pruydqqvj");
+        assert_eq!(encode(0.0, 0.0, 0), "");
+    }
+
+    #[test]
+    fn test_decode() {
+        let env = decode_envelope("ezs42").unwrap();
+        assert!(env.xmin <= -5.6 && -5.6 <= env.xmax);
+        assert!(env.ymin <= 42.6 && 42.6 <= env.ymax);
+        let point = decode("u4pruydqqvj").unwrap();
+        assert!((point.x - 10.40744).abs() < 1e-5);
+        assert!((point.y - 57.64911).abs() < 1e-5);
+        assert_eq!(point.srid, Some(4326));
+        assert!(decode("ezs4a").is_err());
+    }
+
+    #[test]
+    fn test_encode_envelope() {
+        let env = decode_envelope("u4pru").unwrap();
+        let inner = Envelope::new(
+            env.xmin + 0.001,
+            env.ymin + 0.001,
+            env.xmax - 0.001,
+            env.ymax - 0.001,
+        );
+        assert_eq!(encode_envelope(&inner, 12), "u4pru");
+    }
+}
@@ -0,0 +1,259 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Geometry columns for [GeoParquet](https://geoparquet.org/) files.
+//!
+//! [`WkbColumnWriter`] converts EWKB values, e.g. read from PostGIS, into
+//! the ISO WKB values of a GeoParquet geometry column and collects the
+//! column metadata (geometry types, bbox and CRS). Writing the Parquet
+//! file is left to a Parquet library: store [`WkbColumn::values`] in a
+//! binary column and [`metadata_json`] as `geo` key of the file metadata.
+//!
+//! ```rust
+//! use postgis::{ewkb::{self, AsEwkbPoint}, geoparquet::{metadata_json, WkbColumnWriter}};
+//!
+//! let mut writer = WkbColumnWriter::new("geom");
+//! let points = [ewkb::Point::new(8.5, 47.4, Some(4326)), ewkb::Point::new(7.4, 46.9, Some(4326))];
+//! for point in &points {
+//!     writer.push(&point.as_ewkb()).unwrap();
+//! }
+//! writer.push_null();
+//! let column = writer.finish();
+//! assert_eq!(column.values.len(), 3);
+//! assert_eq!(
+//!     metadata_json(&[&column]),
+//!     r#"{"version":"1.0.0","primary_column":"geom","columns":{"geom":{"encoding":"WKB","geometry_types":["Point"],"bbox":[7.4,46.9,8.5,47.4],"crs":{"id":{"authority":"EPSG","code":4326}}}}}"#
+//! );
+//! ```
+
+use crate::{
+    envelope::Envelope,
+    error::Error,
+    ewkb::{self, EwkbWrite},
+    gpkg::ewkb_to_iso_wkb,
+};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// GeoParquet metadata version written by [`metadata_json`].
+pub const VERSION: &str = "1.0.0";
+
+/// ISO WKB values and metadata of a geometry column.
+#[derive(PartialEq, Clone, Debug)]
+pub struct WkbColumn {
+    pub name: String,
+    /// ISO WKB of every row, `None` for NULL geometries.
+    pub values: Vec<Option<Vec<u8>>>,
+    /// GeoParquet geometry type names, e.g. `"Polygon"` or `"LineString Z"`.
+    pub geometry_types: BTreeSet<&'static str>,
+    /// XY bounding box of all values.
+    pub bbox: Option<Envelope>,
+    /// SRID of the values, `None` if unknown.
+    pub srid: Option<i32>,
+}
+
+impl WkbColumn {
+    /// Column metadata as JSON object.
+    ///
+    /// The CRS is written as PROJJSON object with the EPSG id only, or
+    /// `null` for geometries without SRID.
+    pub fn metadata_json(&self) -> String {
+        let mut json = String::from(r#"{"encoding":"WKB","geometry_types":["#);
+        for (i, name) in self.geometry_types.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(json, r#""{}""#, name);
+        }
+        json.push(']');
+        if let Some(bbox) = self.bbox {
+            let _ = write!(
+                json,
+                r#","bbox":[{},{},{},{}]"#,
+                bbox.xmin, bbox.ymin, bbox.xmax, bbox.ymax
+            );
+        }
+        match self.srid {
+            Some(srid) => {
+                let _ = write!(
+                    json,
+                    r#","crs":{{"id":{{"authority":"EPSG","code":{}}}}}"#,
+                    srid
+                );
+            }
+            None => json.push_str(r#","crs":null"#),
+        }
+        json.push('}');
+        json
+    }
+}
+
+/// Value of the `geo` file metadata key for the given geometry columns.
+/// The first column is the primary column.
+pub fn metadata_json(columns: &[&WkbColumn]) -> String {
+    let primary = columns.first().map_or("", |c| c.name.as_str());
+    let mut json = format!(
+        r#"{{"version":"{}","primary_column":{},"columns":{{"#,
+        VERSION,
+        json_string(primary)
+    );
+    for (i, column) in columns.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let _ = write!(
+            json,
+            "{}:{}",
+            json_string(&column.name),
+            column.metadata_json()
+        );
+    }
+    json.push_str("}}");
+    json
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Converts EWKB values into a [`WkbColumn`].
+#[derive(Debug)]
+pub struct WkbColumnWriter {
+    column: WkbColumn,
+    srid_seen: bool,
+}
+
+impl WkbColumnWriter {
+    pub fn new(name: &str) -> Self {
+        WkbColumnWriter {
+            column: WkbColumn {
+                name: name.to_string(),
+                values: Vec::new(),
+                geometry_types: BTreeSet::new(),
+                bbox: None,
+                srid: None,
+            },
+            srid_seen: false,
+        }
+    }
+
+    /// Add an EWKB value.
+    ///
+    /// All values of a column must have the same SRID. Geometries with M
+    /// values are rejected, GeoParquet only supports XY and XYZ.
+    pub fn push_ewkb(&mut self, ewkb: &[u8]) -> Result<(), Error> {
+        let header = ewkb::read_header(ewkb)?;
+        if header.has_m {
+            return Err(Error::Write(
+                "GeoParquet doesn't support M coordinates".into(),
+            ));
+        }
+        if self.srid_seen && header.srid != self.column.srid {
+            return Err(Error::Write(format!(
+                "SRID {:?} differs from column SRID {:?}",
+                header.srid, self.column.srid
+            )));
+        }
+        let type_name = geometry_type_name(header.geom_type, header.has_z)?;
+
+        let mut raw = ewkb;
+        let mut wkb = Vec::with_capacity(ewkb.len());
+        let mut bbox = None;
+        ewkb_to_iso_wkb(&mut raw, &mut wkb, &mut bbox)?;
+
+        self.column.srid = header.srid;
+        self.srid_seen = true;
+        self.column.geometry_types.insert(type_name);
+        if let Some(bbox) = bbox {
+            self.column.bbox = Some(match self.column.bbox {
+                Some(column_bbox) => column_bbox.union(&bbox),
+                None => bbox,
+            });
+        }
+        self.column.values.push(Some(wkb));
+        Ok(())
+    }
+
+    /// Add a geometry, e.g. `writer.push(&point.as_ewkb())`.
+    pub fn push<G: EwkbWrite>(&mut self, geom: &G) -> Result<(), Error> {
+        let mut ewkb = Vec::with_capacity(geom.ewkb_size());
+        geom.write_ewkb(&mut ewkb)?;
+        self.push_ewkb(&ewkb)
+    }
+
+    /// Add a NULL geometry.
+    pub fn push_null(&mut self) {
+        self.column.values.push(None);
+    }
+
+    pub fn finish(self) -> WkbColumn {
+        self.column
+    }
+}
+
+fn geometry_type_name(geom_type: u32, has_z: bool) -> Result<&'static str, Error> {
+    let names = match geom_type {
+        1 => ["Point", "Point Z"],
+        2 => ["LineString", "LineString Z"],
+        3 => ["Polygon", "Polygon Z"],
+        4 => ["MultiPoint", "MultiPoint Z"],
+        5 => ["MultiLineString", "MultiLineString Z"],
+        6 => ["MultiPolygon", "MultiPolygon Z"],
+        7 => ["GeometryCollection", "GeometryCollection Z"],
+        _ => {
+            return Err(Error::Write(format!(
+                "unsupported geometry type {}",
+                geom_type
+            )))
+        }
+    };
+    Ok(names[has_z as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_wkb_column() {
+        let mut writer = WkbColumnWriter::new("geom");
+        let polygon = Polygon::from(vec![vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 0.0)]]);
+        writer.push(&polygon.as_ewkb()).unwrap();
+        let line = LineStringZ::from(vec![(-1.0, 5.0, 10.0), (1.0, 6.0, 11.0)]);
+        writer.push(&line.as_ewkb()).unwrap();
+        assert!(writer
+            .push(&PointM::new(1.0, 2.0, 3.0, None).as_ewkb())
+            .is_err());
+        assert!(writer
+            .push(&Point::new(1.0, 2.0, Some(4326)).as_ewkb())
+            .is_err());
+        let column = writer.finish();
+
+        // ISO WKB type 1002 (LineString Z)
+        assert_eq!(
+            &column.values[1].as_ref().unwrap()[..5],
+            &[1, 0xEA, 0x03, 0, 0]
+        );
+        assert_eq!(column.values.len(), 2);
+        assert_eq!(
+            column.metadata_json(),
+            r#"{"encoding":"WKB","geometry_types":["LineString Z","Polygon"],"bbox":[-1,0,2,6],"crs":null}"#
+        );
+        assert_eq!(json_string("a\"b\n"), r#""a\"b\u000a""#);
+    }
+}
@@ -0,0 +1,343 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Read and write geometries in [GeoPackage binary](http://www.geopackage.org/spec/#gpb_format) format.
+//!
+//! A GeoPackage geometry blob consists of a header with SRS id and optional
+//! envelope, followed by the geometry in ISO WKB.
+//!
+//! ```rust
+//! use postgis::{ewkb::{self, AsEwkbPoint}, gpkg::{GpkgRead, GpkgWrite}};
+//!
+//! let point = ewkb::Point::new(10.0, -20.0, Some(4326));
+//! let blob = point.as_ewkb().to_gpkg();
+//! let point2 = ewkb::Point::read_gpkg(&mut blob.as_slice()).unwrap();
+//! assert_eq!(point, point2);
+//! ```
+
+use crate::{
+    envelope::Envelope,
+    error::Error,
+    ewkb::{EwkbRead, EwkbWrite, ReadOptions},
+};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::prelude::*;
+
+const MAGIC: &[u8; 2] = b"GP";
+
+/// GeoPackage binary header.
+#[derive(PartialEq, Clone, Debug)]
+pub struct GpkgHeader {
+    pub version: u8,
+    /// SRS id of the geometry. Undefined SRS (0 and -1) is mapped to `None`.
+    pub srid: Option<i32>,
+    /// XY envelope, if stored in the header.
+    pub envelope: Option<Envelope>,
+    pub is_empty: bool,
+}
+
+fn read_f64_with<R: Read>(raw: &mut R, is_le: bool) -> Result<f64, Error> {
+    Ok(if is_le {
+        raw.read_f64::<LittleEndian>()?
+    } else {
+        raw.read_f64::<BigEndian>()?
+    })
+}
+
+impl GpkgHeader {
+    pub fn read<R: Read>(raw: &mut R) -> Result<Self, Error> {
+        let mut magic = [0; 2];
+        raw.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::Read("invalid GeoPackage magic number".into()));
+        }
+        let version = raw.read_u8()?;
+        let flags = raw.read_u8()?;
+        if flags & 0b0010_0000 != 0 {
+            return Err(Error::Read(
+                "extended GeoPackage geometry types are not supported".into(),
+            ));
+        }
+        let is_le = flags & 0b1 != 0;
+        let srs_id = if is_le {
+            raw.read_i32::<LittleEndian>()?
+        } else {
+            raw.read_i32::<BigEndian>()?
+        };
+        let envelope_values = match (flags >> 1) & 0b111 {
+            0 => 0,
+            1 => 4,
+            2 | 3 => 6,
+            4 => 8,
+            code => {
+                return Err(Error::Read(format!(
+                    "invalid GeoPackage envelope code {}",
+                    code
+                )))
+            }
+        };
+        let mut values = [0.0; 8];
+        for value in values.iter_mut().take(envelope_values) {
+            *value = read_f64_with(raw, is_le)?;
+        }
+        let envelope = if envelope_values > 0 {
+            Some(Envelope::new(values[0], values[2], values[1], values[3]))
+        } else {
+            None
+        };
+        Ok(GpkgHeader {
+            version,
+            srid: if srs_id > 0 { Some(srs_id) } else { None },
+            envelope,
+            is_empty: flags & 0b1_0000 != 0,
+        })
+    }
+
+    pub fn write<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_all(MAGIC)?;
+        w.write_u8(self.version)?;
+        let mut flags = 0b1; // LE
+        if self.envelope.is_some() {
+            flags |= 1 << 1;
+        }
+        if self.is_empty {
+            flags |= 0b1_0000;
+        }
+        w.write_u8(flags)?;
+        w.write_i32::<LittleEndian>(self.srid.unwrap_or(0))?;
+        if let Some(env) = self.envelope {
+            for v in &[env.xmin, env.xmax, env.ymin, env.ymax] {
+                w.write_f64::<LittleEndian>(*v)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read geometries from GeoPackage binary blobs.
+pub trait GpkgRead: EwkbRead {
+    fn read_gpkg<R: Read>(raw: &mut R) -> Result<Self, Error> {
+        let header = GpkgHeader::read(raw)?;
+        let is_be = raw.read_i8()? == 0i8;
+        let type_id = if is_be {
+            raw.read_u32::<BigEndian>()?
+        } else {
+            raw.read_u32::<LittleEndian>()?
+        };
+        if type_id & 0x20000000 == 0x20000000 {
+            // EWKB with SRID: header SRS id takes precedence
+            if is_be {
+                raw.read_i32::<BigEndian>()?;
+            } else {
+                raw.read_i32::<LittleEndian>()?;
+            }
+        }
+        Self::read_ewkb_body(raw, is_be, type_id, header.srid, &ReadOptions::default())
+    }
+}
+
+impl<T: EwkbRead> GpkgRead for T {}
+
+/// Write geometries as GeoPackage binary blobs with XY envelope.
+pub trait GpkgWrite: EwkbWrite {
+    fn write_gpkg<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+        let mut ewkb = Vec::new();
+        self.write_ewkb(&mut ewkb)?;
+        let mut wkb = Vec::with_capacity(ewkb.len());
+        let mut envelope = None;
+        ewkb_to_iso_wkb(&mut ewkb.as_slice(), &mut wkb, &mut envelope)?;
+        let header = GpkgHeader {
+            version: 0,
+            srid: self.opt_srid(),
+            envelope,
+            is_empty: envelope.is_none(),
+        };
+        header.write(w)?;
+        w.write_all(&wkb)?;
+        Ok(())
+    }
+
+    fn to_gpkg(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::new();
+        self.write_gpkg(&mut buf).unwrap();
+        buf
+    }
+}
+
+impl<T: EwkbWrite> GpkgWrite for T {}
+
+fn expand(envelope: &mut Option<Envelope>, x: f64, y: f64) {
+    if x.is_nan() || y.is_nan() {
+        return;
+    }
+    let env = envelope.get_or_insert(Envelope::new(x, y, x, y));
+    env.xmin = env.xmin.min(x);
+    env.ymin = env.ymin.min(y);
+    env.xmax = env.xmax.max(x);
+    env.ymax = env.ymax.max(y);
+}
+
+fn copy_u32(raw: &mut &[u8], out: &mut Vec<u8>, is_be: bool) -> Result<u32, Error> {
+    let v = if is_be {
+        raw.read_u32::<BigEndian>()?
+    } else {
+        raw.read_u32::<LittleEndian>()?
+    };
+    let mut buf = [0; 4];
+    if is_be {
+        BigEndian::write_u32(&mut buf, v);
+    } else {
+        LittleEndian::write_u32(&mut buf, v);
+    }
+    out.extend_from_slice(&buf);
+    Ok(v)
+}
+
+fn copy_points(
+    raw: &mut &[u8],
+    out: &mut Vec<u8>,
+    is_be: bool,
+    dims: usize,
+    npoints: usize,
+    envelope: &mut Option<Envelope>,
+) -> Result<(), Error> {
+    let len = npoints * dims * 8;
+    if raw.len() < len {
+        return Err(Error::Read("unexpected end of EWKB data".into()));
+    }
+    let (coords, rest) = raw.split_at(len);
+    for point in coords.chunks(dims * 8) {
+        let (x, y) = if is_be {
+            (BigEndian::read_f64(point), BigEndian::read_f64(&point[8..]))
+        } else {
+            (
+                LittleEndian::read_f64(point),
+                LittleEndian::read_f64(&point[8..]),
+            )
+        };
+        expand(envelope, x, y);
+    }
+    out.extend_from_slice(coords);
+    *raw = rest;
+    Ok(())
+}
+
+/// Convert EWKB into ISO WKB by dropping SRIDs and replacing the dimension
+/// flags with ISO type codes. Collects the XY envelope of all coordinates.
+pub(crate) fn ewkb_to_iso_wkb(
+    raw: &mut &[u8],
+    out: &mut Vec<u8>,
+    envelope: &mut Option<Envelope>,
+) -> Result<(), Error> {
+    let byte_order = raw.read_u8()?;
+    let is_be = byte_order == 0;
+    out.push(byte_order);
+    let type_id = if is_be {
+        raw.read_u32::<BigEndian>()?
+    } else {
+        raw.read_u32::<LittleEndian>()?
+    };
+    if type_id & 0x20000000 == 0x20000000 {
+        // skip SRID
+        raw.read_u32::<LittleEndian>()?;
+    }
+    let code = (type_id & 0xffff) % 1000;
+    let iso_dims = (type_id & 0xffff) / 1000;
+    let has_z = type_id & 0x80000000 != 0 || iso_dims == 1 || iso_dims == 3;
+    let has_m = type_id & 0x40000000 != 0 || iso_dims == 2 || iso_dims == 3;
+    let iso_type = code + if has_z { 1000 } else { 0 } + if has_m { 2000 } else { 0 };
+    let mut buf = [0; 4];
+    if is_be {
+        BigEndian::write_u32(&mut buf, iso_type);
+    } else {
+        LittleEndian::write_u32(&mut buf, iso_type);
+    }
+    out.extend_from_slice(&buf);
+    let dims = 2 + has_z as usize + has_m as usize;
+    match code {
+        0x01 => copy_points(raw, out, is_be, dims, 1, envelope)?,
+        0x02 => {
+            let n = copy_u32(raw, out, is_be)? as usize;
+            copy_points(raw, out, is_be, dims, n, envelope)?;
+        }
+        0x03 => {
+            let nrings = copy_u32(raw, out, is_be)?;
+            for _ in 0..nrings {
+                let n = copy_u32(raw, out, is_be)? as usize;
+                copy_points(raw, out, is_be, dims, n, envelope)?;
+            }
+        }
+        0x04..=0x07 => {
+            let ngeoms = copy_u32(raw, out, is_be)?;
+            for _ in 0..ngeoms {
+                ewkb_to_iso_wkb(raw, out, envelope)?;
+            }
+        }
+        _ => {
+            return Err(Error::Read(format!(
+                "unsupported geometry type id {}",
+                type_id
+            )))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_point_roundtrip() {
+        let point = PointZ::new(10.0, -20.0, 100.0, Some(4326));
+        let blob = point.as_ewkb().to_gpkg();
+        assert_eq!(&blob[..8], &[0x47, 0x50, 0, 0b11, 0xE6, 0x10, 0, 0]);
+        // ISO WKB type 1001 (PointZ)
+        assert_eq!(&blob[40..45], &[1, 0xE9, 0x03, 0, 0]);
+        let header = GpkgHeader::read(&mut blob.as_slice()).unwrap();
+        assert_eq!(
+            header.envelope,
+            Some(Envelope::new(10.0, -20.0, 10.0, -20.0))
+        );
+        assert_eq!(PointZ::read_gpkg(&mut blob.as_slice()).unwrap(), point);
+    }
+
+    #[test]
+    fn test_geometry_roundtrip() {
+        let p = |x, y| Point::new(x, y, Some(3857));
+        let line = LineStringT::<Point> {
+            srid: Some(3857),
+            points: vec![p(0., 0.), p(2., 1.)],
+        };
+        let multi = MultiLineStringT::<Point> {
+            srid: Some(3857),
+            lines: vec![line.clone(), line],
+        };
+        let blob = multi.as_ewkb().to_gpkg();
+        let header = GpkgHeader::read(&mut blob.as_slice()).unwrap();
+        assert_eq!(header.srid, Some(3857));
+        assert_eq!(header.envelope, Some(Envelope::new(0., 0., 2., 1.)));
+        let geom = GeometryT::<Point>::read_gpkg(&mut blob.as_slice()).unwrap();
+        match geom {
+            GeometryT::MultiLineString(ml) => {
+                assert_eq!(ml.srid, Some(3857));
+                assert_eq!(ml.lines.len(), 2);
+                assert_eq!(ml.lines[0].points[1].x, 2.0);
+            }
+            _ => panic!("expected MultiLineString"),
+        }
+    }
+
+    #[test]
+    fn test_read_big_endian_header() {
+        // header BE without envelope, SRS id 4326, followed by BE ISO WKB POINT(1 2)
+        let mut blob = vec![0x47, 0x50, 0, 0, 0, 0, 0x10, 0xE6, 0, 0, 0, 0, 1];
+        blob.extend_from_slice(&1f64.to_be_bytes());
+        blob.extend_from_slice(&2f64.to_be_bytes());
+        let point = Point::read_gpkg(&mut blob.as_slice()).unwrap();
+        assert_eq!(point, Point::new(1.0, 2.0, Some(4326)));
+        assert!(Point::read_gpkg(&mut &b"XX"[..]).is_err());
+    }
+}
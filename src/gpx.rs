@@ -0,0 +1,221 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Export of tracks, routes and waypoints as [GPX 1.1](https://www.topografix.com/GPX/1/1/).
+//!
+//! Coordinates are written as longitude (X) and latitude (Y) in WGS 84.
+//! Z values are written as elevation, M values as time in seconds since
+//! the Unix epoch (UTC).
+//!
+//! ```rust
+//! use postgis::{ewkb, gpx::Gpx};
+//!
+//! let ride = ewkb::LineStringZM::from(vec![
+//!     (8.54, 47.37, 408.0, 1700000000.0),
+//!     (8.55, 47.38, 412.5, 1700000060.0),
+//! ]);
+//! let mut gpx = Gpx::new("rust-postgis");
+//! gpx.add_track(Some("Morning ride"), &[ride]);
+//! let xml = gpx.to_string();
+//! assert!(xml.contains(r#"<trkpt lat="47.37" lon="8.54"><ele>408</ele><time>2023-11-14T22:13:20Z</time></trkpt>"#));
+//! ```
+
+use crate::{
+    ewkb::{EwkbRead, LineStringT},
+    types as postgis,
+};
+use std::fmt::{self, Write};
+
+/// GPX document.
+#[derive(Clone, Debug, Default)]
+pub struct Gpx {
+    creator: String,
+    waypoints: String,
+    routes: String,
+    tracks: String,
+}
+
+impl Gpx {
+    pub fn new(creator: &str) -> Self {
+        Gpx {
+            creator: creator.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a track with one segment per LineString.
+    pub fn add_track<'a, P, I>(&mut self, name: Option<&str>, segments: I) -> &mut Self
+    where
+        P: 'a + postgis::Point + EwkbRead,
+        I: IntoIterator<Item = &'a LineStringT<P>>,
+    {
+        self.tracks.push_str("<trk>");
+        write_name(&mut self.tracks, name);
+        for segment in segments {
+            self.tracks.push_str("<trkseg>");
+            for point in segment.points.iter() {
+                write_point(&mut self.tracks, "trkpt", point);
+            }
+            self.tracks.push_str("</trkseg>");
+        }
+        self.tracks.push_str("</trk>\n");
+        self
+    }
+
+    /// Add a route through the points of a LineString.
+    pub fn add_route<P>(&mut self, name: Option<&str>, route: &LineStringT<P>) -> &mut Self
+    where
+        P: postgis::Point + EwkbRead,
+    {
+        self.routes.push_str("<rte>");
+        write_name(&mut self.routes, name);
+        for point in route.points.iter() {
+            write_point(&mut self.routes, "rtept", point);
+        }
+        self.routes.push_str("</rte>\n");
+        self
+    }
+
+    /// Add waypoints, e.g. the points of a MultiPoint.
+    pub fn add_waypoints<'a, P, I>(&mut self, points: I) -> &mut Self
+    where
+        P: 'a + postgis::Point,
+        I: IntoIterator<Item = &'a P>,
+    {
+        for point in points {
+            write_point(&mut self.waypoints, "wpt", point);
+            self.waypoints.push('\n');
+        }
+        self
+    }
+}
+
+impl fmt::Display for Gpx {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        let mut creator = String::new();
+        escape(&mut creator, &self.creator);
+        writeln!(
+            f,
+            r#"<gpx version="1.1" creator="{}" xmlns="http://www.topografix.com/GPX/1/1">"#,
+            creator
+        )?;
+        // elements in the order of the GPX schema
+        f.write_str(&self.waypoints)?;
+        f.write_str(&self.routes)?;
+        f.write_str(&self.tracks)?;
+        f.write_str("</gpx>\n")
+    }
+}
+
+fn write_name(out: &mut String, name: Option<&str>) {
+    if let Some(name) = name {
+        out.push_str("<name>");
+        escape(out, name);
+        out.push_str("</name>");
+    }
+}
+
+fn write_point<P: postgis::Point>(out: &mut String, tag: &str, point: &P) {
+    let _ = write!(out, r#"<{} lat="{}" lon="{}">"#, tag, point.y(), point.x());
+    if let Some(ele) = point.opt_z() {
+        let _ = write!(out, "<ele>{}</ele>", ele);
+    }
+    if let Some(time) = point.opt_m() {
+        out.push_str("<time>");
+        write_time(out, time);
+        out.push_str("</time>");
+    }
+    let _ = write!(out, "</{}>", tag);
+}
+
+fn escape(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+// ISO 8601 UTC time of seconds since the Unix epoch, with milliseconds
+// if the time has a fractional part
+fn write_time(out: &mut String, seconds: f64) {
+    let millis = (seconds * 1000.0).round() as i64;
+    let (days, millis_of_day) = (millis.div_euclid(86_400_000), millis.rem_euclid(86_400_000));
+    let (year, month, day) = civil_from_days(days);
+    let secs = millis_of_day / 1000;
+    let _ = write!(
+        out,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+    if millis_of_day % 1000 != 0 {
+        let _ = write!(out, ".{:03}", millis_of_day % 1000);
+    }
+    out.push('Z');
+}
+
+// Date of days since 1970-01-01 in the proleptic Gregorian calendar
+// (http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{LineString, MultiPointZ, PointZ};
+
+    #[test]
+    fn test_gpx() {
+        let route = LineString::from(vec![(8.5, 47.4), (8.6, 47.5)]);
+        let stops = MultiPointZ::from(vec![(8.5, 47.4, 400.0)]);
+        let mut gpx = Gpx::new("a & b");
+        gpx.add_track(None, &[route.clone(), route.clone()])
+            .add_route(Some("<Route>"), &route)
+            .add_waypoints(&stops.points)
+            .add_waypoints(&[PointZ::new(1.0, 2.0, 3.5, None)]);
+        assert_eq!(
+            gpx.to_string(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="a &amp; b" xmlns="http://www.topografix.com/GPX/1/1">
+<wpt lat="47.4" lon="8.5"><ele>400</ele></wpt>
+<wpt lat="2" lon="1"><ele>3.5</ele></wpt>
+<rte><name>&lt;Route&gt;</name><rtept lat="47.4" lon="8.5"></rtept><rtept lat="47.5" lon="8.6"></rtept></rte>
+<trk><trkseg><trkpt lat="47.4" lon="8.5"></trkpt><trkpt lat="47.5" lon="8.6"></trkpt></trkseg><trkseg><trkpt lat="47.4" lon="8.5"></trkpt><trkpt lat="47.5" lon="8.6"></trkpt></trkseg></trk>
+</gpx>
+"#
+        );
+    }
+
+    #[test]
+    fn test_time() {
+        let mut out = String::new();
+        write_time(&mut out, 0.0);
+        write_time(&mut out, 951_782_400.25);
+        write_time(&mut out, -1.0);
+        assert_eq!(
+            out,
+            "1970-01-01T00:00:00Z2000-02-29T00:00:00.250Z1969-12-31T23:59:59Z"
+        );
+    }
+}
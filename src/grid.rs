@@ -0,0 +1,146 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Square and hexagonal grids for binning.
+//!
+//! Like `ST_SquareGrid` and `ST_HexagonGrid`, cells are aligned to the
+//! origin, so grids of different bounding boxes with the same cell size
+//! share their cells. Only cells overlapping the interior of the bounding
+//! box are returned.
+//!
+//! ```rust
+//! use postgis::{envelope::Envelope, grid};
+//!
+//! let bbox = Envelope::new(0.5, 0.5, 2.5, 1.5);
+//! let squares = grid::square_grid(&bbox, 1.0, Some(3857));
+//! assert_eq!(squares.polygons.len(), 6);
+//! let hexagons = grid::hex_grid(&bbox, 1.0, Some(3857));
+//! assert_eq!(hexagons.polygons[0].rings[0].points.len(), 7);
+//! ```
+
+use crate::{
+    envelope::Envelope,
+    ewkb::{LineString, MultiPolygon, Point, Polygon},
+};
+
+/// Squares with side length `size` covering `bbox`, row by row.
+///
+/// Panics if `size` is not positive.
+pub fn square_grid(bbox: &Envelope, size: f64, srid: Option<i32>) -> MultiPolygon {
+    assert!(size > 0.0, "grid cell size must be positive");
+    let mut grid = MultiPolygon::new_with_srid(srid);
+    for j in (bbox.ymin / size).floor() as i64..(bbox.ymax / size).ceil() as i64 {
+        for i in (bbox.xmin / size).floor() as i64..(bbox.xmax / size).ceil() as i64 {
+            let (x, y) = (i as f64 * size, j as f64 * size);
+            let cell = Envelope::new(x, y, x + size, y + size);
+            grid.polygons.push(cell.to_polygon(srid));
+        }
+    }
+    grid
+}
+
+/// Flat-topped hexagons with edge length `size` covering `bbox`, column by
+/// column. Odd columns are shifted up by half a hexagon height.
+///
+/// Panics if `size` is not positive.
+pub fn hex_grid(bbox: &Envelope, size: f64, srid: Option<i32>) -> MultiPolygon {
+    assert!(size > 0.0, "grid cell size must be positive");
+    let dx = 1.5 * size;
+    let height = 3f64.sqrt() * size;
+    let rect = [
+        (bbox.xmin, bbox.ymin),
+        (bbox.xmax, bbox.ymin),
+        (bbox.xmax, bbox.ymax),
+        (bbox.xmin, bbox.ymax),
+    ];
+    let mut grid = MultiPolygon::new_with_srid(srid);
+    for i in ((bbox.xmin - size) / dx).floor() as i64..=((bbox.xmax + size) / dx).ceil() as i64 {
+        let cx = i as f64 * dx;
+        let offset = if i % 2 == 0 { 0.0 } else { height / 2.0 };
+        let jmin = ((bbox.ymin - offset) / height - 0.5).floor() as i64;
+        let jmax = ((bbox.ymax - offset) / height + 0.5).ceil() as i64;
+        for j in jmin..=jmax {
+            let cy = j as f64 * height + offset;
+            let mut hexagon = [(0.0, 0.0); 6];
+            for (k, vertex) in hexagon.iter_mut().enumerate() {
+                let angle = (k as f64 * 60.0).to_radians();
+                *vertex = (cx + size * angle.cos(), cy + size * angle.sin());
+            }
+            if !overlaps(&hexagon, &rect) {
+                continue;
+            }
+            let mut ring = LineString::new_with_srid(srid);
+            for &(x, y) in hexagon.iter().chain(&hexagon[..1]) {
+                ring.points.push(Point::new(x, y, srid));
+            }
+            let mut polygon = Polygon::new_with_srid(srid);
+            polygon.rings.push(ring);
+            grid.polygons.push(polygon);
+        }
+    }
+    grid
+}
+
+// Interior overlap of a flat-topped hexagon and a rectangle, using the
+// separating axis theorem with the edge normals of both polygons
+fn overlaps(hexagon: &[(f64, f64)], rect: &[(f64, f64)]) -> bool {
+    [0f64, 30.0, 90.0, 150.0].iter().all(|angle| {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let project = |points: &[(f64, f64)]| {
+            points
+                .iter()
+                .map(|&(x, y)| x * cos + y * sin)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                    (min.min(v), max.max(v))
+                })
+        };
+        let (amin, amax) = project(hexagon);
+        let (bmin, bmax) = project(rect);
+        // tolerance for the rounding of the hexagon vertices
+        let eps = 1e-9 * (amax - amin);
+        amin + eps < bmax && bmin + eps < amax
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square_grid() {
+        let grid = square_grid(&Envelope::new(-1.5, 0.0, 1.0, 0.5), 1.0, None);
+        let cells: Vec<_> = grid
+            .polygons
+            .iter()
+            .map(|p| (p.rings[0].points[0].x, p.rings[0].points[0].y))
+            .collect();
+        assert_eq!(cells, vec![(-2.0, 0.0), (-1.0, 0.0), (0.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_hex_grid() {
+        // bbox inside the hexagon at the origin
+        let grid = hex_grid(&Envelope::new(-0.5, -0.5, 0.5, 0.5), 1.0, Some(4326));
+        assert_eq!(grid.polygons.len(), 1);
+        assert_eq!(
+            grid.polygons[0].rings[0].points[0],
+            Point::new(1.0, 0.0, Some(4326))
+        );
+
+        // touching the right vertex of the hexagon at the origin only
+        let grid = hex_grid(&Envelope::new(1.0, -0.1, 1.2, 0.1), 1.0, None);
+        assert_eq!(grid.polygons.len(), 2);
+        let centers: Vec<_> = grid
+            .polygons
+            .iter()
+            .map(|p| {
+                let ring = &p.rings[0].points;
+                ((ring[0].x + ring[3].x) / 2.0, (ring[0].y + ring[3].y) / 2.0)
+            })
+            .collect();
+        let h = 3f64.sqrt();
+        assert!((centers[0].0 - 1.5).abs() < 1e-9 && (centers[0].1 + h / 2.0).abs() < 1e-9);
+        assert!((centers[1].0 - 1.5).abs() < 1e-9 && (centers[1].1 - h / 2.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,167 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Hex encoding of EWKB and TWKB.
+//!
+//! PostGIS outputs upper case hex, but some tools compare hex fixtures
+//! case-sensitively in lower case. Decoding accepts both.
+//!
+//! ```rust
+//! use postgis::ewkb::{self, AsEwkbPoint};
+//! use postgis::hex::{FromHex, HexCase, ToHex};
+//!
+//! let point = ewkb::Point::new(10.0, -20.0, Some(4326));
+//! let hex = point.as_ewkb().to_hex_with(HexCase::Lower);
+//! assert_eq!(hex, "0101000020e6100000000000000000244000000000000034c0");
+//! assert_eq!(ewkb::Point::from_hex(&hex).unwrap(), point);
+//!
+//! // SELECT ST_AsTWKB('POINT(1 2)'::geometry)
+//! let twkb = Vec::from_hex("01000204").unwrap();
+//! assert_eq!(twkb.to_hex(), "01000204");
+//! let point = postgis::twkb::Point::from_hex("01000204").unwrap();
+//! assert_eq!((point.x, point.y), (1.0, 2.0));
+//! ```
+
+use crate::{
+    error::Error,
+    ewkb::{EwkbRead, EwkbWrite},
+    twkb::{self, TwkbGeom},
+};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+/// Letter case of the hex digits A-F.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HexCase {
+    /// As in PostGIS output.
+    #[default]
+    Upper,
+    Lower,
+}
+
+impl HexCase {
+    fn digits(self) -> &'static [u8; 16] {
+        match self {
+            HexCase::Upper => b"0123456789ABCDEF",
+            HexCase::Lower => b"0123456789abcdef",
+        }
+    }
+}
+
+/// Write bytes as hex digits.
+pub(crate) fn encode<W: fmt::Write + ?Sized>(w: &mut W, buf: &[u8], case: HexCase) -> fmt::Result {
+    let digits = case.digits();
+    for &b in buf {
+        w.write_char(digits[(b >> 4) as usize] as char)?;
+        w.write_char(digits[(b & 0x0f) as usize] as char)?;
+    }
+    Ok(())
+}
+
+fn hex_digit(c: u8) -> Result<u8, Error> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        _ => Err(Error::Read(format!("invalid hex digit {:?}", c as char))),
+    }
+}
+
+/// Hex encoding of binary geometry values.
+pub trait ToHex {
+    /// Upper case hex string.
+    fn to_hex(&self) -> String {
+        self.to_hex_with(HexCase::Upper)
+    }
+
+    fn to_hex_with(&self, case: HexCase) -> String;
+}
+
+impl ToHex for [u8] {
+    fn to_hex_with(&self, case: HexCase) -> String {
+        let mut hex = String::with_capacity(2 * self.len());
+        encode(&mut hex, self, case).unwrap();
+        hex
+    }
+}
+
+impl<T: EwkbWrite> ToHex for T {
+    fn to_hex_with(&self, case: HexCase) -> String {
+        let mut hex = String::with_capacity(2 * self.ewkb_size());
+        self.write_hex_ewkb_with(&mut hex, case).unwrap();
+        hex
+    }
+}
+
+/// Decoding of hex strings in upper or lower case.
+pub trait FromHex: Sized {
+    fn from_hex(hex: &str) -> Result<Self, Error>;
+}
+
+impl FromHex for Vec<u8> {
+    fn from_hex(hex: &str) -> Result<Self, Error> {
+        let hex = hex.as_bytes();
+        if !hex.len().is_multiple_of(2) {
+            return Err(Error::Read("odd number of hex digits".into()));
+        }
+        hex.chunks(2)
+            .map(|pair| Ok(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+            .collect()
+    }
+}
+
+impl<T: EwkbRead> FromHex for T {
+    fn from_hex(hex: &str) -> Result<Self, Error> {
+        T::read_ewkb_from_slice(&Vec::from_hex(hex)?)
+    }
+}
+
+macro_rules! impl_from_hex_for_twkb {
+    ($($geotype:ident),+) => {
+        $(
+            impl FromHex for twkb::$geotype {
+                fn from_hex(hex: &str) -> Result<Self, Error> {
+                    Self::read_twkb_from_slice(&Vec::from_hex(hex)?)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_hex_for_twkb!(
+    Point,
+    LineString,
+    Polygon,
+    MultiPoint,
+    MultiLineString,
+    MultiPolygon,
+    Geometry
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{self, AsEwkbLineString};
+
+    #[test]
+    fn test_hex_case() {
+        let line = ewkb::LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+        let upper = line.as_ewkb().to_hex();
+        let lower = line.as_ewkb().to_hex_with(HexCase::Lower);
+        assert_eq!(upper, line.as_ewkb().to_hex_ewkb());
+        assert_eq!(lower, upper.to_lowercase());
+        assert_eq!(ewkb::LineString::from_hex(&lower).unwrap(), line);
+        assert_eq!(
+            Vec::from_hex(&upper).unwrap().to_hex_with(HexCase::Lower),
+            lower
+        );
+
+        // SELECT ST_AsTWKB('LINESTRING (10 -20, -0 -0.5)'::geometry, 1)
+        let twkb = twkb::LineString::from_hex("220002C8018F03C7018603").unwrap();
+        assert_eq!(twkb.points.len(), 2);
+
+        assert!(Vec::from_hex("123").is_err());
+        assert!(Vec::from_hex("0G").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! I/O traits used by the EWKB and TWKB codecs.
+//!
+//! With the `std` feature, [`Read`] and [`Write`] are the `std::io` traits.
+//! Without it, they are minimal replacements implemented for byte slices and
+//! vectors, which can be implemented for other sources and sinks.
+
+#[cfg(feature = "std")]
+pub(crate) use byteorder::{ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+pub use std::io::{Read, Write};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use self::no_std::{ReadBytesExt, WriteBytesExt};
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{Read, Write};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use crate::error::Error;
+    use alloc::vec::Vec;
+    use byteorder::ByteOrder;
+
+    /// Source of bytes.
+    pub trait Read {
+        /// Fill `buf` completely or fail.
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error>;
+    }
+
+    /// Sink for bytes.
+    pub trait Write {
+        /// Write all of `buf` or fail.
+        fn write_all(&mut self, buf: &[u8]) -> Result<(), Error>;
+    }
+
+    impl Read for &[u8] {
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+            if self.len() < buf.len() {
+                return Err(Error::Read("unexpected end of data".into()));
+            }
+            let (head, tail) = self.split_at(buf.len());
+            buf.copy_from_slice(head);
+            *self = tail;
+            Ok(())
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+            (**self).read_exact(buf)
+        }
+    }
+
+    impl Write for Vec<u8> {
+        fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
+            self.extend_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    impl Write for &mut [u8] {
+        fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
+            if self.len() < buf.len() {
+                return Err(Error::Write("failed to write whole buffer".into()));
+            }
+            let (head, tail) = core::mem::take(self).split_at_mut(buf.len());
+            head.copy_from_slice(buf);
+            *self = tail;
+            Ok(())
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
+            (**self).write_all(buf)
+        }
+    }
+
+    // Subset of the byteorder extension traits, which require std
+    pub trait ReadBytesExt: Read {
+        fn read_u8(&mut self) -> Result<u8, Error> {
+            let mut buf = [0; 1];
+            self.read_exact(&mut buf)?;
+            Ok(buf[0])
+        }
+
+        fn read_i8(&mut self) -> Result<i8, Error> {
+            Ok(self.read_u8()? as i8)
+        }
+
+        fn read_u32<B: ByteOrder>(&mut self) -> Result<u32, Error> {
+            let mut buf = [0; 4];
+            self.read_exact(&mut buf)?;
+            Ok(B::read_u32(&buf))
+        }
+
+        fn read_i32<B: ByteOrder>(&mut self) -> Result<i32, Error> {
+            let mut buf = [0; 4];
+            self.read_exact(&mut buf)?;
+            Ok(B::read_i32(&buf))
+        }
+
+        fn read_f64<B: ByteOrder>(&mut self) -> Result<f64, Error> {
+            let mut buf = [0; 8];
+            self.read_exact(&mut buf)?;
+            Ok(B::read_f64(&buf))
+        }
+    }
+
+    impl<R: Read + ?Sized> ReadBytesExt for R {}
+
+    pub trait WriteBytesExt: Write {
+        fn write_u8(&mut self, n: u8) -> Result<(), Error> {
+            self.write_all(&[n])
+        }
+
+        fn write_u32<B: ByteOrder>(&mut self, n: u32) -> Result<(), Error> {
+            let mut buf = [0; 4];
+            B::write_u32(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        fn write_i32<B: ByteOrder>(&mut self, n: i32) -> Result<(), Error> {
+            let mut buf = [0; 4];
+            B::write_i32(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+    }
+
+    impl<W: Write + ?Sized> WriteBytesExt for W {}
+}
@@ -5,8 +5,28 @@
 //! An extension to rust-postgres, adds support for PostGIS.
 //!
 //! - PostGIS type helper
-//! - GCJ02 support (used offically in Mainland China)
+//! - GCJ02 and BD09 support (used offically in Mainland China)
 //! - Tiny WKB (TWKB) support
+//! - Geohash encoding and decoding
+//! - GeoPackage binary (GPB) support
+//!
+//! Without the default `std` feature, the crate is `no_std` and provides the
+//! EWKB and TWKB codecs and geometry types using `alloc` and the minimal I/O
+//! traits in [`io`].
+//!
+//! The `ToSql` and `FromSql` implementations and the [`sql`] module need the
+//! default `postgres` feature. Disable it to use the codecs and geometry types
+//! with `std` but without the database client dependencies:
+//!
+//! ```toml
+//! postgis = { version = "0.9", default-features = false, features = ["std"] }
+//! ```
+//!
+//! With the `serde` feature, the ewkb and twkb geometries, [`coord::Coord`]
+//! and [`envelope::Envelope`] implement `Serialize` and `Deserialize`. The
+//! layout follows the struct fields (coordinates, then `srid`), enums are
+//! externally tagged by geometry type. Caches written with a serde format
+//! like bincode are read back without decoding EWKB.
 //!
 //! ```rust,no_run
 //! use postgres::{Client, NoTls};
@@ -37,11 +57,85 @@
 //!     Err(err) => { println!("Error: {}", err) }
 //! }
 //! ```
+//!
+//! Geometry arrays like the result of `array_agg` are read and written as `Vec`:
+//!
+//! ```rust,no_run
+//! # use postgres::{Client, NoTls};
+//! # use postgis::ewkb;
+//! # let mut client = Client::connect("host=localhost user=postgres", NoTls).unwrap();
+//! let row = client.query_one("SELECT array_agg(stop) FROM stops", &[]).unwrap();
+//! let stops: Vec<ewkb::Point> = row.get(0);
+//! let _ = client.execute("INSERT INTO routes (stops) VALUES ($1)", &[&stops]);
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+#[macro_use]
+mod macros;
 
+#[cfg(feature = "std")]
+pub mod algorithm;
+#[cfg(feature = "std")]
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod bulk;
+#[cfg(any(feature = "zstd", feature = "flate2"))]
+pub mod compress;
+pub mod coord;
+#[cfg(feature = "std")]
+pub mod copy;
+#[cfg(feature = "std")]
+pub mod delta;
+#[cfg(feature = "std")]
+pub mod diff;
+pub mod envelope;
 pub mod error;
+pub mod io;
 mod types;
 pub use types::{LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon};
 pub mod ewkb;
+pub mod ewkt;
+#[cfg(feature = "std")]
+pub mod fgb;
+#[cfg(feature = "test-util")]
+pub mod fixtures;
+#[cfg(feature = "gen")]
+pub mod gen;
+#[cfg(feature = "geo-traits")]
+mod geo_interop;
+#[cfg(feature = "std")]
+pub mod geohash;
+#[cfg(feature = "std")]
+pub mod geoparquet;
+#[cfg(feature = "std")]
+pub mod gpkg;
+#[cfg(feature = "gpx")]
+pub mod gpx;
+#[cfg(feature = "std")]
+pub mod grid;
+pub mod hex;
+#[cfg(feature = "std")]
 pub mod mars;
+#[cfg(feature = "mvt")]
+pub mod mvt;
+#[cfg(feature = "postgres")]
 mod postgis;
+#[cfg(feature = "std")]
+pub mod predicates;
+#[cfg(feature = "postgres")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod replication;
+#[cfg(feature = "rstar")]
+mod rtree;
+#[cfg(feature = "std")]
+pub mod shp;
+#[cfg(feature = "postgres")]
+pub mod sql;
+#[cfg(feature = "std")]
+pub mod tiles;
 pub mod twkb;
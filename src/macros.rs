@@ -0,0 +1,141 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Geometry construction macros.
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __opt_srid {
+    () => {
+        None
+    };
+    ($srid:expr) => {
+        Some($srid)
+    };
+}
+
+/// Create an [`ewkb`](crate::ewkb) point. The point type follows from the given dimensions.
+///
+/// ```rust
+/// use postgis::{ewkb, point};
+///
+/// assert_eq!(point!(x: 1.0, y: 2.0), ewkb::Point::new(1.0, 2.0, None));
+/// assert_eq!(point!(x: 1.0, y: 2.0, z: 3.0, srid: 4326), ewkb::PointZ::new(1.0, 2.0, 3.0, Some(4326)));
+/// assert_eq!(point!(x: 1.0, y: 2.0, m: 4.0), ewkb::PointM::new(1.0, 2.0, 4.0, None));
+/// assert_eq!(point!(x: 1.0, y: 2.0, z: 3.0, m: 4.0), ewkb::PointZM::new(1.0, 2.0, 3.0, 4.0, None));
+/// ```
+#[macro_export]
+macro_rules! point {
+    (x: $x:expr, y: $y:expr $(, srid: $srid:expr)? $(,)?) => {
+        $crate::ewkb::Point::new($x, $y, $crate::__opt_srid!($($srid)?))
+    };
+    (x: $x:expr, y: $y:expr, z: $z:expr $(, srid: $srid:expr)? $(,)?) => {
+        $crate::ewkb::PointZ::new($x, $y, $z, $crate::__opt_srid!($($srid)?))
+    };
+    (x: $x:expr, y: $y:expr, m: $m:expr $(, srid: $srid:expr)? $(,)?) => {
+        $crate::ewkb::PointM::new($x, $y, $m, $crate::__opt_srid!($($srid)?))
+    };
+    (x: $x:expr, y: $y:expr, z: $z:expr, m: $m:expr $(, srid: $srid:expr)? $(,)?) => {
+        $crate::ewkb::PointZM::new($x, $y, $z, $m, $crate::__opt_srid!($($srid)?))
+    };
+}
+
+/// Create an [`ewkb::LineStringT`](crate::ewkb::LineStringT) from point coordinates.
+///
+/// An optional leading `srid: <srid>;` applies to the line and all its points.
+///
+/// ```rust
+/// use postgis::{line_string, point};
+///
+/// let line = line_string![srid: 4326; (x: 0.0, y: 0.0), (x: 1.0, y: 1.0)];
+/// assert_eq!(line.srid, Some(4326));
+/// assert_eq!(line.points[1], point!(x: 1.0, y: 1.0, srid: 4326));
+/// ```
+#[macro_export]
+macro_rules! line_string {
+    (srid: $srid:expr; $(($($coord:tt)*)),* $(,)?) => {
+        $crate::ewkb::LineStringT {
+            points: vec![$($crate::point!($($coord)*, srid: $srid)),*].into_iter().collect(),
+            srid: Some($srid),
+        }
+    };
+    ($(($($coord:tt)*)),* $(,)?) => {
+        $crate::ewkb::LineStringT {
+            points: vec![$($crate::point!($($coord)*)),*].into_iter().collect(),
+            srid: None,
+        }
+    };
+}
+
+/// Create an [`ewkb::PolygonT`](crate::ewkb::PolygonT), either from the
+/// coordinates of its exterior ring or with `exterior:` and `interiors:` rings.
+/// Rings are closed automatically.
+///
+/// ```rust
+/// use postgis::polygon;
+///
+/// let triangle = polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 0.0, y: 1.0)];
+/// assert_eq!(triangle.rings[0].points.len(), 4);
+///
+/// let square = polygon!(
+///     srid: 3857;
+///     exterior: [(x: 0.0, y: 0.0), (x: 4.0, y: 0.0), (x: 4.0, y: 4.0), (x: 0.0, y: 4.0)],
+///     interiors: [[(x: 1.0, y: 1.0), (x: 1.0, y: 2.0), (x: 2.0, y: 2.0)]],
+/// );
+/// assert_eq!(square.srid, Some(3857));
+/// assert_eq!(square.rings.len(), 2);
+/// ```
+#[macro_export]
+macro_rules! polygon {
+    (srid: $srid:expr; exterior: [$($ext:tt)*] $(, interiors: [$([$($int:tt)*]),* $(,)?])? $(,)?) => {
+        $crate::builder::PolygonBuilder::new()
+            .srid($srid)
+            .exterior($crate::line_string![srid: $srid; $($ext)*].points)
+            $($(.interior($crate::line_string![srid: $srid; $($int)*].points))*)?
+            .build()
+    };
+    (exterior: [$($ext:tt)*] $(, interiors: [$([$($int:tt)*]),* $(,)?])? $(,)?) => {
+        $crate::builder::PolygonBuilder::new()
+            .exterior($crate::line_string![$($ext)*].points)
+            $($(.interior($crate::line_string![$($int)*].points))*)?
+            .build()
+    };
+    (srid: $srid:expr; $($coords:tt)*) => {
+        $crate::polygon!(srid: $srid; exterior: [$($coords)*])
+    };
+    ($($coords:tt)*) => {
+        $crate::polygon!(exterior: [$($coords)*])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ewkb::{LineStringT, PointZ};
+
+    #[test]
+    fn test_line_string() {
+        let line = line_string![(x: 0.0, y: 0.0, z: 1.0), (x: 1.0, y: 1.0, z: 2.0),];
+        assert_eq!(line.srid, None);
+        assert_eq!(line.points[1], PointZ::new(1.0, 1.0, 2.0, None));
+        let empty: LineStringT<PointZ> = line_string![];
+        assert!(empty.points.is_empty());
+    }
+
+    #[test]
+    fn test_polygon() {
+        let poly = polygon!(
+            exterior: [(x: 0.0, y: 0.0, m: 1.0), (x: 4.0, y: 0.0, m: 1.0), (x: 4.0, y: 4.0, m: 1.0)],
+            interiors: [
+                [(x: 1.0, y: 1.0, m: 2.0), (x: 1.0, y: 2.0, m: 2.0), (x: 2.0, y: 2.0, m: 2.0)],
+                [(x: 3.0, y: 3.0, m: 3.0), (x: 3.0, y: 3.5, m: 3.0), (x: 3.5, y: 3.5, m: 3.0)],
+            ],
+        );
+        assert_eq!(poly.srid, None);
+        assert_eq!(poly.rings.len(), 3);
+        assert_eq!(poly.rings[2].points[3], point!(x: 3.0, y: 3.0, m: 3.0));
+        let poly = polygon![srid: 4326; (x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 0.0, y: 1.0)];
+        assert_eq!(poly.rings[0].srid, Some(4326));
+        assert_eq!(poly.rings[0].points[0].srid, Some(4326));
+    }
+}
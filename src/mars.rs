@@ -5,9 +5,13 @@
 //  Description : WGS84 GCJ02 conversion for rust
 //  Time-stamp: <2015-06-01 10:45:55 andelf>
 
-//! Conversion between GCJ-02 and WGS-84 coordinates.
+//! Conversion between WGS-84, GCJ-02 and BD-09 (Baidu) coordinates.
 
-use crate::ewkb;
+use crate::algorithm::MapCoords;
+use crate::error::Error;
+use crate::ewkb::{self, EwkbRead, Srid};
+use crate::types as postgis;
+use std::f64::consts::PI;
 
 // https://github.com/Artoria2e5/emq/blob/master/emq/src/Algorithm/Coords/Converter.java
 struct Converter {
@@ -150,6 +154,7 @@ fn wgtochina_lb(
     wg_heit: i32,
     _wg_week: i32,
     wg_time: i32,
+    jitter: bool,
 ) -> (f64, f64) {
     let mut point: (f64, f64) = (wg_lng as f64, wg_lat as f64);
 
@@ -222,16 +227,62 @@ fn wgtochina_lb(
     x_add = transform_yj5(x_l - 105.0, y_l - 35.0);
     y_add = transform_yjy5(x_l - 105.0, y_l - 35.0);
     h_add = wg_heit as f64;
-    x_add = x_add + h_add * 0.001 + yj_sin2(wg_time as f64 * 0.0174532925199433) + me.random_yj();
-    y_add = y_add + h_add * 0.001 + yj_sin2(wg_time as f64 * 0.0174532925199433) + me.random_yj();
+    x_add += h_add * 0.001;
+    y_add += h_add * 0.001;
+    if jitter {
+        x_add += yj_sin2(wg_time as f64 * 0.0174532925199433) + me.random_yj();
+        y_add += yj_sin2(wg_time as f64 * 0.0174532925199433) + me.random_yj();
+    }
     point = (0.0, 0.0);
     point.0 = (x_l + transform_jy5(y_l, x_add)) * 3686400.0;
     point.1 = (y_l + transform_jyj5(y_l, y_add)) * 3686400.0;
     return point;
 }
 
+/// Variant of the GCJ-02 algorithm.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Gcj02Mode {
+    /// Without the pseudo-random jitter terms.
+    #[default]
+    Deterministic,
+    /// Original algorithm including the jitter terms, as used up to version 0.9.
+    Legacy,
+}
+
 // WGS84 coords to MARS
 pub fn from_wgs84(x: f64, y: f64) -> (f64, f64) {
+    from_wgs84_mode(x, y, Gcj02Mode::Deterministic)
+}
+
+// WGS84 coords to MARS
+pub fn from_wgs84_mode(x: f64, y: f64, mode: Gcj02Mode) -> (f64, f64) {
+    gcj02_offset(x, y, mode, &is_in_china)
+}
+
+/// WGS84 coords to MARS, applying the offset only where `in_region` returns
+/// true instead of using [`is_in_china`].
+///
+/// ```rust
+/// use postgis::mars;
+///
+/// // convert everything, like versions before the boundary check
+/// let (x, y) = mars::from_wgs84_with(114.17, 22.28, |_, _| true);
+/// assert!(x != 114.17 && y != 22.28);
+/// assert_eq!(mars::from_wgs84(114.17, 22.28), (114.17, 22.28));
+/// ```
+pub fn from_wgs84_with(x: f64, y: f64, in_region: impl Fn(f64, f64) -> bool) -> (f64, f64) {
+    gcj02_offset(x, y, Gcj02Mode::Deterministic, &in_region)
+}
+
+fn gcj02_offset(
+    x: f64,
+    y: f64,
+    mode: Gcj02Mode,
+    in_region: &dyn Fn(f64, f64) -> bool,
+) -> (f64, f64) {
+    if !in_region(x, y) {
+        return (x, y);
+    }
     let x1 = x * 3686400.0;
     let y1 = y * 3686400.0;
     let gps_week = 0;
@@ -245,6 +296,7 @@ pub fn from_wgs84(x: f64, y: f64) -> (f64, f64) {
         gps_height as i32,
         gps_week as i32,
         gps_week_time as i32,
+        mode == Gcj02Mode::Legacy,
     );
     let mut tempx = point.0;
     let mut tempy = point.1;
@@ -254,91 +306,189 @@ pub fn from_wgs84(x: f64, y: f64) -> (f64, f64) {
     (tempx, tempy)
 }
 
-// MARS coords to WGS84
+/// Maximum difference in degrees between `from_wgs84(to_wgs84(x, y))` and `(x, y)`.
+pub const WGS84_EPSILON: f64 = 0.000001;
+
+/// Iteration limit of `to_wgs84`. The inverse usually converges in 2 to 4 steps.
+pub const WGS84_MAX_ITERATIONS: u32 = 30;
+
+/// MARS coords to WGS84.
+///
+/// The result is accurate to [`WGS84_EPSILON`] degrees with respect to
+/// `from_wgs84`. If the inverse does not converge, the last estimate is returned.
 pub fn to_wgs84(x: f64, y: f64) -> (f64, f64) {
-    // TODO: figure out if it is in China
-    let epsilon: f64 = 0.00001;
-    fn bisection_find_vals(
-        x: f64,
-        y: f64,
-        x0: f64,
-        y0: f64,
-        x1: f64,
-        y1: f64,
-        epsilon: f64,
-    ) -> (f64, f64) {
-        let (mut x0, mut y0, mut x1, mut y1) = (x0, y0, x1, y1);
-        let (mut x_, mut y_): (f64, f64);
-
-        loop {
-            x_ = (x0 + x1) / 2.0;
-            y_ = (y0 + y1) / 2.0;
-            let (x_e, y_e) = from_wgs84(x_, y_);
-
-            // println!("x0: {}, y0: {}, x1: {}, y1: {}", x0, y0, x1, y1);
-            // println!("target => {:?}         {:?}", (x,y), (x_e, y_e));
-
-            if (x - x_e).abs() <= epsilon && (y - y_e).abs() <= epsilon {
-                break;
-            }
+    to_wgs84_mode(x, y, Gcj02Mode::Deterministic)
+}
 
-            let (x_e0, y_e0) = from_wgs84(x0, y0);
-            let (x_e1, y_e1) = from_wgs84(x1, y1);
+/// MARS coords to WGS84, inverse of `from_wgs84_mode`.
+pub fn to_wgs84_mode(x: f64, y: f64, mode: Gcj02Mode) -> (f64, f64) {
+    match invert(
+        x,
+        y,
+        (x, y),
+        WGS84_EPSILON,
+        WGS84_MAX_ITERATIONS,
+        mode,
+        &is_in_china,
+    ) {
+        Ok(wgs) | Err(wgs) => wgs,
+    }
+}
 
-            // if over some bound
-            let mut adjusted = true;
+/// MARS coords to WGS84 with a given accuracy.
+///
+/// Returns coordinates whose conversion with `from_wgs84` differs by at most
+/// `epsilon` degrees from `(x, y)`, or an error after `max_iterations` steps.
+/// `from_wgs84` works on a grid of 1/3686400 degrees, so `epsilon` should not
+/// be smaller than 0.0000003.
+pub fn to_wgs84_with(
+    x: f64,
+    y: f64,
+    epsilon: f64,
+    max_iterations: u32,
+) -> Result<(f64, f64), Error> {
+    invert(
+        x,
+        y,
+        (x, y),
+        epsilon,
+        max_iterations,
+        Gcj02Mode::Deterministic,
+        &is_in_china,
+    )
+    .map_err(|(wx, wy)| {
+        Error::Other(format!(
+            "GCJ-02 inverse of ({}, {}) did not converge in {} iterations, last estimate ({}, {})",
+            x, y, max_iterations, wx, wy
+        ))
+    })
+}
 
-            if x < x_e0 {
-                //x1 = x0;
-                x0 -= x_e0 - x; // instead of 0.5
-            } else if x > x_e1 {
-                //x0 = x1;
-                x1 += x - x_e1;
-            } else {
-                adjusted = false;
-            }
+// Re-apply the offset of the current estimate until it maps to (x, y).
+// Returns the last estimate as error if not converged.
+fn invert(
+    x: f64,
+    y: f64,
+    start: (f64, f64),
+    epsilon: f64,
+    max_iterations: u32,
+    mode: Gcj02Mode,
+    in_region: &dyn Fn(f64, f64) -> bool,
+) -> Result<(f64, f64), (f64, f64)> {
+    let (mut wx, mut wy) = start;
+    for _ in 0..max_iterations {
+        let (gx, gy) = gcj02_offset(wx, wy, mode, in_region);
+        let (dx, dy) = (gx - x, gy - y);
+        if dx.abs() <= epsilon && dy.abs() <= epsilon {
+            return Ok((wx, wy));
+        }
+        wx -= dx;
+        wy -= dy;
+    }
+    Err((wx, wy))
+}
 
-            // ----*---y_e0-------y_e----------y_e1--------*--------
-            if y < y_e0 {
-                //y1 = y0;
-                y0 -= y_e0 - y;
-            } else if y > y_e1 {
-                //y0 = y1;
-                y1 += y - y_e1;
-            } else {
-                adjusted |= false;
-            }
+/// Convert WGS84 coords to MARS in place.
+pub fn from_wgs84_slice(coords: &mut [(f64, f64)]) {
+    for c in coords.iter_mut() {
+        *c = from_wgs84(c.0, c.1);
+    }
+}
 
-            if adjusted {
-                continue;
-            }
+/// Convert MARS coords to WGS84 in place.
+///
+/// The inverse of each coordinate starts at the offset found for the
+/// previous one, which saves iterations for GPS traces.
+pub fn to_wgs84_slice(coords: &mut [(f64, f64)]) {
+    let mut inverse = to_wgs84_iter(std::iter::empty());
+    for c in coords.iter_mut() {
+        *c = inverse.convert(c.0, c.1);
+    }
+}
 
-            if x_e0 <= x && x <= x_e {
-                x1 = x_;
-            } else if x_e <= x && x <= x_e1 {
-                x0 = x_;
-            }
+pub fn from_wgs84_iter<I>(coords: I) -> impl Iterator<Item = (f64, f64)>
+where
+    I: IntoIterator<Item = (f64, f64)>,
+{
+    coords.into_iter().map(|(x, y)| from_wgs84(x, y))
+}
 
-            if y_e0 <= y && y <= y_e {
-                y1 = y_;
-            } else if y_e <= y && y <= y_e1 {
-                y0 = y_;
-            }
+/// Iterator version of [`to_wgs84_slice`].
+pub fn to_wgs84_iter<I>(coords: I) -> ToWgs84Iter<I::IntoIter>
+where
+    I: IntoIterator<Item = (f64, f64)>,
+{
+    ToWgs84Iter {
+        coords: coords.into_iter(),
+        offset: None,
+    }
+}
 
-            if x1 - x0 < epsilon * 0.1 {
-                x0 = x0 - x0 * 0.01;
-                x1 = x1 + x1 * 0.01;
-            }
-            if y1 - y0 < epsilon * 0.1 {
-                y0 = y0 - y0 * 0.01;
-                y1 = y1 + y1 * 0.01;
-            }
-        }
-        //        bisection_find_vals(x, y, x_0, y_0, x_1, y_1, epsilon)
-        (x_, y_)
+pub struct ToWgs84Iter<I> {
+    coords: I,
+    offset: Option<(f64, f64)>,
+}
+
+impl<I> ToWgs84Iter<I> {
+    fn convert(&mut self, x: f64, y: f64) -> (f64, f64) {
+        let (dx, dy) = self.offset.unwrap_or((0.0, 0.0));
+        let start = (x - dx, y - dy);
+        let wgs = match invert(
+            x,
+            y,
+            start,
+            WGS84_EPSILON,
+            WGS84_MAX_ITERATIONS,
+            Gcj02Mode::Deterministic,
+            &is_in_china,
+        ) {
+            Ok(wgs) | Err(wgs) => wgs,
+        };
+        self.offset = Some((x - wgs.0, y - wgs.1));
+        wgs
     }
+}
+
+impl<I: Iterator<Item = (f64, f64)>> Iterator for ToWgs84Iter<I> {
+    type Item = (f64, f64);
 
-    bisection_find_vals(x, y, x - 0.1, y - 0.1, x + 0.1, y + 0.1, epsilon)
+    fn next(&mut self) -> Option<(f64, f64)> {
+        let (x, y) = self.coords.next()?;
+        Some(self.convert(x, y))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.coords.size_hint()
+    }
+}
+
+const BD_X_PI: f64 = PI * 3000.0 / 180.0;
+
+// GCJ02 coords to BD09
+pub fn gcj02_to_bd09(x: f64, y: f64) -> (f64, f64) {
+    let z = (x * x + y * y).sqrt() + 0.00002 * (y * BD_X_PI).sin();
+    let theta = y.atan2(x) + 0.000003 * (x * BD_X_PI).cos();
+    (z * theta.cos() + 0.0065, z * theta.sin() + 0.006)
+}
+
+// BD09 coords to GCJ02
+pub fn bd09_to_gcj02(x: f64, y: f64) -> (f64, f64) {
+    let (x, y) = (x - 0.0065, y - 0.006);
+    let z = (x * x + y * y).sqrt() - 0.00002 * (y * BD_X_PI).sin();
+    let theta = y.atan2(x) - 0.000003 * (x * BD_X_PI).cos();
+    (z * theta.cos(), z * theta.sin())
+}
+
+// WGS84 coords to BD09
+pub fn wgs84_to_bd09(x: f64, y: f64) -> (f64, f64) {
+    let (x, y) = from_wgs84(x, y);
+    gcj02_to_bd09(x, y)
+}
+
+// BD09 coords to WGS84
+pub fn bd09_to_wgs84(x: f64, y: f64) -> (f64, f64) {
+    let (x, y) = bd09_to_gcj02(x, y);
+    to_wgs84(x, y)
 }
 
 impl ewkb::Point {
@@ -360,10 +510,304 @@ impl ewkb::Point {
     pub fn to_gcj02(&self) -> (f64, f64) {
         from_wgs84(self.x, self.y)
     }
+    /// Like `to_gcj02`, with a custom region check, see [`from_wgs84_with`].
+    pub fn to_gcj02_with(&self, in_region: impl Fn(f64, f64) -> bool) -> (f64, f64) {
+        from_wgs84_with(self.x, self.y, in_region)
+    }
+    pub fn from_bd09(x: f64, y: f64) -> ewkb::Point {
+        let (x0, y0) = bd09_to_wgs84(x, y);
+        ewkb::Point {
+            x: x0,
+            y: y0,
+            srid: Some(4326),
+        }
+    }
+    pub fn to_bd09(&self) -> (f64, f64) {
+        wgs84_to_bd09(self.x, self.y)
+    }
 }
 
+// --- China boundary
+
+// Simplified outline of mainland China and Hainan (WGS84), without Taiwan.
+// Accurate to a few ten kilometers along land borders.
+#[rustfmt::skip]
+const CHINA_BOUNDARY: &[(f64, f64)] = &[
+    (135.09, 48.45), (134.70, 48.30), (132.60, 47.70), (130.90, 47.90), (130.60, 48.90),
+    (129.50, 49.40), (127.50, 50.10), (125.80, 52.90), (123.50, 53.50), (121.90, 53.40),
+    (120.80, 53.25), (120.70, 52.50), (119.20, 50.40), (117.80, 49.50), (116.70, 49.85),
+    (115.50, 48.10), (116.00, 47.70), (117.40, 47.70), (118.50, 47.95), (119.70, 47.20),
+    (119.90, 46.70), (117.40, 46.60), (116.00, 45.70), (113.60, 44.75), (111.90, 43.70),
+    (111.00, 43.30), (110.40, 42.80), (107.30, 42.40), (105.00, 41.60), (100.80, 42.60),
+    (96.40, 42.70), (95.40, 44.30), (93.50, 45.00), (90.90, 45.30), (91.00, 46.60),
+    (90.10, 47.90), (88.20, 48.40), (87.80, 49.10), (87.35, 49.17), (86.80, 48.80),
+    (85.60, 48.20), (85.50, 47.05), (83.00, 47.20), (82.30, 45.50), (80.40, 44.30),
+    (80.20, 42.10), (79.00, 41.80), (77.80, 41.10), (76.60, 40.50), (75.60, 40.60),
+    (74.90, 40.40), (73.80, 39.80), (73.50, 39.40), (73.70, 38.50), (74.80, 38.40),
+    (74.90, 37.20), (75.70, 36.90), (76.20, 35.90), (77.80, 35.50), (78.70, 34.00),
+    (79.40, 32.50), (78.80, 31.50), (81.10, 30.10), (83.40, 29.40), (85.20, 28.50),
+    (86.90, 28.00), (88.10, 27.90), (88.80, 27.40), (89.60, 28.10), (91.60, 27.95),
+    (92.50, 27.80), (95.30, 29.00), (96.10, 29.40), (97.30, 28.25), (98.30, 27.50),
+    (98.70, 25.90), (97.70, 24.80), (97.50, 23.90), (98.90, 23.20), (99.40, 22.10),
+    (100.20, 21.40), (101.20, 21.20), (101.80, 21.20), (101.70, 22.40), (102.10, 22.40),
+    (103.90, 22.50), (105.30, 23.30), (106.70, 22.80), (106.80, 22.00), (108.00, 21.50),
+    (108.40, 19.20), (108.60, 18.20), (109.80, 18.00), (111.20, 19.40), (111.00, 21.40),
+    (112.50, 21.60), (113.60, 21.95), (114.60, 22.10), (115.50, 22.60), (116.80, 22.80),
+    (117.50, 23.30), (118.50, 24.30), (119.30, 25.00), (119.90, 25.50), (120.20, 26.50),
+    (120.70, 27.50), (121.80, 28.70), (122.50, 29.90), (122.30, 31.00), (121.50, 32.30),
+    (120.90, 33.20), (120.30, 34.40), (119.50, 35.10), (120.60, 36.10), (122.70, 37.40),
+    (121.60, 38.85), (124.20, 39.80), (125.30, 40.70), (126.90, 41.80), (128.00, 42.00),
+    (129.70, 42.45), (130.60, 42.42), (130.90, 42.90), (131.20, 44.00), (131.00, 44.90),
+    (133.10, 45.10), (134.00, 46.50), (134.70, 47.70), (135.09, 48.45),
+];
+
+// Hong Kong and Macau, which use WGS84
+#[rustfmt::skip]
+const CHINA_BOUNDARY_HOLES: &[&[(f64, f64)]] = &[
+    &[(113.83, 22.15), (113.83, 22.37), (114.01, 22.51), (114.10, 22.53), (114.24, 22.56),
+      (114.45, 22.56), (114.45, 22.15), (113.83, 22.15)],
+    &[(113.52, 22.10), (113.52, 22.22), (113.56, 22.22), (113.61, 22.13), (113.60, 22.10),
+      (113.52, 22.10)],
+];
+
+// Even-odd rule
+fn ring_contains(ring: &[(f64, f64)], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+        if (y1 > y) != (y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Whether GCJ-02 applies to WGS84 coordinates, using an embedded simplified
+/// boundary of mainland China without Hong Kong, Macau and Taiwan.
+pub fn is_in_china(x: f64, y: f64) -> bool {
+    ring_contains(CHINA_BOUNDARY, x, y)
+        && !CHINA_BOUNDARY_HOLES
+            .iter()
+            .any(|hole| ring_contains(hole, x, y))
+}
+
+macro_rules! impl_gcj02_for_geometry {
+    ($geotype:ident) => {
+        impl<P> ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + MapCoords + Srid,
+        {
+            /// Convert all vertices from WGS84 to GCJ02 and set `srid`.
+            pub fn to_gcj02(&self, srid: Option<i32>) -> Self {
+                self.to_gcj02_with(srid, is_in_china)
+            }
+
+            /// Like `to_gcj02`, with a custom region check, see [`from_wgs84_with`].
+            pub fn to_gcj02_with(
+                &self,
+                srid: Option<i32>,
+                in_region: impl Fn(f64, f64) -> bool,
+            ) -> Self {
+                let mut geom = self.map_coords(|x, y, z, m| {
+                    let (x, y) = gcj02_offset(x, y, Gcj02Mode::Deterministic, &in_region);
+                    (x, y, z, m)
+                });
+                geom.set_srid(srid);
+                geom
+            }
+
+            /// Convert all vertices from GCJ02 to WGS84 and set `srid`.
+            pub fn from_gcj02(&self, srid: Option<i32>) -> Self {
+                self.from_gcj02_with(srid, is_in_china)
+            }
+
+            /// Like `from_gcj02`, inverting `to_gcj02_with` with the same region check.
+            pub fn from_gcj02_with(
+                &self,
+                srid: Option<i32>,
+                in_region: impl Fn(f64, f64) -> bool,
+            ) -> Self {
+                let mut geom = self.map_coords(|x, y, z, m| {
+                    let (x, y) = match invert(
+                        x,
+                        y,
+                        (x, y),
+                        WGS84_EPSILON,
+                        WGS84_MAX_ITERATIONS,
+                        Gcj02Mode::Deterministic,
+                        &in_region,
+                    ) {
+                        Ok(wgs) | Err(wgs) => wgs,
+                    };
+                    (x, y, z, m)
+                });
+                geom.set_srid(srid);
+                geom
+            }
+        }
+    };
+}
+
+impl_gcj02_for_geometry!(LineStringT);
+impl_gcj02_for_geometry!(PolygonT);
+impl_gcj02_for_geometry!(MultiPointT);
+impl_gcj02_for_geometry!(MultiLineStringT);
+impl_gcj02_for_geometry!(MultiPolygonT);
+impl_gcj02_for_geometry!(GeometryCollectionT);
+impl_gcj02_for_geometry!(GeometryT);
+
 #[test]
 fn test_mars_to_wgs84() {
     let (x, y) = to_wgs84(116.501419, 39.99844);
-    println!("x = {} y = {}", x, y);
+    let (gx, gy) = from_wgs84(x, y);
+    assert!((gx - 116.501419).abs() <= WGS84_EPSILON && (gy - 39.99844).abs() <= WGS84_EPSILON);
+
+    // the former bisection did not terminate for this point
+    let (x, y) = to_wgs84_mode(116.5564582453276, 40.02639852842064, Gcj02Mode::Legacy);
+    assert!((x - 116.5506).abs() < 0.000001 && (y - 40.0253).abs() < 0.000001);
+    let (gx, gy) = from_wgs84(116.5506, 40.0253);
+    let (x, y) = to_wgs84_with(gx, gy, 0.0000005, 10).unwrap();
+    assert!((x - 116.5506).abs() < 0.000001 && (y - 40.0253).abs() < 0.000001);
+
+    assert!(to_wgs84_with(116.501419, 39.99844, 0.0, 5).is_err());
+    // outside of China
+    assert_eq!(to_wgs84(8.55, 47.37), (8.55, 47.37));
+}
+
+#[test]
+fn test_bd09() {
+    let (x, y) = gcj02_to_bd09(116.404, 39.915);
+    assert!((x - 116.410369).abs() < 1e-6 && (y - 39.921337).abs() < 1e-6);
+    let (x, y) = bd09_to_gcj02(x, y);
+    assert!((x - 116.404).abs() < 1e-6 && (y - 39.915).abs() < 1e-6);
+
+    let point = ewkb::Point::new_wgs84(116.397, 39.909);
+    let (x, y) = point.to_bd09();
+    let back = ewkb::Point::from_bd09(x, y);
+    assert!((back.x - point.x).abs() < 1e-4 && (back.y - point.y).abs() < 1e-4);
+}
+
+#[test]
+fn test_mars_slice() {
+    let trace: Vec<(f64, f64)> = (0..50)
+        .map(|i| (116.3 + i as f64 * 0.002, 39.9 + i as f64 * 0.001))
+        .collect();
+    let mut coords = trace.clone();
+    from_wgs84_slice(&mut coords);
+    assert_eq!(coords[7], from_wgs84(trace[7].0, trace[7].1));
+    to_wgs84_slice(&mut coords);
+    for (c, orig) in coords.iter().zip(&trace) {
+        assert!((c.0 - orig.0).abs() < 1e-4 && (c.1 - orig.1).abs() < 1e-4);
+    }
+    let back: Vec<_> = to_wgs84_iter(from_wgs84_iter(trace.clone())).collect();
+    assert_eq!(back, coords);
+}
+
+#[test]
+fn test_geometry_gcj02() {
+    let mut line = ewkb::LineString::from(vec![(116.30, 39.90), (116.31, 39.91)]);
+    line.srid = Some(4326);
+    let gcj = line.to_gcj02(None);
+    assert_eq!(gcj.srid, None);
+    assert_eq!(gcj.points[1].srid, None);
+    assert_eq!(
+        (gcj.points[1].x, gcj.points[1].y),
+        from_wgs84(116.31, 39.91)
+    );
+
+    let geom = ewkb::GeometryT::MultiLineString(ewkb::MultiLineString {
+        lines: vec![gcj],
+        srid: None,
+    });
+    match geom.from_gcj02(Some(4326)) {
+        ewkb::GeometryT::MultiLineString(multi) => {
+            assert_eq!(multi.srid, Some(4326));
+            let p = multi.lines[0].points[1];
+            assert_eq!(p.srid, Some(4326));
+            assert!((p.x - 116.31).abs() < 1e-4 && (p.y - 39.91).abs() < 1e-4);
+        }
+        _ => panic!("expected MultiLineString"),
+    }
+}
+
+#[test]
+fn test_gcj02_mode() {
+    let p = from_wgs84(116.397, 39.909);
+    assert_eq!(p, from_wgs84(116.397, 39.909));
+    assert_eq!(p, from_wgs84_mode(116.397, 39.909, Gcj02Mode::default()));
+    // the jitter terms shift coordinates by less than a meter
+    let legacy = from_wgs84_mode(116.397, 39.909, Gcj02Mode::Legacy);
+    assert!(legacy != p);
+    assert!((legacy.0 - p.0).abs() < 0.00001 && (legacy.1 - p.1).abs() < 0.00001);
+}
+
+#[test]
+fn test_is_in_china() {
+    let inside = [
+        (116.40, 39.90), // Beijing
+        (121.47, 31.23), // Shanghai
+        (114.06, 22.54), // Shenzhen
+        (113.26, 23.13), // Guangzhou
+        (110.35, 20.02), // Haikou
+        (91.11, 29.65),  // Lhasa
+        (87.62, 43.82),  // Urumqi
+        (75.99, 39.47),  // Kashgar
+        (126.63, 45.75), // Harbin
+        (102.71, 25.04), // Kunming
+        (111.98, 43.65), // Erenhot
+        (114.12, 22.55), // Shenzhen Luohu
+    ];
+    let outside = [
+        (121.56, 25.04), // Taipei
+        (120.30, 22.63), // Kaohsiung
+        (114.17, 22.28), // Hong Kong
+        (113.54, 22.19), // Macau
+        (106.91, 47.92), // Ulaanbaatar
+        (76.95, 43.24),  // Almaty
+        (85.32, 27.71),  // Kathmandu
+        (105.85, 21.03), // Hanoi
+        (125.75, 39.03), // Pyongyang
+        (131.89, 43.12), // Vladivostok
+        (111.90, 43.72), // Zamyn-Uud, across the border from Erenhot
+        (114.17, 22.53), // Sheung Shui, across the river from Shenzhen
+    ];
+    for &(x, y) in inside.iter() {
+        assert!(is_in_china(x, y), "{} {}", x, y);
+    }
+    for &(x, y) in outside.iter() {
+        assert!(!is_in_china(x, y), "{} {}", x, y);
+        assert_eq!(from_wgs84(x, y), (x, y));
+    }
+}
+
+#[test]
+fn test_region_check() {
+    // Hong Kong is outside of the default region
+    let hk = (114.17, 22.28);
+    assert_eq!(from_wgs84(hk.0, hk.1), hk);
+    let everywhere = |_: f64, _: f64| true;
+    let gcj = from_wgs84_with(hk.0, hk.1, everywhere);
+    assert!(gcj != hk);
+    assert_eq!(
+        ewkb::Point::new_wgs84(hk.0, hk.1).to_gcj02_with(everywhere),
+        gcj
+    );
+    assert_eq!(
+        from_wgs84_with(116.40, 39.90, is_in_china),
+        from_wgs84(116.40, 39.90)
+    );
+    assert_eq!(
+        from_wgs84_with(116.40, 39.90, |_, _| false),
+        (116.40, 39.90)
+    );
+
+    let line = ewkb::LineString::from(vec![hk, (116.40, 39.90)]);
+    let gcj_line = line.to_gcj02_with(Some(4326), everywhere);
+    assert_eq!((gcj_line.points[0].x, gcj_line.points[0].y), gcj);
+    let default_line = line.to_gcj02(Some(4326));
+    assert_eq!((default_line.points[0].x, default_line.points[0].y), hk);
+    let back = gcj_line.from_gcj02_with(Some(4326), everywhere);
+    for (p, orig) in back.points.iter().zip(line.points.iter()) {
+        assert!((p.x - orig.x).abs() < 1e-5 && (p.y - orig.y).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,549 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Encode geometries as [Mapbox Vector Tile](https://github.com/mapbox/vector-tile-spec)
+//! geometry command sequences.
+//!
+//! Geometries are transformed into the tile coordinate space, clipped to the
+//! (buffered) tile extent and quantized to integer coordinates. The result can
+//! be written into the `geometry` field of a vector tile feature.
+//!
+//! ```rust
+//! use postgis::{ewkb, mvt::{AsMvt, GeomType, TileTransform}, tiles::Tile};
+//!
+//! let tile = TileTransform::new(Tile::new(0, 0, 0).bounds(), 4096);
+//! let point = ewkb::Point::new(0.0, 0.0, Some(3857));
+//! let geom = point.to_mvt(&tile).unwrap();
+//! assert_eq!(geom.geom_type, GeomType::Point);
+//! assert_eq!(geom.commands, vec![9, 4096, 4096]);
+//! ```
+
+use crate::{
+    algorithm,
+    coord::Coord,
+    envelope::Envelope,
+    ewkb, twkb,
+    types::{self as postgis, LineString},
+};
+
+const CMD_MOVE_TO: u32 = 1;
+const CMD_LINE_TO: u32 = 2;
+const CMD_CLOSE_PATH: u32 = 7;
+
+/// MVT feature geometry type.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GeomType {
+    Point = 1,
+    LineString = 2,
+    Polygon = 3,
+}
+
+/// Encoded feature geometry.
+#[derive(PartialEq, Clone, Debug)]
+pub struct MvtGeometry {
+    pub geom_type: GeomType,
+    pub commands: Vec<u32>,
+}
+
+/// Mapping from map coordinates into tile coordinates.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct TileTransform {
+    /// Tile bounds in the coordinate system of the geometries.
+    pub bounds: Envelope,
+    /// Tile extent in tile coordinates (usually 4096).
+    pub extent: u32,
+    /// Buffer around the tile in tile coordinates kept when clipping.
+    pub buffer: u32,
+}
+
+impl TileTransform {
+    pub fn new(bounds: Envelope, extent: u32) -> Self {
+        TileTransform {
+            bounds,
+            extent,
+            buffer: 0,
+        }
+    }
+
+    pub fn with_buffer(mut self, buffer: u32) -> Self {
+        self.buffer = buffer;
+        self
+    }
+
+    fn project<P: postgis::Point>(&self, p: &P) -> (f64, f64) {
+        let extent = self.extent as f64;
+        (
+            (p.x() - self.bounds.xmin) / self.bounds.width() * extent,
+            (self.bounds.ymax - p.y()) / self.bounds.height() * extent,
+        )
+    }
+
+    fn clip_box(&self) -> (f64, f64) {
+        (
+            -(self.buffer as f64),
+            self.extent as f64 + self.buffer as f64,
+        )
+    }
+}
+
+/// Conversion into MVT geometry commands.
+pub trait AsMvt {
+    /// Encode the geometry for the given tile. Returns `None` if nothing is left
+    /// after clipping and quantization.
+    fn to_mvt(&self, tile: &TileTransform) -> Option<MvtGeometry>;
+}
+
+// --- command encoding
+
+fn command(id: u32, count: u32) -> u32 {
+    (id & 0x7) | (count << 3)
+}
+
+fn zig_zag(n: i32) -> u32 {
+    ((n << 1) ^ (n >> 31)) as u32
+}
+
+struct Encoder {
+    commands: Vec<u32>,
+    cursor: (i32, i32),
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Encoder {
+            commands: Vec::new(),
+            cursor: (0, 0),
+        }
+    }
+
+    fn push_point(&mut self, p: (i32, i32)) {
+        self.commands.push(zig_zag(p.0 - self.cursor.0));
+        self.commands.push(zig_zag(p.1 - self.cursor.1));
+        self.cursor = p;
+    }
+
+    fn points(&mut self, points: &[(i32, i32)]) {
+        self.commands
+            .push(command(CMD_MOVE_TO, points.len() as u32));
+        for &p in points {
+            self.push_point(p);
+        }
+    }
+
+    fn line(&mut self, line: &[(i32, i32)]) {
+        self.commands.push(command(CMD_MOVE_TO, 1));
+        self.push_point(line[0]);
+        self.commands
+            .push(command(CMD_LINE_TO, line.len() as u32 - 1));
+        for &p in &line[1..] {
+            self.push_point(p);
+        }
+    }
+
+    fn ring(&mut self, ring: &[(i32, i32)]) {
+        self.line(ring);
+        self.commands.push(command(CMD_CLOSE_PATH, 1));
+    }
+
+    fn finish(self, geom_type: GeomType) -> Option<MvtGeometry> {
+        if self.commands.is_empty() {
+            None
+        } else {
+            Some(MvtGeometry {
+                geom_type,
+                commands: self.commands,
+            })
+        }
+    }
+}
+
+fn quantize(points: &[(f64, f64)]) -> Vec<(i32, i32)> {
+    let mut quantized: Vec<(i32, i32)> = points
+        .iter()
+        .map(|&(x, y)| (x.round() as i32, y.round() as i32))
+        .collect();
+    quantized.dedup();
+    quantized
+}
+
+// --- clipping
+
+fn clip_box(min: f64, max: f64) -> Envelope {
+    Envelope::new(min, min, max, max)
+}
+
+fn to_coords(points: &[(f64, f64)]) -> Vec<Coord> {
+    points.iter().map(|&(x, y)| Coord::new(x, y)).collect()
+}
+
+fn from_coords(coords: &[Coord]) -> Vec<(f64, f64)> {
+    coords.iter().map(|c| (c.x, c.y)).collect()
+}
+
+/// Clip a polyline, returning the pieces inside the clip box.
+fn clip_line(points: &[(f64, f64)], min: f64, max: f64) -> Vec<Vec<(f64, f64)>> {
+    algorithm::clip_line(&to_coords(points), &clip_box(min, max))
+        .iter()
+        .map(|part| from_coords(part))
+        .collect()
+}
+
+/// Sutherland-Hodgman clipping of a closed ring. The result is not closed.
+fn clip_ring(points: &[(f64, f64)], min: f64, max: f64) -> Vec<(f64, f64)> {
+    from_coords(&algorithm::clip_ring(
+        &to_coords(points),
+        &clip_box(min, max),
+    ))
+}
+
+/// Twice the signed area in tile coordinates. Positive for clockwise rings
+/// in screen orientation (y down).
+fn signed_area(ring: &[(i32, i32)]) -> i64 {
+    let mut area = 0i64;
+    for i in 0..ring.len() {
+        let (x0, y0) = ring[i];
+        let (x1, y1) = ring[(i + 1) % ring.len()];
+        area += x0 as i64 * y1 as i64 - x1 as i64 * y0 as i64;
+    }
+    area
+}
+
+// --- geometry encoding
+
+fn project_points<'a, P, I>(tile: &TileTransform, points: I) -> Vec<(f64, f64)>
+where
+    P: 'a + postgis::Point,
+    I: Iterator<Item = &'a P>,
+{
+    points.map(|p| tile.project(p)).collect()
+}
+
+fn encode_points<'a, P, I>(encoder: &mut Encoder, tile: &TileTransform, points: I)
+where
+    P: 'a + postgis::Point,
+    I: Iterator<Item = &'a P>,
+{
+    let (min, max) = tile.clip_box();
+    let inside: Vec<(f64, f64)> = project_points(tile, points)
+        .into_iter()
+        .filter(|&(x, y)| x >= min && x <= max && y >= min && y <= max)
+        .collect();
+    let quantized: Vec<(i32, i32)> = inside
+        .iter()
+        .map(|&(x, y)| (x.round() as i32, y.round() as i32))
+        .collect();
+    if !quantized.is_empty() {
+        encoder.points(&quantized);
+    }
+}
+
+fn encode_line<'a, L>(encoder: &mut Encoder, tile: &TileTransform, line: &'a L)
+where
+    L: postgis::LineString<'a>,
+{
+    let (min, max) = tile.clip_box();
+    for part in clip_line(&project_points(tile, line.points()), min, max) {
+        let part = quantize(&part);
+        if part.len() > 1 {
+            encoder.line(&part);
+        }
+    }
+}
+
+fn encode_polygon<'a, Y>(encoder: &mut Encoder, tile: &TileTransform, polygon: &'a Y)
+where
+    Y: postgis::Polygon<'a>,
+{
+    let (min, max) = tile.clip_box();
+    for (i, ring) in polygon.rings().enumerate() {
+        let mut ring = quantize(&clip_ring(&project_points(tile, ring.points()), min, max));
+        if ring.len() > 1 && ring.first() == ring.last() {
+            ring.pop();
+        }
+        let area = signed_area(&ring);
+        if ring.len() < 3 || area == 0 {
+            if i == 0 {
+                // exterior ring vanished
+                return;
+            }
+            continue;
+        }
+        // exterior rings clockwise, interior rings counter-clockwise
+        if (i == 0) != (area > 0) {
+            ring.reverse();
+        }
+        encoder.ring(&ring);
+    }
+}
+
+macro_rules! impl_mvt_for_point {
+    ($ptype:path) => {
+        impl AsMvt for $ptype {
+            fn to_mvt(&self, tile: &TileTransform) -> Option<MvtGeometry> {
+                let mut encoder = Encoder::new();
+                encode_points(&mut encoder, tile, std::iter::once(self));
+                encoder.finish(GeomType::Point)
+            }
+        }
+    };
+}
+
+impl_mvt_for_point!(ewkb::Point);
+impl_mvt_for_point!(ewkb::PointZ);
+impl_mvt_for_point!(ewkb::PointM);
+impl_mvt_for_point!(ewkb::PointZM);
+impl_mvt_for_point!(twkb::Point);
+
+fn encode_geometry<P>(
+    encoder: &mut Encoder,
+    tile: &TileTransform,
+    geom: &ewkb::GeometryT<P>,
+) -> GeomType
+where
+    P: postgis::Point + ewkb::EwkbRead,
+{
+    use crate::ewkb::GeometryT as G;
+    match *geom {
+        G::Point(ref p) => {
+            encode_points(encoder, tile, std::iter::once(p));
+            GeomType::Point
+        }
+        G::MultiPoint(ref mp) => {
+            encode_multipoint(encoder, tile, mp);
+            GeomType::Point
+        }
+        G::LineString(ref l) => {
+            encode_line(encoder, tile, l);
+            GeomType::LineString
+        }
+        G::MultiLineString(ref ml) => {
+            encode_multiline(encoder, tile, ml);
+            GeomType::LineString
+        }
+        G::Polygon(ref y) => {
+            encode_polygon(encoder, tile, y);
+            GeomType::Polygon
+        }
+        G::MultiPolygon(ref my) => {
+            encode_multipolygon(encoder, tile, my);
+            GeomType::Polygon
+        }
+        G::GeometryCollection(ref gc) => encode_collection(encoder, tile, gc),
+    }
+}
+
+/// MVT features have a single type: members with another type than the first
+/// encoded member are skipped.
+fn encode_collection<P>(
+    encoder: &mut Encoder,
+    tile: &TileTransform,
+    gc: &ewkb::GeometryCollectionT<P>,
+) -> GeomType
+where
+    P: postgis::Point + ewkb::EwkbRead,
+{
+    let mut geom_type = None;
+    for g in gc.geometries.iter() {
+        let mut member = Encoder::new();
+        member.cursor = encoder.cursor;
+        let t = encode_geometry(&mut member, tile, g);
+        if member.commands.is_empty() {
+            continue;
+        }
+        if *geom_type.get_or_insert(t) == t {
+            encoder.cursor = member.cursor;
+            encoder.commands.extend(member.commands);
+        }
+    }
+    geom_type.unwrap_or(GeomType::Point)
+}
+
+macro_rules! impl_mvt_for_geom {
+    ($geotype:ty, $gtype:expr, $encode:ident) => {
+        impl AsMvt for $geotype {
+            fn to_mvt(&self, tile: &TileTransform) -> Option<MvtGeometry> {
+                let mut encoder = Encoder::new();
+                $encode(&mut encoder, tile, self);
+                encoder.finish($gtype)
+            }
+        }
+    };
+    (generic $geotype:ident, $gtype:expr, $encode:ident) => {
+        impl<P> AsMvt for ewkb::$geotype<P>
+        where
+            P: postgis::Point + ewkb::EwkbRead,
+        {
+            fn to_mvt(&self, tile: &TileTransform) -> Option<MvtGeometry> {
+                let mut encoder = Encoder::new();
+                $encode(&mut encoder, tile, self);
+                encoder.finish($gtype)
+            }
+        }
+    };
+}
+
+fn encode_multipoint<'a, T: postgis::MultiPoint<'a>>(
+    e: &mut Encoder,
+    tile: &TileTransform,
+    g: &'a T,
+) {
+    encode_points(e, tile, g.points());
+}
+
+fn encode_multiline<'a, T: postgis::MultiLineString<'a>>(
+    e: &mut Encoder,
+    tile: &TileTransform,
+    g: &'a T,
+) {
+    for l in g.lines() {
+        encode_line(e, tile, l);
+    }
+}
+
+fn encode_multipolygon<'a, T: postgis::MultiPolygon<'a>>(
+    e: &mut Encoder,
+    tile: &TileTransform,
+    g: &'a T,
+) {
+    for y in g.polygons() {
+        encode_polygon(e, tile, y);
+    }
+}
+
+impl_mvt_for_geom!(generic LineStringT, GeomType::LineString, encode_line);
+impl_mvt_for_geom!(generic PolygonT, GeomType::Polygon, encode_polygon);
+impl_mvt_for_geom!(generic MultiPointT, GeomType::Point, encode_multipoint);
+impl_mvt_for_geom!(generic MultiLineStringT, GeomType::LineString, encode_multiline);
+impl_mvt_for_geom!(generic MultiPolygonT, GeomType::Polygon, encode_multipolygon);
+impl_mvt_for_geom!(twkb::LineString, GeomType::LineString, encode_line);
+impl_mvt_for_geom!(twkb::Polygon, GeomType::Polygon, encode_polygon);
+impl_mvt_for_geom!(twkb::MultiPoint, GeomType::Point, encode_multipoint);
+impl_mvt_for_geom!(
+    twkb::MultiLineString,
+    GeomType::LineString,
+    encode_multiline
+);
+impl_mvt_for_geom!(twkb::MultiPolygon, GeomType::Polygon, encode_multipolygon);
+
+impl<P> AsMvt for ewkb::GeometryT<P>
+where
+    P: postgis::Point + ewkb::EwkbRead,
+{
+    fn to_mvt(&self, tile: &TileTransform) -> Option<MvtGeometry> {
+        let mut encoder = Encoder::new();
+        let geom_type = encode_geometry(&mut encoder, tile, self);
+        encoder.finish(geom_type)
+    }
+}
+
+impl<P> AsMvt for ewkb::GeometryCollectionT<P>
+where
+    P: postgis::Point + ewkb::EwkbRead,
+{
+    fn to_mvt(&self, tile: &TileTransform) -> Option<MvtGeometry> {
+        let mut encoder = Encoder::new();
+        let geom_type = encode_collection(&mut encoder, tile, self);
+        encoder.finish(geom_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile() -> TileTransform {
+        TileTransform::new(Envelope::new(0.0, 0.0, 4096.0, 4096.0), 4096)
+    }
+
+    #[test]
+    fn test_zig_zag() {
+        assert_eq!(zig_zag(0), 0);
+        assert_eq!(zig_zag(-1), 1);
+        assert_eq!(zig_zag(1), 2);
+        assert_eq!(zig_zag(-2), 3);
+    }
+
+    #[test]
+    fn test_encode_point() {
+        // spec example: POINT(25 17)
+        let p = ewkb::Point::new(25.0, 4096.0 - 17.0, None);
+        let geom = p.to_mvt(&tile()).unwrap();
+        assert_eq!(geom.commands, vec![9, 50, 34]);
+        let outside = ewkb::Point::new(-10.0, 10.0, None);
+        assert_eq!(outside.to_mvt(&tile()), None);
+    }
+
+    #[test]
+    fn test_encode_line() {
+        // spec example: LINESTRING(2 2, 2 10, 10 10)
+        let p = |x, y| ewkb::Point::new(x, 4096.0 - y, None);
+        let line = ewkb::LineString {
+            points: vec![p(2., 2.), p(2., 10.), p(10., 10.)],
+            srid: None,
+        };
+        let geom = line.to_mvt(&tile()).unwrap();
+        assert_eq!(geom.geom_type, GeomType::LineString);
+        assert_eq!(geom.commands, vec![9, 4, 4, 18, 0, 16, 16, 0]);
+    }
+
+    #[test]
+    fn test_encode_polygon() {
+        // spec example: POLYGON((3 6, 8 12, 20 34, 3 6))
+        let p = |x, y| ewkb::Point::new(x, 4096.0 - y, None);
+        let ring = ewkb::LineString {
+            points: vec![p(3., 6.), p(8., 12.), p(20., 34.), p(3., 6.)],
+            srid: None,
+        };
+        let poly = ewkb::Polygon {
+            rings: vec![ring],
+            srid: None,
+        };
+        let geom = poly.to_mvt(&tile()).unwrap();
+        assert_eq!(geom.geom_type, GeomType::Polygon);
+        assert_eq!(geom.commands, vec![9, 6, 12, 18, 10, 12, 24, 44, 15]);
+    }
+
+    #[test]
+    fn test_clipping() {
+        let p = |x, y| ewkb::Point::new(x, y, None);
+        let line = ewkb::LineString {
+            points: vec![p(-100., 100.), p(100., 100.), p(100., -100.)],
+            srid: None,
+        };
+        let geom = line.to_mvt(&tile()).unwrap();
+        // MoveTo(0, 3996) LineTo(100, 3996), (100, 4096)
+        assert_eq!(geom.commands, vec![9, 0, 7992, 18, 200, 0, 0, 200]);
+
+        let ring = ewkb::LineString {
+            points: vec![
+                p(-100., -100.),
+                p(100., -100.),
+                p(100., 100.),
+                p(-100., 100.),
+                p(-100., -100.),
+            ],
+            srid: None,
+        };
+        let poly = ewkb::Polygon {
+            rings: vec![ring],
+            srid: None,
+        };
+        let geom = poly.to_mvt(&tile()).unwrap();
+        assert_eq!(geom.commands.len(), 1 + 2 + 1 + 2 * 3 + 1);
+
+        let far = ewkb::Polygon {
+            rings: vec![ewkb::LineString {
+                points: vec![
+                    p(-300., -300.),
+                    p(-200., -300.),
+                    p(-200., -200.),
+                    p(-300., -300.),
+                ],
+                srid: None,
+            }],
+            srid: None,
+        };
+        assert_eq!(far.to_mvt(&tile()), None);
+    }
+}
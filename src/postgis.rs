@@ -7,27 +7,94 @@ use crate::{
         self, AsEwkbGeometry, AsEwkbGeometryCollection, AsEwkbLineString, AsEwkbMultiLineString,
         AsEwkbMultiPoint, AsEwkbMultiPolygon, AsEwkbPoint, AsEwkbPolygon, EwkbRead, EwkbWrite,
     },
+    sql,
     twkb::{self, TwkbGeom},
     types::{LineString, Point, Polygon},
 };
 use bytes::{BufMut, BytesMut};
 use postgres_types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
 use std::error::Error;
-use std::io::Cursor;
+
+// Error for failed conversions, describing the geometry found in `raw`.
+// `expected` holds the accepted geometry type codes, empty for any type.
+fn conversion_error<T: EwkbRead>(
+    ty: &Type,
+    target: &str,
+    expected: &[u32],
+    raw: &[u8],
+    err: crate::error::Error,
+) -> Box<dyn Error + Sync + Send> {
+    let found = match ewkb::EwkbSlice::new(raw) {
+        Ok(found) => found,
+        Err(_) => return format!("cannot convert {} to {}: invalid EWKB", ty, target).into(),
+    };
+    let srid = found
+        .srid()
+        .map_or_else(|| "none".to_string(), |srid| srid.to_string());
+    let expected_desc = if expected.is_empty() {
+        format!("any geometry{}", dims_name(T::point_type()))
+    } else {
+        expected
+            .iter()
+            .map(|code| {
+                format!(
+                    "{}{}",
+                    geometry_type_name(*code),
+                    dims_name(T::point_type())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" or ")
+    };
+    let mut msg = format!(
+        "cannot convert {} to {}: expected {}, found {}{} (WKB type {}, SRID {})",
+        ty,
+        target,
+        expected_desc,
+        geometry_type_name(found.geometry_type()),
+        dims_name(found.point_type()),
+        found.geometry_type(),
+        srid
+    );
+    if expected.is_empty() || expected.contains(&found.geometry_type()) {
+        msg = format!("{}: {}", msg, err);
+    }
+    msg.into()
+}
+
+fn geometry_type_name(code: u32) -> &'static str {
+    match code {
+        1 => "Point",
+        2 => "LineString",
+        3 => "Polygon",
+        4 => "MultiPoint",
+        5 => "MultiLineString",
+        6 => "MultiPolygon",
+        7 => "GeometryCollection",
+        _ => "unknown geometry",
+    }
+}
+
+fn dims_name(point_type: ewkb::PointType) -> &'static str {
+    match point_type {
+        ewkb::PointType::Point => "",
+        ewkb::PointType::PointZ => " Z",
+        ewkb::PointType::PointM => " M",
+        ewkb::PointType::PointZM => " ZM",
+    }
+}
 
 macro_rules! accepts_geography {
     () => {
         fn accepts(ty: &Type) -> bool {
-            match ty.name() {
-                "geography" | "geometry" => true,
-                _ => false,
-            }
+            sql::is_geometry(ty) || sql::is_geography(ty)
         }
     };
 }
 
 impl<'a> ToSql for ewkb::EwkbPoint<'a> {
     fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.reserve(self.ewkb_size());
         self.write_ewkb(&mut out.writer())?;
         Ok(IsNull::No)
     }
@@ -40,9 +107,8 @@ macro_rules! impl_sql_for_point_type {
     ($ptype:ident) => {
         impl<'a> FromSql<'a> for ewkb::$ptype {
             fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-                let mut rdr = Cursor::new(raw);
-                ewkb::$ptype::read_ewkb(&mut rdr)
-                    .map_err(|_| format!("cannot convert {} to {}", ty, stringify!($ptype)).into())
+                ewkb::$ptype::read_ewkb_exact(raw)
+                    .map_err(|e| conversion_error::<Self>(ty, stringify!($ptype), &[1], raw, e))
             }
 
             accepts_geography!();
@@ -54,7 +120,9 @@ macro_rules! impl_sql_for_point_type {
                 _: &Type,
                 out: &mut BytesMut,
             ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
-                self.as_ewkb().write_ewkb(&mut out.writer())?;
+                let ewkb = self.as_ewkb();
+                out.reserve(ewkb.ewkb_size());
+                ewkb.write_ewkb(&mut out.writer())?;
                 Ok(IsNull::No)
             }
 
@@ -70,16 +138,14 @@ impl_sql_for_point_type!(PointM);
 impl_sql_for_point_type!(PointZM);
 
 macro_rules! impl_sql_for_geom_type {
-    ($geotype:ident) => {
+    ($geotype:ident, $code:expr) => {
         impl<'a, T> FromSql<'a> for ewkb::$geotype<T>
         where
             T: 'a + Point + EwkbRead,
         {
             fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-                let mut rdr = Cursor::new(raw);
-                ewkb::$geotype::<T>::read_ewkb(&mut rdr).map_err(|_| {
-                    format!("cannot convert {} to {}", ty, stringify!($geotype)).into()
-                })
+                ewkb::$geotype::<T>::read_ewkb_exact(raw)
+                    .map_err(|e| conversion_error::<T>(ty, stringify!($geotype), &[$code], raw, e))
             }
 
             accepts_geography!();
@@ -94,7 +160,9 @@ macro_rules! impl_sql_for_geom_type {
                 _: &Type,
                 out: &mut BytesMut,
             ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
-                self.as_ewkb().write_ewkb(&mut out.writer())?;
+                let ewkb = self.as_ewkb();
+                out.reserve(ewkb.ewkb_size());
+                ewkb.write_ewkb(&mut out.writer())?;
                 Ok(IsNull::No)
             }
 
@@ -104,11 +172,37 @@ macro_rules! impl_sql_for_geom_type {
     };
 }
 
-impl_sql_for_geom_type!(LineStringT);
-impl_sql_for_geom_type!(PolygonT);
-impl_sql_for_geom_type!(MultiPointT);
-impl_sql_for_geom_type!(MultiLineStringT);
-impl_sql_for_geom_type!(MultiPolygonT);
+impl_sql_for_geom_type!(LineStringT, 2);
+impl_sql_for_geom_type!(PolygonT, 3);
+impl_sql_for_geom_type!(MultiPointT, 4);
+impl_sql_for_geom_type!(MultiLineStringT, 5);
+impl_sql_for_geom_type!(MultiPolygonT, 6);
+#[cfg(feature = "smallvec")]
+impl_sql_for_geom_type!(SmallLineStringT, 2);
+#[cfg(feature = "smallvec")]
+impl_sql_for_geom_type!(SmallPolygonT, 3);
+
+macro_rules! impl_from_sql_for_promoted {
+    ($geotype:ident, $code:expr) => {
+        impl<'a, T> FromSql<'a> for ewkb::Promoted<ewkb::$geotype<T>>
+        where
+            T: 'a + Point + EwkbRead + ewkb::Srid,
+        {
+            fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+                Self::read_ewkb_exact(raw).map_err(|e| {
+                    let target = concat!("Promoted<", stringify!($geotype), ">");
+                    conversion_error::<T>(ty, target, &[$code - 3, $code], raw, e)
+                })
+            }
+
+            accepts_geography!();
+        }
+    };
+}
+
+impl_from_sql_for_promoted!(MultiPointT, 4);
+impl_from_sql_for_promoted!(MultiLineStringT, 5);
+impl_from_sql_for_promoted!(MultiPolygonT, 6);
 
 macro_rules! impl_sql_for_ewkb_type {
     ($ewkbtype:ident contains points) => {
@@ -122,6 +216,7 @@ macro_rules! impl_sql_for_ewkb_type {
                 _: &Type,
                 out: &mut BytesMut,
             ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                out.reserve(self.ewkb_size());
                 self.write_ewkb(&mut out.writer())?;
                 Ok(IsNull::No)
             }
@@ -143,6 +238,7 @@ macro_rules! impl_sql_for_ewkb_type {
                 _: &Type,
                 out: &mut BytesMut,
             ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                out.reserve(self.ewkb_size());
                 self.write_ewkb(&mut out.writer())?;
                 Ok(IsNull::No)
             }
@@ -168,6 +264,7 @@ macro_rules! impl_sql_for_ewkb_type {
                 _: &Type,
                 out: &mut BytesMut,
             ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                out.reserve(self.ewkb_size());
                 self.write_ewkb(&mut out.writer())?;
                 Ok(IsNull::No)
             }
@@ -186,9 +283,8 @@ where
     P: Point + EwkbRead,
 {
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        ewkb::GeometryT::<P>::read_ewkb(&mut rdr)
-            .map_err(|_| format!("cannot convert {} to {}", ty, stringify!(P)).into())
+        ewkb::GeometryT::<P>::read_ewkb_exact(raw)
+            .map_err(|e| conversion_error::<P>(ty, "GeometryT", &[], raw, e))
     }
 
     accepts_geography!();
@@ -203,7 +299,9 @@ macro_rules! impl_geometry_to_sql {
                 _: &Type,
                 out: &mut BytesMut,
             ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
-                self.as_ewkb().write_ewkb(&mut out.writer())?;
+                let ewkb = self.as_ewkb();
+                out.reserve(ewkb.ewkb_size());
+                ewkb.write_ewkb(&mut out.writer())?;
                 Ok(IsNull::No)
             }
 
@@ -223,9 +321,8 @@ where
     P: Point + EwkbRead,
 {
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        ewkb::GeometryCollectionT::<P>::read_ewkb(&mut rdr)
-            .map_err(|_| format!("cannot convert {} to {}", ty, stringify!(P)).into())
+        ewkb::GeometryCollectionT::<P>::read_ewkb_exact(raw)
+            .map_err(|e| conversion_error::<P>(ty, "GeometryCollectionT", &[7], raw, e))
     }
 
     accepts_geography!();
@@ -236,7 +333,9 @@ where
     P: Point + EwkbRead,
 {
     fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
-        self.as_ewkb().write_ewkb(&mut out.writer())?;
+        let ewkb = self.as_ewkb();
+        out.reserve(ewkb.ewkb_size());
+        ewkb.write_ewkb(&mut out.writer())?;
         Ok(IsNull::No)
     }
 
@@ -244,12 +343,55 @@ where
     accepts_geography!();
 }
 
+impl<'a> FromSql<'a> for ewkb::Ewkb {
+    fn from_sql(_: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(ewkb::Ewkb(raw.to_vec()))
+    }
+
+    accepts_geography!();
+}
+
+impl ToSql for ewkb::Ewkb {
+    fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.extend_from_slice(&self.0);
+        Ok(IsNull::No)
+    }
+
+    to_sql_checked!();
+    accepts_geography!();
+}
+
+impl<'a> FromSql<'a> for ewkb::EwkbBytes<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(ewkb::EwkbBytes(raw))
+    }
+
+    accepts_geography!();
+}
+
+impl<'a> ToSql for ewkb::EwkbBytes<'a> {
+    fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.extend_from_slice(self.0);
+        Ok(IsNull::No)
+    }
+
+    to_sql_checked!();
+    accepts_geography!();
+}
+
+impl<'a> FromSql<'a> for ewkb::EwkbSlice<'a> {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        ewkb::EwkbSlice::new(raw).map_err(|_| format!("cannot convert {} to EwkbSlice", ty).into())
+    }
+
+    accepts_geography!();
+}
+
 // --- TWKB ---
 
 impl<'a> FromSql<'a> for twkb::Point {
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        twkb::Point::read_twkb(&mut rdr)
+        twkb::Point::read_twkb_from_slice(raw)
             .map_err(|_| format!("cannot convert {} to Point", ty).into())
     }
 
@@ -258,8 +400,7 @@ impl<'a> FromSql<'a> for twkb::Point {
 
 impl<'a> FromSql<'a> for twkb::LineString {
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        twkb::LineString::read_twkb(&mut rdr)
+        twkb::LineString::read_twkb_from_slice(raw)
             .map_err(|_| format!("cannot convert {} to LineString", ty).into())
     }
 
@@ -268,8 +409,7 @@ impl<'a> FromSql<'a> for twkb::LineString {
 
 impl<'a> FromSql<'a> for twkb::Polygon {
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        twkb::Polygon::read_twkb(&mut rdr)
+        twkb::Polygon::read_twkb_from_slice(raw)
             .map_err(|_| format!("cannot convert {} to Polygon", ty).into())
     }
 
@@ -279,16 +419,14 @@ impl<'a> FromSql<'a> for twkb::Polygon {
 impl<'a> FromSql<'a> for twkb::MultiPoint {
     accepts!(BYTEA);
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        twkb::MultiPoint::read_twkb(&mut rdr)
+        twkb::MultiPoint::read_twkb_from_slice(raw)
             .map_err(|_| format!("cannot convert {} to MultiPoint", ty).into())
     }
 }
 
 impl<'a> FromSql<'a> for twkb::MultiLineString {
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        twkb::MultiLineString::read_twkb(&mut rdr)
+        twkb::MultiLineString::read_twkb_from_slice(raw)
             .map_err(|_| format!("cannot convert {} to MultiLineString", ty).into())
     }
 
@@ -297,8 +435,7 @@ impl<'a> FromSql<'a> for twkb::MultiLineString {
 
 impl<'a> FromSql<'a> for twkb::MultiPolygon {
     fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
-        let mut rdr = Cursor::new(raw);
-        twkb::MultiPolygon::read_twkb(&mut rdr)
+        twkb::MultiPolygon::read_twkb_from_slice(raw)
             .map_err(|_| format!("cannot convert {} to MultiPolygon", ty).into())
     }
 
@@ -631,6 +768,138 @@ mod tests {
         assert_eq!(format!("{:.0?}", geom), "Point(PointZ { x: 10, y: -20, z: 99, srid: Some(4326) })");
     }
 
+    #[test]
+    #[ignore]
+    #[rustfmt::skip]
+    fn test_select_ewkb_slice() {
+        let mut client = connect();
+        let result = or_panic!(client.query("SELECT 'SRID=4326;LINESTRING (10 -20, 0 -0.5)'::geometry", &[]));
+        let row = result.iter().last().unwrap();
+        let view = row.get::<_, ewkb::EwkbSlice>(0);
+        assert_eq!(view.srid(), Some(4326));
+        assert_eq!(view.geometry_type(), 2);
+        assert_eq!(view.decode::<ewkb::LineString>().unwrap().points.len(), 2);
+    }
+
+    #[test]
+    #[ignore]
+    #[rustfmt::skip]
+    fn test_ewkb_passthrough() {
+        let mut client = connect();
+        or_panic!(client.execute("CREATE TEMPORARY TABLE geomtests (geom geometry)", &[]));
+        // big endian EWKB is stored as sent
+        let raw = ewkb::Ewkb(vec![0, 32, 0, 0, 1, 0, 0, 16, 230, 64, 36, 0, 0, 0, 0, 0, 0, 192, 52, 0, 0, 0, 0, 0, 0]);
+        or_panic!(client.execute("INSERT INTO geomtests (geom) VALUES ($1)", &[&raw]));
+        let result = or_panic!(client.query("SELECT geom, ST_AsEWKT(geom) FROM geomtests", &[]));
+        let row = result.iter().last().unwrap();
+        assert_eq!(row.get::<_, String>(1), "SRID=4326;POINT(10 -20)");
+        let copied = row.get::<_, ewkb::Ewkb>(0);
+        assert_eq!(copied.view().unwrap().srid(), Some(4326));
+        let borrowed = row.get::<_, ewkb::EwkbBytes>(0);
+        assert_eq!(borrowed.0, &copied.0[..]);
+    }
+
+    #[test]
+    #[ignore]
+    #[rustfmt::skip]
+    fn test_copy_binary() {
+        use crate::copy::CopyWriter;
+        use std::io::Write;
+        let mut client = connect();
+        or_panic!(client.execute("CREATE TEMPORARY TABLE geomtests (id integer, geom geometry(Point, 4326))", &[]));
+
+        let mut writer = or_panic!(client.copy_in("COPY geomtests (id, geom) FROM STDIN (FORMAT binary)"));
+        let mut copy = CopyWriter::new(&mut writer).unwrap();
+        for id in 0..100 {
+            let point = ewkb::Point { x: id as f64, y: -20.0, srid: Some(4326) };
+            copy.row().int4(id).geometry(&point.as_ewkb()).finish().unwrap();
+        }
+        copy.finish().unwrap().flush().unwrap();
+        or_panic!(writer.finish());
+
+        let result = or_panic!(client.query("SELECT count(*), sum(ST_X(geom))::integer FROM geomtests WHERE ST_SRID(geom)=4326", &[]));
+        let row = result.iter().last().unwrap();
+        assert_eq!(row.get::<_, i64>(0), 100);
+        assert_eq!(row.get::<_, i32>(1), 4950);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_conversion_error() {
+        use crate::ewkb::EwkbWrite;
+        use postgres_types::{FromSql, Kind, Type};
+        let ty = Type::new("geometry".to_string(), 18000, Kind::Simple, "public".to_string());
+        let mut line = ewkb::LineStringZ::from(vec![(10.0, -20.0, 1.0), (0., -0.5, 1.0)]);
+        line.srid = Some(4326);
+        let mut raw = Vec::new();
+        line.as_ewkb().write_ewkb(&mut raw).unwrap();
+        let err = <ewkb::Polygon as FromSql>::from_sql(&ty, &raw).err().unwrap();
+        assert_eq!(err.to_string(), "cannot convert geometry to PolygonT: expected Polygon, found LineString Z (WKB type 2, SRID 4326)");
+        let err = <ewkb::Promoted<ewkb::MultiPoint> as FromSql>::from_sql(&ty, &raw).err().unwrap();
+        assert_eq!(err.to_string(), "cannot convert geometry to Promoted<MultiPointT>: expected Point or MultiPoint, found LineString Z (WKB type 2, SRID 4326)");
+        let err = <ewkb::Geometry as FromSql>::from_sql(&ty, &raw[..20]).err().unwrap();
+        assert!(err.to_string().starts_with("cannot convert geometry to GeometryT: expected any geometry, found LineString Z (WKB type 2, SRID 4326): "));
+        let err = <ewkb::Point as FromSql>::from_sql(&ty, &[2, 0]).err().unwrap();
+        assert_eq!(err.to_string(), "cannot convert geometry to Point: invalid EWKB");
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_ewkb_raw() {
+        use bytes::BytesMut;
+        use postgres_types::{FromSql, Kind, ToSql, Type};
+        let ty = Type::new("geometry".to_string(), 18000, Kind::Simple, "public".to_string());
+        // not even valid EWKB is passed through untouched
+        for bytes in [vec![0, 0, 0, 0, 1, 64, 36, 0, 0, 0, 0, 0, 0, 192, 52, 0, 0, 0, 0, 0, 0], vec![7, 7]] {
+            let raw = ewkb::Ewkb(bytes.clone());
+            let mut out = BytesMut::new();
+            raw.to_sql(&ty, &mut out).unwrap();
+            assert_eq!(&out[..], &bytes[..]);
+            assert_eq!(ewkb::Ewkb::from_sql(&ty, &out).unwrap(), raw);
+            let borrowed = ewkb::EwkbBytes::from_sql(&ty, &out).unwrap();
+            assert_eq!(borrowed.0.as_ptr(), out.as_ptr());
+            assert_eq!(ewkb::Ewkb::from(borrowed), raw);
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_geometry_array() {
+        use bytes::BytesMut;
+        use postgres_types::{FromSql, Kind, ToSql, Type};
+        let member = Type::new("geometry".to_string(), 18000, Kind::Simple, "public".to_string());
+        let ty = Type::new("_geometry".to_string(), 18001, Kind::Array(member), "public".to_string());
+        let geoms: Vec<ewkb::Geometry> = vec![
+            ewkb::GeometryT::Point(ewkb::Point::new(10.0, -20.0, Some(4326))),
+            ewkb::GeometryT::LineString(ewkb::LineString::from(vec![(10.0, -20.0), (0., -0.5)])),
+        ];
+        assert!(<Vec<ewkb::Geometry> as ToSql>::accepts(&ty));
+        assert!(<Vec<ewkb::Geometry> as FromSql>::accepts(&ty));
+        let mut raw = BytesMut::new();
+        geoms.to_sql(&ty, &mut raw).unwrap();
+        assert_eq!(Vec::<ewkb::Geometry>::from_sql(&ty, &raw).unwrap(), geoms);
+
+        let points = vec![Some(ewkb::Point::new(1.0, 2.0, None)), None];
+        let mut raw = BytesMut::new();
+        points.to_sql(&ty, &mut raw).unwrap();
+        assert_eq!(Vec::<Option<ewkb::Point>>::from_sql(&ty, &raw).unwrap(), points);
+        assert!(Vec::<ewkb::LineString>::from_sql(&ty, &raw).is_err());
+    }
+
+    #[test]
+    #[ignore]
+    #[rustfmt::skip]
+    fn test_select_array_agg() {
+        let mut client = connect();
+        let result = or_panic!(client.query("SELECT array_agg(geom) FROM (VALUES ('POINT (10 -20)'::geometry), ('SRID=4326;POINT (0 -0.5)')) AS t(geom)", &[]));
+        let points: Vec<ewkb::Point> = result.iter().last().unwrap().get(0);
+        assert_eq!(points, vec![ewkb::Point::new(10.0, -20.0, None), ewkb::Point::new(0.0, -0.5, Some(4326))]);
+
+        let result = or_panic!(client.query("SELECT ST_Collect($1::geometry[])", &[&points]));
+        let multi: ewkb::MultiPoint = result.iter().last().unwrap().get(0);
+        assert_eq!(multi.len(), 2);
+    }
+
     #[test]
     #[ignore]
     #[cfg_attr(rustfmt, rustfmt_skip)]
@@ -638,7 +907,7 @@ mod tests {
         let mut client = connect();
         let result = or_panic!(client.query("SELECT ('LINESTRING (10 -20, -0 -0.5)')::geometry", &[]));
         let poly = result.iter().map(|r| r.try_get::<_, ewkb::Polygon>(0)).last().unwrap();
-        assert_eq!(format!("{:?}", poly), "Err(Error { kind: FromSql(0), cause: Some(\"cannot convert geometry to PolygonT\") })");
+        assert_eq!(format!("{:?}", poly), "Err(Error { kind: FromSql(0), cause: Some(\"cannot convert geometry to PolygonT: expected Polygon, found LineString (WKB type 2, SRID none)\") })");
     }
 
     #[test]
@@ -648,11 +917,11 @@ mod tests {
         let mut client = connect();
         let result = or_panic!(client.query("SELECT ST_AsTWKB('POINT(10 -20)'::geometry)", &[]));
         let point = result.iter().map(|r| r.get::<_, twkb::Point>(0)).last().unwrap();
-        assert_eq!(point, twkb::Point {x: 10.0, y: -20.0});
+        assert_eq!(point, twkb::Point {x: 10.0, y: -20.0, z: None, m: None});
 
         let result = or_panic!(client.query("SELECT ST_AsTWKB('SRID=4326;POINT(10 -20)'::geometry)", &[]));
         let point = result.iter().map(|r| r.get::<_, twkb::Point>(0)).last().unwrap();
-        assert_eq!(point, twkb::Point {x: 10.0, y: -20.0});
+        assert_eq!(point, twkb::Point {x: 10.0, y: -20.0, z: None, m: None});
 
         let result = or_panic!(client.query("SELECT ST_AsTWKB('POINT EMPTY'::geometry)", &[]));
         let point = result.iter().map(|r| r.get::<_, twkb::Point>(0)).last().unwrap();
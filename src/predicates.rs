@@ -0,0 +1,129 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Basic spatial predicates for coarse client-side filtering.
+//!
+//! ```rust
+//! use postgis::ewkb;
+//! use postgis::predicates::{BboxPredicates, Contains};
+//!
+//! let square = ewkb::Polygon::from(vec![vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.), (0., 0.)]]);
+//! assert!(square.contains(&ewkb::Point::new(1., 1., None)));
+//! assert!(!square.contains(&ewkb::Point::new(5., 1., None)));
+//!
+//! let line = ewkb::LineString::from(vec![(3., 3.), (8., 8.)]);
+//! assert!(square.intersects_bbox(&line));
+//! ```
+
+use crate::{
+    coord::Coords,
+    ewkb::{self, EwkbRead},
+    types as postgis,
+};
+
+/// Point in polygon test, like `ST_Contains` with a point argument.
+///
+/// Points on the boundary, including the boundary of holes, are not contained.
+pub trait Contains {
+    fn contains<T: postgis::Point>(&self, point: &T) -> bool;
+}
+
+impl<P> Contains for ewkb::PolygonT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn contains<T: postgis::Point>(&self, point: &T) -> bool {
+        let (x, y) = (point.x(), point.y());
+        let mut inside = false;
+        for ring in self.rings.iter() {
+            for w in ring.points.windows(2) {
+                let (x1, y1, x2, y2) = (w[0].x(), w[0].y(), w[1].x(), w[1].y());
+                if on_segment(x, y, x1, y1, x2, y2) {
+                    return false;
+                }
+                if (y1 > y) != (y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1 {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+}
+
+impl<P> Contains for ewkb::MultiPolygonT<P>
+where
+    P: postgis::Point + EwkbRead,
+{
+    fn contains<T: postgis::Point>(&self, point: &T) -> bool {
+        self.polygons.iter().any(|polygon| polygon.contains(point))
+    }
+}
+
+fn on_segment(x: f64, y: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
+    (x2 - x1) * (y - y1) == (x - x1) * (y2 - y1)
+        && x >= x1.min(x2)
+        && x <= x1.max(x2)
+        && y >= y1.min(y2)
+        && y <= y1.max(y2)
+}
+
+/// Bounding box comparisons, like the PostGIS `&&` operator.
+///
+/// Empty geometries intersect nothing.
+pub trait BboxPredicates: Coords {
+    fn intersects_bbox<G: Coords + ?Sized>(&self, other: &G) -> bool {
+        match (self.bbox(), other.bbox()) {
+            (Some(a), Some(b)) => a.intersects(&b),
+            _ => false,
+        }
+    }
+
+    fn disjoint_bbox<G: Coords + ?Sized>(&self, other: &G) -> bool {
+        !self.intersects_bbox(other)
+    }
+}
+
+impl<T: Coords + ?Sized> BboxPredicates for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_contains() {
+        let poly = Polygon::from(vec![
+            vec![(0., 0.), (10., 0.), (10., 10.), (0., 10.), (0., 0.)],
+            vec![(2., 2.), (4., 2.), (4., 4.), (2., 4.), (2., 2.)],
+        ]);
+        assert!(poly.contains(&Point::new(1., 1., None)));
+        assert!(poly.contains(&PointZ::new(5., 5., 3., None)));
+        assert!(!poly.contains(&Point::new(3., 3., None)));
+        assert!(!poly.contains(&Point::new(11., 3., None)));
+        assert!(!poly.contains(&Point::new(0., 5., None)));
+        assert!(!poly.contains(&Point::new(4., 3., None)));
+
+        let multi = MultiPolygon {
+            polygons: vec![
+                poly,
+                Polygon::from(vec![vec![(20., 0.), (30., 0.), (25., 5.), (20., 0.)]]),
+            ],
+            srid: None,
+        };
+        assert!(multi.contains(&Point::new(25., 2., None)));
+        assert!(!multi.contains(&Point::new(15., 2., None)));
+    }
+
+    #[test]
+    fn test_bbox_predicates() {
+        let poly = Polygon::from(vec![vec![(0., 0.), (10., 0.), (0., 10.), (0., 0.)]]);
+        let touching = LineString::from(vec![(10., 10.), (12., 12.)]);
+        let outside = Point::new(10.5, 3., None);
+        assert!(poly.intersects_bbox(&touching));
+        assert!(poly.disjoint_bbox(&outside));
+        assert!(!poly.intersects_bbox(&Polygon::new()));
+        let geom: Geometry = GeometryT::Point(Point::new(5., 5., None));
+        assert!(geom.intersects_bbox(&poly));
+    }
+}
@@ -0,0 +1,184 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Query parameters for common PostGIS operators.
+//!
+//! The parameters are sent as EWKB, so spatial conditions don't have to be
+//! built from EWKT strings:
+//!
+//! ```rust,no_run
+//! # use postgres::{Client, NoTls};
+//! use postgis::{envelope::Envelope, ewkb, query::{BboxParam, DWithinParam}};
+//!
+//! # let mut client = Client::connect("host=localhost user=postgres", NoTls).unwrap();
+//! let bbox = BboxParam::new(Envelope::new(8.0, 47.0, 9.0, 48.0), Some(4326));
+//! let rows = client.query("SELECT name FROM stations WHERE geom && $1", &[&bbox]).unwrap();
+//!
+//! let near = DWithinParam::new(ewkb::Point::new(8.5, 47.4, Some(4326)), 500.0);
+//! let sql = format!("SELECT name FROM stations WHERE {}", near.condition("geom", 1));
+//! let rows = client.query(sql.as_str(), &near.params()).unwrap();
+//! ```
+
+use crate::{envelope::Envelope, ewkb};
+use bytes::BytesMut;
+use postgres_types::{to_sql_checked, IsNull, ToSql, Type};
+use std::error::Error;
+
+/// Bounding box for the `&&` operator, sent as polygon.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct BboxParam {
+    pub envelope: Envelope,
+    pub srid: Option<i32>,
+}
+
+impl BboxParam {
+    pub fn new(envelope: Envelope, srid: Option<i32>) -> Self {
+        BboxParam { envelope, srid }
+    }
+
+    /// `column && $first`
+    pub fn condition(&self, column: &str, first: usize) -> String {
+        format!("{} && ${}", column, first)
+    }
+}
+
+impl ToSql for BboxParam {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.envelope.to_polygon(self.srid).to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <ewkb::Polygon as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+/// Bounding box grown by `distance` on every side, like `ST_Expand`.
+/// Sent as polygon, for use with the `&&` operator.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct ExpandParam {
+    pub envelope: Envelope,
+    pub distance: f64,
+    pub srid: Option<i32>,
+}
+
+impl ExpandParam {
+    pub fn new(envelope: Envelope, distance: f64, srid: Option<i32>) -> Self {
+        ExpandParam {
+            envelope,
+            distance,
+            srid,
+        }
+    }
+
+    /// `column && $first`
+    pub fn condition(&self, column: &str, first: usize) -> String {
+        format!("{} && ${}", column, first)
+    }
+}
+
+impl ToSql for ExpandParam {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        BboxParam::new(self.envelope.expand(self.distance), self.srid).to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <BboxParam as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+/// Geometry and distance in meters for `ST_DWithin` on geography.
+///
+/// The condition takes two parameters, the geometry (with SRID 4326) and
+/// the distance.
+#[derive(PartialEq, Clone, Debug)]
+pub struct DWithinParam<G> {
+    pub geom: G,
+    pub meters: f64,
+}
+
+impl<G: ToSql + Sync> DWithinParam<G> {
+    pub fn new(geom: G, meters: f64) -> Self {
+        DWithinParam { geom, meters }
+    }
+
+    /// `ST_DWithin(column::geography, $first::geography, $first+1)`
+    pub fn condition(&self, column: &str, first: usize) -> String {
+        format!(
+            "ST_DWithin({}::geography, ${}::geography, ${})",
+            column,
+            first,
+            first + 1
+        )
+    }
+
+    /// Parameters for the placeholders of [`condition`](Self::condition).
+    pub fn params(&self) -> [&(dyn ToSql + Sync); 2] {
+        [&self.geom, &self.meters]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{AsEwkbPolygon, EwkbWrite};
+    use postgres_types::Kind;
+
+    fn geometry_type() -> Type {
+        Type::new(
+            "geometry".to_string(),
+            18000,
+            Kind::Simple,
+            "public".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_bbox_param() {
+        let envelope = Envelope::new(0.0, 0.0, 2.0, 1.0);
+        let param = BboxParam::new(envelope, Some(4326));
+        let mut out = BytesMut::new();
+        param.to_sql_checked(&geometry_type(), &mut out).unwrap();
+        let mut expected = Vec::new();
+        envelope
+            .to_polygon(Some(4326))
+            .as_ewkb()
+            .write_ewkb(&mut expected)
+            .unwrap();
+        assert_eq!(&out[..], &expected[..]);
+        assert_eq!(param.condition("geom", 3), "geom && $3");
+
+        let param = ExpandParam::new(envelope, 1.0, None);
+        let mut out = BytesMut::new();
+        param.to_sql_checked(&geometry_type(), &mut out).unwrap();
+        let mut expected = Vec::new();
+        Envelope::new(-1.0, -1.0, 3.0, 2.0)
+            .to_polygon(None)
+            .as_ewkb()
+            .write_ewkb(&mut expected)
+            .unwrap();
+        assert_eq!(&out[..], &expected[..]);
+    }
+
+    #[test]
+    fn test_dwithin_param() {
+        let param = DWithinParam::new(ewkb::Point::new(8.5, 47.4, Some(4326)), 500.0);
+        assert_eq!(
+            param.condition("geom", 2),
+            "ST_DWithin(geom::geography, $2::geography, $3)"
+        );
+        assert_eq!(param.params().len(), 2);
+    }
+}
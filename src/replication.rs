@@ -0,0 +1,100 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Decoding geometry values from logical replication streams.
+//!
+//! Logical decoding plugins emit geometry columns either in text form, which
+//! is hex encoded EWKB (`wal2json`, `test_decoding`, `pgoutput` text mode), or
+//! as raw EWKB (`pgoutput` binary mode).
+//!
+//! ```rust
+//! use postgis::ewkb;
+//! use postgis::replication::decode_text;
+//!
+//! // {"kind":"insert","columnnames":["id","geom"],"columnvalues":[1,"0101000020E6100000000000000000244000000000000034C0"]}
+//! let geom: ewkb::Geometry = decode_text("0101000020E6100000000000000000244000000000000034C0").unwrap();
+//! match geom {
+//!     ewkb::GeometryT::Point(p) => assert_eq!((p.x, p.y, p.srid), (10.0, -20.0, Some(4326))),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use crate::{error::Error, ewkb::EwkbRead, hex::FromHex};
+
+/// Decode a geometry value in text output format.
+///
+/// Surrounding whitespace and single or double quotes, as in `test_decoding`
+/// output, are ignored.
+pub fn decode_text<T: EwkbRead>(value: &str) -> Result<T, Error> {
+    let value = value.trim();
+    let value = strip_quotes(value, '\'').unwrap_or(value);
+    let value = strip_quotes(value, '"').unwrap_or(value);
+    T::read_ewkb_from_slice(&hex_to_ewkb(value)?)
+}
+
+/// Decode a geometry value in binary format.
+pub fn decode_binary<T: EwkbRead>(value: &[u8]) -> Result<T, Error> {
+    T::read_ewkb_from_slice(value)
+}
+
+/// Decode a geometry value in text or binary format.
+///
+/// EWKB starts with a byte order marker of 0 or 1, which is never the
+/// first byte of a text value.
+pub fn decode<T: EwkbRead>(value: &[u8]) -> Result<T, Error> {
+    match value.first() {
+        Some(0) | Some(1) => decode_binary(value),
+        _ => {
+            let text = std::str::from_utf8(value)
+                .map_err(|_| Error::Read("invalid UTF-8 in text value".into()))?;
+            decode_text(text)
+        }
+    }
+}
+
+/// Convert hex encoded EWKB into bytes.
+pub fn hex_to_ewkb(hex: &str) -> Result<Vec<u8>, Error> {
+    Vec::from_hex(hex)
+}
+
+fn strip_quotes(value: &str, quote: char) -> Option<&str> {
+    value.strip_prefix(quote)?.strip_suffix(quote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::{self, AsEwkbLineString, EwkbWrite};
+
+    #[test]
+    fn test_decode() {
+        let mut line = ewkb::LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+        line.srid = Some(4326);
+        let hex = line.as_ewkb().to_hex_ewkb();
+        let mut bin = Vec::new();
+        line.as_ewkb().write_ewkb(&mut bin).unwrap();
+
+        // test_decoding: table public.t: INSERT: id[integer]:1 geom[geometry]:'0102...'
+        let quoted = format!("'{}'", hex);
+        let lower = hex.to_lowercase();
+        for value in &[hex.as_str(), quoted.as_str(), lower.as_str()] {
+            let geom: ewkb::Geometry = decode_text(value).unwrap();
+            match geom {
+                ewkb::GeometryT::LineString(decoded) => {
+                    assert_eq!(decoded.srid, Some(4326));
+                    assert_eq!((decoded.points[1].x, decoded.points[1].y), (0., -0.5));
+                }
+                _ => panic!("LineString expected"),
+            }
+        }
+        let decoded: ewkb::LineString = decode(hex.as_bytes()).unwrap();
+        assert_eq!(decoded.points.len(), 2);
+        let decoded: ewkb::LineString = decode(&bin).unwrap();
+        assert_eq!(decoded.srid, Some(4326));
+
+        assert!(decode_text::<ewkb::Geometry>("0101").is_err());
+        assert!(decode_text::<ewkb::Geometry>("010G").is_err());
+        assert!(hex_to_ewkb("123").is_err());
+    }
+}
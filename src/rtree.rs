@@ -0,0 +1,112 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! [`rstar`](https://docs.rs/rstar) R-tree support for the ewkb geometry types.
+//!
+//! Geometries are indexed by their 2D bounding box, distances are measured
+//! to the bounding box (exact for points).
+//!
+//! ```rust
+//! use postgis::ewkb;
+//! use rstar::RTree;
+//!
+//! let tree = RTree::bulk_load(vec![
+//!     ewkb::LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]),
+//!     ewkb::LineString::from(vec![(5.0, 5.0), (6.0, 5.0)]),
+//! ]);
+//! let nearest = tree.nearest_neighbor(&[4.0, 4.0]).unwrap();
+//! ```
+
+use crate::{
+    coord::Coords,
+    ewkb::{self, EwkbRead},
+    types as postgis,
+};
+use rstar::{Envelope as _, PointDistance, RTreeObject, AABB};
+
+fn aabb<G: Coords>(geom: &G) -> AABB<[f64; 2]> {
+    match geom.bbox() {
+        Some(bbox) => AABB::from_corners([bbox.xmin, bbox.ymin], [bbox.xmax, bbox.ymax]),
+        // empty geometries are never found
+        None => AABB::new_empty(),
+    }
+}
+
+macro_rules! impl_rtree_for_point {
+    ($ptype:ident) => {
+        impl RTreeObject for ewkb::$ptype {
+            type Envelope = AABB<[f64; 2]>;
+
+            fn envelope(&self) -> Self::Envelope {
+                aabb(self)
+            }
+        }
+
+        impl PointDistance for ewkb::$ptype {
+            fn distance_2(&self, point: &[f64; 2]) -> f64 {
+                self.envelope().distance_2(point)
+            }
+        }
+    };
+}
+
+impl_rtree_for_point!(Point);
+impl_rtree_for_point!(PointZ);
+impl_rtree_for_point!(PointM);
+impl_rtree_for_point!(PointZM);
+
+macro_rules! impl_rtree_for_geom {
+    ($geotype:ident) => {
+        impl<P> RTreeObject for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + Coords,
+        {
+            type Envelope = AABB<[f64; 2]>;
+
+            fn envelope(&self) -> Self::Envelope {
+                aabb(self)
+            }
+        }
+
+        impl<P> PointDistance for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead + Coords,
+        {
+            fn distance_2(&self, point: &[f64; 2]) -> f64 {
+                self.envelope().distance_2(point)
+            }
+        }
+    };
+}
+
+impl_rtree_for_geom!(LineStringT);
+impl_rtree_for_geom!(PolygonT);
+impl_rtree_for_geom!(MultiPointT);
+impl_rtree_for_geom!(MultiLineStringT);
+impl_rtree_for_geom!(MultiPolygonT);
+impl_rtree_for_geom!(GeometryCollectionT);
+impl_rtree_for_geom!(GeometryT);
+
+#[cfg(test)]
+mod tests {
+    use crate::ewkb;
+    use rstar::{RTree, RTreeObject, AABB};
+
+    #[test]
+    fn test_rtree() {
+        let tree = RTree::bulk_load(vec![
+            ewkb::LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]),
+            ewkb::LineString::from(vec![(5.0, 5.0), (6.0, 5.0)]),
+            ewkb::LineString::new(),
+        ]);
+        let nearest = tree.nearest_neighbor(&[4.0, 4.0]).unwrap();
+        assert_eq!(nearest.points[0], ewkb::Point::new(5.0, 5.0, None));
+        let found =
+            tree.locate_in_envelope_intersecting(&AABB::from_corners([0.5, 0.5], [2.0, 2.0]));
+        assert_eq!(found.count(), 1);
+
+        let point = ewkb::Point::new(1.0, 2.0, None);
+        assert_eq!(point.envelope(), AABB::from_point([1.0, 2.0]));
+    }
+}
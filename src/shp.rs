@@ -0,0 +1,625 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Conversion between [ESRI Shapefile](https://www.esri.com/content/dam/esrisites/sitecore-archive/Files/Pdfs/library/whitepapers/pdfs/shapefile.pdf)
+//! record geometries and the [`ewkb`](crate::ewkb) types.
+//!
+//! Shapefile PolyLines are read as MultiLineStrings and Polygons as
+//! MultiPolygons, where every clockwise ring starts a new polygon and the
+//! following counter-clockwise rings are its holes.
+//!
+//! ```rust
+//! use postgis::{ewkb, shp::{ShpRead, ShpWrite}};
+//!
+//! let point = ewkb::PointZ::new(1.0, 2.0, 3.0, None);
+//! let mut buf = Vec::new();
+//! point.write_shp(&mut buf).unwrap();
+//! assert_eq!(ewkb::PointZ::read_shp(&buf).unwrap(), point);
+//! ```
+
+use crate::{
+    error::Error,
+    ewkb::{self, EwkbRead, NewFromOptVals, PointType},
+    types as postgis,
+};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::prelude::*;
+
+/// M values below this threshold mean "no data".
+const NO_DATA: f64 = -1e38;
+const NO_DATA_VALUE: f64 = -1e39;
+
+/// Shapefile shape types.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ShapeType {
+    Null = 0,
+    Point = 1,
+    PolyLine = 3,
+    Polygon = 5,
+    MultiPoint = 8,
+    PointZ = 11,
+    PolyLineZ = 13,
+    PolygonZ = 15,
+    MultiPointZ = 18,
+    PointM = 21,
+    PolyLineM = 23,
+    PolygonM = 25,
+    MultiPointM = 28,
+}
+
+impl ShapeType {
+    pub fn from_i32(code: i32) -> Option<ShapeType> {
+        use self::ShapeType::*;
+        Some(match code {
+            0 => Null,
+            1 => Point,
+            3 => PolyLine,
+            5 => Polygon,
+            8 => MultiPoint,
+            11 => PointZ,
+            13 => PolyLineZ,
+            15 => PolygonZ,
+            18 => MultiPointZ,
+            21 => PointM,
+            23 => PolyLineM,
+            25 => PolygonM,
+            28 => MultiPointM,
+            _ => return None,
+        })
+    }
+
+    pub fn has_z(&self) -> bool {
+        (*self as i32) > 10 && (*self as i32) < 20
+    }
+
+    /// Z types may carry M values, M types always do.
+    pub fn has_m(&self) -> bool {
+        (*self as i32) > 10
+    }
+
+    /// Shape type without Z/M (Point, PolyLine, Polygon or MultiPoint).
+    pub fn base(&self) -> ShapeType {
+        ShapeType::from_i32(*self as i32 % 10).unwrap_or(ShapeType::Null)
+    }
+
+    fn with_dims(&self, point_type: PointType) -> ShapeType {
+        let offset = match point_type {
+            PointType::Point => 0,
+            PointType::PointZ | PointType::PointZM => 10,
+            PointType::PointM => 20,
+        };
+        ShapeType::from_i32(self.base() as i32 + offset).unwrap_or(ShapeType::Null)
+    }
+}
+
+type Coord = (f64, f64, Option<f64>, Option<f64>);
+
+/// Decoded record content: shape type and coordinate parts.
+struct Shape {
+    shape_type: ShapeType,
+    parts: Vec<Vec<Coord>>,
+}
+
+fn read_box(raw: &mut &[u8]) -> Result<(), Error> {
+    for _ in 0..4 {
+        raw.read_f64::<LittleEndian>()?;
+    }
+    Ok(())
+}
+
+fn read_shape(buf: &[u8]) -> Result<Shape, Error> {
+    let raw = &mut &buf[..];
+    let code = raw.read_i32::<LittleEndian>()?;
+    let shape_type = ShapeType::from_i32(code)
+        .ok_or_else(|| Error::Read(format!("unsupported shape type {}", code)))?;
+    let base = shape_type.base();
+    if shape_type == ShapeType::Null {
+        return Ok(Shape {
+            shape_type,
+            parts: Vec::new(),
+        });
+    }
+    if base == ShapeType::Point {
+        let x = raw.read_f64::<LittleEndian>()?;
+        let y = raw.read_f64::<LittleEndian>()?;
+        let z = if shape_type.has_z() {
+            Some(raw.read_f64::<LittleEndian>()?)
+        } else {
+            None
+        };
+        let m = if shape_type.has_m() && raw.len() >= 8 {
+            Some(raw.read_f64::<LittleEndian>()?).filter(|m| *m > NO_DATA)
+        } else {
+            None
+        };
+        return Ok(Shape {
+            shape_type,
+            parts: vec![vec![(x, y, z, m)]],
+        });
+    }
+    read_box(raw)?;
+    let (nparts, npoints) = if base == ShapeType::MultiPoint {
+        (1, raw.read_i32::<LittleEndian>()? as usize)
+    } else {
+        let nparts = raw.read_i32::<LittleEndian>()? as usize;
+        (nparts, raw.read_i32::<LittleEndian>()? as usize)
+    };
+    let mut starts = vec![0];
+    if base != ShapeType::MultiPoint {
+        starts.clear();
+        for _ in 0..nparts {
+            starts.push(raw.read_i32::<LittleEndian>()? as usize);
+        }
+    }
+    let mut coords: Vec<Coord> = Vec::with_capacity(npoints);
+    for _ in 0..npoints {
+        let x = raw.read_f64::<LittleEndian>()?;
+        let y = raw.read_f64::<LittleEndian>()?;
+        coords.push((x, y, None, None));
+    }
+    if shape_type.has_z() {
+        raw.read_f64::<LittleEndian>()?;
+        raw.read_f64::<LittleEndian>()?;
+        for c in coords.iter_mut() {
+            c.2 = Some(raw.read_f64::<LittleEndian>()?);
+        }
+    }
+    // M values are optional for Z types
+    if shape_type.has_m() && raw.len() >= 16 + 8 * npoints {
+        raw.read_f64::<LittleEndian>()?;
+        raw.read_f64::<LittleEndian>()?;
+        for c in coords.iter_mut() {
+            c.3 = Some(raw.read_f64::<LittleEndian>()?).filter(|m| *m > NO_DATA);
+        }
+    }
+    let mut parts = Vec::with_capacity(nparts);
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).cloned().unwrap_or(npoints);
+        if start > end || end > npoints {
+            return Err(Error::Read("invalid shapefile part index".into()));
+        }
+        parts.push(coords[start..end].to_vec());
+    }
+    Ok(Shape { shape_type, parts })
+}
+
+fn write_shape<W: Write + ?Sized>(
+    w: &mut W,
+    shape_type: ShapeType,
+    parts: &[Vec<Coord>],
+) -> Result<(), Error> {
+    w.write_i32::<LittleEndian>(shape_type as i32)?;
+    let base = shape_type.base();
+    let coords: Vec<&Coord> = parts.iter().flat_map(|p| p.iter()).collect();
+    let m_or_nodata = |c: &Coord| c.3.unwrap_or(NO_DATA_VALUE);
+    if base == ShapeType::Point {
+        let c = coords
+            .first()
+            .ok_or_else(|| Error::Write("empty point".into()))?;
+        w.write_f64::<LittleEndian>(c.0)?;
+        w.write_f64::<LittleEndian>(c.1)?;
+        if shape_type.has_z() {
+            w.write_f64::<LittleEndian>(c.2.unwrap_or(0.0))?;
+        }
+        if shape_type.has_m() {
+            w.write_f64::<LittleEndian>(m_or_nodata(c))?;
+        }
+        return Ok(());
+    }
+    let range = |values: &mut dyn Iterator<Item = f64>| {
+        values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        })
+    };
+    let (xmin, xmax) = range(&mut coords.iter().map(|c| c.0));
+    let (ymin, ymax) = range(&mut coords.iter().map(|c| c.1));
+    let bbox = if coords.is_empty() {
+        [0.0; 4]
+    } else {
+        [xmin, ymin, xmax, ymax]
+    };
+    for v in bbox.iter() {
+        w.write_f64::<LittleEndian>(*v)?;
+    }
+    if base != ShapeType::MultiPoint {
+        w.write_i32::<LittleEndian>(parts.len() as i32)?;
+    }
+    w.write_i32::<LittleEndian>(coords.len() as i32)?;
+    if base != ShapeType::MultiPoint {
+        let mut start = 0;
+        for part in parts {
+            w.write_i32::<LittleEndian>(start)?;
+            start += part.len() as i32;
+        }
+    }
+    for c in coords.iter() {
+        w.write_f64::<LittleEndian>(c.0)?;
+        w.write_f64::<LittleEndian>(c.1)?;
+    }
+    if shape_type.has_z() {
+        let (zmin, zmax) = range(&mut coords.iter().map(|c| c.2.unwrap_or(0.0)));
+        w.write_f64::<LittleEndian>(zmin)?;
+        w.write_f64::<LittleEndian>(zmax)?;
+        for c in coords.iter() {
+            w.write_f64::<LittleEndian>(c.2.unwrap_or(0.0))?;
+        }
+    }
+    let has_m = coords.iter().any(|c| c.3.is_some());
+    if shape_type.has_m() && (has_m || !shape_type.has_z()) {
+        let (mmin, mmax) = range(&mut coords.iter().filter_map(|c| c.3));
+        let (mmin, mmax) = if has_m {
+            (mmin, mmax)
+        } else {
+            (NO_DATA_VALUE, NO_DATA_VALUE)
+        };
+        w.write_f64::<LittleEndian>(mmin)?;
+        w.write_f64::<LittleEndian>(mmax)?;
+        for c in coords.iter() {
+            w.write_f64::<LittleEndian>(m_or_nodata(c))?;
+        }
+    }
+    Ok(())
+}
+
+fn coord<P: postgis::Point>(p: &P) -> Coord {
+    (p.x(), p.y(), p.opt_z(), p.opt_m())
+}
+
+fn to_point<P>(shape_type: ShapeType, c: &Coord) -> Result<P, Error>
+where
+    P: NewFromOptVals + EwkbRead,
+{
+    let point_type = P::point_type();
+    let needs_z = point_type == PointType::PointZ || point_type == PointType::PointZM;
+    let needs_m = point_type == PointType::PointM || point_type == PointType::PointZM;
+    if needs_z && c.2.is_none() {
+        return Err(Error::Read(format!("{:?} has no Z values", shape_type)));
+    }
+    if needs_m && !shape_type.has_m() {
+        return Err(Error::Read(format!("{:?} has no M values", shape_type)));
+    }
+    Ok(P::new_from_opt_vals(
+        c.0,
+        c.1,
+        c.2,
+        Some(c.3.unwrap_or(f64::NAN)),
+        None,
+    ))
+}
+
+fn to_line<P>(shape_type: ShapeType, part: &[Coord]) -> Result<ewkb::LineStringT<P>, Error>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    Ok(ewkb::LineStringT {
+        points: part
+            .iter()
+            .map(|c| to_point(shape_type, c))
+            .collect::<Result<_, _>>()?,
+        srid: None,
+    })
+}
+
+/// Twice the signed area. Negative for clockwise rings.
+fn ring_area(ring: &[Coord]) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+        .sum()
+}
+
+fn check_type(shape: &Shape, expected: ShapeType) -> Result<(), Error> {
+    if shape.shape_type.base() != expected {
+        return Err(Error::Read(format!(
+            "expected shape type {:?}, found {:?}",
+            expected, shape.shape_type
+        )));
+    }
+    Ok(())
+}
+
+fn to_multipolygon<P>(shape: &Shape) -> Result<ewkb::MultiPolygonT<P>, Error>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    let mut polygons: Vec<ewkb::PolygonT<P>> = Vec::new();
+    for part in shape.parts.iter() {
+        let ring = to_line(shape.shape_type, part)?;
+        match polygons.last_mut() {
+            Some(poly) if ring_area(part) > 0.0 => poly.rings.push(ring),
+            _ => polygons.push(ewkb::PolygonT {
+                rings: std::iter::once(ring).collect(),
+                srid: None,
+            }),
+        }
+    }
+    Ok(ewkb::MultiPolygonT {
+        polygons,
+        srid: None,
+    })
+}
+
+fn ring_coords<'a, L: postgis::LineString<'a>>(ring: &'a L, clockwise: bool) -> Vec<Coord> {
+    let mut coords: Vec<Coord> = ring.points().map(coord).collect();
+    if (ring_area(&coords) < 0.0) != clockwise {
+        coords.reverse();
+    }
+    coords
+}
+
+fn polygon_parts<'a, Y: postgis::Polygon<'a>>(polygon: &'a Y, parts: &mut Vec<Vec<Coord>>) {
+    if let Some(exterior) = polygon.exterior() {
+        parts.push(ring_coords(exterior, true));
+    }
+    for ring in polygon.interiors() {
+        parts.push(ring_coords(ring, false));
+    }
+}
+
+/// Read geometries from shapefile record contents.
+pub trait ShpRead: Sized {
+    /// Read the record content (without record header).
+    fn read_shp(buf: &[u8]) -> Result<Self, Error>;
+
+    /// Read a record header and its content, returning the record number.
+    fn read_shp_record<R: Read>(raw: &mut R) -> Result<(i32, Self), Error> {
+        let record_number = raw.read_i32::<BigEndian>()?;
+        let content_length = raw.read_i32::<BigEndian>()?;
+        let mut buf = vec![0; content_length as usize * 2];
+        raw.read_exact(&mut buf)?;
+        Ok((record_number, Self::read_shp(&buf)?))
+    }
+}
+
+/// Write geometries as shapefile record contents.
+pub trait ShpWrite {
+    fn shape_type(&self) -> ShapeType;
+
+    /// Write the record content (without record header).
+    fn write_shp<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error>;
+
+    /// Write a record header followed by the record content.
+    fn write_shp_record<W: Write + ?Sized>(
+        &self,
+        record_number: i32,
+        w: &mut W,
+    ) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        self.write_shp(&mut buf)?;
+        w.write_i32::<BigEndian>(record_number)?;
+        w.write_i32::<BigEndian>(buf.len() as i32 / 2)?;
+        w.write_all(&buf)?;
+        Ok(())
+    }
+}
+
+macro_rules! impl_shp_for_point {
+    ($ptype:ident) => {
+        impl ShpRead for ewkb::$ptype {
+            fn read_shp(buf: &[u8]) -> Result<Self, Error> {
+                let shape = read_shape(buf)?;
+                check_type(&shape, ShapeType::Point)?;
+                to_point(shape.shape_type, &shape.parts[0][0])
+            }
+        }
+
+        impl ShpWrite for ewkb::$ptype {
+            fn shape_type(&self) -> ShapeType {
+                ShapeType::Point.with_dims(PointType::$ptype)
+            }
+            fn write_shp<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+                write_shape(w, self.shape_type(), &[vec![coord(self)]])
+            }
+        }
+    };
+}
+
+impl_shp_for_point!(Point);
+impl_shp_for_point!(PointZ);
+impl_shp_for_point!(PointM);
+impl_shp_for_point!(PointZM);
+
+impl<P> ShpRead for ewkb::MultiPointT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn read_shp(buf: &[u8]) -> Result<Self, Error> {
+        let shape = read_shape(buf)?;
+        check_type(&shape, ShapeType::MultiPoint)?;
+        Ok(ewkb::MultiPointT {
+            points: to_line(shape.shape_type, &shape.parts[0])?.points,
+            srid: None,
+        })
+    }
+}
+
+impl<P> ShpRead for ewkb::MultiLineStringT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn read_shp(buf: &[u8]) -> Result<Self, Error> {
+        let shape = read_shape(buf)?;
+        check_type(&shape, ShapeType::PolyLine)?;
+        Ok(ewkb::MultiLineStringT {
+            lines: shape
+                .parts
+                .iter()
+                .map(|part| to_line(shape.shape_type, part))
+                .collect::<Result<_, _>>()?,
+            srid: None,
+        })
+    }
+}
+
+impl<P> ShpRead for ewkb::MultiPolygonT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn read_shp(buf: &[u8]) -> Result<Self, Error> {
+        let shape = read_shape(buf)?;
+        check_type(&shape, ShapeType::Polygon)?;
+        to_multipolygon(&shape)
+    }
+}
+
+impl<P> ShpRead for ewkb::GeometryT<P>
+where
+    P: postgis::Point + NewFromOptVals + EwkbRead,
+{
+    fn read_shp(buf: &[u8]) -> Result<Self, Error> {
+        let shape = read_shape(buf)?;
+        let shape_type = shape.shape_type;
+        Ok(match shape_type.base() {
+            ShapeType::Point => ewkb::GeometryT::Point(to_point(shape_type, &shape.parts[0][0])?),
+            ShapeType::MultiPoint => ewkb::GeometryT::MultiPoint(ewkb::MultiPointT {
+                points: to_line(shape_type, &shape.parts[0])?.points,
+                srid: None,
+            }),
+            ShapeType::PolyLine => ewkb::GeometryT::MultiLineString(ewkb::MultiLineStringT {
+                lines: shape
+                    .parts
+                    .iter()
+                    .map(|part| to_line(shape_type, part))
+                    .collect::<Result<_, _>>()?,
+                srid: None,
+            }),
+            ShapeType::Polygon => ewkb::GeometryT::MultiPolygon(to_multipolygon(&shape)?),
+            _ => return Err(Error::Read("cannot read null shape".into())),
+        })
+    }
+}
+
+macro_rules! impl_shp_write {
+    ($geotype:ident as $shapetype:ident, |$geom:ident, $parts:ident| $body:block) => {
+        impl<P> ShpWrite for ewkb::$geotype<P>
+        where
+            P: postgis::Point + EwkbRead,
+        {
+            fn shape_type(&self) -> ShapeType {
+                ShapeType::$shapetype.with_dims(P::point_type())
+            }
+            fn write_shp<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Error> {
+                let $geom = self;
+                let mut $parts: Vec<Vec<Coord>> = Vec::new();
+                $body
+                write_shape(w, self.shape_type(), &$parts)
+            }
+        }
+    };
+}
+
+impl_shp_write!(MultiPointT as MultiPoint, |geom, parts| {
+    parts.push(geom.points.iter().map(coord).collect());
+});
+impl_shp_write!(LineStringT as PolyLine, |geom, parts| {
+    parts.push(geom.points.iter().map(coord).collect());
+});
+impl_shp_write!(MultiLineStringT as PolyLine, |geom, parts| {
+    for line in geom.lines.iter() {
+        parts.push(line.points.iter().map(coord).collect());
+    }
+});
+impl_shp_write!(PolygonT as Polygon, |geom, parts| {
+    polygon_parts(geom, &mut parts);
+});
+impl_shp_write!(MultiPolygonT as Polygon, |geom, parts| {
+    for polygon in geom.polygons.iter() {
+        polygon_parts(polygon, &mut parts);
+    }
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::*;
+
+    #[test]
+    fn test_point() {
+        let point = Point::new(1.0, 2.0, None);
+        let mut buf = Vec::new();
+        point.write_shp(&mut buf).unwrap();
+        assert_eq!(buf.len(), 20);
+        assert_eq!(&buf[..4], &[1, 0, 0, 0]);
+        assert_eq!(Point::read_shp(&buf).unwrap(), point);
+        assert!(PointZ::read_shp(&buf).is_err());
+
+        let point = PointM::new(1.0, 2.0, 5.0, None);
+        assert_eq!(point.shape_type(), ShapeType::PointM);
+        let mut buf = Vec::new();
+        point.write_shp_record(7, &mut buf).unwrap();
+        assert_eq!(&buf[..8], &[0, 0, 0, 7, 0, 0, 0, 14]);
+        assert_eq!(
+            PointM::read_shp_record(&mut buf.as_slice()).unwrap(),
+            (7, point)
+        );
+    }
+
+    #[test]
+    fn test_polyline() {
+        let p = |x, y, z| PointZ::new(x, y, z, None);
+        let line1 = LineStringT::<PointZ> {
+            srid: None,
+            points: vec![p(0., 0., 1.), p(1., 1., 2.)],
+        };
+        let line2 = LineStringT::<PointZ> {
+            srid: None,
+            points: vec![p(5., 5., 3.), p(6., 5., 4.), p(7., 7., 5.)],
+        };
+        let multi = MultiLineStringT::<PointZ> {
+            srid: None,
+            lines: vec![line1, line2],
+        };
+        assert_eq!(multi.shape_type(), ShapeType::PolyLineZ);
+        let mut buf = Vec::new();
+        multi.write_shp(&mut buf).unwrap();
+        // type, bbox, nparts, npoints, parts, xy, zrange, z (no M)
+        assert_eq!(buf.len(), 4 + 32 + 8 + 8 + 5 * 16 + 16 + 5 * 8);
+        assert_eq!(MultiLineStringT::<PointZ>::read_shp(&buf).unwrap(), multi);
+        let flat = MultiLineStringT::<Point>::read_shp(&buf).unwrap();
+        assert_eq!(flat.lines[1].points[2], Point::new(7., 7., None));
+    }
+
+    #[test]
+    fn test_polygon() {
+        let p = |x, y| Point::new(x, y, None);
+        let ring = |pts: Vec<Point>| LineStringT::<Point> {
+            srid: None,
+            points: pts,
+        };
+        // counter-clockwise exterior, clockwise hole: orientation is fixed on write
+        let shell = ring(vec![
+            p(0., 0.),
+            p(10., 0.),
+            p(10., 10.),
+            p(0., 10.),
+            p(0., 0.),
+        ]);
+        let hole = ring(vec![p(2., 2.), p(2., 4.), p(4., 4.), p(4., 2.), p(2., 2.)]);
+        let other = ring(vec![p(20., 20.), p(21., 20.), p(21., 21.), p(20., 20.)]);
+        let multi = MultiPolygonT::<Point> {
+            srid: None,
+            polygons: vec![
+                PolygonT {
+                    srid: None,
+                    rings: vec![shell, hole],
+                },
+                PolygonT {
+                    srid: None,
+                    rings: vec![other],
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        multi.write_shp(&mut buf).unwrap();
+        let read = MultiPolygonT::<Point>::read_shp(&buf).unwrap();
+        assert_eq!(read.polygons.len(), 2);
+        assert_eq!(read.polygons[0].rings.len(), 2);
+        assert_eq!(read.polygons[0].rings[0].points[1], p(0., 10.));
+        assert_eq!(read.polygons[0].rings[1].points[1], p(4., 2.));
+        match GeometryT::<Point>::read_shp(&buf).unwrap() {
+            GeometryT::MultiPolygon(mp) => assert_eq!(mp, read),
+            _ => panic!("expected MultiPolygon"),
+        }
+        assert!(MultiLineStringT::<Point>::read_shp(&buf).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Control of the PostgreSQL types accepted by `FromSql` and `ToSql`.
+//!
+//! By default, the geometry types of this crate accept columns and parameters
+//! of any type named `geometry` or `geography`. The [`Geometry`] and
+//! [`Geography`] wrappers only accept one kind of type:
+//!
+//! ```rust,no_run
+//! # use postgres::{Client, NoTls};
+//! use postgis::ewkb;
+//! use postgis::sql::Geography;
+//!
+//! # let mut client = Client::connect("host=localhost user=postgres", NoTls).unwrap();
+//! let row = client.query_one("SELECT location FROM stations", &[]).unwrap();
+//! // fails for geometry columns
+//! let Geography(location) = row.get::<_, Geography<ewkb::Point>>(0);
+//! ```
+//!
+//! Types living in other schemas, renamed types or domains are accepted by
+//! the [`Matching`] wrapper with a marker type implementing [`AcceptTypes`]:
+//!
+//! ```rust
+//! use postgis::{ewkb, sql::{AcceptTypes, Matching, TypeMatcher}};
+//! use postgres_types::{FromSql, Kind, Type};
+//! use std::sync::OnceLock;
+//!
+//! struct GisGeometry;
+//!
+//! impl AcceptTypes for GisGeometry {
+//!     fn accepts(ty: &Type) -> bool {
+//!         static TYPES: OnceLock<TypeMatcher> = OnceLock::new();
+//!         TYPES.get_or_init(|| TypeMatcher::new().name("gis.geometry").name("geom_3d")).matches(ty)
+//!     }
+//! }
+//!
+//! let ty = Type::new("geom_3d".to_string(), 90001, Kind::Simple, "public".to_string());
+//! assert!(<Matching<GisGeometry, ewkb::Point> as FromSql>::accepts(&ty));
+//! assert!(!<ewkb::Point as FromSql>::accepts(&ty));
+//! ```
+
+use bytes::BytesMut;
+use postgres_types::{to_sql_checked, FromSql, IsNull, ToSql, Type};
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Set of PostgreSQL types, given by name or OID.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct TypeMatcher {
+    names: Vec<String>,
+    oids: Vec<u32>,
+}
+
+impl TypeMatcher {
+    /// Matcher accepting no types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Types named `geometry` in any schema.
+    pub fn geometry() -> Self {
+        Self::new().name("geometry")
+    }
+
+    /// Types named `geography` in any schema.
+    pub fn geography() -> Self {
+        Self::new().name("geography")
+    }
+
+    /// Accept a type name, optionally schema qualified (`schema.name`).
+    pub fn name(mut self, name: &str) -> Self {
+        self.names.push(name.to_string());
+        self
+    }
+
+    /// Accept a type OID.
+    pub fn oid(mut self, oid: u32) -> Self {
+        self.oids.push(oid);
+        self
+    }
+
+    /// Whether `ty` is one of the types given by name or OID.
+    pub fn matches(&self, ty: &Type) -> bool {
+        self.oids.contains(&ty.oid())
+            || self.names.iter().any(|name| match name.rfind('.') {
+                Some(pos) => ty.schema() == &name[..pos] && ty.name() == &name[pos + 1..],
+                None => ty.name() == name,
+            })
+    }
+}
+
+/// Whether `ty` is a PostGIS geometry type, i.e. named `geometry` in any schema.
+pub fn is_geometry(ty: &Type) -> bool {
+    ty.name() == "geometry"
+}
+
+/// Whether `ty` is a PostGIS geography type, i.e. named `geography` in any schema.
+pub fn is_geography(ty: &Type) -> bool {
+    ty.name() == "geography"
+}
+
+macro_rules! impl_strict_wrapper {
+    ($wrapper:ident, $accepts:ident, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(PartialEq, Clone, Debug)]
+        pub struct $wrapper<T>(pub T);
+
+        impl<T> $wrapper<T> {
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<'a, T: FromSql<'a>> FromSql<'a> for $wrapper<T> {
+            fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+                T::from_sql(ty, raw).map($wrapper)
+            }
+
+            fn accepts(ty: &Type) -> bool {
+                $accepts(ty)
+            }
+        }
+
+        impl<T: ToSql> ToSql for $wrapper<T> {
+            fn to_sql(
+                &self,
+                ty: &Type,
+                out: &mut BytesMut,
+            ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                self.0.to_sql(ty, out)
+            }
+
+            fn accepts(ty: &Type) -> bool {
+                $accepts(ty)
+            }
+
+            to_sql_checked!();
+        }
+    };
+}
+
+impl_strict_wrapper!(
+    Geometry,
+    is_geometry,
+    "Value which is only accepted for geometry types."
+);
+impl_strict_wrapper!(
+    Geography,
+    is_geography,
+    "Value which is only accepted for geography types."
+);
+
+/// Types accepted by the [`Matching`] wrapper, implemented on a marker type.
+pub trait AcceptTypes {
+    /// Whether values can be read from and written to `ty`.
+    fn accepts(ty: &Type) -> bool;
+}
+
+/// Value which is only accepted for the types of `A`.
+pub struct Matching<A, T>(pub T, PhantomData<fn() -> A>);
+
+impl<A, T> Matching<A, T> {
+    pub fn new(value: T) -> Self {
+        Matching(value, PhantomData)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<A, T: fmt::Debug> fmt::Debug for Matching<A, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Matching").field(&self.0).finish()
+    }
+}
+
+impl<'a, A: AcceptTypes, T: FromSql<'a>> FromSql<'a> for Matching<A, T> {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        T::from_sql(ty, raw).map(Matching::new)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        A::accepts(ty)
+    }
+}
+
+impl<A: AcceptTypes, T: ToSql> ToSql for Matching<A, T> {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.0.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        A::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb;
+    use postgres_types::Kind;
+
+    fn pg_type(schema: &str, name: &str, oid: u32) -> Type {
+        Type::new(name.to_string(), oid, Kind::Simple, schema.to_string())
+    }
+
+    #[test]
+    fn test_type_matcher() {
+        let matcher = TypeMatcher::geometry().name("gis.geom_3d").oid(90001);
+        assert!(matcher.matches(&pg_type("public", "geometry", 18000)));
+        assert!(matcher.matches(&pg_type("gis", "geom_3d", 18001)));
+        assert!(!matcher.matches(&pg_type("public", "geom_3d", 18002)));
+        assert!(matcher.matches(&pg_type("public", "renamed", 90001)));
+        assert!(!matcher.matches(&pg_type("public", "geography", 18003)));
+        assert!(!TypeMatcher::new().matches(&pg_type("public", "geometry", 18000)));
+    }
+
+    #[test]
+    fn test_strict_accepts() {
+        let geometry = pg_type("public", "geometry", 18000);
+        let geography = pg_type("public", "geography", 18003);
+        assert!(<ewkb::Point as FromSql>::accepts(&geometry));
+        assert!(<ewkb::Point as FromSql>::accepts(&geography));
+        assert!(<Geometry<ewkb::Point> as FromSql>::accepts(&geometry));
+        assert!(!<Geometry<ewkb::Point> as FromSql>::accepts(&geography));
+        assert!(!<Geography<ewkb::Point> as ToSql>::accepts(&geometry));
+        assert!(<Geography<ewkb::Point> as ToSql>::accepts(&geography));
+    }
+
+    struct Renamed;
+
+    impl AcceptTypes for Renamed {
+        fn accepts(ty: &Type) -> bool {
+            TypeMatcher::new().name("gis.geom").oid(90001).matches(ty)
+        }
+    }
+
+    #[test]
+    fn test_matching() {
+        let renamed = pg_type("gis", "geom", 18000);
+        let geometry = pg_type("public", "geometry", 18001);
+        assert!(<Matching<Renamed, ewkb::Point> as FromSql>::accepts(
+            &renamed
+        ));
+        assert!(!<Matching<Renamed, ewkb::Point> as ToSql>::accepts(
+            &geometry
+        ));
+        assert!(<Matching<Renamed, ewkb::Point> as ToSql>::accepts(
+            &pg_type("public", "geom", 90001)
+        ));
+        assert!(!<ewkb::Point as FromSql>::accepts(&renamed));
+
+        let point = Matching::<Renamed, _>::new(ewkb::Point::new(1.0, 2.0, None));
+        let mut out = BytesMut::new();
+        point.to_sql_checked(&renamed, &mut out).unwrap();
+        assert!(point.to_sql_checked(&geometry, &mut out).is_err());
+        let read = Matching::<Renamed, ewkb::Point>::from_sql(&renamed, &out).unwrap();
+        assert_eq!(read.into_inner(), point.into_inner());
+    }
+}
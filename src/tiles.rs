@@ -0,0 +1,234 @@
+//
+// Copyright (c) ShuYu Wang <andelf@gmail.com>, Feather Workshop and Pirmin Kalberer. All rights reserved.
+//
+
+//! Slippy-map tile helpers for the Web Mercator (EPSG:3857) tile pyramid.
+//!
+//! Tiles are addressed in XYZ scheme (origin top left) by default,
+//! [`Tile::from_tms`] and [`Tile::tms_y`] convert from and to TMS (origin bottom left).
+//!
+//! ```rust
+//! use postgis::tiles::{self, Tile};
+//!
+//! let tile = Tile::from_lonlat(8.54, 47.37, 10);
+//! assert_eq!(tile, Tile::new(536, 358, 10));
+//! let bounds = tile.bounds();
+//! assert_eq!(tiles::tiles_covering(&bounds, 11).count(), 4);
+//! ```
+
+use crate::{envelope::Envelope, ewkb};
+use std::f64::consts::PI;
+
+/// Half circumference of the Web Mercator sphere in meters.
+pub const EXTENT: f64 = 20037508.342789244;
+/// SRID of Web Mercator.
+pub const WEB_MERCATOR_SRID: i32 = 3857;
+
+/// Convert WGS-84 longitude/latitude into Web Mercator meters.
+pub fn lonlat_to_mercator(lon: f64, lat: f64) -> (f64, f64) {
+    let x = lon * EXTENT / 180.0;
+    let y = ((90.0 + lat) * PI / 360.0).tan().ln() * EXTENT / PI;
+    (x, y)
+}
+
+/// Convert Web Mercator meters into WGS-84 longitude/latitude.
+pub fn mercator_to_lonlat(x: f64, y: f64) -> (f64, f64) {
+    let lon = x * 180.0 / EXTENT;
+    let lat = (2.0 * (y * PI / EXTENT).exp().atan() - PI / 2.0).to_degrees();
+    (lon, lat)
+}
+
+/// Tile address in XYZ scheme.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub z: u8,
+}
+
+fn tile_size(z: u8) -> f64 {
+    2.0 * EXTENT / (1u64 << z) as f64
+}
+
+fn clamp_index(v: f64, z: u8) -> u32 {
+    let max = ((1u64 << z) - 1) as f64;
+    v.max(0.0).min(max) as u32
+}
+
+impl Tile {
+    pub fn new(x: u32, y: u32, z: u8) -> Self {
+        Tile { x, y, z }
+    }
+
+    /// Tile from TMS address, where row 0 is at the bottom.
+    pub fn from_tms(x: u32, y: u32, z: u8) -> Self {
+        Tile::new(x, ((1u64 << z) - 1) as u32 - y, z)
+    }
+
+    /// Row of this tile in TMS scheme.
+    pub fn tms_y(&self) -> u32 {
+        ((1u64 << self.z) - 1) as u32 - self.y
+    }
+
+    /// Tile containing the given Web Mercator coordinate.
+    pub fn from_mercator(x: f64, y: f64, z: u8) -> Self {
+        let size = tile_size(z);
+        Tile::new(
+            clamp_index(((x + EXTENT) / size).floor(), z),
+            clamp_index(((EXTENT - y) / size).floor(), z),
+            z,
+        )
+    }
+
+    /// Tile containing the given WGS-84 coordinate.
+    pub fn from_lonlat(lon: f64, lat: f64, z: u8) -> Self {
+        let (x, y) = lonlat_to_mercator(lon, lat);
+        Tile::from_mercator(x, y, z)
+    }
+
+    /// Tile bounds in Web Mercator meters.
+    pub fn bounds(&self) -> Envelope {
+        let size = tile_size(self.z);
+        let xmin = -EXTENT + self.x as f64 * size;
+        let ymax = EXTENT - self.y as f64 * size;
+        Envelope::new(xmin, ymax - size, xmin + size, ymax)
+    }
+
+    /// Tile bounds in WGS-84 longitude/latitude.
+    pub fn bounds_lonlat(&self) -> Envelope {
+        let b = self.bounds();
+        let (xmin, ymin) = mercator_to_lonlat(b.xmin, b.ymin);
+        let (xmax, ymax) = mercator_to_lonlat(b.xmax, b.ymax);
+        Envelope::new(xmin, ymin, xmax, ymax)
+    }
+
+    /// Tile boundary as polygon with SRID 3857.
+    pub fn to_polygon(&self) -> ewkb::Polygon {
+        self.bounds().to_polygon(Some(WEB_MERCATOR_SRID))
+    }
+
+    /// Parent tile one zoom level up. Returns `None` for zoom level 0.
+    pub fn parent(&self) -> Option<Tile> {
+        if self.z == 0 {
+            None
+        } else {
+            Some(Tile::new(self.x / 2, self.y / 2, self.z - 1))
+        }
+    }
+
+    /// The four tiles one zoom level down.
+    pub fn children(&self) -> [Tile; 4] {
+        let (x, y, z) = (self.x * 2, self.y * 2, self.z + 1);
+        [
+            Tile::new(x, y, z),
+            Tile::new(x + 1, y, z),
+            Tile::new(x, y + 1, z),
+            Tile::new(x + 1, y + 1, z),
+        ]
+    }
+}
+
+/// Iterate over all tiles at `zoom` intersecting a Web Mercator envelope.
+///
+/// Tiles only touching the envelope at their border are not included.
+pub fn tiles_covering(bbox: &Envelope, zoom: u8) -> TileIter {
+    let size = tile_size(zoom);
+    let xmin = clamp_index(((bbox.xmin + EXTENT) / size).floor(), zoom);
+    let xmax = clamp_index(((bbox.xmax + EXTENT) / size).ceil() - 1.0, zoom).max(xmin);
+    let ymin = clamp_index(((EXTENT - bbox.ymax) / size).floor(), zoom);
+    let ymax = clamp_index(((EXTENT - bbox.ymin) / size).ceil() - 1.0, zoom).max(ymin);
+    TileIter {
+        zoom,
+        xmin,
+        xmax,
+        ymax,
+        x: xmin,
+        y: ymin,
+    }
+}
+
+/// Iterate over all tiles at `zoom` intersecting a WGS-84 envelope.
+pub fn tiles_covering_lonlat(bbox: &Envelope, zoom: u8) -> TileIter {
+    let (xmin, ymin) = lonlat_to_mercator(bbox.xmin, bbox.ymin);
+    let (xmax, ymax) = lonlat_to_mercator(bbox.xmax, bbox.ymax);
+    tiles_covering(&Envelope::new(xmin, ymin, xmax, ymax), zoom)
+}
+
+/// Iterator returned by [`tiles_covering`], yielding tiles row by row.
+#[derive(Clone, Debug)]
+pub struct TileIter {
+    zoom: u8,
+    xmin: u32,
+    xmax: u32,
+    ymax: u32,
+    x: u32,
+    y: u32,
+}
+
+impl Iterator for TileIter {
+    type Item = Tile;
+
+    fn next(&mut self) -> Option<Tile> {
+        if self.y > self.ymax {
+            return None;
+        }
+        let tile = Tile::new(self.x, self.y, self.zoom);
+        if self.x == self.xmax {
+            self.x = self.xmin;
+            self.y += 1;
+        } else {
+            self.x += 1;
+        }
+        Some(tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_bounds() {
+        let tile = Tile::new(0, 0, 0);
+        assert_eq!(
+            tile.bounds(),
+            Envelope::new(-EXTENT, -EXTENT, EXTENT, EXTENT)
+        );
+        let tile = Tile::new(1, 0, 1);
+        assert_eq!(tile.bounds(), Envelope::new(0.0, 0.0, EXTENT, EXTENT));
+        let b = tile.bounds_lonlat();
+        assert!((b.xmax - 180.0).abs() < 1e-9);
+        assert!((b.ymax - 85.0511287798).abs() < 1e-9);
+        assert_eq!(Tile::from_mercator(1.0, 1.0, 1), tile);
+    }
+
+    #[test]
+    fn test_tms() {
+        let tile = Tile::new(3, 1, 2);
+        assert_eq!(tile.tms_y(), 2);
+        assert_eq!(Tile::from_tms(3, 2, 2), tile);
+        assert_eq!(tile.parent(), Some(Tile::new(1, 0, 1)));
+        assert!(tile.children().iter().all(|c| c.parent() == Some(tile)));
+    }
+
+    #[test]
+    fn test_tiles_covering() {
+        let tile = Tile::new(5, 9, 4);
+        let tiles: Vec<_> = tiles_covering(&tile.bounds(), 4).collect();
+        assert_eq!(tiles, vec![tile]);
+        let tiles: Vec<_> = tiles_covering(&tile.bounds(), 5).collect();
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(tiles[0], Tile::new(10, 18, 5));
+        assert_eq!(tiles[3], Tile::new(11, 19, 5));
+        let world = Envelope::new(-180.0, -85.0, 180.0, 85.0);
+        assert_eq!(tiles_covering_lonlat(&world, 3).count(), 64);
+    }
+
+    #[test]
+    fn test_tile_polygon() {
+        let poly = Tile::new(0, 0, 0).to_polygon();
+        assert_eq!(poly.srid, Some(3857));
+        assert_eq!(poly.rings[0].points.len(), 5);
+        assert_eq!(poly.rings[0].points[2].x, EXTENT);
+    }
+}
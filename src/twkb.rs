@@ -2,7 +2,7 @@
 // Copyright (c) Pirmin Kalberer. All rights reserved.
 //
 
-//! Read geometries in [Tiny WKB](https://github.com/TWKB/Specification/blob/master/twkb.md) format.
+//! Read and write geometries in [Tiny WKB](https://github.com/TWKB/Specification/blob/master/twkb.md) format.
 //!
 //! ```rust,no_run
 //! # use postgres::{Client, NoTls};
@@ -16,48 +16,143 @@
 //! }
 //! ```
 
-use crate::{error::Error, ewkb, types as postgis};
-use byteorder::ReadBytesExt;
-use std::f64;
-use std::fmt;
-use std::io::prelude::*;
-use std::slice::Iter;
-use std::u8;
-
-#[derive(PartialEq, Clone, Copy, Debug)]
+use crate::io::{Read, ReadBytesExt};
+use crate::{
+    envelope::Envelope,
+    error::Error,
+    ewkb::{self, BitEq},
+    types as postgis,
+};
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec::Vec};
+use core::f64;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::slice::Iter;
+use core::u8;
+
+#[derive(PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub x: f64,
-    pub y: f64, // TODO: support for z, m
+    pub y: f64,
+    pub z: Option<f64>,
+    pub m: Option<f64>,
+}
+
+impl BitEq for Point {
+    fn bit_eq(&self, other: &Self) -> bool {
+        self.x.bit_eq(&other.x)
+            && self.y.bit_eq(&other.y)
+            && self.z.bit_eq(&other.z)
+            && self.m.bit_eq(&other.m)
+    }
+
+    fn bit_hash<H: Hasher>(&self, state: &mut H) {
+        self.x.bit_hash(state);
+        self.y.bit_hash(state);
+        self.z.bit_hash(state);
+        self.m.bit_hash(state);
+    }
+}
+
+// Z and M are only shown when present
+impl fmt::Debug for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Point");
+        s.field("x", &self.x).field("y", &self.y);
+        if let Some(ref z) = self.z {
+            s.field("z", z);
+        }
+        if let Some(ref m) = self.m {
+            s.field("m", m);
+        }
+        s.finish()
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineString {
     pub points: Vec<Point>,
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polygon {
     pub rings: Vec<LineString>,
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiPoint {
     pub points: Vec<Point>,
     pub ids: Option<Vec<u64>>,
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiLineString {
     pub lines: Vec<LineString>,
     pub ids: Option<Vec<u64>>,
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiPolygon {
     pub polygons: Vec<Polygon>,
     pub ids: Option<Vec<u64>>,
 }
 
+macro_rules! impl_ids {
+    ($geotype:ident, $itemtype:ident named $itemname:ident) => {
+        impl $geotype {
+            /// Create from `(id, member)` pairs, with an id list.
+            pub fn from_id_pairs<I: IntoIterator<Item = (u64, $itemtype)>>(pairs: I) -> Self {
+                let (ids, $itemname): (Vec<u64>, Vec<$itemtype>) = pairs.into_iter().unzip();
+                $geotype {
+                    $itemname,
+                    ids: Some(ids),
+                }
+            }
+
+            /// Members paired with their id. The id is `None` without id list.
+            pub fn iter_with_ids(&self) -> impl Iterator<Item = (Option<u64>, &$itemtype)> + '_ {
+                let ids = self.ids.as_deref().unwrap_or(&[]);
+                self.$itemname
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, item)| (ids.get(i).copied(), item))
+            }
+        }
+    };
+}
+
+impl_ids!(MultiPoint, Point named points);
+impl_ids!(MultiLineString, LineString named lines);
+impl_ids!(MultiPolygon, Polygon named polygons);
+
+macro_rules! impl_bit_eq {
+    ($geotype:ident: $itemname:ident $(, $ids:ident)?) => {
+        impl BitEq for $geotype {
+            fn bit_eq(&self, other: &Self) -> bool {
+                self.$itemname[..].bit_eq(&other.$itemname[..]) $(&& self.$ids == other.$ids)?
+            }
+
+            fn bit_hash<H: Hasher>(&self, state: &mut H) {
+                self.$itemname[..].bit_hash(state);
+                $(self.$ids.hash(state);)?
+            }
+        }
+    };
+}
+
+impl_bit_eq!(LineString: points);
+impl_bit_eq!(Polygon: rings);
+impl_bit_eq!(MultiPoint: points, ids);
+impl_bit_eq!(MultiLineString: lines, ids);
+impl_bit_eq!(MultiPolygon: polygons, ids);
+
 #[doc(hidden)]
 #[derive(Default, Debug)]
 pub struct TwkbInfo {
@@ -74,46 +169,20 @@ pub struct TwkbInfo {
 
 pub trait TwkbGeom: fmt::Debug + Sized {
     fn read_twkb<R: Read>(raw: &mut R) -> Result<Self, Error> {
-        let mut twkb_info: TwkbInfo = Default::default();
-        // type_and_prec     byte
-        // metadata_header   byte
-        // [extended_dims]   byte
-        // [size]            uvarint
-        // [bounds]          bbox
-        let type_and_prec = raw.read_u8()?;
-        twkb_info.geom_type = type_and_prec & 0x0F;
-        twkb_info.precision = decode_zig_zag_64(((type_and_prec & 0xF0) >> 4) as u64) as i8;
-        let metadata_header = raw.read_u8()?;
-        let has_bbox = (metadata_header & 0b0001) != 0;
-        let has_size_attribute = (metadata_header & 0b0010) != 0;
-        twkb_info.has_idlist = (metadata_header & 0b0100) != 0;
-        let has_ext_prec_info = (metadata_header & 0b1000) != 0;
-        twkb_info.is_empty_geom = (metadata_header & 0b10000) != 0;
-        if has_ext_prec_info {
-            let ext_prec_info = raw.read_u8()?;
-            twkb_info.has_z = ext_prec_info & 0b0001 != 0;
-            twkb_info.has_m = ext_prec_info & 0b0010 != 0;
-            twkb_info.prec_z = Some((ext_prec_info & 0x1C) >> 2);
-            twkb_info.prec_m = Some((ext_prec_info & 0xE0) >> 5);
-        }
-        if has_size_attribute {
-            twkb_info.size = Some(read_raw_varint64(raw)?);
-        }
-        if has_bbox {
-            let _xmin = read_int64(raw)?;
-            let _deltax = read_int64(raw)?;
-            let _ymin = read_int64(raw)?;
-            let _deltay = read_int64(raw)?;
-            if twkb_info.has_z {
-                let _zmin = read_int64(raw)?;
-                let _deltaz = read_int64(raw)?;
-            }
-            if twkb_info.has_m {
-                let _mmin = read_int64(raw)?;
-                let _deltam = read_int64(raw)?;
-            }
-        }
-        Self::read_twkb_body(raw, &twkb_info)
+        Self::read_twkb_with_bbox(raw).map(|(geom, _)| geom)
+    }
+
+    /// Read a TWKB geometry and its bounding box, if the bbox flag is set.
+    /// Only the X/Y extent is returned, Z and M ranges are skipped.
+    fn read_twkb_with_bbox<R: Read>(raw: &mut R) -> Result<(Self, Option<Envelope>), Error> {
+        let (twkb_info, bbox) = read_header(raw)?;
+        let geom = Self::read_twkb_body(raw, &twkb_info)?;
+        Ok((geom, bbox))
+    }
+
+    /// Read a TWKB geometry from a byte slice.
+    fn read_twkb_from_slice(mut data: &[u8]) -> Result<Self, Error> {
+        Self::read_twkb(&mut data)
     }
 
     #[doc(hidden)]
@@ -131,13 +200,13 @@ pub trait TwkbGeom: fmt::Debug + Sized {
         let x2 = x + read_varint64_as_f64(raw, twkb_info.precision)?;
         let y2 = y + read_varint64_as_f64(raw, twkb_info.precision)?;
         let z2 = if twkb_info.has_z {
-            let dz = read_varint64_as_f64(raw, twkb_info.precision)?;
+            let dz = read_varint64_as_f64(raw, twkb_info.prec_z.unwrap_or(0) as i8)?;
             z.map(|v| v + dz)
         } else {
             None
         };
         let m2 = if twkb_info.has_m {
-            let dm = read_varint64_as_f64(raw, twkb_info.precision)?;
+            let dm = read_varint64_as_f64(raw, twkb_info.prec_m.unwrap_or(0) as i8)?;
             m.map(|v| v + dm)
         } else {
             None
@@ -158,7 +227,53 @@ pub trait TwkbGeom: fmt::Debug + Sized {
 
 // --- helper functions for reading ---
 
-fn read_raw_varint64<R: Read>(raw: &mut R) -> Result<u64, Error> {
+fn read_header<R: Read>(raw: &mut R) -> Result<(TwkbInfo, Option<Envelope>), Error> {
+    let mut twkb_info: TwkbInfo = Default::default();
+    // type_and_prec     byte
+    // metadata_header   byte
+    // [extended_dims]   byte
+    // [size]            uvarint
+    // [bounds]          bbox
+    let type_and_prec = raw.read_u8()?;
+    twkb_info.geom_type = type_and_prec & 0x0F;
+    twkb_info.precision = decode_zig_zag_64(((type_and_prec & 0xF0) >> 4) as u64) as i8;
+    let metadata_header = raw.read_u8()?;
+    let has_bbox = (metadata_header & 0b0001) != 0;
+    let has_size_attribute = (metadata_header & 0b0010) != 0;
+    twkb_info.has_idlist = (metadata_header & 0b0100) != 0;
+    let has_ext_prec_info = (metadata_header & 0b1000) != 0;
+    twkb_info.is_empty_geom = (metadata_header & 0b10000) != 0;
+    if has_ext_prec_info {
+        let ext_prec_info = raw.read_u8()?;
+        twkb_info.has_z = ext_prec_info & 0b0001 != 0;
+        twkb_info.has_m = ext_prec_info & 0b0010 != 0;
+        twkb_info.prec_z = Some((ext_prec_info & 0x1C) >> 2);
+        twkb_info.prec_m = Some((ext_prec_info & 0xE0) >> 5);
+    }
+    if has_size_attribute {
+        twkb_info.size = Some(read_raw_varint64(raw)?);
+    }
+    let bbox = if has_bbox {
+        let xmin = read_varint64_as_f64(raw, twkb_info.precision)?;
+        let deltax = read_varint64_as_f64(raw, twkb_info.precision)?;
+        let ymin = read_varint64_as_f64(raw, twkb_info.precision)?;
+        let deltay = read_varint64_as_f64(raw, twkb_info.precision)?;
+        if twkb_info.has_z {
+            let _zmin = read_int64(raw)?;
+            let _deltaz = read_int64(raw)?;
+        }
+        if twkb_info.has_m {
+            let _mmin = read_int64(raw)?;
+            let _deltam = read_int64(raw)?;
+        }
+        Some(Envelope::new(xmin, ymin, xmin + deltax, ymin + deltay))
+    } else {
+        None
+    };
+    Ok((twkb_info, bbox))
+}
+
+pub(crate) fn read_raw_varint64<R: Read>(raw: &mut R) -> Result<u64, Error> {
     // from rust-protobuf
     let mut r: u64 = 0;
     let mut i = 0;
@@ -180,7 +295,7 @@ fn read_int64<R: Read>(raw: &mut R) -> Result<i64, Error> {
     read_raw_varint64(raw).map(|v| v as i64)
 }
 
-fn decode_zig_zag_64(n: u64) -> i64 {
+pub(crate) fn decode_zig_zag_64(n: u64) -> i64 {
     ((n >> 1) as i64) ^ (-((n & 1) as i64))
 }
 
@@ -199,8 +314,8 @@ fn read_varint64_as_f64<R: Read>(raw: &mut R, precision: i8) -> Result<f64, Erro
 // ---
 
 impl Point {
-    fn new_from_opt_vals(x: f64, y: f64, _z: Option<f64>, _m: Option<f64>) -> Self {
-        Point { x: x, y: y }
+    fn new_from_opt_vals(x: f64, y: f64, z: Option<f64>, m: Option<f64>) -> Self {
+        Point { x, y, z, m }
     }
 }
 
@@ -211,6 +326,12 @@ impl postgis::Point for Point {
     fn y(&self) -> f64 {
         self.y
     }
+    fn opt_z(&self) -> Option<f64> {
+        self.z
+    }
+    fn opt_m(&self) -> Option<f64> {
+        self.m
+    }
 }
 
 impl TwkbGeom for Point {
@@ -221,12 +342,18 @@ impl TwkbGeom for Point {
         let x = read_varint64_as_f64(raw, twkb_info.precision)?;
         let y = read_varint64_as_f64(raw, twkb_info.precision)?;
         let z = if twkb_info.has_z {
-            Some(read_varint64_as_f64(raw, twkb_info.precision)?)
+            Some(read_varint64_as_f64(
+                raw,
+                twkb_info.prec_z.unwrap_or(0) as i8,
+            )?)
         } else {
             None
         };
         let m = if twkb_info.has_m {
-            Some(read_varint64_as_f64(raw, twkb_info.precision)?)
+            Some(read_varint64_as_f64(
+                raw,
+                twkb_info.prec_m.unwrap_or(0) as i8,
+            )?)
         } else {
             None
         };
@@ -248,24 +375,10 @@ impl TwkbGeom for LineString {
     fn read_twkb_body<R: Read>(raw: &mut R, twkb_info: &TwkbInfo) -> Result<Self, Error> {
         // npoints           uvarint
         // pointarray        varint[]
-        let mut points: Vec<Point> = Vec::new();
-        if !twkb_info.is_empty_geom {
-            let npoints = read_raw_varint64(raw)?;
-            points.reserve(npoints as usize);
-            let mut x = 0.0;
-            let mut y = 0.0;
-            let mut z = if twkb_info.has_z { Some(0.0) } else { None };
-            let mut m = if twkb_info.has_m { Some(0.0) } else { None };
-            for _ in 0..npoints {
-                let (x2, y2, z2, m2) = Self::read_relative_point(raw, twkb_info, x, y, z, m)?;
-                points.push(Point::new_from_opt_vals(x2, y2, z2, m2));
-                x = x2;
-                y = y2;
-                z = z2;
-                m = m2;
-            }
+        if twkb_info.is_empty_geom {
+            return Ok(LineString { points: Vec::new() });
         }
-        Ok(LineString { points: points })
+        Cursor::new(twkb_info).read_line(raw, twkb_info)
     }
 }
 
@@ -297,33 +410,7 @@ impl TwkbGeom for Polygon {
         // ...
         // npoints[n]        uvarint
         // pointarray[n]     varint[]
-        let mut rings: Vec<LineString> = Vec::new();
-        let nrings = read_raw_varint64(raw)?;
-        rings.reserve(nrings as usize);
-        let mut x = 0.0;
-        let mut y = 0.0;
-        let mut z = if twkb_info.has_z { Some(0.0) } else { None };
-        let mut m = if twkb_info.has_m { Some(0.0) } else { None };
-        for _ in 0..nrings {
-            let mut points: Vec<Point> = Vec::new();
-            let npoints = read_raw_varint64(raw)?;
-            points.reserve(npoints as usize);
-            let (x0, y0, z0, m0) = (x, y, z, m);
-            for _ in 0..npoints {
-                let (x2, y2, z2, m2) = Self::read_relative_point(raw, twkb_info, x, y, z, m)?;
-                points.push(Point::new_from_opt_vals(x2, y2, z2, m2));
-                x = x2;
-                y = y2;
-                z = z2;
-                m = m2;
-            }
-            // close ring, if necessary
-            if x != x0 && y != y0 && z != z0 && m != m0 {
-                points.push(Point::new_from_opt_vals(x0, y0, z0, m0));
-            }
-            rings.push(LineString { points: points });
-        }
-        Ok(Polygon { rings: rings })
+        Cursor::new(twkb_info).read_polygon(raw, twkb_info)
     }
 }
 
@@ -360,25 +447,11 @@ impl TwkbGeom for MultiPoint {
         let mut ids: Option<Vec<u64>> = None;
         if !twkb_info.is_empty_geom {
             let npoints = read_raw_varint64(raw)?;
-            points.reserve(npoints as usize);
-
             if twkb_info.has_idlist {
                 let idlist = Self::read_idlist(raw, npoints as usize)?;
                 ids = Some(idlist);
             }
-
-            let mut x = 0.0;
-            let mut y = 0.0;
-            let mut z = if twkb_info.has_z { Some(0.0) } else { None };
-            let mut m = if twkb_info.has_m { Some(0.0) } else { None };
-            for _ in 0..npoints {
-                let (x2, y2, z2, m2) = Self::read_relative_point(raw, twkb_info, x, y, z, m)?;
-                points.push(Point::new_from_opt_vals(x2, y2, z2, m2));
-                x = x2;
-                y = y2;
-                z = z2;
-                m = m2;
-            }
+            points = Cursor::new(twkb_info).read_points(raw, twkb_info, npoints)?;
         }
         Ok(MultiPoint {
             points: points,
@@ -426,23 +499,9 @@ impl TwkbGeom for MultiLineString {
             ids = Some(idlist);
         }
 
-        let mut x = 0.0;
-        let mut y = 0.0;
-        let mut z = if twkb_info.has_z { Some(0.0) } else { None };
-        let mut m = if twkb_info.has_m { Some(0.0) } else { None };
+        let mut cursor = Cursor::new(twkb_info);
         for _ in 0..nlines {
-            let mut points: Vec<Point> = Vec::new();
-            let npoints = read_raw_varint64(raw)?;
-            points.reserve(npoints as usize);
-            for _ in 0..npoints {
-                let (x2, y2, z2, m2) = Self::read_relative_point(raw, twkb_info, x, y, z, m)?;
-                points.push(Point::new_from_opt_vals(x2, y2, z2, m2));
-                x = x2;
-                y = y2;
-                z = z2;
-                m = m2;
-            }
-            lines.push(LineString { points: points });
+            lines.push(cursor.read_line(raw, twkb_info)?);
         }
         Ok(MultiLineString {
             lines: lines,
@@ -497,34 +556,9 @@ impl TwkbGeom for MultiPolygon {
             ids = Some(idlist);
         }
 
-        let mut x = 0.0;
-        let mut y = 0.0;
-        let mut z = if twkb_info.has_z { Some(0.0) } else { None };
-        let mut m = if twkb_info.has_m { Some(0.0) } else { None };
+        let mut cursor = Cursor::new(twkb_info);
         for _ in 0..npolygons {
-            let mut rings: Vec<LineString> = Vec::new();
-            let nrings = read_raw_varint64(raw)?;
-            rings.reserve(nrings as usize);
-            for _ in 0..nrings {
-                let mut points: Vec<Point> = Vec::new();
-                let npoints = read_raw_varint64(raw)?;
-                points.reserve(npoints as usize);
-                let (x0, y0, z0, m0) = (x, y, z, m);
-                for _ in 0..npoints {
-                    let (x2, y2, z2, m2) = Self::read_relative_point(raw, twkb_info, x, y, z, m)?;
-                    points.push(Point::new_from_opt_vals(x2, y2, z2, m2));
-                    x = x2;
-                    y = y2;
-                    z = z2;
-                    m = m2;
-                }
-                // close ring, if necessary
-                if x != x0 && y != y0 && z != z0 && m != m0 {
-                    points.push(Point::new_from_opt_vals(x0, y0, z0, m0));
-                }
-                rings.push(LineString { points: points });
-            }
-            polygons.push(Polygon { rings: rings });
+            polygons.push(cursor.read_polygon(raw, twkb_info)?);
         }
         Ok(MultiPolygon {
             polygons: polygons,
@@ -567,10 +601,652 @@ impl<'a> ewkb::AsEwkbMultiPolygon<'a> for MultiPolygon {
     }
 }
 
+// --- delta decoding ---
+
+// Last decoded position. The coordinates of all points of a geometry, across
+// rings and members of multi geometries, are deltas to the previous point.
+struct Cursor {
+    x: f64,
+    y: f64,
+    z: Option<f64>,
+    m: Option<f64>,
+}
+
+impl Cursor {
+    fn new(twkb_info: &TwkbInfo) -> Self {
+        Cursor {
+            x: 0.0,
+            y: 0.0,
+            z: if twkb_info.has_z { Some(0.0) } else { None },
+            m: if twkb_info.has_m { Some(0.0) } else { None },
+        }
+    }
+
+    fn read_point<R: Read>(&mut self, raw: &mut R, twkb_info: &TwkbInfo) -> Result<Point, Error> {
+        let (x, y, z, m) =
+            Point::read_relative_point(raw, twkb_info, self.x, self.y, self.z, self.m)?;
+        *self = Cursor { x, y, z, m };
+        Ok(Point::new_from_opt_vals(x, y, z, m))
+    }
+
+    fn read_points<R: Read>(
+        &mut self,
+        raw: &mut R,
+        twkb_info: &TwkbInfo,
+        npoints: u64,
+    ) -> Result<Vec<Point>, Error> {
+        let mut points = Vec::with_capacity(npoints as usize);
+        for _ in 0..npoints {
+            points.push(self.read_point(raw, twkb_info)?);
+        }
+        Ok(points)
+    }
+
+    fn read_line<R: Read>(
+        &mut self,
+        raw: &mut R,
+        twkb_info: &TwkbInfo,
+    ) -> Result<LineString, Error> {
+        let npoints = read_raw_varint64(raw)?;
+        let points = self.read_points(raw, twkb_info, npoints)?;
+        Ok(LineString { points })
+    }
+
+    fn read_ring<R: Read>(
+        &mut self,
+        raw: &mut R,
+        twkb_info: &TwkbInfo,
+    ) -> Result<LineString, Error> {
+        let (x0, y0, z0, m0) = (self.x, self.y, self.z, self.m);
+        let mut ring = self.read_line(raw, twkb_info)?;
+        // close ring, if necessary
+        if self.x != x0 && self.y != y0 && self.z != z0 && self.m != m0 {
+            ring.points.push(Point::new_from_opt_vals(x0, y0, z0, m0));
+        }
+        Ok(ring)
+    }
+
+    fn read_polygon<R: Read>(
+        &mut self,
+        raw: &mut R,
+        twkb_info: &TwkbInfo,
+    ) -> Result<Polygon, Error> {
+        let nrings = read_raw_varint64(raw)?;
+        let mut rings = Vec::with_capacity(nrings as usize);
+        for _ in 0..nrings {
+            rings.push(self.read_ring(raw, twkb_info)?);
+        }
+        Ok(Polygon { rings })
+    }
+}
+
+// --- streaming ---
+
+/// TWKB geometry of any type except GeometryCollection, see [`TwkbStream`].
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Geometry {
+    Point(Point),
+    LineString(LineString),
+    Polygon(Polygon),
+    MultiPoint(MultiPoint),
+    MultiLineString(MultiLineString),
+    MultiPolygon(MultiPolygon),
+}
+
+impl TwkbGeom for Geometry {
+    fn read_twkb_body<R: Read>(raw: &mut R, twkb_info: &TwkbInfo) -> Result<Self, Error> {
+        match twkb_info.geom_type {
+            1 => Point::read_twkb_body(raw, twkb_info).map(Geometry::Point),
+            2 => LineString::read_twkb_body(raw, twkb_info).map(Geometry::LineString),
+            3 => Polygon::read_twkb_body(raw, twkb_info).map(Geometry::Polygon),
+            4 => MultiPoint::read_twkb_body(raw, twkb_info).map(Geometry::MultiPoint),
+            5 => MultiLineString::read_twkb_body(raw, twkb_info).map(Geometry::MultiLineString),
+            6 => MultiPolygon::read_twkb_body(raw, twkb_info).map(Geometry::MultiPolygon),
+            code => Err(Error::Read(format!(
+                "unsupported TWKB geometry type {}",
+                code
+            ))),
+        }
+    }
+}
+
+/// Reads the members of a TWKB MultiPoint, MultiLineString, MultiPolygon or
+/// GeometryCollection one at a time, so large aggregates can be processed
+/// with bounded memory.
+///
+/// ```rust
+/// use postgis::ewkb::{AsEwkbMultiLineString, EwkbWrite, MultiLineString};
+/// use postgis::twkb::{self, Geometry, TwkbStream};
+///
+/// let multi = MultiLineString::from(vec![vec![(0.0, 0.0), (1.0, 1.0)], vec![(2.0, 2.0), (3.0, 3.0)]]);
+/// let mut ewkb = Vec::new();
+/// multi.as_ewkb().write_ewkb(&mut ewkb).unwrap();
+/// let data = twkb::ewkb_to_twkb(&ewkb, &Default::default(), None).unwrap();
+///
+/// let stream = TwkbStream::new(data.as_slice()).unwrap();
+/// assert_eq!(stream.len(), 2);
+/// for member in stream {
+///     match member.unwrap() {
+///         Geometry::LineString(line) => assert_eq!(line.points.len(), 2),
+///         other => panic!("unexpected {:?}", other),
+///     }
+/// }
+/// ```
+pub struct TwkbStream<R: Read> {
+    raw: R,
+    twkb_info: TwkbInfo,
+    bbox: Option<Envelope>,
+    ids: Option<Vec<u64>>,
+    cursor: Cursor,
+    remaining: usize,
+    failed: bool,
+}
+
+impl<R: Read> TwkbStream<R> {
+    /// Parse the geometry header, member count and id list.
+    pub fn new(mut raw: R) -> Result<Self, Error> {
+        let (twkb_info, bbox) = read_header(&mut raw)?;
+        if !(4..=7).contains(&twkb_info.geom_type) {
+            return Err(Error::Read(format!(
+                "cannot stream members of TWKB geometry type {}",
+                twkb_info.geom_type
+            )));
+        }
+        let remaining = if twkb_info.is_empty_geom {
+            0
+        } else {
+            read_raw_varint64(&mut raw)? as usize
+        };
+        let ids = if twkb_info.has_idlist && remaining > 0 {
+            Some(MultiPoint::read_idlist(&mut raw, remaining)?)
+        } else {
+            None
+        };
+        let cursor = Cursor::new(&twkb_info);
+        Ok(TwkbStream {
+            raw,
+            twkb_info,
+            bbox,
+            ids,
+            cursor,
+            remaining,
+            failed: false,
+        })
+    }
+
+    /// Bounding box of the whole geometry, if the TWKB has one.
+    pub fn bbox(&self) -> Option<Envelope> {
+        self.bbox
+    }
+
+    /// Ids of all members, if the TWKB has an id list.
+    pub fn ids(&self) -> Option<&[u64]> {
+        self.ids.as_deref()
+    }
+
+    /// Number of members not read yet.
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl<R: Read> Iterator for TwkbStream<R> {
+    type Item = Result<Geometry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.failed {
+            return None;
+        }
+        self.remaining -= 1;
+        let raw = &mut self.raw;
+        let twkb_info = &self.twkb_info;
+        let member = match twkb_info.geom_type {
+            4 => self.cursor.read_point(raw, twkb_info).map(Geometry::Point),
+            5 => self
+                .cursor
+                .read_line(raw, twkb_info)
+                .map(Geometry::LineString),
+            6 => self
+                .cursor
+                .read_polygon(raw, twkb_info)
+                .map(Geometry::Polygon),
+            // members of collections have their own header
+            _ => Geometry::read_twkb(raw),
+        };
+        self.failed = member.is_err();
+        Some(member)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            (0, Some(0))
+        } else {
+            (0, Some(self.remaining))
+        }
+    }
+}
+
+// --- owned ewkb conversion ---
+
+/// Owned EWKB geometry with the dimensions of the TWKB source.
+#[derive(PartialEq, Clone, Debug)]
+pub enum OwnedEwkb<P, Z, M, ZM> {
+    Point(P),
+    PointZ(Z),
+    PointM(M),
+    PointZM(ZM),
+}
+
+fn point_type(first: Option<&Point>) -> ewkb::PointType {
+    match first.map(|p| (p.z.is_some(), p.m.is_some())) {
+        Some((true, true)) => ewkb::PointType::PointZM,
+        Some((true, false)) => ewkb::PointType::PointZ,
+        Some((false, true)) => ewkb::PointType::PointM,
+        _ => ewkb::PointType::Point,
+    }
+}
+
+fn point_to<P: ewkb::NewFromOptVals>(p: &Point, srid: Option<i32>) -> P {
+    P::new_from_opt_vals(p.x, p.y, p.z, p.m, srid)
+}
+
+fn line_to<P>(line: &LineString, srid: Option<i32>) -> ewkb::LineStringT<P>
+where
+    P: postgis::Point + ewkb::EwkbRead + ewkb::NewFromOptVals,
+{
+    ewkb::LineStringT {
+        points: line.points.iter().map(|p| point_to(p, srid)).collect(),
+        srid,
+    }
+}
+
+fn polygon_to<P>(polygon: &Polygon, srid: Option<i32>) -> ewkb::PolygonT<P>
+where
+    P: postgis::Point + ewkb::EwkbRead + ewkb::NewFromOptVals,
+{
+    ewkb::PolygonT {
+        rings: polygon.rings.iter().map(|r| line_to(r, srid)).collect(),
+        srid,
+    }
+}
+
+fn multipoint_to<P>(multi: &MultiPoint, srid: Option<i32>) -> ewkb::MultiPointT<P>
+where
+    P: postgis::Point + ewkb::EwkbRead + ewkb::NewFromOptVals,
+{
+    ewkb::MultiPointT {
+        points: multi.points.iter().map(|p| point_to(p, srid)).collect(),
+        srid,
+    }
+}
+
+fn multiline_to<P>(multi: &MultiLineString, srid: Option<i32>) -> ewkb::MultiLineStringT<P>
+where
+    P: postgis::Point + ewkb::EwkbRead + ewkb::NewFromOptVals,
+{
+    ewkb::MultiLineStringT {
+        lines: multi.lines.iter().map(|l| line_to(l, srid)).collect(),
+        srid,
+    }
+}
+
+fn multipolygon_to<P>(multi: &MultiPolygon, srid: Option<i32>) -> ewkb::MultiPolygonT<P>
+where
+    P: postgis::Point + ewkb::EwkbRead + ewkb::NewFromOptVals,
+{
+    ewkb::MultiPolygonT {
+        polygons: multi.polygons.iter().map(|p| polygon_to(p, srid)).collect(),
+        srid,
+    }
+}
+
+macro_rules! impl_to_ewkb_owned {
+    ($twkbtype:ident, $convert:ident, |$geom:ident| $first:expr => $ewkbtype:ident, $ewkbtypez:ident, $ewkbtypem:ident, $ewkbtypezm:ident) => {
+        impl $twkbtype {
+            /// Convert into an owned EWKB geometry, with Z and M values if the TWKB has them.
+            pub fn to_ewkb_owned(
+                &self,
+                srid: Option<i32>,
+            ) -> OwnedEwkb<ewkb::$ewkbtype, ewkb::$ewkbtypez, ewkb::$ewkbtypem, ewkb::$ewkbtypezm>
+            {
+                let $geom = self;
+                match point_type($first) {
+                    ewkb::PointType::Point => OwnedEwkb::Point($convert($geom, srid)),
+                    ewkb::PointType::PointZ => OwnedEwkb::PointZ($convert($geom, srid)),
+                    ewkb::PointType::PointM => OwnedEwkb::PointM($convert($geom, srid)),
+                    ewkb::PointType::PointZM => OwnedEwkb::PointZM($convert($geom, srid)),
+                }
+            }
+        }
+    };
+}
+
+impl_to_ewkb_owned!(Point, point_to, |p| Some(p) => Point, PointZ, PointM, PointZM);
+impl_to_ewkb_owned!(LineString, line_to, |line| line.points.first()
+    => LineString, LineStringZ, LineStringM, LineStringZM);
+impl_to_ewkb_owned!(Polygon, polygon_to, |poly| poly.rings.iter().flat_map(|r| &r.points).next()
+    => Polygon, PolygonZ, PolygonM, PolygonZM);
+impl_to_ewkb_owned!(MultiPoint, multipoint_to, |multi| multi.points.first()
+    => MultiPoint, MultiPointZ, MultiPointM, MultiPointZM);
+impl_to_ewkb_owned!(MultiLineString, multiline_to, |multi| multi.lines.iter().flat_map(|l| &l.points).next()
+    => MultiLineString, MultiLineStringZ, MultiLineStringM, MultiLineStringZM);
+impl_to_ewkb_owned!(MultiPolygon, multipolygon_to, |multi| multi.polygons.iter().flat_map(|p| &p.rings).flat_map(|r| &r.points).next()
+    => MultiPolygon, MultiPolygonZ, MultiPolygonM, MultiPolygonZM);
+
+// --- writing ---
+
+/// Options for [`ewkb_to_twkb`].
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct TwkbWriteOptions {
+    /// Decimal digits of X and Y, -8 to 7.
+    pub precision: i8,
+    /// Decimal digits of Z, 0 to 7.
+    pub precision_z: u8,
+    /// Decimal digits of M, 0 to 7.
+    pub precision_m: u8,
+    /// Include a bounding box.
+    pub bbox: bool,
+    /// Include the size attribute.
+    pub size: bool,
+}
+
+/// Convert an EWKB geometry to TWKB.
+///
+/// `ids` is written as the id list of Multi geometries and GeometryCollections
+/// and must have one entry per member.
+///
+/// ```rust
+/// use postgis::ewkb::{AsEwkbLineString, EwkbWrite, LineString};
+/// use postgis::twkb::{ewkb_to_twkb, TwkbWriteOptions};
+///
+/// let line = LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+/// let mut ewkb = Vec::new();
+/// line.as_ewkb().write_ewkb(&mut ewkb).unwrap();
+/// let options = TwkbWriteOptions { precision: 1, ..Default::default() };
+/// // SELECT ST_AsTWKB('LINESTRING (10 -20, -0 -0.5)'::geometry, 1)
+/// assert_eq!(ewkb_to_twkb(&ewkb, &options, None).unwrap(), vec![0x22, 0x00, 0x02, 0xc8, 0x01, 0x8f, 0x03, 0xc7, 0x01, 0x86, 0x03]);
+/// ```
+pub fn ewkb_to_twkb(
+    ewkb: &[u8],
+    options: &TwkbWriteOptions,
+    ids: Option<&[u64]>,
+) -> Result<Vec<u8>, Error> {
+    if !(-8..=7).contains(&options.precision) || options.precision_z > 7 || options.precision_m > 7
+    {
+        return Err(Error::Write("TWKB precision out of range".into()));
+    }
+    let slice = ewkb::EwkbSlice::new(ewkb)?;
+    let mut out = Vec::new();
+    match slice.point_type() {
+        ewkb::PointType::Point => {
+            TwkbEncoder::new(options, false, false).write_geometry(
+                &mut out,
+                &slice.decode::<ewkb::GeometryT<ewkb::Point>>()?,
+                ids,
+            )?;
+        }
+        ewkb::PointType::PointZ => {
+            TwkbEncoder::new(options, true, false).write_geometry(
+                &mut out,
+                &slice.decode::<ewkb::GeometryT<ewkb::PointZ>>()?,
+                ids,
+            )?;
+        }
+        ewkb::PointType::PointM => {
+            TwkbEncoder::new(options, false, true).write_geometry(
+                &mut out,
+                &slice.decode::<ewkb::GeometryT<ewkb::PointM>>()?,
+                ids,
+            )?;
+        }
+        ewkb::PointType::PointZM => {
+            TwkbEncoder::new(options, true, true).write_geometry(
+                &mut out,
+                &slice.decode::<ewkb::GeometryT<ewkb::PointZM>>()?,
+                ids,
+            )?;
+        }
+    }
+    Ok(out)
+}
+
+// Delta encoding state and extent of the scaled coordinates
+struct CoordState {
+    last: [i64; 4],
+    min: [i64; 4],
+    max: [i64; 4],
+    has_coords: bool,
+}
+
+impl CoordState {
+    fn new() -> Self {
+        CoordState {
+            last: [0; 4],
+            min: [i64::MAX; 4],
+            max: [i64::MIN; 4],
+            has_coords: false,
+        }
+    }
+
+    fn extend(&mut self, other: &CoordState) {
+        for d in 0..4 {
+            self.min[d] = self.min[d].min(other.min[d]);
+            self.max[d] = self.max[d].max(other.max[d]);
+        }
+        self.has_coords |= other.has_coords;
+    }
+}
+
+struct TwkbEncoder<'o> {
+    options: &'o TwkbWriteOptions,
+    has_z: bool,
+    has_m: bool,
+    // scale factors of x, y, z, m
+    scales: [f64; 4],
+}
+
+impl<'o> TwkbEncoder<'o> {
+    fn new(options: &'o TwkbWriteOptions, has_z: bool, has_m: bool) -> Self {
+        let xy = 10f64.powi(options.precision as i32);
+        TwkbEncoder {
+            options,
+            has_z,
+            has_m,
+            scales: [
+                xy,
+                xy,
+                10f64.powi(options.precision_z as i32),
+                10f64.powi(options.precision_m as i32),
+            ],
+        }
+    }
+
+    fn dims(&self) -> impl Iterator<Item = usize> {
+        let (has_z, has_m) = (self.has_z, self.has_m);
+        (0..4).filter(move |d| match d {
+            2 => has_z,
+            3 => has_m,
+            _ => true,
+        })
+    }
+
+    // Write header, optional size and bbox, and body. Returns the extent.
+    fn write_geometry<P>(
+        &self,
+        out: &mut Vec<u8>,
+        geom: &ewkb::GeometryT<P>,
+        ids: Option<&[u64]>,
+    ) -> Result<CoordState, Error>
+    where
+        P: postgis::Point + ewkb::EwkbRead,
+    {
+        let (type_code, num_members) = match *geom {
+            ewkb::GeometryT::Point(ref p) => (1, if p.x().is_nan() { 0 } else { 1 }),
+            ewkb::GeometryT::LineString(ref line) => (2, line.points.len()),
+            ewkb::GeometryT::Polygon(ref poly) => (3, poly.rings.len()),
+            ewkb::GeometryT::MultiPoint(ref multi) => (4, multi.points.len()),
+            ewkb::GeometryT::MultiLineString(ref multi) => (5, multi.lines.len()),
+            ewkb::GeometryT::MultiPolygon(ref multi) => (6, multi.polygons.len()),
+            ewkb::GeometryT::GeometryCollection(ref collection) => (7, collection.geometries.len()),
+        };
+        if let Some(ids) = ids {
+            if type_code < 4 || ids.len() != num_members {
+                return Err(Error::Write(format!(
+                    "{} ids given for {} members of geometry type {}",
+                    ids.len(),
+                    num_members,
+                    type_code
+                )));
+            }
+        }
+        let is_empty = num_members == 0;
+
+        let mut state = CoordState::new();
+        let mut body = Vec::new();
+        if !is_empty {
+            if type_code > 3 {
+                write_raw_varint64(&mut body, num_members as u64);
+                write_idlist(&mut body, ids);
+            }
+            match *geom {
+                ewkb::GeometryT::Point(ref p) => self.write_point(&mut body, p, &mut state),
+                ewkb::GeometryT::LineString(ref line) => {
+                    self.write_points(&mut body, line.points.iter(), &mut state)
+                }
+                ewkb::GeometryT::Polygon(ref poly) => {
+                    self.write_rings(&mut body, &poly.rings, &mut state)
+                }
+                ewkb::GeometryT::MultiPoint(ref multi) => {
+                    for p in multi.points.iter() {
+                        self.write_point(&mut body, p, &mut state);
+                    }
+                }
+                ewkb::GeometryT::MultiLineString(ref multi) => {
+                    for line in multi.lines.iter() {
+                        self.write_points(&mut body, line.points.iter(), &mut state);
+                    }
+                }
+                ewkb::GeometryT::MultiPolygon(ref multi) => {
+                    for poly in multi.polygons.iter() {
+                        self.write_rings(&mut body, &poly.rings, &mut state);
+                    }
+                }
+                ewkb::GeometryT::GeometryCollection(ref collection) => {
+                    for member in collection.geometries.iter() {
+                        let extent = self.write_geometry(&mut body, member, None)?;
+                        state.extend(&extent);
+                    }
+                }
+            }
+        }
+
+        let has_ext_dims = self.has_z || self.has_m;
+        let has_bbox = self.options.bbox && state.has_coords;
+        let has_idlist = ids.is_some() && !is_empty;
+        out.push(type_code | (encode_zig_zag_64(self.options.precision as i64) as u8) << 4);
+        out.push(
+            has_bbox as u8
+                | (self.options.size as u8) << 1
+                | (has_idlist as u8) << 2
+                | (has_ext_dims as u8) << 3
+                | (is_empty as u8) << 4,
+        );
+        if has_ext_dims {
+            out.push(
+                self.has_z as u8
+                    | (self.has_m as u8) << 1
+                    | self.options.precision_z << 2
+                    | self.options.precision_m << 5,
+            );
+        }
+        let mut bbox = Vec::new();
+        if has_bbox {
+            for d in self.dims() {
+                write_int64(&mut bbox, state.min[d]);
+                write_int64(&mut bbox, state.max[d] - state.min[d]);
+            }
+        }
+        if self.options.size {
+            write_raw_varint64(out, (bbox.len() + body.len()) as u64);
+        }
+        out.extend_from_slice(&bbox);
+        out.extend_from_slice(&body);
+        Ok(state)
+    }
+
+    fn write_rings<'a, P, I>(&self, out: &mut Vec<u8>, rings: I, state: &mut CoordState)
+    where
+        P: 'a + postgis::Point + ewkb::EwkbRead,
+        I: IntoIterator<Item = &'a ewkb::LineStringT<P>>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let rings = rings.into_iter();
+        write_raw_varint64(out, rings.len() as u64);
+        for ring in rings {
+            self.write_points(out, ring.points.iter(), state);
+        }
+    }
+
+    fn write_points<'a, P, I>(&self, out: &mut Vec<u8>, points: I, state: &mut CoordState)
+    where
+        P: 'a + postgis::Point,
+        I: ExactSizeIterator<Item = &'a P>,
+    {
+        write_raw_varint64(out, points.len() as u64);
+        for p in points {
+            self.write_point(out, p, state);
+        }
+    }
+
+    fn write_point<P: postgis::Point>(&self, out: &mut Vec<u8>, p: &P, state: &mut CoordState) {
+        let values = [
+            p.x(),
+            p.y(),
+            p.opt_z().unwrap_or(0.0),
+            p.opt_m().unwrap_or(0.0),
+        ];
+        for d in self.dims() {
+            let v = (values[d] * self.scales[d]).round() as i64;
+            write_int64(out, v - state.last[d]);
+            state.last[d] = v;
+            state.min[d] = state.min[d].min(v);
+            state.max[d] = state.max[d].max(v);
+        }
+        state.has_coords = true;
+    }
+}
+
+fn write_idlist(out: &mut Vec<u8>, ids: Option<&[u64]>) {
+    for id in ids.unwrap_or(&[]) {
+        write_raw_varint64(out, *id);
+    }
+}
+
+pub(crate) fn write_raw_varint64(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+pub(crate) fn write_int64(out: &mut Vec<u8>, v: i64) {
+    write_raw_varint64(out, encode_zig_zag_64(v))
+}
+
+fn encode_zig_zag_64(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
 #[cfg(test)]
 use ewkb::{
-    AsEwkbLineString, AsEwkbMultiLineString, AsEwkbMultiPoint, AsEwkbMultiPolygon, AsEwkbPoint,
-    AsEwkbPolygon, EwkbWrite,
+    AsEwkbGeometryCollection, AsEwkbLineString, AsEwkbMultiLineString, AsEwkbMultiPoint,
+    AsEwkbMultiPolygon, AsEwkbPoint, AsEwkbPolygon, EwkbWrite,
 };
 
 #[cfg(test)]
@@ -592,7 +1268,7 @@ fn test_read_point() {
 
     let twkb = hex_to_vec("0108011427c601"); // SELECT encode(ST_AsTWKB('POINT(10 -20 99)'::geometry), 'hex')
     let point = Point::read_twkb(&mut twkb.as_slice()).unwrap();
-    assert_eq!(format!("{:.0?}", point), "Point { x: 10, y: -20 }");
+    assert_eq!(format!("{:.0?}", point), "Point { x: 10, y: -20, z: 99 }");
 
     let twkb = hex_to_vec("2100ca019503"); // SELECT encode(ST_AsTWKB('POINT(10.12 -20.34)'::geometry, 1), 'hex')
     let point = Point::read_twkb(&mut twkb.as_slice()).unwrap();
@@ -716,3 +1392,147 @@ fn test_write_multipoly() {
     assert_eq!(format!("{:?}", multipoly.as_ewkb()), "EwkbMultiPolygon");
     assert_eq!(multipoly.as_ewkb().to_hex_ewkb(), "010600000002000000010300000001000000050000000000000000000000000000000000000000000000000000400000000000000000000000000000004000000000000000400000000000000000000000000000004000000000000000000000000000000000010300000001000000050000000000000000002440000000000000244000000000000000C0000000000000244000000000000000C000000000000000C0000000000000244000000000000000C000000000000024400000000000002440");
 }
+
+#[cfg(test)]
+fn ewkb_bytes<T: EwkbWrite>(geom: T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    geom.write_ewkb(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_ewkb_to_twkb() {
+    let prec = |precision| TwkbWriteOptions { precision, ..Default::default() };
+    let point = ewkb_bytes(ewkb::Point::new(10.0, -20.0, Some(4326)).as_ewkb());
+    assert_eq!(ewkb_to_twkb(&point, &prec(0), None).unwrap(), hex_to_vec("01001427"));
+    let point = ewkb_bytes(ewkb::Point::new(10.12, -20.34, None).as_ewkb());
+    assert_eq!(ewkb_to_twkb(&point, &prec(1), None).unwrap(), hex_to_vec("2100ca019503"));
+    let point = ewkb_bytes(ewkb::Point::new(11.12, -22.34, None).as_ewkb());
+    assert_eq!(ewkb_to_twkb(&point, &prec(-1), None).unwrap(), hex_to_vec("11000203"));
+    let point = ewkb_bytes(ewkb::PointZ::new(10.0, -20.0, 99.0, None).as_ewkb());
+    assert_eq!(ewkb_to_twkb(&point, &prec(0), None).unwrap(), hex_to_vec("0108011427c601"));
+    let point = ewkb_bytes(ewkb::Point::new(f64::NAN, f64::NAN, None).as_ewkb());
+    assert_eq!(ewkb_to_twkb(&point, &prec(0), None).unwrap(), hex_to_vec("0110"));
+
+    let line = ewkb::LineString::from(vec![(10.0, -20.0), (0., -0.5)]);
+    assert_eq!(ewkb_to_twkb(&ewkb_bytes(line.as_ewkb()), &prec(0), None).unwrap(), hex_to_vec("02000214271326"));
+    let line = ewkb::LineString::from(Vec::<(f64, f64)>::new());
+    assert_eq!(ewkb_to_twkb(&ewkb_bytes(line.as_ewkb()), &prec(0), None).unwrap(), hex_to_vec("0210"));
+
+    let poly = ewkb::Polygon::from(vec![vec![(0., 0.), (2., 0.), (2., 2.), (0., 2.), (0., 0.)], vec![(10., 10.), (-2., 10.), (-2., -2.), (10., -2.), (10., 10.)]]);
+    assert_eq!(ewkb_to_twkb(&ewkb_bytes(poly.as_ewkb()), &prec(0), None).unwrap(), hex_to_vec("03000205000004000004030000030514141700001718000018"));
+    let multi = ewkb::MultiPoint::from(vec![(10.0, -20.0), (0., -0.5)]);
+    assert_eq!(ewkb_to_twkb(&ewkb_bytes(multi.as_ewkb()), &prec(0), None).unwrap(), hex_to_vec("04000214271326"));
+    let multi = ewkb::MultiLineString::from(vec![vec![(10.0, -20.0), (0., -0.5)], vec![(0., 0.), (2., 0.)]]);
+    assert_eq!(ewkb_to_twkb(&ewkb_bytes(multi.as_ewkb()), &prec(0), None).unwrap(), hex_to_vec("05000202142713260200020400"));
+    let multi = ewkb::MultiPolygon::from(vec![vec![vec![(0., 0.), (2., 0.), (2., 2.), (0., 2.), (0., 0.)]], vec![vec![(10., 10.), (-2., 10.), (-2., -2.), (10., -2.), (10., 10.)]]]);
+    assert_eq!(ewkb_to_twkb(&ewkb_bytes(multi.as_ewkb()), &prec(0), None).unwrap(), hex_to_vec("060002010500000400000403000003010514141700001718000018"));
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_ewkb_to_twkb_options() {
+    let line = ewkb_bytes(ewkb::LineString::from(vec![(1., 1.), (5., 5.)]).as_ewkb());
+    let options = TwkbWriteOptions { bbox: true, size: true, ..Default::default() };
+    let twkb = ewkb_to_twkb(&line, &options, None).unwrap();
+    assert_eq!(twkb, hex_to_vec("020309020802080202020808"));
+    assert_eq!(LineString::read_twkb(&mut twkb.as_slice()).unwrap().points.len(), 2);
+    let (decoded, bbox) = LineString::read_twkb_with_bbox(&mut twkb.as_slice()).unwrap();
+    assert_eq!(decoded.points.len(), 2);
+    assert_eq!(bbox, Some(Envelope::new(1., 1., 5., 5.)));
+    let options = TwkbWriteOptions { precision: 1, bbox: true, ..Default::default() };
+    let twkb = ewkb_to_twkb(&ewkb_bytes(ewkb::MultiPoint::from(vec![(10.0, -20.0), (0., -0.5)]).as_ewkb()), &options, None).unwrap();
+    let (_, bbox) = MultiPoint::read_twkb_with_bbox(&mut twkb.as_slice()).unwrap();
+    assert_eq!(bbox, Some(Envelope::new(0., -20., 10., -0.5)));
+    assert_eq!(MultiPoint::read_twkb_with_bbox(&mut hex_to_vec("04000214271326").as_slice()).unwrap().1, None);
+
+    let multi = ewkb_bytes(ewkb::MultiPoint::from(vec![(10.0, -20.0), (0., -0.5)]).as_ewkb());
+    let twkb = ewkb_to_twkb(&multi, &Default::default(), Some(&[1, 2])).unwrap();
+    assert_eq!(twkb, hex_to_vec("040402010214271326"));
+    assert_eq!(MultiPoint::read_twkb(&mut twkb.as_slice()).unwrap().ids, Some(vec![1, 2]));
+    assert!(ewkb_to_twkb(&multi, &Default::default(), Some(&[1])).is_err());
+    assert!(ewkb_to_twkb(&line, &Default::default(), Some(&[1])).is_err());
+    assert!(ewkb_to_twkb(&line, &TwkbWriteOptions { precision: 8, ..Default::default() }, None).is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_to_ewkb_owned() {
+    let twkb = hex_to_vec("02000214271326"); // SELECT encode(ST_AsTWKB('LINESTRING (10 -20, -0 -0.5)'::geometry), 'hex')
+    let line = LineString::read_twkb(&mut twkb.as_slice()).unwrap();
+    match line.to_ewkb_owned(Some(4326)) {
+        OwnedEwkb::Point(line) => {
+            assert_eq!(line.srid, Some(4326));
+            assert_eq!(line.points[1], ewkb::Point::new(0.0, -1.0, Some(4326)));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let line = ewkb::LineStringZ::from(vec![(10.0, -20.0, 1.25), (0., -0.5, 2.5)]);
+    let options = TwkbWriteOptions { precision_z: 2, ..Default::default() };
+    let twkb = ewkb_to_twkb(&ewkb_bytes(line.as_ewkb()), &options, None).unwrap();
+    let line = LineString::read_twkb(&mut twkb.as_slice()).unwrap();
+    match line.to_ewkb_owned(None) {
+        OwnedEwkb::PointZ(line) => assert_eq!(line.points[1], ewkb::PointZ::new(0.0, -1.0, 2.5, None)),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let twkb = hex_to_vec("060002010500000400000403000003010514141700001718000018"); // SELECT encode(ST_AsTWKB('MULTIPOLYGON (((0 0, 2 0, 2 2, 0 2, 0 0)), ((10 10, -2 10, -2 -2, 10 -2, 10 10)))'::geometry), 'hex')
+    let multi = MultiPolygon::read_twkb(&mut twkb.as_slice()).unwrap();
+    match multi.to_ewkb_owned(None) {
+        OwnedEwkb::Point(owned) => assert_eq!(owned.as_ewkb().to_hex_ewkb(), multi.as_ewkb().to_hex_ewkb()),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_twkb_stream() {
+    let multi = ewkb::MultiPolygon::from(vec![vec![vec![(0., 0.), (2., 0.), (2., 2.), (0., 2.), (0., 0.)]], vec![vec![(10., 10.), (-2., 10.), (-2., -2.), (10., -2.), (10., 10.)]]]);
+    let twkb = ewkb_to_twkb(&ewkb_bytes(multi.as_ewkb()), &TwkbWriteOptions { bbox: true, ..Default::default() }, Some(&[7, 8])).unwrap();
+    let decoded = MultiPolygon::read_twkb(&mut twkb.as_slice()).unwrap();
+    let stream = TwkbStream::new(twkb.as_slice()).unwrap();
+    assert_eq!(stream.ids(), Some(&[7, 8][..]));
+    assert_eq!(stream.ids(), decoded.ids.as_deref());
+    assert_eq!(stream.bbox(), Some(Envelope::new(-2., -2., 10., 10.)));
+    let members: Vec<Geometry> = stream.collect::<Result<_, _>>().unwrap();
+    assert_eq!(members, decoded.polygons.into_iter().map(Geometry::Polygon).collect::<Vec<_>>());
+
+    let collection = ewkb::GeometryCollection {
+        geometries: vec![
+            ewkb::GeometryT::Point(ewkb::Point::new(1., 2., None)),
+            ewkb::GeometryT::LineString(ewkb::LineString::from(vec![(10.0, -20.0), (0., -0.5)])),
+        ],
+        srid: None,
+    };
+    let twkb = ewkb_to_twkb(&ewkb_bytes(collection.as_ewkb()), &Default::default(), None).unwrap();
+    let mut stream = TwkbStream::new(twkb.as_slice()).unwrap();
+    assert_eq!(stream.len(), 2);
+    assert_eq!(stream.next().unwrap().unwrap(), Geometry::Point(Point { x: 1., y: 2., z: None, m: None }));
+    match stream.next().unwrap().unwrap() {
+        Geometry::LineString(line) => assert_eq!(line.points[1].y, -1.0), // rounded to precision 0
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(stream.next().is_none());
+
+    // no members to stream
+    assert!(TwkbStream::new(hex_to_vec("02000214271326").as_slice()).is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_iter_with_ids() {
+    let p = |x: f64| Point { x, y: 0.0, z: None, m: None };
+    let multi = MultiPoint::from_id_pairs(vec![(3, p(1.0)), (5, p(2.0))]);
+    assert_eq!(multi.ids, Some(vec![3, 5]));
+    let pairs: Vec<(Option<u64>, f64)> = multi.iter_with_ids().map(|(id, pt)| (id, pt.x)).collect();
+    assert_eq!(pairs, vec![(Some(3), 1.0), (Some(5), 2.0)]);
+
+    let multi = MultiPoint { points: vec![p(1.0)], ids: None };
+    assert_eq!(multi.iter_with_ids().next().map(|(id, _)| id), Some(None));
+
+    let multi = MultiLineString::from_id_pairs(vec![(1, LineString { points: vec![p(1.0), p(2.0)] })]);
+    assert_eq!(multi.iter_with_ids().count(), 1);
+    assert_eq!(MultiPolygon::from_id_pairs(vec![]).ids, Some(vec![]));
+}
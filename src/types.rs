@@ -23,6 +23,16 @@ pub trait Polygon<'a>: Send + Sync {
     type ItemType: 'a + LineString<'a>;
     type Iter: Iterator<Item = &'a Self::ItemType>;
     fn rings(&'a self) -> Self::Iter;
+
+    /// The first ring, `None` for empty polygons.
+    fn exterior(&'a self) -> Option<&'a Self::ItemType> {
+        self.rings().next()
+    }
+
+    /// All rings after the exterior ring.
+    fn interiors(&'a self) -> core::iter::Skip<Self::Iter> {
+        self.rings().skip(1)
+    }
 }
 
 pub trait MultiPoint<'a>: Send + Sync {